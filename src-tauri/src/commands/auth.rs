@@ -1,16 +1,28 @@
 use alibabacloud::client::sts::caller_identity::{CallerIdentityBody, IdentityType};
+use std::path::PathBuf;
+
 use serde::Serialize;
-use tauri::State;
-
-use crate::services::{
-    auth::{
-        error::{AKFulfillError, AKValidationError},
-        store::QueryCredentialError as ServiceQueryError,
-        types::AccessKeyCredentials,
-        AccessKeyAuthService,
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{
+    commands::profile::switch_active_client,
+    events::AuthStateChanged,
+    services::{
+        auth::{
+            chain::{CredentialProviderChain, CredentialSource},
+            cli_config::{CliConfig, CliConfigError, CliProfilePreview, CLI_CONFIG_RELATIVE_PATH},
+            error::{AKFulfillError, AKValidationError},
+            profile::{LogoutOutcome, ProfileError},
+            secret::SecretString,
+            store::QueryCredentialError as ServiceQueryError,
+            types::{AccessKeyCredentials, AccessKeyView},
+            vault::{UnlockOutcome, VaultError, VaultStatus},
+            AccessKeyAuthService,
+        },
+        client::{AliyunClientService, ClientValidationError},
+        error::NoSource,
     },
-    client::{AliyunClientService, ClientValidationError},
-    error::NoSource,
 };
 
 #[derive(thiserror::Error, Debug, Serialize, specta::Type)]
@@ -22,14 +34,15 @@ pub enum QueryError {
 
 #[tauri::command]
 #[specta::specta]
-/// Retrieve the currently stored access key credential, if any.
+/// Retrieve a redacted view of the currently stored access key credential, if any.
 ///
-/// This command queries the authentication service for the current
-/// `AccessKeyCredential`. It returns `Ok(Some(credential))` when a
-/// credential is available, `Ok(None)` when no credential is stored,
-/// and `Err(QueryError::Internal(_))` for all other service-level
-/// failures. The documentation intentionally omits details about the
-/// service's internal error variants.
+/// This command queries the authentication service for the access key of
+/// the active profile. The secret never leaves the backend, only its last
+/// characters are shown along with when it was last validated. It returns
+/// `Ok(Some(view))` when a credential is available, `Ok(None)` when no
+/// credential is stored, and `Err(QueryError::Internal(_))` for all other
+/// service-level failures. The documentation intentionally omits details
+/// about the service's internal error variants.
 ///
 /// # Errors
 ///
@@ -44,9 +57,9 @@ pub enum QueryError {
 /// ```
 pub fn current_access_key_credential(
     auth_service: State<AccessKeyAuthService>,
-) -> Result<Option<AccessKeyCredentials>, QueryError> {
+) -> Result<Option<AccessKeyView>, QueryError> {
     auth_service
-        .current_access_key_credentials()
+        .current_access_key_view()
         .map(Some)
         .or_else(|err| match err {
             ServiceQueryError::NotExist => Ok(None),
@@ -90,7 +103,8 @@ impl From<CallerIdentityBody> for CallerIdentity {
 /// Validate the provided access key credentials.
 ///
 /// This command validates the given `AccessKeyCredentials` by making
-/// a request to Aliyun's STS service to retrieve the caller identity.
+/// a request to Aliyun's STS service, at the configured endpoint, to
+/// retrieve the caller identity.
 /// It returns `Ok(CallerIdentityBody)` when the credentials are valid,
 /// and `Err(AKValidationError)` when validation fails due to invalid
 /// credentials or service errors.
@@ -108,8 +122,10 @@ impl From<CallerIdentityBody> for CallerIdentity {
 /// ```
 pub async fn validate_access_key_credentials(
     credentials: AccessKeyCredentials,
+    auth_service: State<'_, AccessKeyAuthService>,
 ) -> Result<CallerIdentity, AKValidationError> {
-    AccessKeyAuthService::validate_access_key_credentials(credentials)
+    auth_service
+        .validate_credentials(&credentials)
         .await
         .map(|r| r.into())
 }
@@ -145,18 +161,178 @@ pub async fn fulfill_access_key_credentials(
         .await
         .map(|r| r.into())?;
 
-    let Some(client) = auth_service.new_client() else {
+    let (Ok((name, _)), Some(client)) = (auth_service.current_profile(), auth_service.new_client())
+    else {
         return Err(AKFulfillError::InternalError {
             message: "can't unwrap valid aliyun client when using auth_service.new_client"
                 .to_owned(),
             source: NoSource::new_boxed(),
         });
     };
-    client_service.initialize(client);
+    client_service.initialize(client, CredentialSource::StoredProfile { name });
 
     Ok(result)
 }
 
+/// Resolves the path of the `aliyun` CLI config, `~/.aliyun/config.json` unless given.
+fn cli_config_path(app: &AppHandle, path: Option<String>) -> Result<PathBuf, CliConfigError> {
+    match path {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => app
+            .path()
+            .home_dir()
+            .map(|home| home.join(CLI_CONFIG_RELATIVE_PATH))
+            .map_err(|_| CliConfigError::NoHomeDir),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the profiles of the `aliyun` CLI config and tell which ones can be imported.
+///
+/// Profiles in the `AK` and `RamRoleArn` modes can be imported, unless a
+/// saved profile already has the same name. `StsToken` and `EcsRamRole`
+/// profiles are listed with the reason they can't be.
+///
+/// # Errors
+///
+/// Returns `Err(CliConfigError)` when the config can't be read or parsed, or
+/// when the saved profiles can't be read.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend, `path` defaults to `~/.aliyun/config.json`
+/// let profiles = invoke("preview_aliyun_cli_profiles", { path: null });
+/// ```
+pub fn preview_aliyun_cli_profiles(
+    path: Option<String>,
+    app: AppHandle,
+    auth_service: State<AccessKeyAuthService>,
+) -> Result<Vec<CliProfilePreview>, CliConfigError> {
+    let config = CliConfig::load(&cli_config_path(&app, path)?)?;
+    Ok(auth_service.preview_cli_profiles(&config)?)
+}
+
+/// The outcome of importing one profile of the `aliyun` CLI config.
+#[derive(Debug, Serialize, specta::Type)]
+pub struct CliProfileImport {
+    pub name: String,
+    /// Why the profile failed to import, `None` if it was imported or skipped.
+    pub error: Option<String>,
+    /// Why the profile was skipped without trying, e.g. `StsToken` and `EcsRamRole`
+    /// profiles which this app can't keep using.
+    pub skipped: Option<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Validate and save the given profiles of the `aliyun` CLI config.
+///
+/// Each profile is validated with its access key before being saved under
+/// the same name, and a failure doesn't stop the other profiles from being
+/// imported. Profiles in a mode this app doesn't support are skipped with
+/// the reason, and the region of a profile becomes its default region. The
+/// current profile of the CLI is imported first, so that it
+/// becomes the active one when no profile was active yet.
+///
+/// # Errors
+///
+/// Returns `Err(CliConfigError)` when the config can't be read or parsed.
+/// Failures of single profiles are reported in the returned list instead.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let imports = invoke("import_aliyun_cli_profiles", { names, path: null });
+/// ```
+pub async fn import_aliyun_cli_profiles(
+    names: Vec<String>,
+    path: Option<String>,
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<CliProfileImport>, CliConfigError> {
+    let config = CliConfig::load(&cli_config_path(&app, path)?)?;
+
+    let mut names = names;
+    names.sort_by_key(|name| *name != config.current);
+
+    let mut imports = Vec::with_capacity(names.len());
+    for name in names {
+        let (error, skipped) = match config.profile(&name) {
+            Some(profile) => match profile.credentials() {
+                Ok(credentials) => {
+                    let error = auth_service
+                        .import_cli_profile(profile, credentials)
+                        .await
+                        .err()
+                        .map(|err| err.to_string());
+                    (error, None)
+                }
+                Err(reason) => (None, Some(reason)),
+            },
+            None => (
+                Some(format!("the aliyun CLI config has no profile {name:?}")),
+                None,
+            ),
+        };
+        imports.push(CliProfileImport {
+            name,
+            error,
+            skipped,
+        });
+    }
+
+    // Checked once saved, a profile switch may have happened during the imports.
+    let active = auth_service.active_profile()?;
+    let imported_active = imports.iter().any(|import| {
+        import.error.is_none()
+            && import.skipped.is_none()
+            && active.as_deref() == Some(import.name.as_str())
+    });
+    if imported_active {
+        // The profiles are saved even if the client of the new active one can't be built.
+        if let Err(err) = switch_active_client(&app, &auth_service, &client_service).await {
+            log::warn!("Failed to build the client of the active profile: {err}");
+        }
+    }
+
+    Ok(imports)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Write the saved profiles into the `aliyun` CLI config, returning its path.
+///
+/// CLI profiles with the same name get their credentials replaced and keep
+/// their other settings. Profiles new to the config get the given region.
+///
+/// # Errors
+///
+/// Returns `Err(CliConfigError)` when the config can't be read, parsed or
+/// written, or when the saved profiles can't be read.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let path = invoke("export_aliyun_cli_profiles", { regionId: "cn-hangzhou", path: null });
+/// ```
+pub fn export_aliyun_cli_profiles(
+    region_id: String,
+    path: Option<String>,
+    app: AppHandle,
+    auth_service: State<AccessKeyAuthService>,
+) -> Result<String, CliConfigError> {
+    let path = cli_config_path(&app, path)?;
+    let mut config = CliConfig::load(&path)?;
+    auth_service.export_cli_profiles(&mut config, &region_id)?;
+    config.save(&path)?;
+    Ok(path.display().to_string())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn has_aliyun_client(client_service: State<'_, AliyunClientService>) -> Result<bool, ()> {
@@ -189,3 +365,154 @@ pub async fn has_valid_aliyun_client(
 ) -> Result<bool, ClientValidationError> {
     client_service.is_valid().await
 }
+
+#[tauri::command]
+#[specta::specta]
+/// Report whether the encrypted credential store has been created and unlocked.
+///
+/// The frontend uses this to decide between asking the user to choose a
+/// master passphrase and asking them to enter the existing one.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let status = invoke("credential_store_status");
+/// ```
+pub fn credential_store_status(
+    auth_service: State<AccessKeyAuthService>,
+) -> Result<VaultStatus, VaultError> {
+    auth_service.vault_status()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Unlock the encrypted credential store with the master passphrase.
+///
+/// On first use the store is created with the given passphrase, and any
+/// plaintext credential saved by older versions is encrypted. Once
+/// unlocked, the Aliyun client is rebuilt from the credential provider
+/// chain, so the active profile is used unless credentials were found
+/// in the environment or the `aliyun` CLI config first.
+///
+/// # Errors
+///
+/// Returns `Err(VaultError::WrongPassphrase)` when the passphrase doesn't
+/// match, and other `VaultError` variants when the store data can't be
+/// read or migrated.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let outcome = invoke("unlock_credential_store", { passphrase });
+/// ```
+pub async fn unlock_credential_store(
+    passphrase: SecretString,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+    chain: State<'_, CredentialProviderChain>,
+) -> Result<UnlockOutcome, VaultError> {
+    let outcome = auth_service.unlock(passphrase.expose_secret())?;
+    // The store stays unlocked even if the client of the active profile can't be
+    // built, the user can still switch to another profile.
+    if let Err(err) = client_service.resolve(&chain, &auth_service).await {
+        log::warn!("Failed to build the client of the active profile: {err}");
+    }
+    Ok(outcome)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Lock the encrypted credential store and drop the in-memory Aliyun client.
+///
+/// The client is kept when its credentials don't come from a stored
+/// profile, e.g. from the environment variables.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("lock_credential_store");
+/// ```
+pub fn lock_credential_store(
+    auth_service: State<AccessKeyAuthService>,
+    client_service: State<AliyunClientService>,
+) -> Result<(), VaultError> {
+    auth_service.lock()?;
+    if let Some(CredentialSource::StoredProfile { .. }) = client_service.source() {
+        client_service.clear();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Tell which provider of the credential chain supplied the credentials of
+/// the Aliyun client, `None` when there is no client.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let source = invoke("active_credential_source");
+/// ```
+pub fn active_credential_source(
+    client_service: State<AliyunClientService>,
+) -> Option<CredentialSource> {
+    client_service.source()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Log out, forgetting the given credential profile, or every stored
+/// credential when no profile is given.
+///
+/// Forgetting everything works while the credential store is locked, and
+/// always drops the in-memory Aliyun client along with the background
+/// refresh of its temporary credentials. Forgetting a single profile only
+/// drops the client built from that profile. `AuthStateChanged` is emitted
+/// afterwards.
+///
+/// Returns `Ok(LogoutOutcome::NothingToDelete)` when there was nothing
+/// stored to forget.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError)` when the profiles can't be read or saved,
+/// e.g. forgetting a single profile while the credential store is locked.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let outcome = invoke("logout", { profile: null });
+/// ```
+pub fn logout(
+    profile: Option<String>,
+    app: AppHandle,
+    auth_service: State<AccessKeyAuthService>,
+    client_service: State<AliyunClientService>,
+) -> Result<LogoutOutcome, ProfileError> {
+    let outcome = auth_service.logout(profile.as_deref())?;
+
+    let client_forgotten = match (&profile, client_service.source()) {
+        (None, _) => true,
+        (Some(profile), Some(CredentialSource::StoredProfile { name })) => *profile == name,
+        _ => false,
+    };
+    if client_forgotten {
+        client_service.clear();
+    }
+
+    let event = AuthStateChanged {
+        outcome: outcome.clone(),
+        active_profile: auth_service.active_profile().ok().flatten(),
+        has_client: client_service.is_initialized(),
+        source: client_service.source(),
+    };
+    if let Err(err) = event.emit(&app) {
+        log::warn!("Failed to emit the auth state change: {err}");
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,102 @@
+use tauri::{AppHandle, State};
+use tauri_specta::Event;
+
+use crate::{
+    events::ActiveProfileChanged,
+    services::{
+        auth::{chain::CredentialProviderChain, AccessKeyAuthService},
+        client::AliyunClientService,
+        demo::{DemoMode, DemoModeError},
+    },
+};
+
+fn emit_client_changed(
+    app: &AppHandle,
+    auth_service: &AccessKeyAuthService,
+    client_service: &AliyunClientService,
+) {
+    let event = ActiveProfileChanged {
+        name: auth_service.active_profile().ok().flatten(),
+        has_client: client_service.is_initialized(),
+        source: client_service.source(),
+    };
+    if let Err(err) = event.emit(app) {
+        log::warn!("Failed to emit the active profile change: {err}");
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Switch to the demo account, served by a fake Aliyun running within the
+/// app, so the app can be tried without an Aliyun account.
+///
+/// The demo account starts over from the same few instances every time.
+/// Saved profiles are left untouched but can't be used until the demo
+/// mode is turned off with `stop_demo_mode`. `ActiveProfileChanged` is
+/// emitted afterwards, with the `Demo` credential source.
+///
+/// # Errors
+///
+/// Returns `Err(DemoModeError)` when the fake can't listen on a local port,
+/// or the client of the demo account can't be built.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("start_demo_mode");
+/// ```
+pub async fn start_demo_mode(
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+    demo: State<'_, DemoMode>,
+) -> Result<(), DemoModeError> {
+    let started = demo.start(&auth_service, &client_service).await;
+    emit_client_changed(&app, &auth_service, &client_service);
+    started
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Leave the demo account, and rebuild the client the way it is at startup.
+///
+/// It returns `false` when the demo mode was already off, in which case
+/// nothing is changed. Otherwise `ActiveProfileChanged` is emitted.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let stopped = invoke("stop_demo_mode");
+/// ```
+pub async fn stop_demo_mode(
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+    chain: State<'_, CredentialProviderChain>,
+    demo: State<'_, DemoMode>,
+) -> Result<bool, ()> {
+    if !demo.stop(&auth_service, &client_service) {
+        return Ok(false);
+    }
+    if let Err(err) = client_service.resolve(&chain, &auth_service).await {
+        log::warn!("Failed to rebuild the client after the demo: {err}");
+    }
+    emit_client_changed(&app, &auth_service, &client_service);
+    Ok(true)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Tell whether the demo mode is on.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let on = invoke("is_demo_mode");
+/// ```
+pub fn is_demo_mode(demo: State<DemoMode>) -> bool {
+    demo.is_active()
+}
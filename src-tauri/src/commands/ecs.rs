@@ -0,0 +1,323 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use tauri::{ipc::Channel, State};
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{
+        batch::{run_batch, BatchCancellations, BatchLimits, BatchReport, InstanceRef},
+        ecs_client,
+        inventory::{self, InstanceInventory, INSTANCE_PAGE_SIZE},
+        power::{power, PowerAction, PowerProgress, StoppedMode, WaitPolicy},
+        release::{self, ReleaseOptions, ReleaseReport},
+        EcsCommandError,
+    },
+};
+
+/// Runs the power action through the client of the region, streaming its progress.
+async fn run_power_action(
+    region_id: &str,
+    instance_id: &str,
+    action: PowerAction,
+    client_service: &AliyunClientService,
+    on_progress: &Channel<PowerProgress>,
+) -> Result<String, EcsCommandError> {
+    let client = ecs_client(client_service, region_id)?;
+    power(
+        &client,
+        instance_id,
+        action,
+        &WaitPolicy::default(),
+        |progress| {
+            if let Err(err) = on_progress.send(progress) {
+                log::warn!("Failed to send the progress of {action:?} on {instance_id}: {err}");
+            }
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the ECS instances of the given regions, of every region available
+/// to the account when `region_ids` is `null`.
+///
+/// Regions are listed in parallel, each one to its last page. A region
+/// failing doesn't fail the whole list: its error is returned among the
+/// `failures`, next to the instances of the other regions.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials, and the error of
+/// DescribeRegions when the regions of the account can't be listed.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let inventory = invoke("list_instances", { regionIds: null });
+/// ```
+pub async fn list_instances(
+    region_ids: Option<Vec<String>>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<InstanceInventory, EcsCommandError> {
+    inventory::list_instances(&client_service, region_ids, INSTANCE_PAGE_SIZE).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Start a stopped instance and wait until it is running.
+///
+/// The progress is streamed through `on_progress`: once Aliyun accepted
+/// the action, after each poll of the status, and once the instance is
+/// running. Returns the status reached.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::IncorrectInstanceStatus)` when the instance is
+/// not stopped, `Err(EcsError::InstanceNotFound)` when it doesn't exist in
+/// the region, and `Err(EcsError::WaitTimedOut)` when it isn't running
+/// after five minutes.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let onProgress = new Channel();
+/// onProgress.onmessage = (progress) => console.log(progress);
+/// let status = invoke("start_instance", { regionId, instanceId, onProgress });
+/// ```
+pub async fn start_instance(
+    region_id: String,
+    instance_id: String,
+    on_progress: Channel<PowerProgress>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<String, EcsCommandError> {
+    let action = PowerAction::Start;
+    run_power_action(
+        &region_id,
+        &instance_id,
+        action,
+        &client_service,
+        &on_progress,
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Stop a running instance gracefully and wait until it is stopped.
+///
+/// With `StopCharging`, a pay-as-you-go instance in a VPC stops being
+/// billed for its vCPUs and memory, which may be out of stock when it is
+/// started again. The progress is streamed as with `start_instance`.
+///
+/// # Errors
+///
+/// Same as `start_instance`, the instance having to be running.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let status = invoke("stop_instance", { regionId, instanceId, stoppedMode: "KeepCharging", onProgress });
+/// ```
+pub async fn stop_instance(
+    region_id: String,
+    instance_id: String,
+    stopped_mode: StoppedMode,
+    on_progress: Channel<PowerProgress>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<String, EcsCommandError> {
+    let action = PowerAction::Stop(stopped_mode);
+    run_power_action(
+        &region_id,
+        &instance_id,
+        action,
+        &client_service,
+        &on_progress,
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Stop a running instance without waiting for its OS to shut down, and
+/// wait until it is stopped.
+///
+/// Unsaved data may be lost, like pulling the plug. See `stop_instance`
+/// for the stopped modes.
+///
+/// # Errors
+///
+/// Same as `start_instance`, the instance having to be running.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let status = invoke("force_stop_instance", { regionId, instanceId, stoppedMode: "StopCharging", onProgress });
+/// ```
+pub async fn force_stop_instance(
+    region_id: String,
+    instance_id: String,
+    stopped_mode: StoppedMode,
+    on_progress: Channel<PowerProgress>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<String, EcsCommandError> {
+    let action = PowerAction::ForceStop(stopped_mode);
+    run_power_action(
+        &region_id,
+        &instance_id,
+        action,
+        &client_service,
+        &on_progress,
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Reboot a running instance and wait until it is running again.
+///
+/// The progress is streamed as with `start_instance`.
+///
+/// # Errors
+///
+/// Same as `start_instance`, the instance having to be running.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let status = invoke("reboot_instance", { regionId, instanceId, onProgress });
+/// ```
+pub async fn reboot_instance(
+    region_id: String,
+    instance_id: String,
+    on_progress: Channel<PowerProgress>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<String, EcsCommandError> {
+    let action = PowerAction::Reboot;
+    run_power_action(
+        &region_id,
+        &instance_id,
+        action,
+        &client_service,
+        &on_progress,
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Apply a power action to many instances at once, e.g. stopping the 30
+/// selected ones.
+///
+/// The instances are sent in batch requests of each region, a few
+/// requests at a time. Each instance succeeds or fails on its own: the
+/// report has the outcome of each one, and how many succeeded, failed or
+/// were skipped. The batch doesn't wait for the instances to reach the
+/// target status, their status when Aliyun answered is reported.
+///
+/// `batch_id` is picked by the frontend, to cancel the batch with
+/// `cancel_batch`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials, failures of
+/// instances being in the report.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let report = invoke("run_power_batch", {
+///     batchId: crypto.randomUUID(),
+///     action: { type: "Stop", stopped_mode: "StopCharging" },
+///     instances: [{ region_id: "cn-hangzhou", instance_id: "i-1" }],
+/// });
+/// ```
+pub async fn run_power_batch(
+    batch_id: String,
+    action: PowerAction,
+    instances: Vec<InstanceRef>,
+    client_service: State<'_, AliyunClientService>,
+    cancellations: State<'_, BatchCancellations>,
+) -> Result<BatchReport, EcsCommandError> {
+    let cancelled = cancellations.register(&batch_id);
+    let report = run_batch(
+        &client_service,
+        action,
+        instances,
+        &BatchLimits::default(),
+        cancelled.clone(),
+    )
+    .await;
+    cancellations.finish(&batch_id);
+    if cancelled.load(Ordering::SeqCst) {
+        log::info!("Cancelled the batch {batch_id} of {action:?}");
+    }
+    report
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Cancel a batch started by `run_power_batch`: the requests not sent yet
+/// are dropped and their instances skipped, the ones already sent go on.
+///
+/// Returns whether the batch was running.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let cancelled = invoke("cancel_batch", { batchId });
+/// ```
+pub fn cancel_batch(batch_id: String, cancellations: State<BatchCancellations>) -> bool {
+    cancellations.cancel(&batch_id)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Release an instance, for good.
+///
+/// `options.confirmation` must be the name of the instance as typed by the
+/// user, or its ID when it has no name. Instances with deletion protection
+/// and subscription instances which haven't expired are refused. With
+/// `snapshot_disks`, a final snapshot of each disk is taken and waited for
+/// before releasing. With `dry_run`, the same checks are made and Aliyun is
+/// asked whether the release would go through, nothing being changed.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::ConfirmationMismatch)`,
+/// `Err(EcsError::DeletionProtected)` or
+/// `Err(EcsError::SubscriptionNotExpired)` when the release is refused,
+/// `Err(EcsError::IncorrectInstanceStatus)` when the instance is running
+/// without `force`, and `Err(EcsError::SnapshotFailed)` when a final
+/// snapshot didn't complete, in which case the instance is kept.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let report = invoke("release_instance", {
+///     regionId,
+///     instanceId,
+///     options: { confirmation: "web-1", snapshot_disks: true, force: false, dry_run: true },
+/// });
+/// ```
+pub async fn release_instance(
+    region_id: String,
+    instance_id: String,
+    options: ReleaseOptions,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<ReleaseReport, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    let snapshot_wait = WaitPolicy {
+        interval: Duration::from_secs(10),
+        timeout: Duration::from_secs(3600),
+    };
+    release::release_instance(&client, &instance_id, &options, &snapshot_wait).await
+}
@@ -0,0 +1,64 @@
+use tauri::{AppHandle, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    services::{
+        auth::AccessKeyAuthService,
+        client::AliyunClientService,
+        demo::DemoMode,
+        endpoint::{EndpointConfig, EndpointConfigError},
+    },
+    STORE_PATH,
+};
+
+#[tauri::command]
+#[specta::specta]
+/// Get the endpoint configuration in use.
+///
+/// The base URL set through the `ALIYUN_ENDPOINT_BASE_URL` environment
+/// variable, if any, takes precedence over the saved one.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let config = invoke("endpoint_config");
+/// ```
+pub fn endpoint_config(client_service: State<AliyunClientService>) -> EndpointConfig {
+    client_service.endpoints()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Save the endpoint configuration and send the following requests
+/// according to it, once the demo mode is off if it is on.
+///
+/// # Errors
+///
+/// Returns `Err(EndpointConfigError)` when an endpoint or a region ID is
+/// not valid, or when the configuration can't be saved.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("set_endpoint_config", { config: { base_url: null, use_vpc: true, overrides: [] } });
+/// ```
+pub fn set_endpoint_config(
+    config: EndpointConfig,
+    app: AppHandle,
+    auth_service: State<AccessKeyAuthService>,
+    client_service: State<AliyunClientService>,
+    demo: State<DemoMode>,
+) -> Result<(), EndpointConfigError> {
+    let store = app
+        .get_store(STORE_PATH)
+        .ok_or_else(|| EndpointConfigError::SaveFailed("the store is not loaded".to_owned()))?;
+    config.save(&store)?;
+
+    if let Some(config) = demo.defer_endpoints(config.with_env_overrides()) {
+        auth_service.set_endpoints(&config);
+        client_service.set_endpoints(config);
+    }
+    Ok(())
+}
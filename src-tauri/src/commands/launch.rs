@@ -0,0 +1,287 @@
+use tauri::State;
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{
+        catalog::{
+            self, ImageInfo, ImageOwner, KeyPairInfo, ResourceKind, SecurityGroupInfo, VSwitchInfo,
+            VpcInfo, ZoneInfo, ZoneResource,
+        },
+        default_region_client, describe_region_infos, ecs_client,
+        launch::{self, ChargeType, LaunchResult, LaunchSpec},
+        vpc_client, EcsCommandError, RegionInfo,
+    },
+};
+
+#[tauri::command]
+#[specta::specta]
+/// List the regions where ECS is available to the account, with their
+/// names.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let regions = invoke("list_regions");
+/// ```
+pub async fn list_regions(
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<RegionInfo>, EcsCommandError> {
+    let client = default_region_client(&client_service)?;
+    describe_region_infos(&client).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the zones of a region.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let zones = invoke("list_zones", { regionId: "cn-hangzhou" });
+/// ```
+pub async fn list_zones(
+    region_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<ZoneInfo>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    catalog::describe_zones(&client).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the instance types, or the disk categories of an instance type,
+/// offered in a zone with the charge type.
+///
+/// Sold out ones are listed too, with `available` being `false`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let categories = invoke("list_available_resources", {
+///     regionId: "cn-hangzhou",
+///     zoneId: "cn-hangzhou-h",
+///     kind: "SystemDisk",
+///     chargeType: { type: "PostPaid" },
+///     instanceType: "ecs.g7.large",
+/// });
+/// ```
+pub async fn list_available_resources(
+    region_id: String,
+    zone_id: String,
+    kind: ResourceKind,
+    charge_type: ChargeType,
+    instance_type: Option<String>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<ZoneResource>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    catalog::describe_available_resources(
+        &client,
+        &zone_id,
+        kind,
+        &charge_type,
+        instance_type.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the available images of an owner in a region, only those the
+/// instance type can boot when one is given.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let images = invoke("list_images", {
+///     regionId: "cn-hangzhou",
+///     owner: "System",
+///     instanceType: "ecs.g7.large",
+/// });
+/// ```
+pub async fn list_images(
+    region_id: String,
+    owner: ImageOwner,
+    instance_type: Option<String>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<ImageInfo>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    catalog::describe_images(&client, owner, instance_type.as_deref()).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the VPCs of a region.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let vpcs = invoke("list_vpcs", { regionId: "cn-hangzhou" });
+/// ```
+pub async fn list_vpcs(
+    region_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<VpcInfo>, EcsCommandError> {
+    let client = vpc_client(&client_service, &region_id)?;
+    catalog::describe_vpcs(&client).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the vSwitches of a VPC, only those of a zone when one is given.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let vswitches = invoke("list_vswitches", {
+///     regionId: "cn-hangzhou",
+///     vpcId: "vpc-1",
+///     zoneId: "cn-hangzhou-h",
+/// });
+/// ```
+pub async fn list_vswitches(
+    region_id: String,
+    vpc_id: String,
+    zone_id: Option<String>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<VSwitchInfo>, EcsCommandError> {
+    let client = vpc_client(&client_service, &region_id)?;
+    catalog::describe_vswitches(&client, Some(&vpc_id), zone_id.as_deref(), None).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the security groups of a VPC.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let groups = invoke("list_security_groups", { regionId: "cn-hangzhou", vpcId: "vpc-1" });
+/// ```
+pub async fn list_security_groups(
+    region_id: String,
+    vpc_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<SecurityGroupInfo>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    catalog::describe_security_groups(&client, Some(&vpc_id), None).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the SSH key pairs of a region.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let keyPairs = invoke("list_key_pairs", { regionId: "cn-hangzhou" });
+/// ```
+pub async fn list_key_pairs(
+    region_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<KeyPairInfo>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    catalog::describe_key_pairs(&client).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Check a launch without creating anything: each choice on its own, the
+/// choices against each other (the vSwitch is in the zone, the security
+/// groups in its VPC, the instance type and system disk are offered there),
+/// then with a dry run of RunInstances.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::InvalidLaunchSpec)` naming the wrong field, or
+/// `Err(EcsError::LaunchRejected)` when the dry run was rejected because of
+/// a field.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// await invoke("validate_launch", { regionId: "cn-hangzhou", spec });
+/// ```
+pub async fn validate_launch(
+    region_id: String,
+    spec: LaunchSpec,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<(), EcsCommandError> {
+    let ecs = ecs_client(&client_service, &region_id)?;
+    let vpc = vpc_client(&client_service, &region_id)?;
+    launch::validate_launch(&ecs, &vpc, &spec).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Create instances, after checking the launch like `validate_launch`.
+///
+/// `client_token` is generated by the frontend once per launch, up to 64
+/// ASCII characters: sending the same launch again with it, e.g. after a
+/// timeout, doesn't create the instances twice.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::InvalidLaunchSpec)` or
+/// `Err(EcsError::LaunchRejected)` naming the field at fault.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let result = invoke("run_instances", {
+///     regionId: "cn-hangzhou",
+///     spec,
+///     clientToken: crypto.randomUUID(),
+/// });
+/// ```
+pub async fn run_instances(
+    region_id: String,
+    spec: LaunchSpec,
+    client_token: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<LaunchResult, EcsCommandError> {
+    let ecs = ecs_client(&client_service, &region_id)?;
+    let vpc = vpc_client(&client_service, &region_id)?;
+    launch::run_instances(&ecs, &vpc, &spec, &client_token).await
+}
@@ -1,6 +1,17 @@
-use tauri_specta::collect_commands;
+use tauri_specta::{collect_commands, collect_events};
+
+use crate::events::{ActiveProfileChanged, AuthStateChanged};
 
 pub mod auth;
+pub mod demo;
+pub mod ecs;
+pub mod endpoint;
+pub mod launch;
+pub mod policy;
+pub mod profile;
+pub mod security_group;
+pub mod store;
+pub mod template;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -10,12 +21,75 @@ fn greet(name: &str) -> String {
 }
 
 pub fn commands_builder() -> tauri_specta::Builder {
-    tauri_specta::Builder::<tauri::Wry>::new().commands(collect_commands![
-        greet,
-        auth::current_access_key_credential,
-        auth::validate_access_key_credentials,
-        auth::fulfill_access_key_credentials,
-        auth::has_aliyun_client,
-        auth::has_valid_aliyun_client
-    ])
+    tauri_specta::Builder::<tauri::Wry>::new()
+        .commands(collect_commands![
+            greet,
+            auth::current_access_key_credential,
+            auth::validate_access_key_credentials,
+            auth::fulfill_access_key_credentials,
+            auth::preview_aliyun_cli_profiles,
+            auth::import_aliyun_cli_profiles,
+            auth::export_aliyun_cli_profiles,
+            auth::has_aliyun_client,
+            auth::has_valid_aliyun_client,
+            auth::credential_store_status,
+            auth::unlock_credential_store,
+            auth::lock_credential_store,
+            auth::active_credential_source,
+            auth::logout,
+            demo::start_demo_mode,
+            demo::stop_demo_mode,
+            demo::is_demo_mode,
+            ecs::list_instances,
+            ecs::start_instance,
+            ecs::stop_instance,
+            ecs::force_stop_instance,
+            ecs::reboot_instance,
+            ecs::run_power_batch,
+            ecs::cancel_batch,
+            ecs::release_instance,
+            endpoint::endpoint_config,
+            endpoint::set_endpoint_config,
+            launch::list_regions,
+            launch::list_zones,
+            launch::list_available_resources,
+            launch::list_images,
+            launch::list_vpcs,
+            launch::list_vswitches,
+            launch::list_security_groups,
+            launch::list_key_pairs,
+            launch::validate_launch,
+            launch::run_instances,
+            policy::feature_ram_policy,
+            policy::attempted_ram_policy,
+            profile::list_credential_profiles,
+            profile::add_credential_profile,
+            profile::add_role_profile,
+            profile::rename_credential_profile,
+            profile::delete_credential_profile,
+            profile::set_active_credential_profile,
+            profile::set_profile_default_region,
+            profile::access_key_age,
+            profile::rotate_access_key,
+            profile::delete_retired_access_key,
+            security_group::list_security_group_rules,
+            security_group::preview_security_group_changes,
+            security_group::apply_security_group_changes,
+            security_group::preview_security_group_rollback,
+            security_group::rollback_security_group,
+            security_group::public_ip,
+            security_group::allow_current_ip,
+            security_group::revoke_expired_access,
+            security_group::audit_security_groups,
+            security_group::export_security_audit,
+            security_group::simulate_reachability,
+            store::store_migration_status,
+            template::list_launch_templates,
+            template::list_launch_template_versions,
+            template::diff_launch_template_versions,
+            template::create_launch_template_version_from_instance,
+            template::set_default_launch_template_version,
+            template::run_instances_from_template
+        ])
+        .events(collect_events![ActiveProfileChanged, AuthStateChanged])
 }
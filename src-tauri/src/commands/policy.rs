@@ -0,0 +1,45 @@
+use tauri::State;
+
+use crate::services::{
+    client::AliyunClientService,
+    policy::{AppFeature, RamPolicy},
+};
+
+#[tauri::command]
+#[specta::specta]
+/// Generate the minimal RAM policy allowing the given features of the app,
+/// all of them when the list is empty.
+///
+/// The policy is returned as the JSON document pasted in the RAM console.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let policy = invoke("feature_ram_policy", { features: ["AccessKeyRotation"] });
+/// ```
+pub fn feature_ram_policy(features: Vec<AppFeature>) -> String {
+    let features = if features.is_empty() {
+        AppFeature::ALL
+    } else {
+        &features
+    };
+    RamPolicy::for_features(features).to_json()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Generate the minimal RAM policy allowing every ECS call attempted with
+/// the current credentials, including the denied ones.
+///
+/// The policy has no statement when nothing has been attempted yet.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let policy = invoke("attempted_ram_policy");
+/// ```
+pub fn attempted_ram_policy(client_service: State<AliyunClientService>) -> String {
+    RamPolicy::for_permissions(client_service.attempted_permissions()).to_json()
+}
@@ -0,0 +1,350 @@
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{
+    commands::auth::CallerIdentity,
+    events::ActiveProfileChanged,
+    services::{
+        auth::{
+            chain::{CredentialProviderChain, CredentialSource},
+            error::AKFulfillError,
+            profile::{ProfileError, ProfileSummary},
+            rotation::{AccessKeyAge, AccessKeyRotation, RotationError},
+            sts::AssumedRole,
+            types::{AccessKeyCredentials, AssumeRoleCredentials},
+            AccessKeyAuthService,
+        },
+        client::AliyunClientService,
+    },
+};
+
+/// Rebuilds the client from the active profile and lets the frontend know about it.
+///
+/// The client is resolved through the credential provider chain, so credentials found
+/// in the environment or the `aliyun` CLI config still come first.
+///
+/// The change is emitted even when the client can't be built, e.g. the role of the
+/// profile can't be assumed, in which case the error is returned afterwards.
+pub(crate) async fn switch_active_client(
+    app: &AppHandle,
+    auth_service: &AccessKeyAuthService,
+    client_service: &AliyunClientService,
+) -> Result<(), ProfileError> {
+    let name = auth_service.active_profile()?;
+    // Through the chain, as at startup and unlock, so that the same credentials win.
+    let chain = app.state::<CredentialProviderChain>();
+    let resolved = client_service.resolve(&chain, auth_service).await;
+    let has_client = matches!(resolved, Ok(Some(_)));
+
+    let event = ActiveProfileChanged {
+        name,
+        has_client,
+        source: client_service.source(),
+    };
+    if let Err(err) = event.emit(app) {
+        log::warn!("Failed to emit the active profile change: {err}");
+    }
+    resolved?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the saved credential profiles, without their secrets.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError)` when the profiles can't be read, e.g. the
+/// credential store is locked.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let profiles = invoke("list_credential_profiles");
+/// ```
+pub fn list_credential_profiles(
+    auth_service: State<AccessKeyAuthService>,
+) -> Result<Vec<ProfileSummary>, ProfileError> {
+    auth_service.list_profiles()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Validate the provided access key credentials and save them as a new profile.
+///
+/// The first profile added becomes the active one, in which case the Aliyun
+/// client is rebuilt and `ActiveProfileChanged` is emitted.
+///
+/// # Errors
+///
+/// Returns `Err(AKFulfillError)` when the credentials are invalid, when
+/// there are failures communicating with the Aliyun service, or when the
+/// profile name is invalid or already taken.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let identity = invoke("add_credential_profile", { name, credentials });
+/// ```
+pub async fn add_credential_profile(
+    name: String,
+    credentials: AccessKeyCredentials,
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<CallerIdentity, AKFulfillError> {
+    let result = auth_service.add_profile(&name, credentials).await?;
+
+    // Checked once saved, a profile switch may have happened during the validation.
+    if auth_service.active_profile()?.as_deref() == Some(name.as_str()) {
+        switch_active_client(&app, &auth_service, &client_service).await?;
+    }
+
+    Ok(result.into())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Save a new profile assuming a RAM role with a source access key.
+///
+/// The role is assumed once to validate the configuration. As with other
+/// profiles, the first profile added becomes the active one, in which case
+/// the temporary credentials are kept refreshed in the background.
+///
+/// # Errors
+///
+/// Returns `Err(AKFulfillError)` when the source access key is invalid,
+/// when the role can't be assumed, or when the profile name is invalid or
+/// already taken.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let assumedRole = invoke("add_role_profile", { name, role });
+/// ```
+pub async fn add_role_profile(
+    name: String,
+    role: AssumeRoleCredentials,
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<AssumedRole, AKFulfillError> {
+    let result = auth_service.add_role_profile(&name, role).await?;
+
+    if auth_service.active_profile()?.as_deref() == Some(name.as_str()) {
+        switch_active_client(&app, &auth_service, &client_service).await?;
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Rename a credential profile, keeping it active if it was.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError)` when the profile doesn't exist, or the new
+/// name is invalid or already taken.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("rename_credential_profile", { from, to });
+/// ```
+pub fn rename_credential_profile(
+    from: String,
+    to: String,
+    app: AppHandle,
+    auth_service: State<AccessKeyAuthService>,
+    client_service: State<AliyunClientService>,
+) -> Result<(), ProfileError> {
+    auth_service.rename_profile(&from, &to)?;
+
+    // The client stays as is, only the name of the active profile changes.
+    let name = auth_service.active_profile()?;
+    if name.as_deref() == Some(to.trim()) {
+        let event = ActiveProfileChanged {
+            name,
+            has_client: client_service.is_initialized(),
+            source: client_service.source(),
+        };
+        if let Err(err) = event.emit(&app) {
+            log::warn!("Failed to emit the active profile change: {err}");
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Delete a credential profile.
+///
+/// Deleting the active profile leaves no profile active and clears the
+/// Aliyun client.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError)` when the profile doesn't exist or the
+/// profiles can't be saved.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("delete_credential_profile", { name });
+/// ```
+pub async fn delete_credential_profile(
+    name: String,
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<(), ProfileError> {
+    if auth_service.delete_profile(&name)? {
+        switch_active_client(&app, &auth_service, &client_service).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Make the given profile the active one and rebuild the Aliyun client from it.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError::NotFound)` when the profile doesn't exist, and
+/// `Err(ProfileError::ClientError)` when the role of the profile can't be
+/// assumed.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("set_active_credential_profile", { name });
+/// ```
+pub async fn set_active_credential_profile(
+    name: String,
+    app: AppHandle,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<(), ProfileError> {
+    auth_service.set_active_profile(&name)?;
+    switch_active_client(&app, &auth_service, &client_service).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set the region a profile works in when a call doesn't pick one, or forget
+/// it with `null`, falling back to `cn-hangzhou`.
+///
+/// The default region of the Aliyun client follows when its credentials come
+/// from this profile.
+///
+/// # Errors
+///
+/// Returns `Err(ProfileError::InvalidRegion)` when the region ID is not
+/// valid, and `Err(ProfileError::NotFound)` when the profile doesn't exist.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// invoke("set_profile_default_region", { name, regionId: "cn-shanghai" });
+/// ```
+pub fn set_profile_default_region(
+    name: String,
+    region_id: Option<String>,
+    auth_service: State<AccessKeyAuthService>,
+    client_service: State<AliyunClientService>,
+) -> Result<(), ProfileError> {
+    auth_service.set_default_region(&name, region_id.as_deref())?;
+    if client_service.source() == Some(CredentialSource::StoredProfile { name: name.clone() }) {
+        client_service.set_default_region(auth_service.default_region(&name)?);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Tell how old the access key of a profile is, when it was last used, and
+/// whether it is due for rotation. For role profiles, it is the source access
+/// key.
+///
+/// # Errors
+///
+/// Returns `Err(AKFulfillError)` when the profile doesn't exist, or when RAM
+/// can't list the access keys of the user.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let age = invoke("access_key_age", { name });
+/// ```
+pub async fn access_key_age(
+    name: String,
+    auth_service: State<'_, AccessKeyAuthService>,
+) -> Result<AccessKeyAge, AKFulfillError> {
+    auth_service.access_key_age(&name).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Rotate the access key of a profile: create a new access key through RAM,
+/// validate it, switch the profile and the Aliyun client to it, then
+/// deactivate the old access key.
+///
+/// The old access key is kept deactivated until `delete_retired_access_key`,
+/// so that it can be turned back on from the console if something still
+/// needs it.
+///
+/// # Errors
+///
+/// Returns `Err(RotationError::StepFailed)` naming the step which failed,
+/// after the steps already taken have been undone, and
+/// `Err(RotationError::NotRotatable)` while the access key retired by the
+/// previous rotation hasn't been deleted, RAM allowing only two access keys
+/// per user.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let rotation = invoke("rotate_access_key", { name });
+/// ```
+pub async fn rotate_access_key(
+    name: String,
+    auth_service: State<'_, AccessKeyAuthService>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<AccessKeyRotation, RotationError> {
+    auth_service.rotate_access_key(&name, &client_service).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Delete the access key deactivated by the last rotation of a profile.
+///
+/// Returns the ID of the deleted access key, `None` when there was nothing
+/// to delete.
+///
+/// # Errors
+///
+/// Returns `Err(RotationError::StepFailed)` when the profile doesn't exist or
+/// RAM refuses to delete the access key.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let deleted = invoke("delete_retired_access_key", { name });
+/// ```
+pub async fn delete_retired_access_key(
+    name: String,
+    auth_service: State<'_, AccessKeyAuthService>,
+) -> Result<Option<String>, RotationError> {
+    auth_service.delete_retired_access_key(&name).await
+}
@@ -0,0 +1,400 @@
+use chrono::Utc;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    services::{
+        client::AliyunClientService,
+        ecs::{
+            audit::{self, AuditOptions, AuditReport, ExportFormat},
+            ecs_client,
+            reachability::{self, ReachabilityReport, TrafficQuery},
+            security_group::{self, RuleEdit, RuleSetDiff, SecurityGroupRules},
+            temporary_access::{self, AccessGrant, PublicIpProvider, TemporaryAccess},
+            EcsCommandError, EcsError,
+        },
+        error::AliyunRequestCommandError,
+    },
+    types::Store,
+    STORE_PATH,
+};
+
+/// The store, failing with the error `failure` builds from the reason.
+fn store<F: FnOnce(String) -> EcsError>(
+    app: &AppHandle,
+    failure: F,
+) -> Result<Store<tauri::Wry>, EcsCommandError> {
+    app.get_store(STORE_PATH).ok_or_else(|| {
+        AliyunRequestCommandError::Specific(failure("the store is not loaded".to_owned()))
+    })
+}
+
+fn backup_failure(message: String) -> EcsError {
+    EcsError::RuleBackupFailed { message }
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get a security group with its ingress and egress rules.
+///
+/// The groups of a VPC are listed by `list_security_groups`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::SecurityGroupNotFound)` when the group doesn't
+/// exist in the region.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let group = invoke("list_security_group_rules", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+/// });
+/// ```
+pub async fn list_security_group_rules(
+    region_id: String,
+    security_group_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<SecurityGroupRules, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    security_group::describe_security_group_rules(&client, &security_group_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Check changes to the rules of a security group and tell which rules they
+/// add, modify and revoke, without changing anything.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::InvalidRule)` when a change is wrong, and
+/// `Err(EcsError::RuleNotFound)` when it names a rule the group doesn't
+/// have.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let preview = invoke("preview_security_group_changes", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+///     edits: [{ type: "Revoke", security_group_rule_id: "sgr-1" }],
+/// });
+/// ```
+pub async fn preview_security_group_changes(
+    region_id: String,
+    security_group_id: String,
+    edits: Vec<RuleEdit>,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    security_group::preview_rule_edits(&client, &security_group_id, &edits).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Apply changes previewed with `preview_security_group_changes`, after
+/// keeping the current rules of the group so that they can be rolled back.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::RulesChanged)` when the changes no longer do what
+/// `preview` says, e.g. the group has been edited since, and
+/// `Err(EcsError::RuleBackupFailed)` when the rules can't be kept, in which
+/// case nothing is changed.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let applied = invoke("apply_security_group_changes", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+///     edits,
+///     preview,
+/// });
+/// ```
+pub async fn apply_security_group_changes(
+    region_id: String,
+    security_group_id: String,
+    edits: Vec<RuleEdit>,
+    preview: RuleSetDiff,
+    app: AppHandle,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    let store = store(&app, backup_failure)?;
+    security_group::apply_rule_edits(&client, &store, &security_group_id, &edits, &preview).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Tell which rules rolling a security group back to how it was before the
+/// last change applied from the app would add and revoke, without changing
+/// anything.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoRuleBackup)` when no change of the group can be
+/// rolled back.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let preview = invoke("preview_security_group_rollback", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+/// });
+/// ```
+pub async fn preview_security_group_rollback(
+    region_id: String,
+    security_group_id: String,
+    app: AppHandle,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    let store = store(&app, backup_failure)?;
+    security_group::preview_rollback(&client, &store, &security_group_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Roll a security group back to how it was before the last change applied
+/// from the app, as previewed with `preview_security_group_rollback`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoRuleBackup)` when no change of the group can be
+/// rolled back, and `Err(EcsError::RulesChanged)` when the rollback no
+/// longer does what `preview` says.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let rolledBack = invoke("rollback_security_group", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+///     preview,
+/// });
+/// ```
+pub async fn rollback_security_group(
+    region_id: String,
+    security_group_id: String,
+    preview: RuleSetDiff,
+    app: AppHandle,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    let store = store(&app, backup_failure)?;
+    security_group::rollback_rules(&client, &store, &security_group_id, &preview).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Find out the public IP of this machine, asked to the public IP URL of the
+/// endpoint configuration.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::PublicIpUnknown)` when the service doesn't answer
+/// with an IP.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let ip = invoke("public_ip");
+/// ```
+pub async fn public_ip(
+    client_service: State<'_, AliyunClientService>,
+) -> Result<String, EcsCommandError> {
+    let provider = PublicIpProvider::new(client_service.endpoints().public_ip_url());
+    let ip = provider
+        .public_ip()
+        .await
+        .map_err(AliyunRequestCommandError::Specific)?;
+    Ok(ip.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Let the public IP of this machine reach a TCP port, e.g. 22 for SSH or
+/// 3389 for RDP, of the instances of a security group for `minutes`.
+///
+/// The rule is revoked in the background once expired, even if the app is
+/// restarted meanwhile. Allowing the same IP and port again extends the
+/// access.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::PublicIpUnknown)` when the IP can't be found out,
+/// and `Err(EcsError::InvalidRule)` when the port or the duration is out of
+/// range, or when the IP can already reach the port for good.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let access = invoke("allow_current_ip", {
+///     regionId: "cn-hangzhou",
+///     securityGroupId: "sg-1",
+///     port: 22,
+///     minutes: 120,
+/// });
+/// ```
+pub async fn allow_current_ip(
+    region_id: String,
+    security_group_id: String,
+    port: u16,
+    minutes: u32,
+    app: AppHandle,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<TemporaryAccess, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    let owner = client_service
+        .source()
+        .ok_or(AliyunRequestCommandError::Specific(EcsError::NoClient))?;
+    let store = store(&app, |message| EcsError::TemporaryAccessNotRecorded {
+        message,
+    })?;
+    let provider = PublicIpProvider::new(client_service.endpoints().public_ip_url());
+    let ip = provider
+        .public_ip()
+        .await
+        .map_err(AliyunRequestCommandError::Specific)?;
+    let grant = AccessGrant { ip, port, minutes };
+    temporary_access::allow_ip(
+        &client,
+        &owner,
+        &store,
+        &security_group_id,
+        &grant,
+        Utc::now(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Revoke the expired temporary access rules right away, rather than at the
+/// next background sweep, and return them.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let revoked = invoke("revoke_expired_access");
+/// ```
+pub async fn revoke_expired_access(
+    app: AppHandle,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<TemporaryAccess>, EcsCommandError> {
+    let store = store(&app, |message| EcsError::TemporaryAccessNotRecorded {
+        message,
+    })?;
+    Ok(temporary_access::revoke_expired_access(&client_service, &store, Utc::now()).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Audit the security groups of every region, or of the regions of the
+/// options, for sensitive ports open to the internet, overly broad port
+/// ranges and groups no instance uses.
+///
+/// A region failing doesn't fail the others, it is reported in the failures.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let report = invoke("audit_security_groups", {
+///     options: { extra_sensitive_ports: [5432], max_port_range: null, region_ids: null },
+/// });
+/// ```
+pub async fn audit_security_groups(
+    options: AuditOptions,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<AuditReport, EcsCommandError> {
+    audit::audit_security_groups(&client_service, options, Utc::now()).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Write an audit report as JSON or CSV to a new file of the download
+/// directory, returning the path of the file.
+///
+/// The file is named after the time of the audit, e.g.
+/// `security-audit-20261018T100000Z.csv`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::ExportFailed)` when the file can't be written, or
+/// already exists.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let path = invoke("export_security_audit", { report, format: "Csv" });
+/// ```
+pub fn export_security_audit(
+    report: AuditReport,
+    format: ExportFormat,
+    app: AppHandle,
+) -> Result<String, EcsCommandError> {
+    let dir = app.path().download_dir().map_err(|err| {
+        AliyunRequestCommandError::Specific(EcsError::ExportFailed {
+            path: String::new(),
+            message: err.to_string(),
+        })
+    })?;
+    let path = report
+        .export_to(&dir, format)
+        .map_err(AliyunRequestCommandError::Specific)?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Tell whether traffic from a CIDR block, an instance or a security group
+/// enters each network interface of an instance, and which rule or policy
+/// decided it, by evaluating the security groups locally.
+///
+/// Nothing is changed, only the instances and security groups are described.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::InvalidTrafficQuery)` when the protocol, port or
+/// CIDR block is wrong, or `Err(EcsError::InstanceNotFound)` for an unknown
+/// destination or source instance.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let report = invoke("simulate_reachability", {
+///     regionId: "cn-hangzhou",
+///     query: {
+///         source: { type: "Cidr", cidr: "203.0.113.7" },
+///         instance_id: "i-1",
+///         ip_protocol: "TCP",
+///         port: 22,
+///     },
+/// });
+/// ```
+pub async fn simulate_reachability(
+    region_id: String,
+    query: TrafficQuery,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<ReachabilityReport, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    reachability::simulate_reachability(&client, &query).await
+}
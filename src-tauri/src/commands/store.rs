@@ -0,0 +1,29 @@
+use tauri::State;
+
+use crate::services::migration::{MigrationReport, StoreMigrationError, StoreMigrationStatus};
+
+#[tauri::command]
+#[specta::specta]
+/// Tell how the schema of the store has been migrated at startup.
+///
+/// It returns `Ok(MigrationReport)` listing the migrations which ran, if
+/// any, and where the store was backed up before running them.
+///
+/// # Errors
+///
+/// Returns `Err(StoreMigrationError)` when the store couldn't be migrated,
+/// in which case it has been left untouched, e.g. it was written by a newer
+/// version of the app. The services using the store are then not started,
+/// so every other command fails.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let report = invoke("store_migration_status");
+/// ```
+pub fn store_migration_status(
+    status: State<StoreMigrationStatus>,
+) -> Result<MigrationReport, StoreMigrationError> {
+    status.0.clone()
+}
@@ -0,0 +1,195 @@
+use tauri::State;
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{
+        ecs_client,
+        launch::LaunchResult,
+        template::{
+            self, LaunchTemplateSummary, LaunchTemplateVersion, TemplateLaunch, VersionDiff,
+        },
+        EcsCommandError,
+    },
+};
+
+#[tauri::command]
+#[specta::specta]
+/// List the launch templates of a region.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::NoClient)` without credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let templates = invoke("list_launch_templates", { regionId: "cn-hangzhou" });
+/// ```
+pub async fn list_launch_templates(
+    region_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<LaunchTemplateSummary>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::describe_launch_templates(&client).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// List the versions of a launch template with their settings, sorted by
+/// number.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::TemplateNotFound)` when the template doesn't
+/// exist in the region.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let versions = invoke("list_launch_template_versions", {
+///     regionId: "cn-hangzhou",
+///     launchTemplateId: "lt-1",
+/// });
+/// ```
+pub async fn list_launch_template_versions(
+    region_id: String,
+    launch_template_id: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<Vec<LaunchTemplateVersion>, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::describe_template_versions(&client, &launch_template_id, &[]).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Show how version `to` of a launch template differs from version `from`,
+/// setting by setting.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::TemplateNotFound)` or
+/// `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let diff = invoke("diff_launch_template_versions", {
+///     regionId: "cn-hangzhou",
+///     launchTemplateId: "lt-1",
+///     from: 1,
+///     to: 2,
+/// });
+/// ```
+pub async fn diff_launch_template_versions(
+    region_id: String,
+    launch_template_id: String,
+    from: u32,
+    to: u32,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<VersionDiff, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::diff_template_versions(&client, &launch_template_id, from, to).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Add a version to a launch template with the configuration of an
+/// instance: its image, type, network, key pair, disks, billing and tags,
+/// but not its name. Returns the number of the new version.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::InstanceNotFound)` or
+/// `Err(EcsError::TemplateNotFound)` when either doesn't exist.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let versionNumber = invoke("create_launch_template_version_from_instance", {
+///     regionId: "cn-hangzhou",
+///     launchTemplateId: "lt-1",
+///     instanceId: "i-1",
+///     description: "web after the upgrade",
+/// });
+/// ```
+pub async fn create_launch_template_version_from_instance(
+    region_id: String,
+    launch_template_id: String,
+    instance_id: String,
+    description: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<u32, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::create_version_from_instance(&client, &launch_template_id, &instance_id, &description)
+        .await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Make a version the default one of its launch template, the one launched
+/// when none is named.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::TemplateNotFound)` or
+/// `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// await invoke("set_default_launch_template_version", {
+///     regionId: "cn-hangzhou",
+///     launchTemplateId: "lt-1",
+///     versionNumber: 3,
+/// });
+/// ```
+pub async fn set_default_launch_template_version(
+    region_id: String,
+    launch_template_id: String,
+    version_number: u32,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<(), EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::set_default_version(&client, &launch_template_id, version_number).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Create instances from a launch template, its default version unless
+/// `launch.version_number` names one, the settings given in `launch`
+/// overriding those of the template.
+///
+/// The launch is checked with a dry run first. `client_token` is generated
+/// by the frontend once per launch, like for `run_instances`.
+///
+/// # Errors
+///
+/// Returns `Err(EcsError::TemplateNotFound)` or
+/// `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist, and
+/// `Err(EcsError::InvalidLaunchSpec)` or `Err(EcsError::LaunchRejected)`
+/// naming the setting at fault.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // from a Tauri frontend
+/// let result = invoke("run_instances_from_template", {
+///     regionId: "cn-hangzhou",
+///     launch: { launch_template_id: "lt-1", version_number: null, amount: 2, ... },
+///     clientToken: crypto.randomUUID(),
+/// });
+/// ```
+pub async fn run_instances_from_template(
+    region_id: String,
+    launch: TemplateLaunch,
+    client_token: String,
+    client_service: State<'_, AliyunClientService>,
+) -> Result<LaunchResult, EcsCommandError> {
+    let client = ecs_client(&client_service, &region_id)?;
+    template::launch_from_template(&client, &launch, &client_token).await
+}
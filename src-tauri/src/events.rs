@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::auth::{chain::CredentialSource, profile::LogoutOutcome};
+
+/// Emitted whenever the active credential profile changes, including when it is
+/// renamed or deleted, so the frontend can refresh everything bound to the account.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type, tauri_specta::Event)]
+pub struct ActiveProfileChanged {
+    /// The newly active profile, `None` when no profile is active anymore.
+    pub name: Option<String>,
+    /// Whether the Aliyun client has been rebuilt from the active profile.
+    pub has_client: bool,
+    /// Where the credentials of the client come from, which is not the active profile
+    /// when the credential provider chain found credentials before it.
+    pub source: Option<CredentialSource>,
+}
+
+/// Emitted after logging out, so the frontend can drop everything tied to the
+/// credentials which have been forgotten.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type, tauri_specta::Event)]
+pub struct AuthStateChanged {
+    /// What has been removed from the credential store.
+    pub outcome: LogoutOutcome,
+    /// The profile still active, `None` when the active profile has been removed.
+    pub active_profile: Option<String>,
+    /// Whether an Aliyun client is still available.
+    pub has_client: bool,
+    /// Where the credentials of the remaining client come from.
+    pub source: Option<CredentialSource>,
+}
@@ -1,18 +1,36 @@
+use std::time::Duration;
+
+use chrono::Utc;
 use specta_typescript::Typescript;
-use tauri::Manager;
-use tauri_plugin_store::StoreBuilder;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::{resolve_store_path, StoreBuilder, StoreExt};
+use tauri_specta::Event;
 
 use crate::{
     commands::commands_builder,
-    services::{auth::AccessKeyAuthService, client::AliyunClientService},
+    events::ActiveProfileChanged,
+    services::{
+        auth::{chain::CredentialProviderChain, AccessKeyAuthService},
+        client::AliyunClientService,
+        demo::DemoMode,
+        ecs::{batch::BatchCancellations, temporary_access},
+        endpoint::EndpointConfig,
+        migration::{migrate_store, StoreMigrationStatus, MIGRATIONS},
+    },
 };
 
 pub mod commands;
+pub mod events;
 pub mod init;
 pub mod services;
 pub mod test_utils;
 pub mod types;
 
+pub(crate) const STORE_PATH: &str = "store.json";
+
+/// How often the expired temporary access rules are looked for.
+const TEMPORARY_ACCESS_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let commands_builder = commands_builder();
@@ -39,23 +57,74 @@ pub fn run() {
         .invoke_handler(commands_builder.invoke_handler())
         .setup(move |app| {
             commands_builder.mount_events(app);
-            let builder = StoreBuilder::new(app, "store.json");
+            let builder = StoreBuilder::new(app, STORE_PATH);
             let store = builder.build().expect("Store plugin build failed");
-            let client_service = AliyunClientService::new();
-            let auth_service = AccessKeyAuthService::new(store);
-            
-            if let Some(client) = auth_service.new_client() {
-                log::info!("Successfully initialized Aliyun client from saved credentials");
-                client_service.initialize(client);
-            } else {
-                log::info!("No valid credentials found, Aliyun client not initialized")
+            let store_path = resolve_store_path(app.handle(), STORE_PATH)?;
+            let migration = migrate_store(&store, &store_path, MIGRATIONS);
+            if let Err(err) = &migration {
+                // None of the services writing to the store is managed, so the store is
+                // left as it is and the frontend gets the error from
+                // `store_migration_status`, the other commands failing.
+                log::error!("Failed to migrate the store: {err}");
+                app.manage(StoreMigrationStatus(migration));
+                return Result::Ok(());
             }
+            let endpoints = EndpointConfig::load(&store).with_env_overrides();
+            let client_service = AliyunClientService::new();
+            client_service.set_endpoints(endpoints.clone());
+            // The credential store starts locked, so stored profiles are only used once
+            // the user unlocks it with the master passphrase.
+            let auth_service =
+                AccessKeyAuthService::new_encrypted(store).with_endpoints(&endpoints);
+            let chain = CredentialProviderChain::with_defaults(app.path().home_dir().ok())
+                .with_metadata_endpoint(endpoints.metadata_url());
 
             app.manage(auth_service);
             app.manage(client_service);
+            app.manage(chain);
+            app.manage(DemoMode::default());
+            app.manage(BatchCancellations::default());
+            app.manage(StoreMigrationStatus(migration));
+
+            tauri::async_runtime::spawn(resolve_startup_credentials(app.handle().clone()));
+            tauri::async_runtime::spawn(sweep_temporary_access(app.handle().clone()));
 
             Result::Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Initializes the client from the credentials found by the provider chain at startup.
+async fn resolve_startup_credentials(app: AppHandle) {
+    let auth_service = app.state::<AccessKeyAuthService>();
+    let client_service = app.state::<AliyunClientService>();
+    let chain = app.state::<CredentialProviderChain>();
+
+    if let Err(err) = client_service.resolve(&chain, &auth_service).await {
+        log::warn!("Failed to build the client from the credential provider chain: {err}");
+    }
+
+    let event = ActiveProfileChanged {
+        name: auth_service.active_profile().ok().flatten(),
+        has_client: client_service.is_initialized(),
+        source: client_service.source(),
+    };
+    if let Err(err) = event.emit(&app) {
+        log::warn!("Failed to emit the active profile change: {err}");
+    }
+}
+
+/// Revokes the expired temporary access rules for as long as the app runs, those left
+/// behind by a previous run included.
+async fn sweep_temporary_access(app: AppHandle) {
+    let mut interval = tokio::time::interval(TEMPORARY_ACCESS_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(store) = app.get_store(STORE_PATH) else {
+            continue;
+        };
+        let client_service = app.state::<AliyunClientService>();
+        temporary_access::revoke_expired_access(&client_service, &store, Utc::now()).await;
+    }
+}
@@ -0,0 +1,354 @@
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::auth::{
+    cli_config::{CliConfig, CLI_CONFIG_RELATIVE_PATH},
+    error::AKFulfillError,
+    metadata::InstanceMetadataProvider,
+    sts::{AssumeRoleProvider, SessionCredentials},
+    types::{AccessKeyCredentials, AssumeRoleCredentials, ProfileCredentials},
+    AccessKeyAuthService,
+};
+
+pub const ACCESS_KEY_ID_ENV: &str = "ALIBABA_CLOUD_ACCESS_KEY_ID";
+pub const ACCESS_KEY_SECRET_ENV: &str = "ALIBABA_CLOUD_ACCESS_KEY_SECRET";
+
+/// Which provider of the chain supplied the credentials of the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type", content = "detail")]
+pub enum CredentialSource {
+    /// The `ALIBABA_CLOUD_ACCESS_KEY_ID` and `ALIBABA_CLOUD_ACCESS_KEY_SECRET` variables.
+    Environment,
+    /// The current profile of the `aliyun` CLI config.
+    CliConfig { profile: String },
+    /// A profile saved in the credential store of the app.
+    StoredProfile { name: String },
+    /// The RAM role attached to the ECS instance the app runs on.
+    InstanceMetadata { role_name: String },
+    /// The demo account, served by a fake Aliyun running within the app.
+    Demo,
+}
+
+/// Issues the temporary credentials of a session, again whenever they need a refresh.
+#[derive(Clone)]
+pub enum SessionIssuer {
+    AssumeRole {
+        provider: AssumeRoleProvider,
+        role: AssumeRoleCredentials,
+    },
+    InstanceMetadata {
+        provider: InstanceMetadataProvider,
+        role_name: String,
+    },
+}
+
+impl SessionIssuer {
+    pub async fn issue(&self) -> Result<SessionCredentials, AKFulfillError> {
+        match self {
+            SessionIssuer::AssumeRole { provider, role } => provider
+                .assume_role(role)
+                .await
+                .map(|(credentials, _)| credentials),
+            SessionIssuer::InstanceMetadata {
+                provider,
+                role_name,
+            } => provider.credentials(role_name).await,
+        }
+    }
+}
+
+impl fmt::Display for SessionIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionIssuer::AssumeRole { role, .. } => write!(f, "role {}", role.role_arn),
+            SessionIssuer::InstanceMetadata { role_name, .. } => {
+                write!(f, "instance RAM role {role_name}")
+            }
+        }
+    }
+}
+
+/// What a provider of the chain supplies.
+pub enum ResolvedCredentials {
+    /// Long-term credentials, or a role still to be assumed.
+    Profile(ProfileCredentials),
+    /// Temporary credentials which have already been issued.
+    Session(SessionIssuer, SessionCredentials),
+}
+
+#[derive(Clone)]
+pub enum CredentialProvider {
+    Environment,
+    /// The `aliyun` CLI config at the given path.
+    CliConfig(PathBuf),
+    StoredProfile,
+    InstanceMetadata(InstanceMetadataProvider),
+}
+
+impl CredentialProvider {
+    /// Returns the credentials of the provider, `None` if it has none to offer.
+    ///
+    /// Errors are only logged, so that the chain carries on with the next provider.
+    async fn provide(
+        &self,
+        auth_service: &AccessKeyAuthService,
+    ) -> Option<(CredentialSource, ResolvedCredentials)> {
+        match self {
+            CredentialProvider::Environment => {
+                let credentials = access_key_from_vars(|key| std::env::var(key).ok())?;
+                Some((CredentialSource::Environment, credentials.into()))
+            }
+            CredentialProvider::CliConfig(path) => {
+                let config = CliConfig::load(path)
+                    .inspect_err(|err| log::warn!("Skipped the aliyun CLI config: {err}"))
+                    .ok()?;
+                let profile = config.profile(&config.current)?;
+                let credentials = profile
+                    .credentials()
+                    .inspect_err(|reason| {
+                        log::info!(
+                            "Skipped the aliyun CLI profile {:?}: {reason}",
+                            profile.name
+                        )
+                    })
+                    .ok()?;
+                let source = CredentialSource::CliConfig {
+                    profile: profile.name.clone(),
+                };
+                Some((source, credentials.into()))
+            }
+            CredentialProvider::StoredProfile => {
+                let (name, credentials) = auth_service.current_profile().ok()?;
+                Some((CredentialSource::StoredProfile { name }, credentials.into()))
+            }
+            CredentialProvider::InstanceMetadata(provider) => {
+                // A locked credential store holds the profiles the user is about to
+                // unlock, the instance is only probed once they have been looked at.
+                if auth_service
+                    .vault_status()
+                    .is_ok_and(|status| status.initialized && !status.unlocked)
+                {
+                    return None;
+                }
+                let issued = async {
+                    let role_name = provider.role_name().await?;
+                    let credentials = provider.credentials(&role_name).await?;
+                    Ok::<_, AKFulfillError>((role_name, credentials))
+                };
+                let (role_name, credentials) = issued
+                    .await
+                    .inspect_err(|err| log::debug!("Skipped the instance metadata: {err}"))
+                    .ok()?;
+
+                let issuer = SessionIssuer::InstanceMetadata {
+                    provider: provider.clone(),
+                    role_name: role_name.clone(),
+                };
+                Some((
+                    CredentialSource::InstanceMetadata { role_name },
+                    ResolvedCredentials::Session(issuer, credentials),
+                ))
+            }
+        }
+    }
+}
+
+impl From<ProfileCredentials> for ResolvedCredentials {
+    fn from(credentials: ProfileCredentials) -> Self {
+        ResolvedCredentials::Profile(credentials)
+    }
+}
+
+impl From<AccessKeyCredentials> for ResolvedCredentials {
+    fn from(credentials: AccessKeyCredentials) -> Self {
+        ResolvedCredentials::Profile(credentials.into())
+    }
+}
+
+/// Reads an access key from the variables, both of them being required.
+fn access_key_from_vars<F>(var: F) -> Option<AccessKeyCredentials>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let access_key_id = var(ACCESS_KEY_ID_ENV).filter(|value| !value.is_empty())?;
+    let access_key_secret = var(ACCESS_KEY_SECRET_ENV).filter(|value| !value.is_empty())?;
+    Some(AccessKeyCredentials::new(access_key_id, access_key_secret))
+}
+
+/// Looks for credentials in several places, the first provider having some wins.
+pub struct CredentialProviderChain {
+    providers: Vec<CredentialProvider>,
+}
+
+impl CredentialProviderChain {
+    pub fn new(providers: Vec<CredentialProvider>) -> Self {
+        Self { providers }
+    }
+
+    /// The environment variables, then the `aliyun` CLI config, then the stored profile,
+    /// and the metadata of the ECS instance last.
+    pub fn with_defaults(home_dir: Option<PathBuf>) -> Self {
+        let mut providers = vec![CredentialProvider::Environment];
+        if let Some(home_dir) = home_dir {
+            providers.push(CredentialProvider::CliConfig(
+                home_dir.join(CLI_CONFIG_RELATIVE_PATH),
+            ));
+        }
+        providers.push(CredentialProvider::StoredProfile);
+        providers.push(CredentialProvider::InstanceMetadata(
+            InstanceMetadataProvider::default(),
+        ));
+        Self::new(providers)
+    }
+
+    /// Replaces the metadata service queried by the chain, e.g. with a local stand-in.
+    pub fn with_metadata_endpoint<E: Into<String>>(mut self, endpoint: E) -> Self {
+        let endpoint = endpoint.into();
+        for provider in &mut self.providers {
+            if let CredentialProvider::InstanceMetadata(metadata) = provider {
+                *metadata = InstanceMetadataProvider::new(endpoint.as_str());
+            }
+        }
+        self
+    }
+
+    pub fn providers(&self) -> &[CredentialProvider] {
+        &self.providers
+    }
+
+    pub async fn resolve(
+        &self,
+        auth_service: &AccessKeyAuthService,
+    ) -> Option<(CredentialSource, ResolvedCredentials)> {
+        for provider in &self.providers {
+            if let Some(resolved) = provider.provide(auth_service).await {
+                log::info!("Using the credentials of {:?}", resolved.0);
+                return Some(resolved);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::services::auth::{
+        metadata::metadata_test_utils::{start_fake_metadata, INSTANCE_ROLE_NAME},
+        store::store_test_utils,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_access_key_from_vars() {
+        let vars = HashMap::from([
+            (ACCESS_KEY_ID_ENV, "ENV_ID".to_owned()),
+            (ACCESS_KEY_SECRET_ENV, "ENV_SECRET".to_owned()),
+        ]);
+        assert_eq!(
+            access_key_from_vars(|key| vars.get(key).cloned()),
+            Some(AccessKeyCredentials::new("ENV_ID", "ENV_SECRET"))
+        );
+        assert_eq!(
+            access_key_from_vars(|key| (key == ACCESS_KEY_ID_ENV).then(|| "ENV_ID".to_owned())),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_in_order() {
+        let metadata = start_fake_metadata().await;
+        let dir = tempfile::TempDir::new().unwrap();
+        let cli_config_path = dir.path().join("config.json");
+        let chain = CredentialProviderChain::new(vec![
+            CredentialProvider::CliConfig(cli_config_path.clone()),
+            CredentialProvider::StoredProfile,
+            CredentialProvider::InstanceMetadata(InstanceMetadataProvider::new(metadata.uri())),
+        ]);
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store());
+
+        // Nothing configured but the instance role.
+        let (source, credentials) = chain.resolve(&auth_service).await.unwrap();
+        assert_eq!(
+            source,
+            CredentialSource::InstanceMetadata {
+                role_name: INSTANCE_ROLE_NAME.to_owned()
+            }
+        );
+        let ResolvedCredentials::Session(..) = credentials else {
+            unreachable!()
+        };
+
+        auth_service
+            .update_profiles(|profiles| {
+                profiles.upsert(
+                    "stored",
+                    AccessKeyCredentials::new("STORED_ID", "STORED_SECRET"),
+                );
+                Ok(())
+            })
+            .unwrap();
+        let (source, _) = chain.resolve(&auth_service).await.unwrap();
+        assert_eq!(
+            source,
+            CredentialSource::StoredProfile {
+                name: "stored".to_owned()
+            }
+        );
+
+        let config = json!({
+            "current": "cli",
+            "profiles": [{
+                "name": "cli",
+                "mode": "AK",
+                "access_key_id": "CLI_ID",
+                "access_key_secret": "CLI_SECRET",
+            }],
+        });
+        std::fs::write(&cli_config_path, config.to_string()).unwrap();
+        let (source, credentials) = chain.resolve(&auth_service).await.unwrap();
+        assert_eq!(
+            source,
+            CredentialSource::CliConfig {
+                profile: "cli".to_owned()
+            }
+        );
+        let ResolvedCredentials::Profile(credentials) = credentials else {
+            unreachable!()
+        };
+        assert_eq!(credentials.access_key().access_key_id, "CLI_ID");
+    }
+
+    #[tokio::test]
+    async fn test_skip_metadata_while_locked() {
+        let metadata = start_fake_metadata().await;
+        let chain = CredentialProviderChain::new(vec![
+            CredentialProvider::StoredProfile,
+            CredentialProvider::InstanceMetadata(InstanceMetadataProvider::new(metadata.uri())),
+        ]);
+        let auth_service = AccessKeyAuthService::new_encrypted(store_test_utils::init_store());
+
+        // Before any passphrase there are no profiles to wait for.
+        assert!(chain.resolve(&auth_service).await.is_some());
+
+        auth_service.unlock("passphrase").unwrap();
+        auth_service.lock().unwrap();
+        assert!(chain.resolve(&auth_service).await.is_none());
+
+        // Unlocked without an active profile, the instance is probed again.
+        auth_service.unlock("passphrase").unwrap();
+        let (source, _) = chain.resolve(&auth_service).await.unwrap();
+        assert_eq!(
+            source,
+            CredentialSource::InstanceMetadata {
+                role_name: INSTANCE_ROLE_NAME.to_owned()
+            }
+        );
+    }
+}
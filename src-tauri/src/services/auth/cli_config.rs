@@ -0,0 +1,472 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    services::auth::{
+        profile::{CredentialProfiles, ProfileError},
+        secret::SecretString,
+        types::{AccessKeyCredentials, AssumeRoleCredentials, ProfileCredentials},
+    },
+    types::SerdeJsonError,
+};
+
+/// Where the `aliyun` CLI keeps its configuration, relative to the home directory.
+pub const CLI_CONFIG_RELATIVE_PATH: &str = ".aliyun/config.json";
+
+/// Used when a `RamRoleArn` profile doesn't name its session.
+const DEFAULT_ROLE_SESSION_NAME: &str = "aliyun-ecs-manager";
+/// Used when a `RamRoleArn` profile doesn't set `expired_seconds`, the CLI's own default.
+const DEFAULT_ROLE_DURATION_SECONDS: u32 = 3600;
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum CliConfigError {
+    #[error("the home directory can't be found")]
+    NoHomeDir,
+    #[error("failed to access {path}: {message}")]
+    Io { path: String, message: String },
+    #[error("the aliyun CLI config is not valid: {}", .0.0)]
+    Invalid(#[from] SerdeJsonError),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+}
+
+impl CliConfigError {
+    fn io(path: &Path, err: io::Error) -> Self {
+        Self::Io {
+            path: path.display().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CliConfigError {
+    fn from(value: serde_json::Error) -> Self {
+        let error: SerdeJsonError = value.into();
+        error.into()
+    }
+}
+
+/// The `config.json` of the `aliyun` CLI.
+///
+/// Fields this app doesn't use are kept as they are, so that exporting into an
+/// existing file doesn't lose anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CliConfig {
+    #[serde(default)]
+    pub current: String,
+    #[serde(default)]
+    pub profiles: Vec<CliProfile>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// A profile of the `aliyun` CLI config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CliProfile {
+    pub name: String,
+    #[serde(default)]
+    pub mode: String,
+    #[serde(default)]
+    pub access_key_id: String,
+    #[serde(default)]
+    pub access_key_secret: SecretString,
+    #[serde(default)]
+    pub sts_token: SecretString,
+    #[serde(default)]
+    pub ram_role_name: String,
+    #[serde(default)]
+    pub ram_role_arn: String,
+    #[serde(default)]
+    pub ram_session_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub external_id: String,
+    #[serde(default)]
+    pub expired_seconds: u32,
+    #[serde(default)]
+    pub region_id: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// What the frontend gets to know about a profile of the `aliyun` CLI config before
+/// importing it.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct CliProfilePreview {
+    pub name: String,
+    pub mode: String,
+    pub region_id: String,
+    pub access_key_id: String,
+    pub role_arn: Option<String>,
+    /// Whether it is the current profile of the CLI.
+    pub current: bool,
+    /// Whether a saved profile already has this name, in which case it can't be imported.
+    pub exists: bool,
+    /// Why the profile can't be imported, `None` if it can.
+    pub unsupported_reason: Option<String>,
+}
+
+impl CliConfig {
+    /// Reads the config, which is empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, CliConfigError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(CliConfigError::io(path, err)),
+        }
+    }
+
+    /// Writes the config the way the CLI does, indented with tabs and readable by
+    /// the owner only.
+    pub fn save(&self, path: &Path) -> Result<(), CliConfigError> {
+        let mut content = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+        self.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut content,
+            formatter,
+        ))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| CliConfigError::io(parent, err))?;
+        }
+
+        // Written next to the config and renamed over it, so a failed write leaves the
+        // config of the CLI as it was.
+        let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp_path = path.with_file_name(temp_name);
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let written = options.open(&temp_path).and_then(|mut file| {
+            // The mode above only applies to new files, a leftover one may be wider.
+            #[cfg(unix)]
+            file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+            file.write_all(&content)?;
+            file.sync_all()
+        });
+        if let Err(err) = written.and_then(|()| fs::rename(&temp_path, path)) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(CliConfigError::io(path, err));
+        }
+        Ok(())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&CliProfile> {
+        self.profiles.iter().find(|profile| profile.is_named(name))
+    }
+
+    pub fn preview(&self, saved: &CredentialProfiles) -> Vec<CliProfilePreview> {
+        self.profiles
+            .iter()
+            .map(|profile| CliProfilePreview {
+                name: profile.name.clone(),
+                mode: profile.mode.clone(),
+                region_id: profile.region_id.clone(),
+                access_key_id: profile.access_key_id.clone(),
+                role_arn: (!profile.ram_role_arn.is_empty()).then(|| profile.ram_role_arn.clone()),
+                current: profile.name == self.current,
+                exists: saved.contains(normalize_profile_name(&profile.name)),
+                unsupported_reason: profile.credentials().err(),
+            })
+            .collect()
+    }
+
+    /// Writes the saved profiles into the config, replacing the credentials of the CLI
+    /// profiles with the same name.
+    ///
+    /// Profiles new to the config get the given region, the other ones keep theirs.
+    /// The current profile of the CLI is only set if there was none.
+    pub fn merge(&mut self, saved: &CredentialProfiles, region_id: &str) {
+        for (name, credentials) in saved.iter() {
+            let index = match self
+                .profiles
+                .iter()
+                .position(|profile| profile.is_named(name))
+            {
+                Some(index) => index,
+                None => {
+                    self.profiles.push(CliProfile {
+                        name: name.to_owned(),
+                        region_id: region_id.to_owned(),
+                        ..Default::default()
+                    });
+                    self.profiles.len() - 1
+                }
+            };
+            self.profiles[index].set_credentials(credentials);
+        }
+
+        if self.current.is_empty() {
+            if let Some(active) = saved.active_name() {
+                self.current = active.to_owned();
+            }
+        }
+    }
+}
+
+/// The name a CLI profile is saved under by this app, which trims profile names.
+fn normalize_profile_name(name: &str) -> &str {
+    name.trim()
+}
+
+impl CliProfile {
+    /// Whether the profile has the given name, once both are normalized.
+    pub fn is_named(&self, name: &str) -> bool {
+        normalize_profile_name(&self.name) == normalize_profile_name(name)
+    }
+
+    /// Converts the profile into credentials this app can save, or tells why it can't.
+    pub fn credentials(&self) -> Result<ProfileCredentials, String> {
+        match self.mode.as_str() {
+            "AK" => self.access_key().map(Into::into),
+            "RamRoleArn" => {
+                let source = self.access_key()?;
+                if self.ram_role_arn.is_empty() {
+                    return Err("the profile has no role ARN".to_owned());
+                }
+                let role_session_name = match self.ram_session_name.as_str() {
+                    "" => DEFAULT_ROLE_SESSION_NAME.to_owned(),
+                    name => name.to_owned(),
+                };
+                let duration_seconds = match self.expired_seconds {
+                    0 => DEFAULT_ROLE_DURATION_SECONDS,
+                    seconds => seconds,
+                };
+
+                Ok(AssumeRoleCredentials {
+                    source,
+                    role_arn: self.ram_role_arn.clone(),
+                    role_session_name,
+                    duration_seconds,
+                    external_id: (!self.external_id.is_empty()).then(|| self.external_id.clone()),
+                }
+                .into())
+            }
+            "StsToken" => Err(
+                "temporary STS tokens expire and can't be refreshed, import the profile they were issued for instead"
+                    .to_owned(),
+            ),
+            "EcsRamRole" => Err(format!(
+                "the ECS RAM role {:?} can only be used from inside an ECS instance",
+                self.ram_role_name
+            )),
+            "" => Err("the profile has no mode".to_owned()),
+            mode => Err(format!("the {mode} mode is not supported")),
+        }
+    }
+
+    fn access_key(&self) -> Result<AccessKeyCredentials, String> {
+        if self.access_key_id.is_empty() || self.access_key_secret.is_empty() {
+            return Err("the profile has no access key".to_owned());
+        }
+        Ok(AccessKeyCredentials {
+            access_key_id: self.access_key_id.clone(),
+            access_key_secret: self.access_key_secret.clone(),
+        })
+    }
+
+    fn set_credentials(&mut self, credentials: &ProfileCredentials) {
+        let access_key = credentials.access_key();
+        self.access_key_id = access_key.access_key_id.clone();
+        self.access_key_secret = access_key.access_key_secret.clone();
+        self.sts_token = SecretString::default();
+        self.ram_role_name.clear();
+
+        match credentials {
+            ProfileCredentials::AccessKey(_) => {
+                self.mode = "AK".to_owned();
+                self.ram_role_arn.clear();
+                self.ram_session_name.clear();
+                self.external_id.clear();
+                self.expired_seconds = 0;
+            }
+            ProfileCredentials::AssumeRole(role) => {
+                self.mode = "RamRoleArn".to_owned();
+                self.ram_role_arn = role.role_arn.clone();
+                self.ram_session_name = role.role_session_name.clone();
+                self.external_id = role.external_id.clone().unwrap_or_default();
+                self.expired_seconds = role.duration_seconds;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_ok;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn cli_config() -> CliConfig {
+        serde_json::from_value(json!({
+            "current": "role",
+            "profiles": [
+                {
+                    "name": "default",
+                    "mode": "AK",
+                    "access_key_id": "DEFAULT_ID",
+                    "access_key_secret": "DEFAULT_SECRET",
+                    "region_id": "cn-hangzhou",
+                    "output_format": "json",
+                    "language": "en"
+                },
+                {
+                    "name": "role",
+                    "mode": "RamRoleArn",
+                    "access_key_id": "SOURCE_ID",
+                    "access_key_secret": "SOURCE_SECRET",
+                    "ram_role_arn": "acs:ram::123456789012:role/ecs-admin",
+                    "ram_session_name": "",
+                    "expired_seconds": 900,
+                    "region_id": "cn-shanghai"
+                },
+                {
+                    "name": "temporary",
+                    "mode": "StsToken",
+                    "access_key_id": "STS.ID",
+                    "access_key_secret": "STS_SECRET",
+                    "sts_token": "TOKEN",
+                    "region_id": "cn-beijing"
+                },
+                {
+                    "name": "instance",
+                    "mode": "EcsRamRole",
+                    "ram_role_name": "ecs-role",
+                    "region_id": "cn-beijing"
+                }
+            ],
+            "meta_path": ""
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_preview_cli_profiles() {
+        let config = cli_config();
+        let saved = CredentialProfiles::single(
+            "default",
+            AccessKeyCredentials::new("SAVED_ID", "SAVED_SECRET"),
+        );
+
+        let preview = config.preview(&saved);
+        let importable = preview
+            .iter()
+            .map(|profile| {
+                (
+                    profile.name.as_str(),
+                    profile.exists,
+                    profile.unsupported_reason.is_none(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            importable,
+            vec![
+                ("default", true, true),
+                ("role", false, true),
+                ("temporary", false, false),
+                ("instance", false, false),
+            ]
+        );
+        assert!(preview[1].current);
+        assert_eq!(preview[1].region_id, "cn-shanghai");
+
+        let role = assert_ok!(config.profile("role").unwrap().credentials());
+        assert_eq!(
+            role,
+            AssumeRoleCredentials {
+                source: AccessKeyCredentials::new("SOURCE_ID", "SOURCE_SECRET"),
+                role_arn: "acs:ram::123456789012:role/ecs-admin".to_owned(),
+                role_session_name: DEFAULT_ROLE_SESSION_NAME.to_owned(),
+                duration_seconds: 900,
+                external_id: None,
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_merge_round_trip() {
+        let mut config = cli_config();
+        let mut saved = CredentialProfiles::default();
+        saved.upsert(
+            "default",
+            AccessKeyCredentials::new("ROTATED_ID", "ROTATED_SECRET"),
+        );
+        saved.upsert(
+            "role",
+            config.profile("role").unwrap().credentials().unwrap(),
+        );
+        saved.upsert("new", AccessKeyCredentials::new("NEW_ID", "NEW_SECRET"));
+
+        config.merge(&saved, "cn-shenzhen");
+        assert_eq!(config.current, "role");
+        assert_eq!(config.profiles.len(), 5);
+
+        // Existing profiles keep their region and the fields this app doesn't know about.
+        let default = config.profile("default").unwrap();
+        assert_eq!(default.access_key_id, "ROTATED_ID");
+        assert_eq!(default.region_id, "cn-hangzhou");
+        assert_eq!(default.extra["output_format"], "json");
+        assert_eq!(config.profile("new").unwrap().region_id, "cn-shenzhen");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".aliyun").join("config.json");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "{}").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        assert_ok!(config.save(&path));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let files = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(files, 1, "the temporary file is renamed over the config");
+        let loaded = CliConfig::load(&path).unwrap();
+
+        assert_eq!(loaded, config);
+        for (name, credentials) in saved.iter() {
+            assert_eq!(
+                &loaded.profile(name).unwrap().credentials().unwrap(),
+                credentials
+            );
+        }
+    }
+
+    #[test]
+    fn test_profile_names_are_trimmed() {
+        let mut config = cli_config();
+        config.profiles[0].name = " default ".to_owned();
+        let saved = CredentialProfiles::single(
+            "default",
+            AccessKeyCredentials::new("SAVED_ID", "SAVED_SECRET"),
+        );
+
+        assert!(config.preview(&saved)[0].exists);
+        assert!(config.profile("default").is_some());
+
+        config.merge(&saved, "cn-shenzhen");
+        assert_eq!(config.profiles.len(), 4);
+        assert_eq!(config.profiles[0].access_key_id, "SAVED_ID");
+    }
+}
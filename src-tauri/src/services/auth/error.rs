@@ -1,11 +1,15 @@
-use alibabacloud::client::error::AliyunRejection;
+use alibabacloud::client::error::{AliyunRejection, OperationError};
 use serde::Serialize;
 use specta::datatype::LiteralType;
 use thiserror::Error;
 
 use crate::services::{
-    auth::store::SaveCredentialError,
+    auth::{
+        profile::ProfileError,
+        store::{QueryCredentialError, SaveCredentialError},
+    },
     error::{AliyunRejectionTypeShadow, AliyunRequestCommandError},
+    retry::CallError,
 };
 
 #[derive(Debug, Error, Serialize, specta::Type)]
@@ -19,12 +23,41 @@ pub struct AKNotValid {
 impl AKNotValid {
     pub fn new(data: AliyunRejection) -> Self {
         Self {
-            r#type: AKNotValidType::default(),
+            r#type: AKNotValidType,
             data,
         }
     }
 }
 
+impl AKNotValid {
+    /// Whether the rejection means the access key itself is not valid.
+    pub fn matches(rejection: &AliyunRejection) -> bool {
+        let code = &rejection.code;
+        let main_code = code.split_once(".").unwrap_or((code, "")).0;
+        log::debug!(
+            "Received aliyun error code: {:#?}, read: {:#?}",
+            code,
+            main_code
+        );
+        main_code == "InvalidAccessKeyId"
+            || main_code == "SignatureDoesNotMatch"
+            || main_code == "MissingAccessKeyId"
+    }
+
+    /// Converts the error of a call, turning access key rejections into [`AKNotValid`].
+    pub fn classify(err: CallError) -> AliyunRequestCommandError<AKNotValid> {
+        let CallError { error, attempts } = err;
+        match error {
+            OperationError::Rejected(aliyun_rejection) if Self::matches(&aliyun_rejection) => {
+                AliyunRequestCommandError::<AKNotValid>::new_specific(AKNotValid::new(
+                    aliyun_rejection,
+                ))
+            }
+            other => CallError::new(other, attempts).into(),
+        }
+    }
+}
+
 const AK_NOT_VALID_TYPE: &str = "AKNotValid";
 
 #[derive(Debug, Default)]
@@ -57,5 +90,23 @@ impl From<SaveCredentialError> for AliyunRequestCommandError<AKNotValid> {
     }
 }
 
+impl From<QueryCredentialError> for AliyunRequestCommandError<AKNotValid> {
+    fn from(value: QueryCredentialError) -> Self {
+        AliyunRequestCommandError::InternalError {
+            message: value.to_string(),
+            source: Box::new(value),
+        }
+    }
+}
+
+impl From<ProfileError> for AliyunRequestCommandError<AKNotValid> {
+    fn from(value: ProfileError) -> Self {
+        AliyunRequestCommandError::InternalError {
+            message: value.to_string(),
+            source: Box::new(value),
+        }
+    }
+}
+
 pub type AKValidationError = AliyunRequestCommandError<AKNotValid>;
 pub type AKFulfillError = AliyunRequestCommandError<AKNotValid>;
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use alibabacloud::client::error::OperationError;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::services::{
+    auth::{error::AKFulfillError, secret::SecretString, sts::SessionCredentials},
+    error::{AliyunRequestCommandError, NoSource},
+};
+
+/// The metadata service every ECS instance can reach.
+pub const DEFAULT_METADATA_ENDPOINT: &str = "http://100.100.100.200";
+
+const CREDENTIALS_PATH: &str = "/latest/meta-data/ram/security-credentials/";
+const TOKEN_PATH: &str = "/latest/api/token";
+const TOKEN_TTL_SECONDS: u32 = 21600;
+
+/// Short, since outside of ECS the metadata service doesn't answer at all.
+const METADATA_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MetadataCredentialsBody {
+    code: String,
+    access_key_id: String,
+    access_key_secret: SecretString,
+    security_token: SecretString,
+    expiration: DateTime<Utc>,
+}
+
+/// Fetches the temporary credentials of the RAM role attached to the ECS instance
+/// the app runs on.
+#[derive(Clone)]
+pub struct InstanceMetadataProvider {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl Default for InstanceMetadataProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_METADATA_ENDPOINT)
+    }
+}
+
+impl InstanceMetadataProvider {
+    pub fn new<E: Into<String>>(endpoint: E) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(METADATA_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            endpoint: endpoint.into().trim_end_matches('/').to_owned(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Requests a session token of the hardened mode, `None` if the service doesn't
+    /// issue them and the normal mode is to be used.
+    async fn token(&self) -> Option<String> {
+        let response = self
+            .http
+            .put(format!("{}{TOKEN_PATH}", self.endpoint))
+            .header(
+                "X-aliyun-ecs-metadata-token-ttl-seconds",
+                TOKEN_TTL_SECONDS.to_string(),
+            )
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.text().await.ok()
+    }
+
+    async fn get(&self, path: &str) -> Result<String, AKFulfillError> {
+        let mut request = self.http.get(format!("{}{path}", self.endpoint));
+        if let Some(token) = self.token().await {
+            request = request.header("X-aliyun-ecs-metadata-token", token);
+        }
+
+        let response = request.send().await.map_err(OperationError::from)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AliyunRequestCommandError::InternalError {
+                message: format!("the instance metadata service responded to {path} with {status}"),
+                source: NoSource::new_boxed(),
+            });
+        }
+        Ok(response.text().await.map_err(OperationError::from)?)
+    }
+
+    /// Returns the name of the RAM role attached to the instance.
+    pub async fn role_name(&self) -> Result<String, AKFulfillError> {
+        let body = self.get(CREDENTIALS_PATH).await?;
+        body.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_owned)
+            .ok_or_else(|| AliyunRequestCommandError::InternalError {
+                message: "no RAM role is attached to the instance".to_owned(),
+                source: NoSource::new_boxed(),
+            })
+    }
+
+    /// Fetches the current temporary credentials of the role.
+    pub async fn credentials(&self, role_name: &str) -> Result<SessionCredentials, AKFulfillError> {
+        let issued_at = Utc::now();
+        let body = self.get(&format!("{CREDENTIALS_PATH}{role_name}")).await?;
+        let body: MetadataCredentialsBody = serde_json::from_str(&body).map_err(|err| {
+            AliyunRequestCommandError::InternalError {
+                message: format!("unexpected credentials of the RAM role {role_name}: {err}"),
+                source: Box::new(err),
+            }
+        })?;
+        if body.code != "Success" {
+            return Err(AliyunRequestCommandError::InternalError {
+                message: format!(
+                    "the instance metadata service failed to issue credentials: {}",
+                    body.code
+                ),
+                source: NoSource::new_boxed(),
+            });
+        }
+
+        Ok(SessionCredentials {
+            access_key_id: body.access_key_id,
+            access_key_secret: body.access_key_secret,
+            security_token: body.security_token,
+            issued_at,
+            expiration: body.expiration,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod metadata_test_utils {
+    use chrono::TimeDelta;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    pub const INSTANCE_ROLE_NAME: &str = "ecs-instance-role";
+
+    /// Starts a stand-in of the metadata service of an instance with a RAM role attached.
+    pub async fn start_fake_metadata() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_string("METADATA_TOKEN"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(CREDENTIALS_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_string(INSTANCE_ROLE_NAME))
+            .mount(&server)
+            .await;
+
+        let expiration = Utc::now() + TimeDelta::hours(6);
+        Mock::given(method("GET"))
+            .and(path(format!("{CREDENTIALS_PATH}{INSTANCE_ROLE_NAME}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "AccessKeyId": "STS.INSTANCE",
+                "AccessKeySecret": "INSTANCE_SECRET",
+                "SecurityToken": "INSTANCE_TOKEN",
+                "Expiration": expiration.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                "LastUpdated": Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                "Code": "Success",
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use super::{metadata_test_utils::*, *};
+
+    #[tokio::test]
+    async fn test_instance_metadata_credentials() {
+        let server = start_fake_metadata().await;
+        let provider = InstanceMetadataProvider::new(server.uri());
+
+        let role_name = provider.role_name().await.unwrap();
+        assert_eq!(role_name, INSTANCE_ROLE_NAME);
+
+        let credentials = provider.credentials(&role_name).await.unwrap();
+        assert_eq!(credentials.access_key_id, "STS.INSTANCE");
+        assert_eq!(credentials.security_token.expose_secret(), "INSTANCE_TOKEN");
+        assert!(!credentials.is_expired());
+
+        assert_matches!(
+            provider.credentials("missing-role").await,
+            Err(AliyunRequestCommandError::InternalError { .. })
+        );
+    }
+}
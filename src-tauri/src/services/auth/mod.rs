@@ -1,136 +1,703 @@
+pub mod chain;
+pub mod cli_config;
 pub mod error;
+pub mod metadata;
+pub mod profile;
+pub mod rotation;
+pub mod secret;
 pub mod store;
+pub mod sts;
 pub mod types;
+pub mod vault;
 
-use alibabacloud::client::{
-    error::OperationError, sts::caller_identity::CallerIdentityBody, AliyunClient,
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
-use crate::services::{
-    auth::{
-        error::AKNotValid,
-        store::{AccessKeyAuthStore, AuthStore, QueryCredentialError},
-        types::AccessKeyCredentials,
+use alibabacloud::client::{sts::caller_identity::CallerIdentityBody, AliyunClient};
+use chrono::Utc;
+
+use crate::{
+    services::{
+        auth::{
+            chain::CredentialSource,
+            cli_config::{CliConfig, CliProfile, CliProfilePreview},
+            error::{AKFulfillError, AKNotValid},
+            profile::{
+                validate_profile_name, CredentialProfiles, LogoutOutcome, ProfileError,
+                ProfileSummary, DEFAULT_PROFILE_NAME,
+            },
+            rotation::{
+                ram_user_name, AccessKeyAge, AccessKeyManager, AccessKeyRotation, AccessKeyStatus,
+                RetiredAccessKey, RotationError, RotationStep,
+            },
+            store::{AuthStore, ProfileAuthStore, QueryCredentialError},
+            sts::{AssumeRoleProvider, AssumedRole},
+            types::{
+                AccessKeyCredentials, AccessKeyView, AssumeRoleCredentials, ProfileCredentials,
+            },
+            vault::{CredentialVault, EncryptedAuthStore, UnlockOutcome, VaultError, VaultStatus},
+        },
+        client::AliyunClientService,
+        endpoint::{AliyunService, EndpointConfig},
+        error::{AliyunRequestCommandError, NoSource},
+        rpc::{RpcClient, RpcParams},
     },
-    error::AliyunRequestCommandError,
+    types::Store,
 };
 
+/// How many times a freshly created access key is validated before giving up.
+const NEW_ACCESS_KEY_VALIDATION_ATTEMPTS: u32 = 5;
+const NEW_ACCESS_KEY_VALIDATION_DELAY: Duration = Duration::from_secs(2);
+
 pub struct AccessKeyAuthService {
-    auth_store: Box<dyn AuthStore<AccessKeyCredentials> + Send + Sync>,
+    auth_store: Box<dyn AuthStore<CredentialProfiles> + Send + Sync>,
+    /// Present when the auth store is encrypted and has to be unlocked before use.
+    vault: Option<Arc<dyn CredentialVault + Send + Sync>>,
+    /// Serializes the read-modify-write cycles on the profiles.
+    profiles_lock: Mutex<()>,
+    /// Issues the temporary credentials of role profiles, and validates credentials.
+    sts: RwLock<AssumeRoleProvider>,
+    /// Creates and retires access keys when rotating them.
+    access_keys: RwLock<AccessKeyManager>,
 }
 
 impl AccessKeyAuthService {
-    pub fn new<R: tauri::Runtime + 'static, S: Into<AccessKeyAuthStore<R>>>(store: S) -> Self {
+    pub fn new<R: tauri::Runtime + 'static, S: Into<ProfileAuthStore<R>>>(store: S) -> Self {
         Self {
             auth_store: Box::new(store.into()),
+            vault: None,
+            profiles_lock: Mutex::new(()),
+            sts: RwLock::new(AssumeRoleProvider::default()),
+            access_keys: RwLock::new(AccessKeyManager::default()),
         }
     }
 
+    /// Creates a service backed by an [`EncryptedAuthStore`], which starts locked.
+    pub fn new_encrypted<R: tauri::Runtime + 'static>(store: Store<R>) -> Self {
+        let store = Arc::new(EncryptedAuthStore::new(store));
+        Self {
+            auth_store: Box::new(store.clone()),
+            vault: Some(store),
+            profiles_lock: Mutex::new(()),
+            sts: RwLock::new(AssumeRoleProvider::default()),
+            access_keys: RwLock::new(AccessKeyManager::default()),
+        }
+    }
+
+    /// Replaces the provider used to assume roles, e.g. to point it at another STS endpoint.
+    pub fn with_sts_provider(self, sts: AssumeRoleProvider) -> Self {
+        *self.sts.write().unwrap() = sts;
+        self
+    }
+
+    pub fn sts(&self) -> AssumeRoleProvider {
+        self.sts.read().unwrap().clone()
+    }
+
+    /// Replaces the manager used to rotate access keys, e.g. to point it at another RAM
+    /// endpoint.
+    pub fn with_access_key_manager(self, access_keys: AccessKeyManager) -> Self {
+        *self.access_keys.write().unwrap() = access_keys;
+        self
+    }
+
+    fn access_keys(&self) -> AccessKeyManager {
+        self.access_keys.read().unwrap().clone()
+    }
+
+    /// Points the STS and RAM requests at the endpoints of the configuration.
+    pub fn set_endpoints(&self, endpoints: &EndpointConfig) {
+        *self.sts.write().unwrap() =
+            AssumeRoleProvider::new(endpoints.resolve(AliyunService::Sts, None));
+        *self.access_keys.write().unwrap() =
+            AccessKeyManager::new(endpoints.resolve(AliyunService::Ram, None));
+    }
+
+    /// Same as [`Self::set_endpoints`], while building the service.
+    pub fn with_endpoints(self, endpoints: &EndpointConfig) -> Self {
+        self.set_endpoints(endpoints);
+        self
+    }
+
+    fn vault(&self) -> Result<&(dyn CredentialVault + Send + Sync), VaultError> {
+        self.vault.as_deref().ok_or(VaultError::NotEncrypted)
+    }
+
+    pub fn vault_status(&self) -> Result<VaultStatus, VaultError> {
+        Ok(self.vault()?.status())
+    }
+
+    /// Unlocks the encrypted auth store, creating it on first use.
+    pub fn unlock(&self, passphrase: &str) -> Result<UnlockOutcome, VaultError> {
+        self.vault()?.unlock(passphrase)
+    }
+
+    pub fn lock(&self) -> Result<(), VaultError> {
+        self.vault()?.lock();
+        Ok(())
+    }
+
+    /// Builds a client from the active profile, unless it is a role profile which needs
+    /// temporary credentials instead.
     pub fn new_client(&self) -> Option<AliyunClient> {
-        let Ok(credentials) = self.current_access_key_credentials() else {
+        let Ok(ProfileCredentials::AccessKey(credentials)) = self.current_profile_credentials()
+        else {
             return None;
         };
 
-        Some(AliyunClient::new(
-            credentials.access_key_id,
-            credentials.access_key_secret,
-        ))
+        Some(credentials.into())
     }
 
+    /// Returns the access key of the active profile, the source access key for role profiles.
     pub fn current_access_key_credentials(
         &self,
     ) -> Result<AccessKeyCredentials, QueryCredentialError> {
-        self.auth_store.query()
+        self.current_profile_credentials()
+            .map(|credentials| credentials.access_key().clone())
+    }
+
+    /// Returns what the frontend may see of the access key of the active profile.
+    pub fn current_access_key_view(&self) -> Result<AccessKeyView, QueryCredentialError> {
+        let profiles = self.auth_store.query()?;
+        let (name, credentials) = profiles.active().ok_or(QueryCredentialError::NotExist)?;
+        Ok(credentials.access_key().view(profiles.validated_at(name)))
+    }
+
+    /// Returns the credentials of the active profile.
+    pub fn current_profile_credentials(&self) -> Result<ProfileCredentials, QueryCredentialError> {
+        self.current_profile().map(|(_, credentials)| credentials)
+    }
+
+    /// Returns the name and the credentials of the active profile.
+    pub fn current_profile(&self) -> Result<(String, ProfileCredentials), QueryCredentialError> {
+        self.auth_store
+            .query()?
+            .active()
+            .map(|(name, credentials)| (name.to_owned(), credentials.clone()))
+            .ok_or(QueryCredentialError::NotExist)
+    }
+
+    fn profiles(&self) -> Result<CredentialProfiles, QueryCredentialError> {
+        match self.auth_store.query() {
+            Err(QueryCredentialError::NotExist) => Ok(CredentialProfiles::default()),
+            other => other,
+        }
+    }
+
+    fn update_profiles<T, F>(&self, f: F) -> Result<T, ProfileError>
+    where
+        F: FnOnce(&mut CredentialProfiles) -> Result<T, ProfileError>,
+    {
+        let _guard = self.profiles_lock.lock().unwrap();
+        let mut profiles = self.profiles()?;
+        let result = f(&mut profiles)?;
+        self.auth_store.save(profiles)?;
+        Ok(result)
+    }
+
+    pub fn list_profiles(&self) -> Result<Vec<ProfileSummary>, ProfileError> {
+        Ok(self.profiles()?.summaries())
+    }
+
+    pub fn active_profile(&self) -> Result<Option<String>, ProfileError> {
+        Ok(self.profiles()?.active_name().map(str::to_owned))
+    }
+
+    pub fn rename_profile(&self, from: &str, to: &str) -> Result<(), ProfileError> {
+        self.update_profiles(|profiles| profiles.rename(from, to))
+    }
+
+    /// Deletes a profile, returning whether it was the active one.
+    pub fn delete_profile(&self, name: &str) -> Result<bool, ProfileError> {
+        self.update_profiles(|profiles| {
+            let was_active = profiles.active_name() == Some(name);
+            profiles.remove(name)?;
+            Ok(was_active)
+        })
+    }
+
+    pub fn set_active_profile(&self, name: &str) -> Result<(), ProfileError> {
+        self.update_profiles(|profiles| profiles.set_active(name))
+    }
+
+    pub fn default_region(&self, name: &str) -> Result<Option<String>, ProfileError> {
+        Ok(self.profiles()?.default_region(name).map(str::to_owned))
+    }
+
+    pub fn set_default_region(
+        &self,
+        name: &str,
+        region_id: Option<&str>,
+    ) -> Result<(), ProfileError> {
+        self.update_profiles(|profiles| profiles.set_default_region(name, region_id))
+    }
+
+    /// Removes the given profile from the store, or every stored credential when `None`.
+    ///
+    /// Removing everything doesn't need the credential store to be unlocked.
+    pub fn logout(&self, profile: Option<&str>) -> Result<LogoutOutcome, ProfileError> {
+        let Some(name) = profile else {
+            let _guard = self.profiles_lock.lock().unwrap();
+            return Ok(if self.auth_store.delete() {
+                LogoutOutcome::AllDeleted
+            } else {
+                LogoutOutcome::NothingToDelete
+            });
+        };
+
+        self.update_profiles(|profiles| {
+            if !profiles.contains(name) {
+                return Ok(LogoutOutcome::NothingToDelete);
+            }
+            let was_active = profiles.active_name() == Some(name);
+            profiles.remove(name)?;
+            Ok(LogoutOutcome::ProfileDeleted {
+                name: name.to_owned(),
+                was_active,
+            })
+        })
     }
-    pub async fn validate_access_key_credentials<C: Into<AliyunClient>>(
-        credentials_or_client: C,
-    ) -> Result<CallerIdentityBody, AliyunRequestCommandError<AKNotValid>> {
-        use OperationError::*;
 
-        let client = credentials_or_client.into();
+    /// Validates the credentials of the client by asking STS for the caller identity,
+    /// temporary credentials with a security token included.
+    pub async fn validate_rpc_client(
+        client: &RpcClient,
+    ) -> Result<CallerIdentityBody, AliyunRequestCommandError<AKNotValid>> {
         client
-            .sts()
-            .get_caller_identity()
+            .call("GetCallerIdentity", "2015-04-01", RpcParams::new())
             .await
-            .map_err(|err| match err {
-                Rejected(aliyun_rejection)
-                    if {
-                        let code = &aliyun_rejection.code;
-                        let main_code = code.split_once(".").unwrap_or((code, "")).0;
-                        log::debug!(
-                            "Received aliyun error code: {:#?}, read: {:#?}",
-                            code,
-                            main_code
-                        );
-                        main_code == "InvalidAccessKeyId"
-                            || main_code == "SignatureDoesNotMatch"
-                            || main_code == "MissingAccessKeyId"
-                    } =>
-                {
-                    AliyunRequestCommandError::<AKNotValid>::new_specific(AKNotValid::new(
-                        aliyun_rejection,
-                    ))
-                }
-                other => other.into(),
-            })
+            .map_err(AKNotValid::classify)
+    }
+
+    /// Validates the credentials at the configured STS endpoint, see [`Self::set_endpoints`].
+    pub async fn validate_credentials(
+        &self,
+        credentials: &AccessKeyCredentials,
+    ) -> Result<CallerIdentityBody, AliyunRequestCommandError<AKNotValid>> {
+        let client = RpcClient::new(self.sts().endpoint(), credentials.into());
+        Self::validate_rpc_client(&client).await
     }
 
+    /// Validates the credentials and saves them into the active profile,
+    /// or into the default profile when no profile is active.
     pub async fn fulfill_access_key_credentials(
         &self,
         credentials: AccessKeyCredentials,
     ) -> Result<CallerIdentityBody, AliyunRequestCommandError<AKNotValid>> {
-        let caller_identity = Self::validate_access_key_credentials(credentials.clone()).await?;
-        self.auth_store.save(credentials)?;
+        let caller_identity = self.validate_credentials(&credentials).await?;
+        self.update_profiles(|profiles| {
+            let name = profiles
+                .active_name()
+                .unwrap_or(DEFAULT_PROFILE_NAME)
+                .to_owned();
+            profiles.upsert(&name, credentials);
+            profiles.mark_validated(&name);
+            Ok(())
+        })?;
+        Ok(caller_identity)
+    }
+
+    /// Validates the credentials and saves them as a new named profile.
+    pub async fn add_profile(
+        &self,
+        name: &str,
+        credentials: AccessKeyCredentials,
+    ) -> Result<CallerIdentityBody, AliyunRequestCommandError<AKNotValid>> {
+        // Fail fast on naming problems, before reaching out to Aliyun.
+        let name = validate_profile_name(name)?;
+        if self.profiles()?.contains(name) {
+            return Err(ProfileError::AlreadyExists(name.to_owned()).into());
+        }
+
+        let caller_identity = self.validate_credentials(&credentials).await?;
+        self.update_profiles(|profiles| {
+            profiles.add(name, credentials)?;
+            profiles.mark_validated(name);
+            Ok(())
+        })?;
         Ok(caller_identity)
     }
+
+    /// Assumes the role once to validate it, then saves it as a new named profile.
+    pub async fn add_role_profile(
+        &self,
+        name: &str,
+        role: AssumeRoleCredentials,
+    ) -> Result<AssumedRole, AliyunRequestCommandError<AKNotValid>> {
+        let name = validate_profile_name(name)?;
+        if self.profiles()?.contains(name) {
+            return Err(ProfileError::AlreadyExists(name.to_owned()).into());
+        }
+
+        let (_, assumed_role) = self.sts().assume_role(&role).await?;
+        self.update_profiles(|profiles| {
+            profiles.add(name, role)?;
+            profiles.mark_validated(name);
+            Ok(())
+        })?;
+        Ok(assumed_role)
+    }
+
+    /// Describes the profiles of the `aliyun` CLI config, telling which ones can be imported.
+    pub fn preview_cli_profiles(
+        &self,
+        config: &CliConfig,
+    ) -> Result<Vec<CliProfilePreview>, ProfileError> {
+        Ok(config.preview(&self.profiles()?))
+    }
+
+    /// Validates the credentials of a profile of the `aliyun` CLI config, see
+    /// [`CliProfile::credentials`], and saves them under the same name.
+    ///
+    /// The access key, the source one for `RamRoleArn` profiles, is validated before
+    /// anything else. The region of the CLI profile becomes its default region.
+    pub async fn import_cli_profile(
+        &self,
+        profile: &CliProfile,
+        credentials: ProfileCredentials,
+    ) -> Result<(), AliyunRequestCommandError<AKNotValid>> {
+        match credentials {
+            ProfileCredentials::AccessKey(credentials) => {
+                self.add_profile(&profile.name, credentials).await?;
+            }
+            ProfileCredentials::AssumeRole(role) => {
+                self.validate_credentials(&role.source).await?;
+                self.add_role_profile(&profile.name, role).await?;
+            }
+        }
+
+        let region_id = profile.region_id.trim();
+        if !region_id.is_empty() {
+            // The profile is saved even if the CLI has a region this app doesn't accept.
+            if let Err(err) = self.set_default_region(&profile.name, Some(region_id)) {
+                log::warn!(
+                    "Failed to keep the region of the CLI profile {}: {err}",
+                    profile.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the saved profiles into the `aliyun` CLI config, see [`CliConfig::merge`].
+    pub fn export_cli_profiles(
+        &self,
+        config: &mut CliConfig,
+        region_id: &str,
+    ) -> Result<(), ProfileError> {
+        config.merge(&self.profiles()?, region_id);
+        Ok(())
+    }
+
+    /// Returns the access key of the profile, the source access key for role profiles,
+    /// along with the one retired by its last rotation.
+    fn profile_access_key(
+        &self,
+        name: &str,
+    ) -> Result<(AccessKeyCredentials, Option<RetiredAccessKey>), ProfileError> {
+        let profiles = self.profiles()?;
+        let credentials = profiles
+            .get(name)
+            .ok_or_else(|| ProfileError::NotFound(name.to_owned()))?;
+        Ok((
+            credentials.access_key().clone(),
+            profiles.retired(name).cloned(),
+        ))
+    }
+
+    /// Tells how old the access key of the profile is and when it was last used.
+    pub async fn access_key_age(&self, name: &str) -> Result<AccessKeyAge, AKFulfillError> {
+        let (credentials, retired) = self.profile_access_key(name)?;
+
+        let keys = self.access_keys().list_access_keys(&credentials).await?;
+        let Some(key) = keys
+            .into_iter()
+            .find(|key| key.access_key_id == credentials.access_key_id)
+        else {
+            return Err(AliyunRequestCommandError::InternalError {
+                message: format!("{credentials} is not listed among the keys of its RAM user"),
+                source: NoSource::new_boxed(),
+            });
+        };
+
+        let identity = self.validate_credentials(&credentials).await?;
+        let last_used_at = match ram_user_name(&identity.arn) {
+            Some(user_name) => self
+                .access_keys()
+                .last_used(&credentials, user_name, &key.access_key_id)
+                .await
+                .inspect_err(|err| {
+                    log::warn!("Failed to tell when {credentials} was last used: {err}")
+                })
+                .ok()
+                .flatten(),
+            None => None,
+        };
+
+        Ok(AccessKeyAge::new(
+            &key,
+            last_used_at,
+            retired.as_ref(),
+            Utc::now(),
+        ))
+    }
+
+    /// Replaces the access key of the profile with a new one created through RAM, the
+    /// source access key for role profiles.
+    ///
+    /// The new access key is validated, saved, swapped into the client when the client
+    /// is built from the profile, and the old access key is deactivated last. When a step
+    /// fails, the steps already taken are undone. The old access key is only deleted by
+    /// [`Self::delete_retired_access_key`], once nothing else uses it.
+    pub async fn rotate_access_key(
+        &self,
+        name: &str,
+        client_service: &AliyunClientService,
+    ) -> Result<AccessKeyRotation, RotationError> {
+        // Errors are turned into messages right away, they can't be held across awaits.
+        let failed = |step, message: String, rolled_back| RotationError::StepFailed {
+            step,
+            message,
+            rolled_back,
+        };
+
+        let (old, retired) = self
+            .profile_access_key(name)
+            .map_err(|err| failed(RotationStep::LoadProfile, err.to_string(), true))?;
+        if let Some(retired) = retired {
+            return Err(RotationError::NotRotatable {
+                profile: name.to_owned(),
+                reason: format!(
+                    "the access key {} retired by the previous rotation has to be deleted first",
+                    retired.access_key_id
+                ),
+            });
+        }
+
+        let new = self
+            .access_keys()
+            .create_access_key(&old)
+            .await
+            .map_err(|err| failed(RotationStep::CreateAccessKey, err.to_string(), true))?;
+        log::info!("Created {new} to replace {old} of profile {name:?}");
+
+        if let Err(message) = self.validate_new_access_key(&new).await {
+            let rolled_back = self.discard_access_key(&old, &new).await;
+            return Err(failed(
+                RotationStep::ValidateAccessKey,
+                message,
+                rolled_back,
+            ));
+        }
+
+        if let Err(message) = self.swap_access_key(name, &new, client_service).await {
+            let rolled_back = self
+                .restore_access_key(name, &old, &new, client_service)
+                .await;
+            return Err(failed(RotationStep::SwapAccessKey, message, rolled_back));
+        }
+
+        let deactivated = self
+            .access_keys()
+            .set_access_key_status(&new, &old.access_key_id, AccessKeyStatus::Inactive)
+            .await
+            .map_err(|err| err.to_string());
+        if let Err(message) = deactivated {
+            let rolled_back = self
+                .restore_access_key(name, &old, &new, client_service)
+                .await;
+            return Err(failed(
+                RotationStep::DeactivateOldAccessKey,
+                message,
+                rolled_back,
+            ));
+        }
+
+        // The rotation is done, failing to remember the old access key only means it has
+        // to be deleted from the console.
+        let retired = RetiredAccessKey {
+            access_key_id: old.access_key_id.clone(),
+            deactivated_at: Utc::now(),
+        };
+        if let Err(err) = self.update_profiles(|profiles| {
+            profiles.set_retired(name, Some(retired));
+            Ok(())
+        }) {
+            log::warn!("Failed to remember the retired {old}: {err}");
+        }
+
+        Ok(AccessKeyRotation {
+            profile: name.to_owned(),
+            access_key: new.view(Some(Utc::now())),
+            retired_access_key_id: old.access_key_id.clone(),
+        })
+    }
+
+    /// Deletes the access key the profile stopped using at its last rotation.
+    ///
+    /// Returns the ID of the deleted access key, `None` if there was none to delete.
+    pub async fn delete_retired_access_key(
+        &self,
+        name: &str,
+    ) -> Result<Option<String>, RotationError> {
+        let failed = |message: String| RotationError::StepFailed {
+            step: RotationStep::DeleteOldAccessKey,
+            message,
+            rolled_back: true,
+        };
+
+        let (credentials, retired) =
+            self.profile_access_key(name)
+                .map_err(|err| RotationError::StepFailed {
+                    step: RotationStep::LoadProfile,
+                    message: err.to_string(),
+                    rolled_back: true,
+                })?;
+        let Some(retired) = retired else {
+            return Ok(None);
+        };
+
+        self.access_keys()
+            .delete_access_key(&credentials, &retired.access_key_id)
+            .await
+            .map_err(|err| failed(err.to_string()))?;
+        self.update_profiles(|profiles| {
+            profiles.set_retired(name, None);
+            Ok(())
+        })
+        .map_err(|err| failed(err.to_string()))?;
+        log::info!("Deleted the retired access key {}", retired.access_key_id);
+        Ok(Some(retired.access_key_id))
+    }
+
+    /// Validates a freshly created access key, which takes a few seconds to be usable.
+    async fn validate_new_access_key(
+        &self,
+        credentials: &AccessKeyCredentials,
+    ) -> Result<(), String> {
+        let client = RpcClient::new(self.sts().endpoint(), credentials.into());
+        let mut attempt = 1;
+        loop {
+            let message = match Self::validate_rpc_client(&client).await {
+                Ok(_) => return Ok(()),
+                Err(AliyunRequestCommandError::Specific(err))
+                    if attempt < NEW_ACCESS_KEY_VALIDATION_ATTEMPTS =>
+                {
+                    err.to_string()
+                }
+                Err(err) => return Err(err.to_string()),
+            };
+            log::info!("{credentials} is not usable yet, attempt {attempt}: {message}");
+            attempt += 1;
+            tokio::time::sleep(NEW_ACCESS_KEY_VALIDATION_DELAY).await;
+        }
+    }
+
+    /// Saves the access key into the profile, and rebuilds the client with it when the
+    /// client is built from the profile.
+    async fn swap_access_key(
+        &self,
+        name: &str,
+        access_key: &AccessKeyCredentials,
+        client_service: &AliyunClientService,
+    ) -> Result<(), String> {
+        let credentials = self
+            .update_profiles(|profiles| {
+                profiles.replace_access_key(name, access_key.clone())?;
+                Ok(profiles.get(name).cloned())
+            })
+            .map_err(|err| err.to_string())?;
+
+        let source = CredentialSource::StoredProfile {
+            name: name.to_owned(),
+        };
+        if let Some(credentials) =
+            credentials.filter(|_| client_service.source().as_ref() == Some(&source))
+        {
+            client_service
+                .activate(credentials.into(), source, &self.sts())
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the new access key, returns whether it succeeded.
+    async fn discard_access_key(
+        &self,
+        old: &AccessKeyCredentials,
+        new: &AccessKeyCredentials,
+    ) -> bool {
+        let deleted = self
+            .access_keys()
+            .delete_access_key(old, &new.access_key_id)
+            .await
+            .map_err(|err| err.to_string());
+        match deleted {
+            Ok(()) => true,
+            Err(message) => {
+                log::error!("Failed to delete {new} while rolling back its rotation: {message}");
+                false
+            }
+        }
+    }
+
+    /// Puts the old access key back into the profile and the client, then deletes the
+    /// new one. Returns whether everything has been undone.
+    async fn restore_access_key(
+        &self,
+        name: &str,
+        old: &AccessKeyCredentials,
+        new: &AccessKeyCredentials,
+        client_service: &AliyunClientService,
+    ) -> bool {
+        let restored = match self.swap_access_key(name, old, client_service).await {
+            Ok(()) => true,
+            Err(message) => {
+                log::error!("Failed to restore {old} into profile {name:?}: {message}");
+                false
+            }
+        };
+        self.discard_access_key(old, new).await && restored
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::env;
-
     use claims::{assert_matches, assert_ok};
     use once_cell::sync::Lazy;
     use pretty_assertions::assert_eq;
 
-    use crate::services::auth::store::store_test_utils;
+    use chrono::TimeDelta;
+
+    use crate::services::{
+        auth::{
+            rotation::rotation_test_utils::{old_credentials, start_fake_ram, OLD_ACCESS_KEY_ID},
+            store::store_test_utils,
+        },
+        fake::{fake_credentials, FakeAliyun, FakeAliyunServer},
+    };
 
     use super::*;
 
-    #[derive(Debug, thiserror::Error)]
-    #[error("Failed to load test credentials from environment variables")]
-    pub struct TestSecretsError(#[from] env::VarError);
-
-    impl AccessKeyCredentials {
-        pub fn from_env() -> Result<Self, TestSecretsError> {
-            // It is recommended to store test credentials in a `.env` file at the project root
-            // for local development. Environment variables set in `.cargo/config.toml`
-            // will override corresponding values from the `.env` file.
-            //
-            // Note: some IDEs and their debuggers may not load environment variables from
-            // `.cargo/config.toml`, which can lead to different behavior when running or
-            // debugging tests inside the IDE.
-            let _ = dotenv::dotenv();
-
-            let access_key_id = env::var("TEST_ACCESS_KEY_ID")?;
-            let access_key_secret = env::var("TEST_ACCESS_KEY_SECRET")?;
-
-            Ok(Self {
-                access_key_id,
-                access_key_secret,
-            })
-        }
-    }
+    static RIGHT_ACCESS_KEY_CREDENTIALS: Lazy<AccessKeyCredentials> = Lazy::new(fake_credentials);
 
-    static RIGHT_ACCESS_KEY_CREDENTIALS: Lazy<AccessKeyCredentials> = Lazy::new(|| {
-        AccessKeyCredentials::from_env().expect("Failed to load test secrets from environment")
-    });
+    /// Starts a fake Aliyun, and a service sending its requests to it.
+    async fn start_fake_aliyun() -> (FakeAliyunServer, AccessKeyAuthService) {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store())
+            .with_endpoints(&EndpointConfig::with_base_url(server.uri()));
+        (server, auth_service)
+    }
 
     #[tokio::test]
     async fn test_validate_access_key_credential() {
         tracing_subscriber::fmt()
             .with_max_level(tracing::Level::DEBUG)
             .init();
+        let (_server, auth_service) = start_fake_aliyun().await;
 
         // Invalid credentials
         for (creds, expected_code) in [
@@ -142,7 +709,9 @@ mod tests {
             (
                 AccessKeyCredentials::new(
                     "invalid",
-                    &RIGHT_ACCESS_KEY_CREDENTIALS.access_key_secret,
+                    RIGHT_ACCESS_KEY_CREDENTIALS
+                        .access_key_secret
+                        .expose_secret(),
                 ),
                 "InvalidAccessKeyId.NotFound",
             ),
@@ -152,7 +721,9 @@ mod tests {
             ),
             (
                 AccessKeyCredentials::new(
-                    &RIGHT_ACCESS_KEY_CREDENTIALS.access_key_secret,
+                    RIGHT_ACCESS_KEY_CREDENTIALS
+                        .access_key_secret
+                        .expose_secret(),
                     &RIGHT_ACCESS_KEY_CREDENTIALS.access_key_id,
                 ),
                 "InvalidAccessKeyId.NotFound",
@@ -163,7 +734,7 @@ mod tests {
                 creds,
                 expected_code
             );
-            let result = AccessKeyAuthService::validate_access_key_credentials(creds).await;
+            let result = auth_service.validate_credentials(&creds).await;
 
             let Err(AliyunRequestCommandError::Specific(err)) = result else {
                 unreachable!()
@@ -173,10 +744,9 @@ mod tests {
         }
 
         // Valid credentials
-        let result = AccessKeyAuthService::validate_access_key_credentials(
-            RIGHT_ACCESS_KEY_CREDENTIALS.clone(),
-        )
-        .await;
+        let result = auth_service
+            .validate_credentials(&RIGHT_ACCESS_KEY_CREDENTIALS)
+            .await;
 
         let Ok(body) = result else { unreachable!() };
 
@@ -185,8 +755,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_fulfill_access_key_credentials() {
-        let auth_store = store_test_utils::init_auth_store();
-        let auth_service = AccessKeyAuthService::new(auth_store);
+        let (_server, auth_service) = start_fake_aliyun().await;
 
         let current_credentials = auth_service.current_access_key_credentials().unwrap_err();
         assert_matches!(current_credentials, QueryCredentialError::NotExist);
@@ -214,10 +783,208 @@ mod tests {
 
         assert_eq!(
             client.access_key_secret,
-            RIGHT_ACCESS_KEY_CREDENTIALS.access_key_secret
+            RIGHT_ACCESS_KEY_CREDENTIALS
+                .access_key_secret
+                .expose_secret()
         );
 
-        let result = AccessKeyAuthService::validate_access_key_credentials(client).await;
+        let result = auth_service
+            .validate_credentials(&current_credentials)
+            .await;
         assert_ok!(result);
     }
+
+    #[tokio::test]
+    async fn test_fulfill_at_local_endpoint() {
+        let (server, _) = start_fake_ram(TimeDelta::days(1)).await;
+        let endpoints = EndpointConfig::with_base_url(server.uri());
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store())
+            .with_endpoints(&endpoints);
+
+        let Err(AliyunRequestCommandError::Specific(err)) = auth_service
+            .fulfill_access_key_credentials(AccessKeyCredentials::new("UNKNOWN_ID", "SECRET"))
+            .await
+        else {
+            unreachable!()
+        };
+        assert_eq!(err.data.code, "InvalidAccessKeyId.NotFound");
+
+        let caller_identity = auth_service
+            .fulfill_access_key_credentials(old_credentials())
+            .await
+            .unwrap();
+        assert_eq!(caller_identity.account_id, "123456789012");
+        assert_eq!(
+            auth_service.current_access_key_credentials().unwrap(),
+            old_credentials()
+        );
+
+        let client_service = AliyunClientService::new();
+        client_service.set_endpoints(endpoints);
+        assert!(client_service.reload(&auth_service).await.unwrap());
+        assert!(client_service.is_valid().await.unwrap());
+        assert_eq!(
+            client_service.region_client(None).unwrap().rpc().endpoint(),
+            server.uri()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_cli_profile() {
+        let (server, _) = start_fake_ram(TimeDelta::days(1)).await;
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store())
+            .with_endpoints(&EndpointConfig::with_base_url(server.uri()));
+
+        let old = old_credentials();
+        let mut profile = CliProfile::default();
+        profile.name = "cli".to_owned();
+        profile.mode = "AK".to_owned();
+        profile.access_key_id = old.access_key_id;
+        profile.access_key_secret = old.access_key_secret;
+        profile.region_id = "cn-shanghai".to_owned();
+
+        let credentials = profile.credentials().unwrap();
+        assert_ok!(auth_service.import_cli_profile(&profile, credentials).await);
+        assert_eq!(
+            auth_service.default_region("cli").unwrap().as_deref(),
+            Some("cn-shanghai")
+        );
+    }
+
+    #[test]
+    fn test_logout() {
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store());
+        assert_eq!(
+            auth_service.logout(None).unwrap(),
+            LogoutOutcome::NothingToDelete
+        );
+
+        auth_service
+            .update_profiles(|profiles| {
+                profiles.add("work", AccessKeyCredentials::new("WORK_ID", "WORK_SECRET"))?;
+                profiles.add("home", AccessKeyCredentials::new("HOME_ID", "HOME_SECRET"))
+            })
+            .unwrap();
+
+        assert_eq!(
+            auth_service.logout(Some("missing")).unwrap(),
+            LogoutOutcome::NothingToDelete
+        );
+        assert_eq!(
+            auth_service.logout(Some("home")).unwrap(),
+            LogoutOutcome::ProfileDeleted {
+                name: "home".to_owned(),
+                was_active: false
+            }
+        );
+        assert_eq!(
+            auth_service.active_profile().unwrap().as_deref(),
+            Some("work")
+        );
+
+        assert_eq!(
+            auth_service.logout(None).unwrap(),
+            LogoutOutcome::AllDeleted
+        );
+        assert!(auth_service.list_profiles().unwrap().is_empty());
+        assert_matches!(
+            auth_service.current_profile(),
+            Err(QueryCredentialError::NotExist)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotate_access_key() {
+        let (server, ram) = start_fake_ram(TimeDelta::days(100)).await;
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store())
+            .with_sts_provider(AssumeRoleProvider::new(server.uri()))
+            .with_access_key_manager(AccessKeyManager::new(server.uri()));
+        auth_service
+            .update_profiles(|profiles| profiles.add("work", old_credentials()))
+            .unwrap();
+        let client_service = AliyunClientService::new();
+        assert!(client_service.reload(&auth_service).await.unwrap());
+
+        let age = auth_service.access_key_age("work").await.unwrap();
+        assert_eq!(age.access_key_id, OLD_ACCESS_KEY_ID);
+        assert_eq!(age.age_days, 100);
+        assert!(age.rotation_due);
+        assert!(age.last_used_at.is_some());
+
+        // A failing step leaves everything as it was.
+        *ram.failing_action.lock().unwrap() = Some("UpdateAccessKey");
+        let err = auth_service
+            .rotate_access_key("work", &client_service)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            RotationError::StepFailed {
+                step: RotationStep::DeactivateOldAccessKey,
+                rolled_back: true,
+                ..
+            }
+        );
+        assert_eq!(ram.keys.lock().unwrap().len(), 1);
+        assert_eq!(
+            client_service.credentials().unwrap().access_key_id,
+            OLD_ACCESS_KEY_ID
+        );
+        assert_eq!(
+            auth_service.current_access_key_credentials().unwrap(),
+            old_credentials()
+        );
+
+        *ram.failing_action.lock().unwrap() = None;
+        let rotation = auth_service
+            .rotate_access_key("work", &client_service)
+            .await
+            .unwrap();
+        assert_eq!(rotation.retired_access_key_id, OLD_ACCESS_KEY_ID);
+        let new_access_key_id = rotation.access_key.access_key_id;
+        assert_eq!(
+            auth_service
+                .current_access_key_credentials()
+                .unwrap()
+                .access_key_id,
+            new_access_key_id
+        );
+        assert_eq!(
+            client_service.credentials().unwrap().access_key_id,
+            new_access_key_id
+        );
+        assert_eq!(
+            ram.keys.lock().unwrap()[0].status,
+            AccessKeyStatus::Inactive
+        );
+
+        assert_matches!(
+            auth_service
+                .rotate_access_key("work", &client_service)
+                .await,
+            Err(RotationError::NotRotatable { .. })
+        );
+        let age = auth_service.access_key_age("work").await.unwrap();
+        assert!(!age.rotation_due);
+        assert_eq!(
+            age.retired_access_key_id.as_deref(),
+            Some(OLD_ACCESS_KEY_ID)
+        );
+
+        assert_eq!(
+            auth_service
+                .delete_retired_access_key("work")
+                .await
+                .unwrap(),
+            Some(OLD_ACCESS_KEY_ID.to_owned())
+        );
+        assert_eq!(
+            auth_service
+                .delete_retired_access_key("work")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(ram.keys.lock().unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    auth::{
+        rotation::RetiredAccessKey,
+        store::{QueryCredentialError, SaveCredentialError},
+        types::{AccessKeyCredentials, AccessKeyView, ProfileCredentials},
+    },
+    error::{AliyunRequestCommandError, NoOther},
+    region::is_valid_region_id,
+};
+
+/// Name of the profile used when credentials are saved without choosing one.
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+const MAX_PROFILE_NAME_LENGTH: usize = 64;
+
+/// All the named credential profiles, persisted as a whole by an [`AuthStore`].
+///
+/// [`AuthStore`]: crate::services::auth::store::AuthStore
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialProfiles {
+    active: Option<String>,
+    profiles: BTreeMap<String, ProfileCredentials>,
+    /// When the credentials of each profile were last validated against Aliyun.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    validated_at: BTreeMap<String, DateTime<Utc>>,
+    /// The access key each profile stopped using at its last rotation, until deleted.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    retired: BTreeMap<String, RetiredAccessKey>,
+    /// The region each profile works in unless told otherwise.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    default_regions: BTreeMap<String, String>,
+}
+
+/// What the frontend gets to know about a profile.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ProfileSummary {
+    pub name: String,
+    /// The access key in use, the source access key for role profiles.
+    pub access_key: AccessKeyView,
+    /// The role assumed by the profile, if it is a role profile.
+    pub role_arn: Option<String>,
+    pub default_region: Option<String>,
+    pub active: bool,
+}
+
+/// What logging out removed from the credential store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type", content = "detail")]
+pub enum LogoutOutcome {
+    /// There was no stored credential to remove.
+    NothingToDelete,
+    /// The profile has been removed, the others are kept.
+    ProfileDeleted { name: String, was_active: bool },
+    /// Every stored profile has been removed.
+    AllDeleted,
+}
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum ProfileError {
+    #[error("profile name {0:?} is not valid")]
+    InvalidName(String),
+    #[error("profile {0:?} doesn't exist")]
+    NotFound(String),
+    #[error("profile {0:?} already exists")]
+    AlreadyExists(String),
+    #[error("region ID {0:?} is not valid")]
+    InvalidRegion(String),
+    #[error("failed to query the credential profiles: {0}")]
+    QueryError(#[from] QueryCredentialError),
+    #[error("failed to save the credential profiles: {0}")]
+    SaveError(#[from] SaveCredentialError),
+    #[error("failed to build the client of the active profile: {0}")]
+    ClientError(#[from] AliyunRequestCommandError<NoOther>),
+}
+
+/// Trims the profile name and checks it is neither empty nor too long.
+pub fn validate_profile_name(name: &str) -> Result<&str, ProfileError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed.chars().count() > MAX_PROFILE_NAME_LENGTH
+        || trimmed.chars().any(char::is_control)
+    {
+        return Err(ProfileError::InvalidName(name.to_owned()));
+    }
+    Ok(trimmed)
+}
+
+impl CredentialProfiles {
+    /// Creates profiles holding a single active profile.
+    pub fn single<S: Into<String>, C: Into<ProfileCredentials>>(name: S, credentials: C) -> Self {
+        let name = name.into();
+        Self {
+            active: Some(name.clone()),
+            profiles: BTreeMap::from([(name, credentials.into())]),
+            validated_at: BTreeMap::new(),
+            retired: BTreeMap::new(),
+            default_regions: BTreeMap::new(),
+        }
+    }
+
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    pub fn active(&self) -> Option<(&str, &ProfileCredentials)> {
+        let name = self.active.as_deref()?;
+        self.profiles
+            .get(name)
+            .map(|credentials| (name, credentials))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ProfileCredentials> {
+        self.profiles.get(name)
+    }
+
+    pub fn validated_at(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.validated_at.get(name).copied()
+    }
+
+    /// Records that the credentials of the profile have just been validated.
+    pub fn mark_validated(&mut self, name: &str) {
+        if self.contains(name) {
+            self.validated_at.insert(name.to_owned(), Utc::now());
+        }
+    }
+
+    pub fn default_region(&self, name: &str) -> Option<&str> {
+        self.default_regions.get(name).map(String::as_str)
+    }
+
+    /// Sets the region the profile works in, or forgets it when `None`.
+    pub fn set_default_region(
+        &mut self,
+        name: &str,
+        region_id: Option<&str>,
+    ) -> Result<(), ProfileError> {
+        if !self.contains(name) {
+            return Err(ProfileError::NotFound(name.to_owned()));
+        }
+        match region_id.map(str::trim) {
+            Some(region_id) if !is_valid_region_id(region_id) => {
+                return Err(ProfileError::InvalidRegion(region_id.to_owned()));
+            }
+            Some(region_id) => {
+                self.default_regions
+                    .insert(name.to_owned(), region_id.to_owned());
+            }
+            None => {
+                self.default_regions.remove(name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn retired(&self, name: &str) -> Option<&RetiredAccessKey> {
+        self.retired.get(name)
+    }
+
+    /// Records the access key the profile stopped using, or forgets it when `None`.
+    pub fn set_retired(&mut self, name: &str, retired: Option<RetiredAccessKey>) {
+        match retired {
+            Some(retired) if self.contains(name) => {
+                self.retired.insert(name.to_owned(), retired);
+            }
+            _ => {
+                self.retired.remove(name);
+            }
+        }
+    }
+
+    /// Swaps the access key of the profile, the source access key for role profiles,
+    /// and returns the previous one. The new access key is assumed to be validated.
+    pub fn replace_access_key(
+        &mut self,
+        name: &str,
+        access_key: AccessKeyCredentials,
+    ) -> Result<AccessKeyCredentials, ProfileError> {
+        let credentials = self
+            .profiles
+            .get_mut(name)
+            .ok_or_else(|| ProfileError::NotFound(name.to_owned()))?;
+        let previous = std::mem::replace(credentials.access_key_mut(), access_key);
+        self.mark_validated(name);
+        Ok(previous)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.profiles.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ProfileCredentials)> {
+        self.profiles
+            .iter()
+            .map(|(name, credentials)| (name.as_str(), credentials))
+    }
+
+    pub fn summaries(&self) -> Vec<ProfileSummary> {
+        self.profiles
+            .iter()
+            .map(|(name, credentials)| ProfileSummary {
+                name: name.clone(),
+                access_key: credentials.access_key().view(self.validated_at(name)),
+                role_arn: match credentials {
+                    ProfileCredentials::AccessKey(_) => None,
+                    ProfileCredentials::AssumeRole(role) => Some(role.role_arn.clone()),
+                },
+                default_region: self.default_regions.get(name).cloned(),
+                active: self.active.as_deref() == Some(name),
+            })
+            .collect()
+    }
+
+    /// Adds a new profile, which becomes active if no profile is active yet.
+    pub fn add<C: Into<ProfileCredentials>>(
+        &mut self,
+        name: &str,
+        credentials: C,
+    ) -> Result<(), ProfileError> {
+        let name = validate_profile_name(name)?;
+        if self.contains(name) {
+            return Err(ProfileError::AlreadyExists(name.to_owned()));
+        }
+        self.upsert(name, credentials);
+        Ok(())
+    }
+
+    /// Inserts or replaces the credentials of a profile, which becomes active if no
+    /// profile is active yet.
+    ///
+    /// The new credentials are not considered validated until [`Self::mark_validated`].
+    pub fn upsert<C: Into<ProfileCredentials>>(&mut self, name: &str, credentials: C) {
+        self.profiles.insert(name.to_owned(), credentials.into());
+        self.validated_at.remove(name);
+        self.retired.remove(name);
+        if self.active().is_none() {
+            self.active = Some(name.to_owned());
+        }
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), ProfileError> {
+        let to = validate_profile_name(to)?;
+        if from == to {
+            return Ok(());
+        }
+        if self.contains(to) {
+            return Err(ProfileError::AlreadyExists(to.to_owned()));
+        }
+
+        let credentials = self
+            .profiles
+            .remove(from)
+            .ok_or_else(|| ProfileError::NotFound(from.to_owned()))?;
+        self.profiles.insert(to.to_owned(), credentials);
+        if let Some(validated_at) = self.validated_at.remove(from) {
+            self.validated_at.insert(to.to_owned(), validated_at);
+        }
+        if let Some(retired) = self.retired.remove(from) {
+            self.retired.insert(to.to_owned(), retired);
+        }
+        if let Some(region_id) = self.default_regions.remove(from) {
+            self.default_regions.insert(to.to_owned(), region_id);
+        }
+        if self.active.as_deref() == Some(from) {
+            self.active = Some(to.to_owned());
+        }
+        Ok(())
+    }
+
+    /// Removes a profile, leaving no profile active if it was the active one.
+    pub fn remove(&mut self, name: &str) -> Result<ProfileCredentials, ProfileError> {
+        let credentials = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| ProfileError::NotFound(name.to_owned()))?;
+        self.validated_at.remove(name);
+        self.retired.remove(name);
+        self.default_regions.remove(name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+        Ok(credentials)
+    }
+
+    pub fn set_active(&mut self, name: &str) -> Result<(), ProfileError> {
+        if !self.contains(name) {
+            return Err(ProfileError::NotFound(name.to_owned()));
+        }
+        self.active = Some(name.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok};
+    use pretty_assertions::assert_eq;
+
+    use crate::services::auth::types::{AccessKeyCredentials, AssumeRoleCredentials};
+
+    use super::*;
+
+    #[test]
+    fn test_credential_profiles() {
+        let production: ProfileCredentials =
+            AccessKeyCredentials::new("PRODUCTION_ID", "PRODUCTION_SECRET").into();
+        let staging: ProfileCredentials = AssumeRoleCredentials {
+            source: AccessKeyCredentials::new("STAGING_ID", "STAGING_SECRET"),
+            role_arn: "acs:ram::123456789012:role/staging".to_owned(),
+            role_session_name: "aliyun-ecs-manager".to_owned(),
+            duration_seconds: 3600,
+            external_id: None,
+        }
+        .into();
+
+        let mut profiles = CredentialProfiles::default();
+        assert_eq!(profiles.active(), None);
+
+        assert_ok!(profiles.add(" production ", production.clone()));
+        assert_ok!(profiles.add("staging", staging.clone()));
+        assert_eq!(profiles.active(), Some(("production", &production)));
+
+        assert_matches!(
+            profiles.add("staging", production.clone()),
+            Err(ProfileError::AlreadyExists(_))
+        );
+        assert_matches!(
+            profiles.add("  ", production.clone()),
+            Err(ProfileError::InvalidName(_))
+        );
+
+        assert_ok!(profiles.rename("production", "prod"));
+        assert_eq!(profiles.active(), Some(("prod", &production)));
+        assert_matches!(
+            profiles.rename("prod", "staging"),
+            Err(ProfileError::AlreadyExists(_))
+        );
+        assert_matches!(
+            profiles.rename("missing", "other"),
+            Err(ProfileError::NotFound(_))
+        );
+
+        assert_ok!(profiles.set_active("staging"));
+        profiles.mark_validated("staging");
+        assert_ok!(profiles.set_default_region("staging", Some("ap-southeast-1")));
+        assert_matches!(
+            profiles.set_default_region("staging", Some("Hangzhou")),
+            Err(ProfileError::InvalidRegion(_))
+        );
+        let validated_at = profiles.validated_at("staging").unwrap();
+        assert_eq!(
+            profiles.summaries(),
+            vec![
+                ProfileSummary {
+                    name: "prod".to_owned(),
+                    access_key: AccessKeyView {
+                        access_key_id: "PRODUCTION_ID".to_owned(),
+                        masked_secret: "****CRET".to_owned(),
+                        validated_at: None,
+                    },
+                    role_arn: None,
+                    default_region: None,
+                    active: false,
+                },
+                ProfileSummary {
+                    name: "staging".to_owned(),
+                    access_key: AccessKeyView {
+                        access_key_id: "STAGING_ID".to_owned(),
+                        masked_secret: "****CRET".to_owned(),
+                        validated_at: Some(validated_at.to_rfc3339()),
+                    },
+                    role_arn: Some("acs:ram::123456789012:role/staging".to_owned()),
+                    default_region: Some("ap-southeast-1".to_owned()),
+                    active: true,
+                },
+            ]
+        );
+
+        assert_eq!(profiles.remove("staging").unwrap(), staging);
+        assert_eq!(profiles.active(), None);
+        assert_eq!(profiles.validated_at("staging"), None);
+        assert_eq!(profiles.default_region("staging"), None);
+        assert_matches!(
+            profiles.set_active("staging"),
+            Err(ProfileError::NotFound(_))
+        );
+
+        // Saving credentials again picks an active profile back.
+        profiles.upsert("prod", production.clone());
+        assert_eq!(profiles.active(), Some(("prod", &production)));
+    }
+}
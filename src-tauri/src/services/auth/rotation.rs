@@ -0,0 +1,534 @@
+use std::fmt;
+
+use alibabacloud::client::error::OperationError;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::services::{
+    auth::{
+        error::{AKFulfillError, AKNotValid},
+        secret::SecretString,
+        types::{AccessKeyCredentials, AccessKeyView},
+    },
+    retry::CallError,
+    rpc::{RpcClient, RpcParams},
+};
+
+pub const DEFAULT_RAM_ENDPOINT: &str = "ram.aliyuncs.com";
+const RAM_API_VERSION: &str = "2015-05-01";
+
+/// How old an access key may get before it has to be rotated.
+pub const ROTATION_PERIOD_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum AccessKeyStatus {
+    Active,
+    Inactive,
+}
+
+impl AccessKeyStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            AccessKeyStatus::Active => "Active",
+            AccessKeyStatus::Inactive => "Inactive",
+        }
+    }
+}
+
+/// An access key of the RAM user, as listed by RAM.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RamAccessKey {
+    pub access_key_id: String,
+    pub status: AccessKeyStatus,
+    pub create_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListAccessKeysBody {
+    access_keys: ListedAccessKeys,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedAccessKeys {
+    #[serde(default)]
+    access_key: Vec<RamAccessKey>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreateAccessKeyBody {
+    access_key: CreatedAccessKey,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreatedAccessKey {
+    access_key_id: String,
+    access_key_secret: SecretString,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccessKeyLastUsedBody {
+    access_key_last_used: AccessKeyLastUsed,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccessKeyLastUsed {
+    #[serde(default)]
+    last_used_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmptyBody {}
+
+/// Extracts the name of the RAM user from the ARN of a caller identity, `None` for the
+/// root account or an assumed role.
+pub fn ram_user_name(arn: &str) -> Option<&str> {
+    arn.split_once(":user/").map(|(_, name)| name)
+}
+
+/// Manages the access keys of the RAM user the given credentials belong to.
+///
+/// No `UserName` is sent, so RAM applies every action to the caller itself.
+#[derive(Clone)]
+pub struct AccessKeyManager {
+    endpoint: String,
+}
+
+impl Default for AccessKeyManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_RAM_ENDPOINT)
+    }
+}
+
+impl AccessKeyManager {
+    pub fn new<E: Into<String>>(endpoint: E) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        credentials: &AccessKeyCredentials,
+        action: &str,
+        params: RpcParams,
+    ) -> Result<T, CallError> {
+        RpcClient::new(self.endpoint.as_str(), credentials.into())
+            .call(action, RAM_API_VERSION, params)
+            .await
+    }
+
+    pub async fn list_access_keys(
+        &self,
+        credentials: &AccessKeyCredentials,
+    ) -> Result<Vec<RamAccessKey>, AKFulfillError> {
+        let body: ListAccessKeysBody = self
+            .call(credentials, "ListAccessKeys", RpcParams::new())
+            .await
+            .map_err(AKNotValid::classify)?;
+        Ok(body.access_keys.access_key)
+    }
+
+    /// When the access key was last used, `None` if it never was.
+    ///
+    /// RAM only answers for RAM users, whose name has to be given.
+    pub async fn last_used(
+        &self,
+        credentials: &AccessKeyCredentials,
+        user_name: &str,
+        access_key_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, AKFulfillError> {
+        let params = RpcParams::from([
+            ("UserName".to_owned(), user_name.to_owned()),
+            ("UserAccessKeyId".to_owned(), access_key_id.to_owned()),
+        ]);
+        let body: AccessKeyLastUsedBody = self
+            .call(credentials, "GetAccessKeyLastUsed", params)
+            .await
+            .map_err(AKNotValid::classify)?;
+        Ok(body
+            .access_key_last_used
+            .last_used_date
+            .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+            .map(|date| date.to_utc()))
+    }
+
+    pub async fn create_access_key(
+        &self,
+        credentials: &AccessKeyCredentials,
+    ) -> Result<AccessKeyCredentials, AKFulfillError> {
+        let body: CreateAccessKeyBody = self
+            .call(credentials, "CreateAccessKey", RpcParams::new())
+            .await
+            .map_err(AKNotValid::classify)?;
+        Ok(AccessKeyCredentials {
+            access_key_id: body.access_key.access_key_id,
+            access_key_secret: body.access_key.access_key_secret,
+        })
+    }
+
+    pub async fn set_access_key_status(
+        &self,
+        credentials: &AccessKeyCredentials,
+        access_key_id: &str,
+        status: AccessKeyStatus,
+    ) -> Result<(), AKFulfillError> {
+        let params = RpcParams::from([
+            ("UserAccessKeyId".to_owned(), access_key_id.to_owned()),
+            ("Status".to_owned(), status.as_str().to_owned()),
+        ]);
+        self.call::<EmptyBody>(credentials, "UpdateAccessKey", params)
+            .await
+            .map_err(AKNotValid::classify)?;
+        Ok(())
+    }
+
+    /// Deletes the access key, which succeeds when it doesn't exist anymore.
+    pub async fn delete_access_key(
+        &self,
+        credentials: &AccessKeyCredentials,
+        access_key_id: &str,
+    ) -> Result<(), AKFulfillError> {
+        let params = RpcParams::from([("UserAccessKeyId".to_owned(), access_key_id.to_owned())]);
+        match self
+            .call::<EmptyBody>(credentials, "DeleteAccessKey", params)
+            .await
+        {
+            Ok(_) => Ok(()),
+            // Already deleted, e.g. from the console.
+            Err(CallError {
+                error: OperationError::Rejected(rejection),
+                ..
+            }) if rejection.code.starts_with("EntityNotExist") => Ok(()),
+            Err(err) => Err(AKNotValid::classify(err)),
+        }
+    }
+}
+
+/// An access key deactivated by a rotation, kept until it is deleted for good.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetiredAccessKey {
+    pub access_key_id: String,
+    pub deactivated_at: DateTime<Utc>,
+}
+
+/// How old the access key of a profile is, and whether it is due for rotation.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct AccessKeyAge {
+    pub access_key_id: String,
+    /// In RFC 3339 format, like the other timestamps.
+    pub created_at: String,
+    pub age_days: i32,
+    /// `None` when the key has never been used, or RAM can't tell, e.g. for the root
+    /// account.
+    pub last_used_at: Option<String>,
+    pub rotation_due_at: String,
+    pub rotation_due: bool,
+    /// The key deactivated by the last rotation, still to be deleted.
+    pub retired_access_key_id: Option<String>,
+}
+
+impl AccessKeyAge {
+    pub fn new(
+        key: &RamAccessKey,
+        last_used_at: Option<DateTime<Utc>>,
+        retired: Option<&RetiredAccessKey>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let rotation_due_at = key.create_date + TimeDelta::days(ROTATION_PERIOD_DAYS);
+        Self {
+            access_key_id: key.access_key_id.clone(),
+            created_at: key.create_date.to_rfc3339(),
+            age_days: i32::try_from((now - key.create_date).num_days()).unwrap_or(i32::MAX),
+            last_used_at: last_used_at.map(|date| date.to_rfc3339()),
+            rotation_due_at: rotation_due_at.to_rfc3339(),
+            rotation_due: rotation_due_at <= now,
+            retired_access_key_id: retired.map(|retired| retired.access_key_id.clone()),
+        }
+    }
+}
+
+/// The result of a successful rotation.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct AccessKeyRotation {
+    pub profile: String,
+    /// The new access key, now in use.
+    pub access_key: AccessKeyView,
+    /// The previous access key, deactivated but not deleted yet.
+    pub retired_access_key_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+pub enum RotationStep {
+    LoadProfile,
+    CreateAccessKey,
+    ValidateAccessKey,
+    SwapAccessKey,
+    DeactivateOldAccessKey,
+    DeleteOldAccessKey,
+}
+
+impl fmt::Display for RotationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RotationStep::LoadProfile => "load the profile",
+            RotationStep::CreateAccessKey => "create the new access key",
+            RotationStep::ValidateAccessKey => "validate the new access key",
+            RotationStep::SwapAccessKey => "switch to the new access key",
+            RotationStep::DeactivateOldAccessKey => "deactivate the old access key",
+            RotationStep::DeleteOldAccessKey => "delete the old access key",
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum RotationError {
+    #[error("the access key of profile {profile:?} can't be rotated: {reason}")]
+    NotRotatable { profile: String, reason: String },
+    /// A step failed, after which the changes made so far have been undone, unless
+    /// `rolled_back` is false.
+    #[error("failed to {step}: {message}")]
+    StepFailed {
+        step: RotationStep,
+        message: String,
+        rolled_back: bool,
+    },
+}
+
+#[cfg(test)]
+pub mod rotation_test_utils {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    use super::*;
+
+    pub const OLD_ACCESS_KEY_ID: &str = "OLD_ACCESS_KEY_ID";
+    pub const OLD_ACCESS_KEY_SECRET: &str = "OLD_ACCESS_KEY_SECRET";
+    pub const RAM_USER_ARN: &str = "acs:ram::123456789012:user/operator";
+
+    /// A RAM user with access keys, which also answers `GetCallerIdentity` like STS.
+    pub struct FakeRam {
+        pub keys: Mutex<Vec<RamAccessKey>>,
+        /// The action to be rejected, to exercise the rollbacks.
+        pub failing_action: Mutex<Option<&'static str>>,
+    }
+
+    fn rejection(status: u16, code: &str) -> ResponseTemplate {
+        ResponseTemplate::new(status).set_body_json(json!({
+            "RequestId": "FAKE-REQUEST-ID",
+            "HostId": "ram.aliyuncs.com",
+            "Code": code,
+            "Message": code,
+            "Recommend": "",
+        }))
+    }
+
+    impl Respond for FakeRam {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let param = |name: &str| {
+                request
+                    .url
+                    .query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default()
+            };
+            let action = param("Action");
+            let mut keys = self.keys.lock().unwrap();
+
+            let caller = param("AccessKeyId");
+            match keys.iter().find(|key| key.access_key_id == caller) {
+                None => return rejection(404, "InvalidAccessKeyId.NotFound"),
+                Some(key) if key.status == AccessKeyStatus::Inactive => {
+                    return rejection(400, "InvalidAccessKeyId.Inactive")
+                }
+                Some(_) => {}
+            }
+            if *self.failing_action.lock().unwrap() == Some(action.as_str()) {
+                return rejection(500, "InternalError");
+            }
+
+            let target = param("UserAccessKeyId");
+            match action.as_str() {
+                "GetCallerIdentity" => ResponseTemplate::new(200).set_body_json(json!({
+                    "IdentityType": "RAMUser",
+                    "RequestId": "FAKE-REQUEST-ID",
+                    "AccountId": "123456789012",
+                    "PrincipalId": "276543210987654321",
+                    "UserId": "276543210987654321",
+                    "Arn": RAM_USER_ARN,
+                })),
+                "ListAccessKeys" => {
+                    let listed = keys
+                        .iter()
+                        .map(|key| {
+                            json!({
+                                "AccessKeyId": key.access_key_id,
+                                "Status": key.status,
+                                "CreateDate": key.create_date.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    ResponseTemplate::new(200).set_body_json(json!({
+                        "RequestId": "FAKE-REQUEST-ID",
+                        "AccessKeys": { "AccessKey": listed },
+                    }))
+                }
+                "GetAccessKeyLastUsed" => ResponseTemplate::new(200).set_body_json(json!({
+                    "RequestId": "FAKE-REQUEST-ID",
+                    "AccessKeyLastUsed": { "LastUsedDate": "2026-01-02T03:04:05Z" },
+                })),
+                "CreateAccessKey" => {
+                    if keys.len() >= 2 {
+                        return rejection(409, "LimitExceeded.AccessKey");
+                    }
+                    let access_key_id = format!("NEW_ACCESS_KEY_ID_{}", keys.len());
+                    keys.push(RamAccessKey {
+                        access_key_id: access_key_id.clone(),
+                        status: AccessKeyStatus::Active,
+                        create_date: Utc::now(),
+                    });
+                    ResponseTemplate::new(200).set_body_json(json!({
+                        "RequestId": "FAKE-REQUEST-ID",
+                        "AccessKey": {
+                            "AccessKeyId": access_key_id,
+                            "AccessKeySecret": "NEW_ACCESS_KEY_SECRET",
+                            "Status": "Active",
+                            "CreateDate": Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                        },
+                    }))
+                }
+                "UpdateAccessKey" => {
+                    let Some(key) = keys.iter_mut().find(|key| key.access_key_id == target) else {
+                        return rejection(404, "EntityNotExist.User.AccessKey");
+                    };
+                    key.status = if param("Status") == "Active" {
+                        AccessKeyStatus::Active
+                    } else {
+                        AccessKeyStatus::Inactive
+                    };
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "RequestId": "FAKE-REQUEST-ID" }))
+                }
+                "DeleteAccessKey" => {
+                    if !keys.iter().any(|key| key.access_key_id == target) {
+                        return rejection(404, "EntityNotExist.User.AccessKey");
+                    }
+                    keys.retain(|key| key.access_key_id != target);
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "RequestId": "FAKE-REQUEST-ID" }))
+                }
+                _ => rejection(400, "InvalidAction.NotFound"),
+            }
+        }
+    }
+
+    /// Starts a fake RAM user owning only the old access key, created `age` ago.
+    pub async fn start_fake_ram(age: TimeDelta) -> (MockServer, Arc<FakeRam>) {
+        let server = MockServer::start().await;
+        let ram = Arc::new(FakeRam {
+            keys: Mutex::new(vec![RamAccessKey {
+                access_key_id: OLD_ACCESS_KEY_ID.to_owned(),
+                status: AccessKeyStatus::Active,
+                create_date: Utc::now() - age,
+            }]),
+            failing_action: Mutex::new(None),
+        });
+        Mock::given(method("GET"))
+            .respond_with(SharedFakeRam(ram.clone()))
+            .mount(&server)
+            .await;
+        (server, ram)
+    }
+
+    struct SharedFakeRam(Arc<FakeRam>);
+
+    impl Respond for SharedFakeRam {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            self.0.respond(request)
+        }
+    }
+
+    pub fn old_credentials() -> AccessKeyCredentials {
+        AccessKeyCredentials::new(OLD_ACCESS_KEY_ID, OLD_ACCESS_KEY_SECRET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_ok;
+    use pretty_assertions::assert_eq;
+
+    use super::{rotation_test_utils::*, *};
+
+    #[test]
+    fn test_access_key_age() {
+        let now = Utc::now();
+        let key = RamAccessKey {
+            access_key_id: OLD_ACCESS_KEY_ID.to_owned(),
+            status: AccessKeyStatus::Active,
+            create_date: now - TimeDelta::days(100),
+        };
+        let age = AccessKeyAge::new(&key, None, None, now);
+        assert_eq!(age.age_days, 100);
+        assert!(age.rotation_due);
+
+        let key = RamAccessKey {
+            create_date: now - TimeDelta::days(10),
+            ..key
+        };
+        let age = AccessKeyAge::new(&key, Some(now), None, now);
+        assert_eq!(age.age_days, 10);
+        assert!(!age.rotation_due);
+        assert_eq!(age.last_used_at, Some(now.to_rfc3339()));
+
+        assert_eq!(ram_user_name(RAM_USER_ARN), Some("operator"));
+        assert_eq!(ram_user_name("acs:ram::123456789012:root"), None);
+    }
+
+    #[tokio::test]
+    async fn test_access_key_manager() {
+        let (server, ram) = start_fake_ram(TimeDelta::days(1)).await;
+        let manager = AccessKeyManager::new(server.uri());
+        let old = old_credentials();
+
+        let created = manager.create_access_key(&old).await.unwrap();
+        assert_eq!(manager.list_access_keys(&old).await.unwrap().len(), 2);
+        assert!(manager
+            .last_used(&old, "operator", OLD_ACCESS_KEY_ID)
+            .await
+            .unwrap()
+            .is_some());
+
+        manager
+            .set_access_key_status(&created, OLD_ACCESS_KEY_ID, AccessKeyStatus::Inactive)
+            .await
+            .unwrap();
+        assert_eq!(
+            ram.keys.lock().unwrap()[0].status,
+            AccessKeyStatus::Inactive
+        );
+
+        manager
+            .delete_access_key(&created, OLD_ACCESS_KEY_ID)
+            .await
+            .unwrap();
+        let keys = manager.list_access_keys(&created).await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].access_key_id, created.access_key_id);
+        assert_ok!(manager.delete_access_key(&created, OLD_ACCESS_KEY_ID).await);
+    }
+}
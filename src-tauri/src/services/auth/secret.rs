@@ -0,0 +1,99 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+const REDACTED: &str = "[REDACTED]";
+const MASK: &str = "****";
+/// How many trailing characters [`SecretString::masked`] leaves visible.
+const VISIBLE_SUFFIX_LENGTH: usize = 4;
+
+/// A string which never shows up in `Debug` or `Display` output, and is wiped from
+/// memory when dropped.
+///
+/// The value has to be read explicitly through [`SecretString::expose_secret`]. It is
+/// still serialized as is, since the credential store has to persist it, so types
+/// holding one must not be sent to the frontend.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new<S: Into<String>>(secret: S) -> Self {
+        Self(secret.into())
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Masks all but the last characters of the secret, all of them for short secrets.
+    pub fn masked(&self) -> String {
+        let length = self.0.chars().count();
+        if length <= VISIBLE_SUFFIX_LENGTH * 2 {
+            return MASK.to_owned();
+        }
+        let suffix: String = self
+            .0
+            .chars()
+            .skip(length - VISIBLE_SUFFIX_LENGTH)
+            .collect();
+        format!("{MASK}{suffix}")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_secret_string() {
+        let secret = SecretString::new("AccessKeySecretValue1234");
+        assert_eq!(format!("{secret:?}"), REDACTED);
+        assert_eq!(secret.to_string(), REDACTED);
+        assert_eq!(secret.masked(), "****1234");
+        assert_eq!(secret.expose_secret(), "AccessKeySecretValue1234");
+        assert_eq!(SecretString::new("short").masked(), MASK);
+
+        assert_eq!(
+            serde_json::to_string(&secret).unwrap(),
+            "\"AccessKeySecretValue1234\""
+        );
+    }
+}
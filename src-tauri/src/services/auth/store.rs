@@ -1,7 +1,9 @@
+use std::sync::Arc;
+
 use serde::Serialize;
 
 use crate::{
-    services::auth::types::AccessKeyCredentials,
+    services::auth::{profile::CredentialProfiles, types::AccessKeyCredentials},
     types::{SerdeJsonError, Store},
 };
 
@@ -11,11 +13,29 @@ pub trait AuthStore<C> {
     fn delete(&self) -> bool;
 }
 
+impl<C, T: AuthStore<C> + ?Sized> AuthStore<C> for Arc<T> {
+    fn save(&self, credential: C) -> Result<(), SaveCredentialError> {
+        (**self).save(credential)
+    }
+
+    fn query(&self) -> Result<C, QueryCredentialError> {
+        (**self).query()
+    }
+
+    fn delete(&self) -> bool {
+        (**self).delete()
+    }
+}
+
 #[derive(Debug, thiserror::Error, Serialize, specta::Type)]
 #[serde(tag = "type", content = "error")]
 pub enum QueryCredentialError {
     #[error("access key credential hasn't been saved")]
     NotExist,
+    #[error("the credential store is locked")]
+    Locked,
+    #[error("failed to decrypt the credential, the store may be corrupted")]
+    DecryptError,
     #[error("failed to deserialize credential using serde_json: {}", .0.0)]
     DeserializeError(#[from] SerdeJsonError),
 }
@@ -24,6 +44,10 @@ pub enum QueryCredentialError {
 pub enum SaveCredentialError {
     #[error("failed to serialize credential using serde_json: {}", 0.0)]
     SerializeError(#[from] SerdeJsonError),
+    #[error("the credential store is locked")]
+    Locked,
+    #[error("failed to encrypt the credential")]
+    EncryptError,
 }
 
 impl From<serde_json::Error> for QueryCredentialError {
@@ -44,7 +68,7 @@ pub struct AccessKeyAuthStore<R: tauri::Runtime> {
     store: Store<R>,
 }
 
-const ACCESS_KEY_CREDENTIAL_STORE_KEY: &str = "access_key_credential";
+pub(crate) const ACCESS_KEY_CREDENTIAL_STORE_KEY: &str = "access_key_credential";
 
 impl<R: tauri::Runtime> AccessKeyAuthStore<R> {
     pub fn new(store: Store<R>) -> Self {
@@ -80,9 +104,50 @@ impl<R: tauri::Runtime> From<Store<R>> for AccessKeyAuthStore<R> {
     }
 }
 
+/// Keeps all the credential profiles in plaintext under a single store key.
+pub struct ProfileAuthStore<R: tauri::Runtime> {
+    store: Store<R>,
+}
+
+const CREDENTIAL_PROFILES_STORE_KEY: &str = "credential_profiles";
+
+impl<R: tauri::Runtime> ProfileAuthStore<R> {
+    pub fn new(store: Store<R>) -> Self {
+        Self { store }
+    }
+}
+
+impl<R: tauri::Runtime> AuthStore<CredentialProfiles> for ProfileAuthStore<R> {
+    fn save(&self, profiles: CredentialProfiles) -> Result<(), SaveCredentialError> {
+        let to_valued = serde_json::to_value(profiles)?;
+        self.store.set(CREDENTIAL_PROFILES_STORE_KEY, to_valued);
+        Ok(())
+    }
+
+    fn query(&self) -> Result<CredentialProfiles, QueryCredentialError> {
+        self.store
+            .get(CREDENTIAL_PROFILES_STORE_KEY)
+            .ok_or(QueryCredentialError::NotExist)
+            .and_then(|value| {
+                let result = serde_json::from_value::<CredentialProfiles>(value);
+                result.map_err(QueryCredentialError::from)
+            })
+    }
+
+    fn delete(&self) -> bool {
+        self.store.delete(CREDENTIAL_PROFILES_STORE_KEY)
+    }
+}
+
+impl<R: tauri::Runtime> From<Store<R>> for ProfileAuthStore<R> {
+    fn from(value: Store<R>) -> Self {
+        Self::new(value)
+    }
+}
+
 #[cfg(test)]
 pub mod store_test_utils {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     use tauri::test::{mock_builder, MockRuntime};
     use tauri_plugin_store::StoreBuilder;
@@ -96,17 +161,27 @@ pub mod store_test_utils {
         store_path
     }
 
-    pub fn init_auth_store() -> AccessKeyAuthStore<MockRuntime> {
-        let store_path = auth_store_path();
+    pub fn init_store() -> Store<MockRuntime> {
+        init_store_at(&auth_store_path())
+    }
 
+    /// Builds a store backed by the file at `store_path`, loading it if it exists.
+    pub fn init_store_at(store_path: &Path) -> Store<MockRuntime> {
         let app = mock_builder()
             .invoke_handler(tauri::generate_handler![])
             .plugin(tauri_plugin_store::Builder::new().build())
             .build(tauri::generate_context!())
             .unwrap();
 
-        let store = StoreBuilder::new(&app, store_path).build().unwrap();
-        AccessKeyAuthStore::new(store)
+        StoreBuilder::new(&app, store_path).build().unwrap()
+    }
+
+    pub fn init_auth_store() -> AccessKeyAuthStore<MockRuntime> {
+        AccessKeyAuthStore::new(init_store())
+    }
+
+    pub fn init_profile_store() -> ProfileAuthStore<MockRuntime> {
+        ProfileAuthStore::new(init_store())
     }
 }
 
@@ -120,6 +195,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_access_key_auth_store() {
         let auth_store = store_test_utils::init_auth_store();
 
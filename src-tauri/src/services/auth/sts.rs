@@ -0,0 +1,304 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    auth::{
+        error::{AKFulfillError, AKNotValid},
+        secret::SecretString,
+        types::AssumeRoleCredentials,
+    },
+    error::AliyunRequestCommandError,
+    rpc::{RpcClient, RpcCredentials, RpcParams},
+};
+
+pub const DEFAULT_STS_ENDPOINT: &str = "sts.aliyuncs.com";
+const STS_API_VERSION: &str = "2015-04-01";
+
+const MIN_DURATION_SECONDS: u32 = 900;
+const MAX_DURATION_SECONDS: u32 = 43200;
+
+/// Temporary credentials issued by STS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCredentials {
+    pub access_key_id: String,
+    pub access_key_secret: SecretString,
+    pub security_token: SecretString,
+    pub issued_at: DateTime<Utc>,
+    pub expiration: DateTime<Utc>,
+}
+
+impl SessionCredentials {
+    /// When the credentials should be refreshed, once four fifths of their lifetime elapsed.
+    pub fn refresh_at(&self) -> DateTime<Utc> {
+        self.issued_at + (self.expiration - self.issued_at) * 4 / 5
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration <= Utc::now()
+    }
+}
+
+impl From<&SessionCredentials> for RpcCredentials {
+    fn from(session: &SessionCredentials) -> Self {
+        Self {
+            access_key_id: session.access_key_id.clone(),
+            access_key_secret: session.access_key_secret.clone(),
+            security_token: Some(session.security_token.clone()),
+        }
+    }
+}
+
+/// The identity taken by a successful AssumeRole call.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct AssumedRole {
+    pub arn: String,
+    pub assumed_role_id: String,
+    /// When the temporary credentials expire, in RFC 3339 format.
+    pub expiration: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleBody {
+    credentials: AssumeRoleBodyCredentials,
+    assumed_role_user: AssumedRoleUser,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleBodyCredentials {
+    access_key_id: String,
+    access_key_secret: SecretString,
+    security_token: SecretString,
+    expiration: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumedRoleUser {
+    arn: String,
+    assumed_role_id: String,
+}
+
+/// Issues temporary credentials for role profiles by calling STS AssumeRole.
+#[derive(Clone)]
+pub struct AssumeRoleProvider {
+    endpoint: String,
+}
+
+impl Default for AssumeRoleProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_STS_ENDPOINT)
+    }
+}
+
+impl AssumeRoleProvider {
+    pub fn new<E: Into<String>>(endpoint: E) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Checks the role configuration before sending it to STS.
+    pub fn check(role: &AssumeRoleCredentials) -> Result<(), AKFulfillError> {
+        let problem = if role.role_arn.trim().is_empty() {
+            Some("the role ARN must not be empty".to_owned())
+        } else if !(2..=64).contains(&role.role_session_name.len())
+            || !role
+                .role_session_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.@=,".contains(c))
+        {
+            Some(format!(
+                "the role session name {:?} must be 2 to 64 characters of letters, digits or -_.@=,",
+                role.role_session_name
+            ))
+        } else if !(MIN_DURATION_SECONDS..=MAX_DURATION_SECONDS).contains(&role.duration_seconds) {
+            Some(format!(
+                "the duration must be between {MIN_DURATION_SECONDS} and {MAX_DURATION_SECONDS} seconds"
+            ))
+        } else {
+            None
+        };
+
+        match problem {
+            Some(message) => Err(AliyunRequestCommandError::InvalidInput { message }),
+            None => Ok(()),
+        }
+    }
+
+    /// Assumes the role with its source access key.
+    ///
+    /// A source access key rejected by STS is reported as [`AKNotValid`].
+    pub async fn assume_role(
+        &self,
+        role: &AssumeRoleCredentials,
+    ) -> Result<(SessionCredentials, AssumedRole), AKFulfillError> {
+        Self::check(role)?;
+
+        let source = RpcCredentials {
+            access_key_id: role.source.access_key_id.clone(),
+            access_key_secret: role.source.access_key_secret.clone(),
+            security_token: None,
+        };
+        let mut params = RpcParams::from([
+            ("RoleArn".to_owned(), role.role_arn.clone()),
+            ("RoleSessionName".to_owned(), role.role_session_name.clone()),
+            (
+                "DurationSeconds".to_owned(),
+                role.duration_seconds.to_string(),
+            ),
+        ]);
+        if let Some(external_id) = &role.external_id {
+            params.insert("ExternalId".to_owned(), external_id.clone());
+        }
+
+        let issued_at = Utc::now();
+        let body: AssumeRoleBody = RpcClient::new(self.endpoint.as_str(), source)
+            .call("AssumeRole", STS_API_VERSION, params)
+            .await
+            .map_err(AKNotValid::classify)?;
+
+        let credentials = body.credentials;
+        let assumed_role = AssumedRole {
+            arn: body.assumed_role_user.arn,
+            assumed_role_id: body.assumed_role_user.assumed_role_id,
+            expiration: credentials.expiration.to_rfc3339(),
+        };
+        let session = SessionCredentials {
+            access_key_id: credentials.access_key_id,
+            access_key_secret: credentials.access_key_secret,
+            security_token: credentials.security_token,
+            issued_at,
+            expiration: credentials.expiration,
+        };
+
+        Ok((session, assumed_role))
+    }
+}
+
+#[cfg(test)]
+pub mod sts_test_utils {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use chrono::TimeDelta;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, query_param},
+        Mock, MockServer, Request, Respond, ResponseTemplate,
+    };
+
+    use crate::services::auth::types::AccessKeyCredentials;
+
+    use super::*;
+
+    pub const ROLE_ARN: &str = "acs:ram::123456789012:role/ecs-admin";
+
+    /// Issues numbered temporary credentials living for `lifetime`.
+    pub struct FakeAssumeRole {
+        pub issued: AtomicUsize,
+        pub lifetime: TimeDelta,
+    }
+
+    impl Respond for FakeAssumeRole {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let access_key_id = request
+                .url
+                .query_pairs()
+                .find(|(key, _)| key == "AccessKeyId")
+                .map(|(_, value)| value.into_owned());
+            if access_key_id.as_deref() != Some("SOURCE_ACCESS_KEY_ID") {
+                return ResponseTemplate::new(404).set_body_json(json!({
+                    "RequestId": "FAKE-REQUEST-ID",
+                    "HostId": "sts.aliyuncs.com",
+                    "Code": "InvalidAccessKeyId.NotFound",
+                    "Message": "Specified access key is not found.",
+                    "Recommend": "",
+                }));
+            }
+
+            let issued = self.issued.fetch_add(1, Ordering::SeqCst) + 1;
+            let expiration = Utc::now() + self.lifetime;
+            ResponseTemplate::new(200).set_body_json(json!({
+                "RequestId": "FAKE-REQUEST-ID",
+                "AssumedRoleUser": {
+                    "Arn": format!("{ROLE_ARN}/session"),
+                    "AssumedRoleId": "344584339364951186:session",
+                },
+                "Credentials": {
+                    "AccessKeyId": format!("STS.TEMPORARY.{issued}"),
+                    "AccessKeySecret": "TEMPORARY_SECRET",
+                    "SecurityToken": "TEMPORARY_TOKEN",
+                    "Expiration": expiration.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                },
+            }))
+        }
+    }
+
+    pub async fn start_fake_sts(lifetime: TimeDelta) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("Action", "AssumeRole"))
+            .respond_with(FakeAssumeRole {
+                issued: AtomicUsize::new(0),
+                lifetime,
+            })
+            .mount(&server)
+            .await;
+        server
+    }
+
+    pub fn role_credentials(source_access_key_id: &str) -> AssumeRoleCredentials {
+        AssumeRoleCredentials {
+            source: AccessKeyCredentials::new(source_access_key_id, "SOURCE_ACCESS_KEY_SECRET"),
+            role_arn: ROLE_ARN.to_owned(),
+            role_session_name: "aliyun-ecs-manager".to_owned(),
+            duration_seconds: 900,
+            external_id: Some("external".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use super::{sts_test_utils::*, *};
+
+    #[tokio::test]
+    async fn test_assume_role() {
+        let server = start_fake_sts(TimeDelta::hours(1)).await;
+        let provider = AssumeRoleProvider::new(server.uri());
+
+        let (session, assumed_role) = provider
+            .assume_role(&role_credentials("SOURCE_ACCESS_KEY_ID"))
+            .await
+            .unwrap();
+        assert_eq!(session.access_key_id, "STS.TEMPORARY.1");
+        assert_eq!(session.security_token.expose_secret(), "TEMPORARY_TOKEN");
+        assert_eq!(assumed_role.arn, format!("{ROLE_ARN}/session"));
+        assert!(!session.is_expired());
+        assert!(session.refresh_at() < session.expiration);
+
+        let result = provider
+            .assume_role(&role_credentials("UNKNOWN_ACCESS_KEY_ID"))
+            .await;
+        let Err(AliyunRequestCommandError::Specific(err)) = result else {
+            unreachable!()
+        };
+        assert_eq!(err.data.code, "InvalidAccessKeyId.NotFound");
+
+        let mut role = role_credentials("SOURCE_ACCESS_KEY_ID");
+        role.duration_seconds = 60;
+        assert_matches!(
+            provider.assume_role(&role).await,
+            Err(AliyunRequestCommandError::InvalidInput { .. })
+        );
+    }
+}
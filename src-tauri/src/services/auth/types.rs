@@ -1,10 +1,18 @@
+use std::fmt;
+
 use alibabacloud::client::AliyunClient;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::services::{auth::secret::SecretString, rpc::RpcCredentials};
+
+/// An access key, as entered by the user and kept in the credential store.
+///
+/// Never returned to the frontend, which gets an [`AccessKeyView`] instead.
 #[derive(Debug, Serialize, Deserialize, specta::Type, Clone, PartialEq)]
 pub struct AccessKeyCredentials {
     pub access_key_id: String,
-    pub access_key_secret: String,
+    pub access_key_secret: SecretString,
 }
 
 impl AccessKeyCredentials {
@@ -13,16 +21,103 @@ impl AccessKeyCredentials {
         access_key_secret: T2,
     ) -> Self {
         let access_key_id = access_key_id.into();
-        let access_key_secret = access_key_secret.into();
+        let access_key_secret = SecretString::new(access_key_secret);
         Self {
             access_key_id,
             access_key_secret,
         }
     }
+
+    pub fn view(&self, validated_at: Option<DateTime<Utc>>) -> AccessKeyView {
+        AccessKeyView {
+            access_key_id: self.access_key_id.clone(),
+            masked_secret: self.access_key_secret.masked(),
+            validated_at: validated_at.map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+impl fmt::Display for AccessKeyCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "access key {}", self.access_key_id)
+    }
+}
+
+/// What the frontend gets to see of an access key.
+#[derive(Debug, Serialize, specta::Type, Clone, PartialEq)]
+pub struct AccessKeyView {
+    pub access_key_id: String,
+    /// The last characters of the secret, the rest being masked.
+    pub masked_secret: String,
+    /// When the access key was last validated against Aliyun, in RFC 3339 format.
+    pub validated_at: Option<String>,
+}
+
+/// A profile assuming a RAM role, signed with the source access key.
+#[derive(Debug, Serialize, Deserialize, specta::Type, Clone, PartialEq)]
+pub struct AssumeRoleCredentials {
+    pub source: AccessKeyCredentials,
+    pub role_arn: String,
+    pub role_session_name: String,
+    /// Lifetime of the temporary credentials, between 900 and 43200 seconds.
+    pub duration_seconds: u32,
+    pub external_id: Option<String>,
+}
+
+/// The credentials a profile is made of.
+///
+/// Untagged so that profiles saved as bare access key credentials are still readable.
+#[derive(Debug, Serialize, Deserialize, specta::Type, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ProfileCredentials {
+    AccessKey(AccessKeyCredentials),
+    AssumeRole(AssumeRoleCredentials),
+}
+
+impl ProfileCredentials {
+    /// The long-term access key, which is the source access key for role profiles.
+    pub fn access_key(&self) -> &AccessKeyCredentials {
+        match self {
+            ProfileCredentials::AccessKey(credentials) => credentials,
+            ProfileCredentials::AssumeRole(role) => &role.source,
+        }
+    }
+
+    pub fn access_key_mut(&mut self) -> &mut AccessKeyCredentials {
+        match self {
+            ProfileCredentials::AccessKey(credentials) => credentials,
+            ProfileCredentials::AssumeRole(role) => &mut role.source,
+        }
+    }
+}
+
+impl From<&AccessKeyCredentials> for RpcCredentials {
+    fn from(credentials: &AccessKeyCredentials) -> Self {
+        Self {
+            access_key_id: credentials.access_key_id.clone(),
+            access_key_secret: credentials.access_key_secret.clone(),
+            security_token: None,
+        }
+    }
+}
+
+impl From<AccessKeyCredentials> for ProfileCredentials {
+    fn from(credentials: AccessKeyCredentials) -> Self {
+        ProfileCredentials::AccessKey(credentials)
+    }
+}
+
+impl From<AssumeRoleCredentials> for ProfileCredentials {
+    fn from(role: AssumeRoleCredentials) -> Self {
+        ProfileCredentials::AssumeRole(role)
+    }
 }
 
 impl From<AccessKeyCredentials> for AliyunClient {
     fn from(credentials: AccessKeyCredentials) -> Self {
-        AliyunClient::new(credentials.access_key_id, credentials.access_key_secret)
+        AliyunClient::new(
+            credentials.access_key_id,
+            credentials.access_key_secret.expose_secret().to_owned(),
+        )
     }
 }
@@ -0,0 +1,437 @@
+use std::sync::RwLock;
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{
+    services::auth::{
+        profile::{CredentialProfiles, DEFAULT_PROFILE_NAME},
+        store::{
+            AccessKeyAuthStore, AuthStore, QueryCredentialError, SaveCredentialError,
+            ACCESS_KEY_CREDENTIAL_STORE_KEY,
+        },
+        types::ProfileCredentials,
+    },
+    types::{SerdeJsonError, Store},
+};
+
+/// A store that can be locked and unlocked with a user master passphrase.
+pub trait CredentialVault {
+    fn status(&self) -> VaultStatus;
+    fn unlock(&self, passphrase: &str) -> Result<UnlockOutcome, VaultError>;
+    fn lock(&self);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, specta::Type)]
+pub struct VaultStatus {
+    /// Whether a master passphrase has ever been set for this store.
+    pub initialized: bool,
+    /// Whether the derived key is currently held in memory.
+    pub unlocked: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, specta::Type)]
+pub struct UnlockOutcome {
+    /// The vault didn't exist yet and has been created with the given passphrase.
+    pub created: bool,
+    /// A plaintext credential left by an older version has been encrypted
+    /// into the default profile, or a new one when a profile has that name.
+    pub migrated: bool,
+}
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum VaultError {
+    #[error("the credential store is not encrypted")]
+    NotEncrypted,
+    #[error("the master passphrase must not be empty")]
+    EmptyPassphrase,
+    #[error("the master passphrase is not correct")]
+    WrongPassphrase,
+    #[error("failed to derive the key from the master passphrase: {0}")]
+    KeyDerivation(String),
+    #[error("failed to encrypt the credential store data")]
+    EncryptError,
+    #[error("the credential store data is corrupted: {}", .0.0)]
+    Corrupted(#[from] SerdeJsonError),
+    #[error("failed to migrate the plaintext credential: {0}")]
+    Migration(#[from] QueryCredentialError),
+}
+
+impl From<serde_json::Error> for VaultError {
+    fn from(value: serde_json::Error) -> Self {
+        let error: SerdeJsonError = value.into();
+        error.into()
+    }
+}
+
+const VAULT_HEADER_STORE_KEY: &str = "credential_vault";
+const ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY: &str = "encrypted_credential_profiles";
+
+/// Known plaintext sealed into the header, used to tell a wrong passphrase
+/// apart from corrupted credentials.
+const VAULT_CHECK_PLAINTEXT: &[u8] = b"aliyun-ecs-manager credential vault";
+
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+type VaultKey = Zeroizing<[u8; KEY_LENGTH]>;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultHeader {
+    kdf: KdfParams,
+    check: SealedData,
+}
+
+/// Argon2id parameters, persisted so they can be tuned without breaking existing vaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    salt: String,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    fn generate() -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt: BASE64.encode(salt),
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<VaultKey, VaultError> {
+        let salt = BASE64
+            .decode(&self.salt)
+            .map_err(|err| VaultError::KeyDerivation(err.to_string()))?;
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|err| VaultError::KeyDerivation(err.to_string()))?;
+
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|err| VaultError::KeyDerivation(err.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// AES-256-GCM ciphertext together with its nonce, both base64 encoded.
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedData {
+    nonce: String,
+    ciphertext: String,
+}
+
+impl SealedData {
+    fn seal(key: &VaultKey, plaintext: &[u8]) -> Option<Self> {
+        let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(**key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, plaintext).ok()?;
+        Some(Self {
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Returns `None` when the data can't be authenticated with the given key.
+    fn open(&self, key: &VaultKey) -> Option<Zeroizing<Vec<u8>>> {
+        let nonce: [u8; NONCE_LENGTH] = BASE64.decode(&self.nonce).ok()?.try_into().ok()?;
+        let ciphertext = BASE64.decode(&self.ciphertext).ok()?;
+
+        let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(**key));
+        cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+            .ok()
+            .map(Zeroizing::new)
+    }
+}
+
+/// An [`AuthStore`] keeping the credential profiles encrypted at rest.
+///
+/// The encryption key is derived from a master passphrase with Argon2id and only
+/// lives in memory between [`CredentialVault::unlock`] and [`CredentialVault::lock`].
+/// While locked, every read and write is refused.
+pub struct EncryptedAuthStore<R: tauri::Runtime> {
+    store: Store<R>,
+    key: RwLock<Option<VaultKey>>,
+}
+
+impl<R: tauri::Runtime> EncryptedAuthStore<R> {
+    pub fn new(store: Store<R>) -> Self {
+        Self {
+            store,
+            key: RwLock::new(None),
+        }
+    }
+
+    fn header(&self) -> Result<Option<VaultHeader>, VaultError> {
+        self.store
+            .get(VAULT_HEADER_STORE_KEY)
+            .map(serde_json::from_value::<VaultHeader>)
+            .transpose()
+            .map_err(VaultError::from)
+    }
+
+    fn create_header(&self, passphrase: &str) -> Result<VaultKey, VaultError> {
+        let kdf = KdfParams::generate();
+        let key = kdf.derive_key(passphrase)?;
+        let check =
+            SealedData::seal(&key, VAULT_CHECK_PLAINTEXT).ok_or(VaultError::EncryptError)?;
+
+        let header = serde_json::to_value(VaultHeader { kdf, check })?;
+        self.store.set(VAULT_HEADER_STORE_KEY, header);
+        Ok(key)
+    }
+
+    /// Encrypts the credential written in plaintext by older versions into the default
+    /// profile, then removes it.
+    ///
+    /// When encrypted profiles exist already, the credential is added to them under the
+    /// first free name among `default`, `default-2` and so on, unless a profile holds
+    /// it already. If those profiles can't be read, the plaintext credential is kept.
+    fn migrate_plaintext(&self, key: &VaultKey) -> Result<bool, VaultError> {
+        let plaintext_store = AccessKeyAuthStore::new(self.store.clone());
+        let credential = match plaintext_store.query() {
+            Ok(credential) => ProfileCredentials::from(credential),
+            Err(QueryCredentialError::NotExist) => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut profiles = match self.open_profiles(key) {
+            Ok(profiles) => profiles,
+            Err(QueryCredentialError::NotExist) => CredentialProfiles::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let migrated = !profiles.iter().any(|(_, saved)| *saved == credential);
+        if migrated {
+            let mut name = DEFAULT_PROFILE_NAME.to_owned();
+            let mut suffix = 1;
+            while profiles.contains(&name) {
+                suffix += 1;
+                name = format!("{DEFAULT_PROFILE_NAME}-{suffix}");
+            }
+            profiles.upsert(&name, credential);
+            let sealed = Self::seal_profiles(key, &profiles)?;
+            self.store.set(
+                ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY,
+                serde_json::to_value(sealed)?,
+            );
+        }
+        self.store.delete(ACCESS_KEY_CREDENTIAL_STORE_KEY);
+
+        log::info!("Removed the plaintext access key credential, migrated: {migrated}");
+        Ok(migrated)
+    }
+
+    fn open_profiles(&self, key: &VaultKey) -> Result<CredentialProfiles, QueryCredentialError> {
+        let value = self
+            .store
+            .get(ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY)
+            .ok_or(QueryCredentialError::NotExist)?;
+        let sealed = serde_json::from_value::<SealedData>(value)?;
+        let plaintext = sealed.open(key).ok_or(QueryCredentialError::DecryptError)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn seal_profiles(
+        key: &VaultKey,
+        profiles: &CredentialProfiles,
+    ) -> Result<SealedData, VaultError> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(profiles)?);
+        SealedData::seal(key, &plaintext).ok_or(VaultError::EncryptError)
+    }
+}
+
+impl<R: tauri::Runtime> CredentialVault for EncryptedAuthStore<R> {
+    fn status(&self) -> VaultStatus {
+        VaultStatus {
+            initialized: self.store.has(VAULT_HEADER_STORE_KEY),
+            unlocked: self.key.read().unwrap().is_some(),
+        }
+    }
+
+    fn unlock(&self, passphrase: &str) -> Result<UnlockOutcome, VaultError> {
+        if passphrase.is_empty() {
+            return Err(VaultError::EmptyPassphrase);
+        }
+
+        let (key, created) = match self.header()? {
+            Some(header) => {
+                let key = header.kdf.derive_key(passphrase)?;
+                header.check.open(&key).ok_or(VaultError::WrongPassphrase)?;
+                (key, false)
+            }
+            None => (self.create_header(passphrase)?, true),
+        };
+
+        let migrated = self.migrate_plaintext(&key)?;
+        *self.key.write().unwrap() = Some(key);
+
+        Ok(UnlockOutcome { created, migrated })
+    }
+
+    fn lock(&self) {
+        // The key is zeroized when dropped.
+        *self.key.write().unwrap() = None;
+    }
+}
+
+impl<R: tauri::Runtime> AuthStore<CredentialProfiles> for EncryptedAuthStore<R> {
+    fn save(&self, profiles: CredentialProfiles) -> Result<(), SaveCredentialError> {
+        let guard = self.key.read().unwrap();
+        let key = guard.as_ref().ok_or(SaveCredentialError::Locked)?;
+
+        let sealed = Self::seal_profiles(key, &profiles).map_err(|err| match err {
+            VaultError::Corrupted(err) => SaveCredentialError::SerializeError(err),
+            _ => SaveCredentialError::EncryptError,
+        })?;
+        self.store.set(
+            ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY,
+            serde_json::to_value(sealed)?,
+        );
+        Ok(())
+    }
+
+    fn query(&self) -> Result<CredentialProfiles, QueryCredentialError> {
+        let guard = self.key.read().unwrap();
+        let key = guard.as_ref().ok_or(QueryCredentialError::Locked)?;
+
+        self.open_profiles(key)
+    }
+
+    /// Deletes the encrypted profiles along with a plaintext credential not migrated
+    /// yet, which works while locked too. The header is kept, so is the passphrase.
+    fn delete(&self) -> bool {
+        let sealed = self.store.delete(ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY);
+        let plaintext = self.store.delete(ACCESS_KEY_CREDENTIAL_STORE_KEY);
+        sealed || plaintext
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok};
+    use pretty_assertions::assert_eq;
+
+    use crate::services::auth::{store::store_test_utils, types::AccessKeyCredentials};
+
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    #[test]
+    fn test_encrypted_auth_store() {
+        let store = store_test_utils::init_store();
+        let vault = EncryptedAuthStore::new(store.clone());
+        let profiles = CredentialProfiles::single(
+            DEFAULT_PROFILE_NAME,
+            AccessKeyCredentials::new("YOUR_ACCESS_KEY_ID", "YOUR_ACCESS_KEY_SECRET"),
+        );
+
+        assert_eq!(
+            vault.status(),
+            VaultStatus {
+                initialized: false,
+                unlocked: false
+            }
+        );
+        assert_matches!(vault.query(), Err(QueryCredentialError::Locked));
+        assert_matches!(
+            vault.save(profiles.clone()),
+            Err(SaveCredentialError::Locked)
+        );
+
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert_eq!(
+            outcome,
+            UnlockOutcome {
+                created: true,
+                migrated: false
+            }
+        );
+        assert_matches!(vault.query(), Err(QueryCredentialError::NotExist));
+
+        assert_ok!(vault.save(profiles.clone()));
+        assert_eq!(vault.query().unwrap(), profiles);
+
+        // Nothing readable is written to the underlying store.
+        let raw = store.get(ENCRYPTED_CREDENTIAL_PROFILES_STORE_KEY).unwrap();
+        assert!(!raw.to_string().contains("YOUR_ACCESS_KEY_SECRET"));
+
+        vault.lock();
+        assert_matches!(vault.query(), Err(QueryCredentialError::Locked));
+        assert_matches!(vault.unlock("wrong"), Err(VaultError::WrongPassphrase));
+        assert_matches!(vault.unlock(""), Err(VaultError::EmptyPassphrase));
+
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert!(!outcome.created);
+        assert_eq!(vault.query().unwrap(), profiles);
+
+        // Deleting works while locked, and keeps the passphrase.
+        vault.lock();
+        assert!(vault.delete());
+        assert!(!vault.delete());
+        assert!(vault.status().initialized);
+        assert_ok!(vault.unlock(PASSPHRASE));
+        assert_matches!(vault.query(), Err(QueryCredentialError::NotExist));
+    }
+
+    #[test]
+    fn test_migrate_plaintext_credential() {
+        let store = store_test_utils::init_store();
+        let credential = AccessKeyCredentials::new("YOUR_ACCESS_KEY_ID", "YOUR_ACCESS_KEY_SECRET");
+        AccessKeyAuthStore::new(store.clone())
+            .save(credential.clone())
+            .unwrap();
+
+        let vault = EncryptedAuthStore::new(store.clone());
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert!(outcome.migrated);
+        assert!(!store.has(ACCESS_KEY_CREDENTIAL_STORE_KEY));
+        assert_eq!(
+            vault.query().unwrap(),
+            CredentialProfiles::single(DEFAULT_PROFILE_NAME, credential)
+        );
+
+        vault.lock();
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert!(!outcome.migrated);
+
+        // A credential left next to encrypted profiles joins them under a free name.
+        let other = AccessKeyCredentials::new("OTHER_ACCESS_KEY_ID", "OTHER_ACCESS_KEY_SECRET");
+        AccessKeyAuthStore::new(store.clone())
+            .save(other.clone())
+            .unwrap();
+        vault.lock();
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert!(outcome.migrated);
+        assert!(!store.has(ACCESS_KEY_CREDENTIAL_STORE_KEY));
+        let profiles = vault.query().unwrap();
+        assert_eq!(profiles.active_name(), Some(DEFAULT_PROFILE_NAME));
+        assert_eq!(profiles.get("default-2"), Some(&other.clone().into()));
+
+        // Unless a profile holds it already.
+        AccessKeyAuthStore::new(store.clone()).save(other).unwrap();
+        vault.lock();
+        let outcome = vault.unlock(PASSPHRASE).unwrap();
+        assert!(!outcome.migrated);
+        assert!(!store.has(ACCESS_KEY_CREDENTIAL_STORE_KEY));
+        assert_eq!(vault.query().unwrap(), profiles);
+    }
+}
@@ -1,19 +1,62 @@
 use alibabacloud::client::AliyunClient;
-use std::sync::RwLock;
+use chrono::Utc;
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+use tauri::async_runtime::JoinHandle;
 
 use crate::services::{
-    auth::AccessKeyAuthService,
-    error::{AliyunRequestCommandError, NoOther},
+    auth::{
+        chain::{CredentialProviderChain, CredentialSource, ResolvedCredentials, SessionIssuer},
+        sts::{AssumeRoleProvider, SessionCredentials},
+        types::ProfileCredentials,
+        AccessKeyAuthService,
+    },
+    endpoint::{AliyunService, EndpointConfig},
+    error::{AliyunRequestCommandError, NoOther, RamPermission},
+    policy::PermissionLog,
+    region::{is_valid_region_id, ClientPool, RegionClient, RegionalApi, DEFAULT_REGION_ID},
+    rpc::{RpcClient, RpcCredentials},
 };
 
+/// How long to wait before retrying a failed refresh of temporary credentials.
+const SESSION_REFRESH_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Temporary credentials, together with what is needed to refresh them.
+struct ActiveSession {
+    issuer: SessionIssuer,
+    credentials: RwLock<SessionCredentials>,
+}
+
 /// Service for managing Aliyun client instances.
 ///
 /// This service uses `RwLock` to ensure thread-safety while allowing multiple concurrent readers.
 /// The client is wrapped in an `Option` to handle scenarios where valid credentials may not be
 /// available at application startup.
+///
+/// Role profiles and instance RAM roles are backed by temporary STS credentials instead of an
+/// `AliyunClient`. They are refreshed by a background task before they expire.
+///
+/// Regional APIs go through [`RegionClient`]s, built lazily from the credentials in use and
+/// pooled by region until the credentials change.
 pub struct AliyunClientService {
     /// The Aliyun client instance, wrapped in `Option` to support lazy initialization.
     client: RwLock<Option<AliyunClient>>,
+    /// The session of temporary credentials, exclusive with `client`.
+    session: RwLock<Option<Arc<ActiveSession>>>,
+    /// The task refreshing `session`, aborted whenever the session is replaced.
+    refresh_task: Mutex<Option<JoinHandle<()>>>,
+    /// Where the credentials in use come from.
+    source: RwLock<Option<CredentialSource>>,
+    /// The clients of each region, emptied whenever the credentials change.
+    pool: Arc<ClientPool>,
+    /// The region used when a call doesn't pick one, set from the active profile.
+    default_region: RwLock<Option<String>>,
+    /// Where the requests of the region clients and validations are sent.
+    endpoints: RwLock<EndpointConfig>,
+    /// The permissions of the calls made with the current credentials.
+    permission_log: Arc<PermissionLog>,
 }
 
 impl AliyunClientService {
@@ -21,6 +64,13 @@ impl AliyunClientService {
     pub fn new() -> Self {
         Self {
             client: RwLock::new(None),
+            session: RwLock::new(None),
+            refresh_task: Mutex::new(None),
+            source: RwLock::new(None),
+            pool: Arc::new(ClientPool::default()),
+            default_region: RwLock::new(None),
+            endpoints: RwLock::new(EndpointConfig::default()),
+            permission_log: Arc::new(PermissionLog::default()),
         }
     }
 
@@ -31,9 +81,116 @@ impl AliyunClientService {
     /// # Arguments
     ///
     /// * `client` - The configured `AliyunClient` instance to store.
-    pub fn initialize(&self, client: AliyunClient) {
+    /// * `source` - Where the credentials of the client come from.
+    pub fn initialize(&self, client: AliyunClient, source: CredentialSource) {
+        self.clear_session();
+        self.pool.clear();
+        self.permission_log.clear();
         let mut guard = self.client.write().unwrap();
         *guard = Some(client);
+        *self.source.write().unwrap() = Some(source);
+    }
+
+    /// Initializes the service with temporary credentials.
+    ///
+    /// A background task asks the issuer for new credentials before they expire.
+    ///
+    /// # Arguments
+    ///
+    /// * `issuer` - What issued the credentials, used to refresh them.
+    /// * `credentials` - The temporary credentials.
+    /// * `source` - Where the credentials come from.
+    pub fn initialize_session(
+        &self,
+        issuer: SessionIssuer,
+        credentials: SessionCredentials,
+        source: CredentialSource,
+    ) {
+        self.clear();
+
+        let session = Arc::new(ActiveSession {
+            issuer,
+            credentials: RwLock::new(credentials),
+        });
+        *self.session.write().unwrap() = Some(session.clone());
+        *self.source.write().unwrap() = Some(source);
+
+        let task = tauri::async_runtime::spawn(refresh_session(session, self.pool.clone()));
+        *self.refresh_task.lock().unwrap() = Some(task);
+    }
+
+    /// Initializes the service with the credentials resolved from the given source.
+    ///
+    /// Roles are assumed right away with `sts`. The service is cleared if that fails.
+    pub async fn activate(
+        &self,
+        credentials: ResolvedCredentials,
+        source: CredentialSource,
+        sts: &AssumeRoleProvider,
+    ) -> Result<(), ClientValidationError> {
+        match credentials {
+            ResolvedCredentials::Profile(ProfileCredentials::AccessKey(credentials)) => {
+                self.initialize(credentials.into(), source);
+            }
+            ResolvedCredentials::Profile(ProfileCredentials::AssumeRole(role)) => {
+                let issuer = SessionIssuer::AssumeRole {
+                    provider: sts.clone(),
+                    role,
+                };
+                match issuer.issue().await {
+                    Ok(credentials) => self.initialize_session(issuer, credentials, source),
+                    Err(err) => {
+                        self.clear();
+                        return Err(AliyunRequestCommandError::<NoOther>::from_any(err));
+                    }
+                }
+            }
+            ResolvedCredentials::Session(issuer, credentials) => {
+                self.initialize_session(issuer, credentials, source);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the client from the active profile of the auth service.
+    ///
+    /// The client is cleared when there are no usable credentials, e.g. no profile is
+    /// active or the credential store is locked. Role profiles are assumed right away.
+    /// Returns whether a client is available.
+    pub async fn reload(
+        &self,
+        auth_service: &AccessKeyAuthService,
+    ) -> Result<bool, ClientValidationError> {
+        let Ok((name, credentials)) = auth_service.current_profile() else {
+            self.clear();
+            return Ok(false);
+        };
+
+        let default_region = auth_service.default_region(&name).ok().flatten();
+        let source = CredentialSource::StoredProfile { name };
+        self.activate(credentials.into(), source, &auth_service.sts())
+            .await?;
+        self.set_default_region(default_region);
+        Ok(true)
+    }
+
+    /// Rebuilds the client from the first provider of the chain having credentials.
+    ///
+    /// Returns the source of the credentials, `None` when no provider had any, in which
+    /// case the client is cleared.
+    pub async fn resolve(
+        &self,
+        chain: &CredentialProviderChain,
+        auth_service: &AccessKeyAuthService,
+    ) -> Result<Option<CredentialSource>, ClientValidationError> {
+        let Some((source, credentials)) = chain.resolve(auth_service).await else {
+            self.clear();
+            return Ok(None);
+        };
+
+        self.activate(credentials, source.clone(), &auth_service.sts())
+            .await?;
+        Ok(Some(source))
     }
 
     /// Executes a closure with a reference to the client if it's initialized.
@@ -61,6 +218,90 @@ impl AliyunClientService {
         guard.as_ref().cloned()
     }
 
+    /// Returns the credentials currently in use, the temporary ones for role profiles.
+    pub fn credentials(&self) -> Option<RpcCredentials> {
+        if let Some(session) = self.session.read().unwrap().as_ref() {
+            return Some((&*session.credentials.read().unwrap()).into());
+        }
+        self.with_client(|client| client.into())
+    }
+
+    /// Returns the client of the region, or of the default region when `None`.
+    ///
+    /// Clients are built from the credentials in use the first time a region is asked for,
+    /// `None` is returned without credentials or when the region ID is not valid, since it
+    /// becomes part of the host name the signed requests are sent to.
+    pub fn region_client(&self, region_id: Option<&str>) -> Option<RegionClient> {
+        self.api_client(RegionalApi::Ecs, region_id)
+    }
+
+    /// Like [`Self::region_client`], for the VPC API.
+    pub fn vpc_client(&self, region_id: Option<&str>) -> Option<RegionClient> {
+        self.api_client(RegionalApi::Vpc, region_id)
+    }
+
+    fn api_client(&self, api: RegionalApi, region_id: Option<&str>) -> Option<RegionClient> {
+        let region_id = match region_id {
+            Some(region_id) => region_id.to_owned(),
+            None => self.default_region(),
+        };
+        if !is_valid_region_id(&region_id) {
+            log::warn!("Refused to build a client for the region ID {region_id:?}");
+            return None;
+        }
+        let credentials = self.credentials()?;
+        let endpoint = self.endpoint(api.service(), Some(&region_id));
+        let client = self
+            .pool
+            .get_or_insert(api, &region_id, &endpoint, credentials);
+        Some(client.with_permission_log(self.permission_log.clone()))
+    }
+
+    /// The permissions of the calls made through the region clients since the
+    /// credentials were last changed.
+    pub fn attempted_permissions(&self) -> Vec<RamPermission> {
+        self.permission_log.permissions()
+    }
+
+    /// The endpoint of the service in the region, see [`EndpointConfig::resolve`].
+    pub fn endpoint(&self, service: AliyunService, region_id: Option<&str>) -> String {
+        self.endpoints.read().unwrap().resolve(service, region_id)
+    }
+
+    pub fn endpoints(&self) -> EndpointConfig {
+        self.endpoints.read().unwrap().clone()
+    }
+
+    /// Replaces the endpoint configuration, the pooled region clients are rebuilt with it
+    /// the next time they are asked for.
+    pub fn set_endpoints(&self, endpoints: EndpointConfig) {
+        *self.endpoints.write().unwrap() = endpoints;
+        self.pool.clear();
+    }
+
+    /// The regions having a client in the pool, sorted.
+    pub fn pooled_regions(&self) -> Vec<String> {
+        self.pool.regions()
+    }
+
+    /// The region used by [`Self::region_client`] when none is given.
+    pub fn default_region(&self) -> String {
+        self.default_region
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| DEFAULT_REGION_ID.to_owned())
+    }
+
+    pub fn set_default_region(&self, region_id: Option<String>) {
+        *self.default_region.write().unwrap() = region_id;
+    }
+
+    /// Returns where the credentials in use come from, `None` without a client.
+    pub fn source(&self) -> Option<CredentialSource> {
+        self.source.read().unwrap().clone()
+    }
+
     /// Checks whether the client has been initialized.
     ///
     /// # Returns
@@ -68,18 +309,18 @@ impl AliyunClientService {
     /// * `true` - If the client is initialized and available.
     /// * `false` - If the client has not been initialized.
     pub fn is_initialized(&self) -> bool {
-        self.client.read().unwrap().is_some()
+        self.client.read().unwrap().is_some() || self.session.read().unwrap().is_some()
     }
 
     pub async fn is_valid(&self) -> Result<bool, ClientValidationError> {
         use super::error::AliyunRequestCommandError::*;
 
-        let Some(client) = self.clone_client() else {
+        let Some(credentials) = self.unexpired_credentials() else {
             return Ok(false);
         };
+        let client = RpcClient::new(self.endpoint(AliyunService::Sts, None), credentials);
 
-        let result = AccessKeyAuthService::validate_access_key_credentials(client).await;
-        match result {
+        match AccessKeyAuthService::validate_rpc_client(&client).await {
             Ok(_) => Ok(true),
             Err(err) => match err {
                 Specific(_) => Ok(false),
@@ -87,13 +328,214 @@ impl AliyunClientService {
             },
         }
     }
+
+    /// Same as [`Self::credentials`], `None` once temporary credentials have expired.
+    fn unexpired_credentials(&self) -> Option<RpcCredentials> {
+        if let Some(session) = self.session.read().unwrap().as_ref() {
+            let credentials = session.credentials.read().unwrap();
+            return (!credentials.is_expired()).then(|| (&*credentials).into());
+        }
+        self.with_client(|client| client.into())
+    }
+
+    fn clear_session(&self) {
+        if let Some(task) = self.refresh_task.lock().unwrap().take() {
+            task.abort();
+        }
+        *self.session.write().unwrap() = None;
+    }
+
     /// Clears the client instance.
     ///
     /// This method should be called when logging out or when credentials need to be invalidated.
     pub fn clear(&self) {
+        self.clear_session();
+        self.pool.clear();
+        self.permission_log.clear();
         let mut guard = self.client.write().unwrap();
         *guard = None;
+        *self.source.write().unwrap() = None;
+        *self.default_region.write().unwrap() = None;
+    }
+}
+
+impl Default for AliyunClientService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps asking for new credentials once the current ones are close to expiring.
+///
+/// The region clients built from the previous credentials are dropped from `pool`. The task
+/// ends by itself once the session has been replaced or cleared.
+async fn refresh_session(session: Arc<ActiveSession>, pool: Arc<ClientPool>) {
+    loop {
+        let refresh_at = session.credentials.read().unwrap().refresh_at();
+        let delay = (refresh_at - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(delay).await;
+
+        if Arc::strong_count(&session) == 1 {
+            return;
+        }
+
+        // The error isn't `Send`, so it must be dropped before waiting to retry.
+        let failed = match session.issuer.issue().await {
+            Ok(credentials) => {
+                log::info!(
+                    "Refreshed the temporary credentials of {}, valid until {}",
+                    session.issuer,
+                    credentials.expiration
+                );
+                *session.credentials.write().unwrap() = credentials;
+                pool.clear();
+                false
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to refresh the temporary credentials of {}: {err}",
+                    session.issuer
+                );
+                true
+            }
+        };
+        if failed {
+            tokio::time::sleep(SESSION_REFRESH_RETRY_DELAY).await;
+        }
     }
 }
 
 pub type ClientValidationError = AliyunRequestCommandError<NoOther>;
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::auth::{
+        profile::CredentialProfiles,
+        secret::SecretString,
+        store::{store_test_utils, AuthStore},
+        sts::sts_test_utils::{role_credentials, start_fake_sts},
+        types::AccessKeyCredentials,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_refresh() {
+        let server = start_fake_sts(TimeDelta::hours(1)).await;
+        let provider = AssumeRoleProvider::new(server.uri());
+        let role = role_credentials("SOURCE_ACCESS_KEY_ID");
+
+        let (session, _) = provider.assume_role(&role).await.unwrap();
+        let client_service = AliyunClientService::new();
+        let source = CredentialSource::StoredProfile {
+            name: "role".to_owned(),
+        };
+        client_service.initialize_session(
+            SessionIssuer::AssumeRole {
+                provider: provider.clone(),
+                role: role.clone(),
+            },
+            session.clone(),
+            source.clone(),
+        );
+        assert_eq!(client_service.source(), Some(source.clone()));
+
+        let credentials = client_service.credentials().unwrap();
+        assert_eq!(credentials.access_key_id, "STS.TEMPORARY.1");
+        assert_eq!(
+            credentials
+                .security_token
+                .as_ref()
+                .map(SecretString::expose_secret),
+            Some("TEMPORARY_TOKEN")
+        );
+        assert!(client_service.is_initialized());
+        assert!(client_service.clone_client().is_none());
+
+        // Issued long enough ago to be refreshed right away, unlike the next ones.
+        let due = SessionCredentials {
+            issued_at: session.expiration - TimeDelta::hours(10),
+            ..session
+        };
+        client_service.initialize_session(
+            SessionIssuer::AssumeRole { provider, role },
+            due,
+            source,
+        );
+        let refreshed = async {
+            while client_service.credentials().unwrap().access_key_id == "STS.TEMPORARY.1" {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), refreshed)
+            .await
+            .expect("the credentials were not refreshed");
+        let credentials = client_service.credentials().unwrap();
+        assert_eq!(credentials.access_key_id, "STS.TEMPORARY.2");
+
+        client_service.clear();
+        assert!(!client_service.is_initialized());
+        assert!(client_service.credentials().is_none());
+        assert_eq!(client_service.source(), None);
+    }
+
+    #[tokio::test]
+    async fn test_region_clients() {
+        let mut profiles =
+            CredentialProfiles::single("work", AccessKeyCredentials::new("WORK_ID", "WORK_SECRET"));
+        profiles
+            .set_default_region("work", Some("cn-shanghai"))
+            .unwrap();
+        let auth_store = store_test_utils::init_profile_store();
+        auth_store.save(profiles).unwrap();
+        let auth_service = AccessKeyAuthService::new(auth_store);
+        let client_service = AliyunClientService::new();
+        assert!(client_service.region_client(None).is_none());
+
+        assert!(client_service.reload(&auth_service).await.unwrap());
+        let client = client_service.region_client(None).unwrap();
+        assert_eq!(client.region_id(), "cn-shanghai");
+        assert_eq!(
+            client.rpc().endpoint(),
+            "https://ecs.cn-shanghai.aliyuncs.com"
+        );
+
+        let client = client_service
+            .region_client(Some("ap-southeast-1"))
+            .unwrap();
+        assert_eq!(client.rpc().credentials().access_key_id, "WORK_ID");
+        assert!(client_service.region_client(Some("x.evil.com/")).is_none());
+        assert!(client_service
+            .vpc_client(Some("cn-shanghai.evil"))
+            .is_none());
+        let client = client_service.vpc_client(Some("cn-shanghai")).unwrap();
+        assert_eq!(client.api(), RegionalApi::Vpc);
+        assert_eq!(
+            client.rpc().endpoint(),
+            "https://vpc.cn-shanghai.aliyuncs.com"
+        );
+        assert_eq!(
+            client_service.pooled_regions(),
+            vec!["ap-southeast-1", "cn-shanghai"]
+        );
+
+        // New credentials invalidate the whole pool.
+        client_service.initialize(
+            AccessKeyCredentials::new("OTHER_ID", "OTHER_SECRET").into(),
+            CredentialSource::Environment,
+        );
+        assert!(client_service.pooled_regions().is_empty());
+        let client = client_service
+            .region_client(Some("ap-southeast-1"))
+            .unwrap();
+        assert_eq!(client.rpc().credentials().access_key_id, "OTHER_ID");
+
+        client_service.clear();
+        assert!(client_service.pooled_regions().is_empty());
+        assert!(client_service.region_client(None).is_none());
+        assert_eq!(client_service.default_region(), DEFAULT_REGION_ID);
+    }
+}
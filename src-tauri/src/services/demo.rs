@@ -0,0 +1,142 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::services::{
+    auth::{
+        chain::{CredentialSource, ResolvedCredentials},
+        types::ProfileCredentials,
+        AccessKeyAuthService,
+    },
+    client::AliyunClientService,
+    endpoint::EndpointConfig,
+    fake::{fake_credentials, FakeAliyun, FakeAliyunServer},
+};
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum DemoModeError {
+    #[error("failed to start the demo server: {0}")]
+    ServerFailed(String),
+    #[error("failed to build the client of the demo account: {0}")]
+    ClientFailed(String),
+}
+
+/// The demo account, served by a [`FakeAliyun`] running within the app.
+///
+/// While it is on, every request is sent to the fake instead of the configured endpoints,
+/// which are restored when it is turned off.
+#[derive(Default)]
+pub struct DemoMode {
+    server: Mutex<Option<FakeAliyunServer>>,
+    /// The endpoints in use before the demo started.
+    saved_endpoints: Mutex<Option<EndpointConfig>>,
+}
+
+impl DemoMode {
+    pub fn is_active(&self) -> bool {
+        self.server.lock().unwrap().is_some()
+    }
+
+    /// Starts the fake and builds the client from the demo account, which starts over
+    /// from its initial state every time.
+    pub async fn start(
+        &self,
+        auth_service: &AccessKeyAuthService,
+        client_service: &AliyunClientService,
+    ) -> Result<(), DemoModeError> {
+        let server = FakeAliyunServer::start(FakeAliyun::demo())
+            .await
+            .map_err(|err| DemoModeError::ServerFailed(err.to_string()))?;
+        let endpoints = EndpointConfig {
+            public_ip_url: Some(server.public_ip_url()),
+            ..EndpointConfig::with_base_url(server.uri())
+        };
+        log::info!("Serving the demo account at {}", server.uri());
+
+        let previous = self.server.lock().unwrap().replace(server);
+        if previous.is_none() {
+            *self.saved_endpoints.lock().unwrap() = Some(client_service.endpoints());
+        }
+        auth_service.set_endpoints(&endpoints);
+        client_service.set_endpoints(endpoints);
+
+        let credentials =
+            ResolvedCredentials::Profile(ProfileCredentials::AccessKey(fake_credentials()));
+        client_service
+            .activate(credentials, CredentialSource::Demo, &auth_service.sts())
+            .await
+            .map_err(|err| DemoModeError::ClientFailed(err.to_string()))
+    }
+
+    /// Keeps the endpoints to be put back once the demo stops, when it is on.
+    ///
+    /// Returns them back when the demo is off, for the caller to use them right away.
+    pub fn defer_endpoints(&self, endpoints: EndpointConfig) -> Option<EndpointConfig> {
+        let server = self.server.lock().unwrap();
+        if server.is_none() {
+            return Some(endpoints);
+        }
+        *self.saved_endpoints.lock().unwrap() = Some(endpoints);
+        None
+    }
+
+    /// Stops the fake and puts the previous endpoints back.
+    ///
+    /// The client is cleared, it is up to the caller to rebuild it from the active profile.
+    /// Returns whether the demo was on.
+    pub fn stop(
+        &self,
+        auth_service: &AccessKeyAuthService,
+        client_service: &AliyunClientService,
+    ) -> bool {
+        let Some(server) = self.server.lock().unwrap().take() else {
+            return false;
+        };
+        drop(server);
+
+        let endpoints = self
+            .saved_endpoints
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default();
+        auth_service.set_endpoints(&endpoints);
+        client_service.set_endpoints(endpoints);
+        client_service.clear();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::auth::store::store_test_utils;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_demo_mode() {
+        let auth_service = AccessKeyAuthService::new(store_test_utils::init_profile_store());
+        let client_service = AliyunClientService::new();
+        let saved = EndpointConfig {
+            use_vpc: true,
+            ..EndpointConfig::default()
+        };
+        client_service.set_endpoints(saved.clone());
+        let demo = DemoMode::default();
+
+        demo.start(&auth_service, &client_service).await.unwrap();
+        assert!(demo.is_active());
+        assert_eq!(client_service.source(), Some(CredentialSource::Demo));
+        assert!(client_service.is_valid().await.unwrap());
+        let deferred = EndpointConfig::with_base_url("http://127.0.0.1:1");
+        assert_eq!(demo.defer_endpoints(deferred.clone()), None);
+
+        assert!(demo.stop(&auth_service, &client_service));
+        assert!(!demo.is_active());
+        assert!(!client_service.is_initialized());
+        assert_eq!(client_service.endpoints(), deferred);
+        assert!(!demo.stop(&auth_service, &client_service));
+        assert_eq!(demo.defer_endpoints(saved.clone()), Some(saved));
+    }
+}
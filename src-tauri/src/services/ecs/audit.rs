@@ -0,0 +1,792 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{
+        catalog::{describe_security_groups, SecurityGroupType},
+        default_region_client, describe_regions, ecs_client,
+        inventory::{describe_region_instances, InstanceSummary, INSTANCE_PAGE_SIZE},
+        reachability::{
+            describe_instance_security_groups, evaluate_ingress, SourceEndpoint, TypedSecurityGroup,
+        },
+        security_group::{
+            describe_security_group_rules, parse_port_range, RuleDirection, RulePeer, RulePolicy,
+            RuleSpec, SecurityGroupRules,
+        },
+        EcsCommandError, EcsError, RegionFailure,
+    },
+    error::{AliyunRequestCommandError, NoOther},
+    region::RegionClient,
+};
+
+/// The ports flagged when open to the whole internet: SSH, RDP, MySQL, Redis and
+/// MongoDB.
+pub const SENSITIVE_PORTS: &[u16] = &[22, 3389, 3306, 6379, 27017];
+
+/// The widest port range not flagged, unless the options say otherwise.
+pub const DEFAULT_MAX_PORT_RANGE: u32 = 100;
+
+/// What the audit looks for.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, specta::Type)]
+pub struct AuditOptions {
+    /// Flagged when open to the whole internet, besides [`SENSITIVE_PORTS`].
+    #[serde(default)]
+    pub extra_sensitive_ports: Vec<u16>,
+    /// The widest port range not flagged, [`DEFAULT_MAX_PORT_RANGE`] when `None`.
+    #[serde(default)]
+    pub max_port_range: Option<u32>,
+    /// The regions audited, every region available to the account when `None`.
+    #[serde(default)]
+    pub region_ids: Option<Vec<String>>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    /// Exposed to the internet on an instance with a public IP.
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum FindingKind {
+    /// An ingress rule lets the whole internet reach sensitive ports.
+    SensitivePortOpen { ports: Vec<u16> },
+    /// An ingress rule lets more ports be reached than the options allow.
+    BroadPortRange { port_count: u32 },
+    /// No instance uses the group.
+    UnusedGroup,
+}
+
+impl FindingKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SensitivePortOpen { .. } => "SensitivePortOpen",
+            Self::BroadPortRange { .. } => "BroadPortRange",
+            Self::UnusedGroup => "UnusedGroup",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct AffectedInstance {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub public_ips: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: FindingKind,
+    pub region_id: String,
+    pub security_group_id: String,
+    pub security_group_name: String,
+    /// The rule at fault, `None` for the findings about the whole group.
+    pub security_group_rule_id: Option<String>,
+    pub rule: Option<RuleSpec>,
+    pub message: String,
+    /// The instances using the group.
+    pub affected_instances: Vec<AffectedInstance>,
+}
+
+/// The findings of an audit, the most severe first, and the regions which couldn't be
+/// audited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct AuditReport {
+    /// In RFC 3339.
+    pub audited_at: String,
+    pub groups_audited: u32,
+    pub findings: Vec<Finding>,
+    pub failures: Vec<RegionFailure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, specta::Type)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Whether the peer is the whole IPv4 or IPv6 internet.
+fn is_anywhere(peer: &RulePeer) -> bool {
+    match peer {
+        RulePeer::Cidr { cidr_ip } => cidr_ip == "0.0.0.0/0",
+        RulePeer::Ipv6Cidr { ipv6_cidr_ip } => ipv6_cidr_ip == "::/0",
+        _ => false,
+    }
+}
+
+/// The rule on a single line, e.g. `ingress TCP 22/22 from 0.0.0.0/0 Accept`.
+fn describe_rule(rule: &RuleSpec) -> String {
+    let (direction, way) = match rule.direction {
+        RuleDirection::Ingress => ("ingress", "from"),
+        RuleDirection::Egress => ("egress", "to"),
+    };
+    let peer = match &rule.peer {
+        RulePeer::Cidr { cidr_ip } => cidr_ip,
+        RulePeer::Ipv6Cidr { ipv6_cidr_ip } => ipv6_cidr_ip,
+        RulePeer::SecurityGroup { security_group_id } => security_group_id,
+        RulePeer::PrefixList { prefix_list_id } => prefix_list_id,
+    };
+    format!(
+        "{direction} {} {} {way} {peer} {}",
+        rule.ip_protocol,
+        rule.port_range,
+        rule.policy.as_str()
+    )
+}
+
+/// Whether the internet reaches the port through the group with the traffic of the rule,
+/// the rules of the group being evaluated by priority: a dropping rule may prevail over
+/// the rule.
+fn is_open_to_internet(group: &[TypedSecurityGroup], rule: &RuleSpec, port: u16) -> bool {
+    let anywhere = match rule.peer {
+        RulePeer::Ipv6Cidr { .. } => "::/0",
+        _ => "0.0.0.0/0",
+    };
+    let source = SourceEndpoint {
+        network: anywhere.parse().ok(),
+        security_group_ids: Vec::new(),
+    };
+    let ip_protocol = match rule.ip_protocol.as_str() {
+        "ALL" => "TCP",
+        ip_protocol => ip_protocol,
+    };
+    evaluate_ingress(&source, group, ip_protocol, Some(port)).allowed
+}
+
+/// The ports the rule opens, `None` for all of them.
+fn port_range(rule: &RuleSpec) -> Option<(u16, u16)> {
+    match rule.ip_protocol.as_str() {
+        "TCP" | "UDP" => parse_port_range(&rule.port_range),
+        "ALL" => None,
+        // Without ports, nothing to flag.
+        _ => Some((0, 0)),
+    }
+}
+
+/// Audits the groups of a region, given the instances of the region.
+pub fn audit_groups(
+    region_id: &str,
+    groups: &[SecurityGroupRules],
+    instances: &[InstanceSummary],
+    options: &AuditOptions,
+) -> Vec<Finding> {
+    let max_port_range = options.max_port_range.unwrap_or(DEFAULT_MAX_PORT_RANGE);
+    let sensitive_ports = SENSITIVE_PORTS
+        .iter()
+        .chain(&options.extra_sensitive_ports)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut findings = Vec::new();
+    for group in groups {
+        let affected_instances = instances
+            .iter()
+            .filter(|instance| {
+                instance
+                    .security_group_ids
+                    .contains(&group.security_group_id)
+            })
+            .map(|instance| AffectedInstance {
+                instance_id: instance.instance_id.clone(),
+                instance_name: instance.instance_name.clone(),
+                public_ips: instance.public_ips.clone(),
+            })
+            .collect::<Vec<_>>();
+        let exposed = affected_instances
+            .iter()
+            .any(|instance| !instance.public_ips.is_empty());
+        let finding = |severity, kind, rule: Option<(&str, &RuleSpec)>, message| Finding {
+            severity,
+            kind,
+            region_id: region_id.to_owned(),
+            security_group_id: group.security_group_id.clone(),
+            security_group_name: group.security_group_name.clone(),
+            security_group_rule_id: rule.map(|(rule_id, _)| rule_id.to_owned()),
+            rule: rule.map(|(_, rule)| rule.clone()),
+            message,
+            affected_instances: affected_instances.clone(),
+        };
+
+        if affected_instances.is_empty() {
+            findings.push(finding(
+                Severity::Low,
+                FindingKind::UnusedGroup,
+                None,
+                format!("no instance uses {}", group.security_group_id),
+            ));
+        }
+
+        // The type only matters for traffic from the groups, not from the internet.
+        let typed = [TypedSecurityGroup {
+            security_group_type: SecurityGroupType::Normal,
+            group: group.clone(),
+        }];
+        let accepted = group.rules.iter().filter(|rule| {
+            rule.rule.direction == RuleDirection::Ingress && rule.rule.policy == RulePolicy::Accept
+        });
+        for rule in accepted {
+            let spec = &rule.rule;
+            let anywhere = is_anywhere(&spec.peer);
+            let range = port_range(spec);
+            let id_and_rule = Some((rule.security_group_rule_id.as_str(), spec));
+
+            let mut ports = sensitive_ports
+                .iter()
+                .copied()
+                .filter(|port| range.is_none_or(|(start, end)| (start..=end).contains(port)))
+                .filter(|port| !anywhere || is_open_to_internet(&typed, spec, *port))
+                .collect::<Vec<_>>();
+            ports.sort_unstable();
+            ports.dedup();
+            if anywhere && !ports.is_empty() {
+                let list = ports
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                findings.push(finding(
+                    if exposed {
+                        Severity::Critical
+                    } else {
+                        Severity::High
+                    },
+                    FindingKind::SensitivePortOpen { ports },
+                    id_and_rule,
+                    format!(
+                        "{} lets the internet reach the ports {list}",
+                        describe_rule(spec)
+                    ),
+                ));
+            }
+
+            let port_count = match range {
+                Some((start, end)) if start > 0 => u32::from(end - start) + 1,
+                Some(_) => 0,
+                None => 65535,
+            };
+            if port_count > max_port_range {
+                findings.push(finding(
+                    if anywhere {
+                        Severity::Medium
+                    } else {
+                        Severity::Low
+                    },
+                    FindingKind::BroadPortRange { port_count },
+                    id_and_rule,
+                    format!("{} opens {port_count} ports", describe_rule(spec)),
+                ));
+            }
+        }
+    }
+    findings
+}
+
+/// Audits every security group of the region of `client`, returning how many there are
+/// and the findings.
+async fn audit_region(
+    client: &RegionClient,
+    options: &AuditOptions,
+) -> Result<(u32, Vec<Finding>), AliyunRequestCommandError<NoOther>> {
+    let infos = describe_security_groups(client, None, None)
+        .await
+        .map_err(AliyunRequestCommandError::from_any)?;
+    let mut groups = Vec::new();
+    for info in &infos {
+        let group = describe_security_group_rules(client, &info.security_group_id)
+            .await
+            .map_err(AliyunRequestCommandError::from_any)?;
+        groups.push(group);
+    }
+    let mut instances = describe_region_instances(client, INSTANCE_PAGE_SIZE).await?;
+    // An instance uses the groups of all of its network interfaces, not only those of
+    // the primary one which the instance lists.
+    let mut interface_groups = describe_instance_security_groups(client)
+        .await
+        .map_err(AliyunRequestCommandError::from_any)?;
+    for instance in &mut instances {
+        if let Some(groups) = interface_groups.remove(&instance.instance_id) {
+            let known = std::mem::take(&mut instance.security_group_ids);
+            instance.security_group_ids = known.into_iter().chain(groups).collect();
+            instance.security_group_ids.sort_unstable();
+            instance.security_group_ids.dedup();
+        }
+    }
+    Ok((
+        groups.len() as u32,
+        audit_groups(client.region_id(), &groups, &instances, options),
+    ))
+}
+
+/// Audits the security groups of the regions of the options in parallel, of every region
+/// available to the account when `None`.
+///
+/// A region failing doesn't fail the others, it is reported in the failures.
+pub async fn audit_security_groups(
+    client_service: &AliyunClientService,
+    options: AuditOptions,
+    now: DateTime<Utc>,
+) -> Result<AuditReport, EcsCommandError> {
+    let region_ids = match &options.region_ids {
+        Some(region_ids) => region_ids.clone(),
+        None => {
+            let client = default_region_client(client_service)?;
+            describe_regions(&client).await?
+        }
+    };
+
+    let mut report = AuditReport {
+        audited_at: now.to_rfc3339_opts(SecondsFormat::Secs, true),
+        groups_audited: 0,
+        findings: Vec::new(),
+        failures: Vec::new(),
+    };
+    let mut tasks = JoinSet::new();
+    for region_id in region_ids {
+        let client = match ecs_client(client_service, &region_id) {
+            Ok(client) => client,
+            Err(err) => {
+                let err = AliyunRequestCommandError::from_any(err);
+                report.failures.push(RegionFailure::new(&region_id, &err));
+                continue;
+            }
+        };
+        let options = options.clone();
+        // The error isn't `Send`, it is turned into a failure within the task.
+        tasks.spawn(async move {
+            audit_region(&client, &options)
+                .await
+                .map_err(|err| RegionFailure::new(&region_id, &err))
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(Ok((groups_audited, findings))) => {
+                report.groups_audited += groups_audited;
+                report.findings.extend(findings);
+            }
+            Ok(Err(failure)) => report.failures.push(failure),
+            Err(err) => {
+                return Err(AliyunRequestCommandError::InternalError {
+                    message: format!("auditing the security groups of a region failed: {err}"),
+                    source: Box::new(err),
+                })
+            }
+        }
+    }
+    report.findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.region_id.cmp(&b.region_id))
+            .then_with(|| a.security_group_id.cmp(&b.security_group_id))
+            .then_with(|| a.security_group_rule_id.cmp(&b.security_group_rule_id))
+    });
+    report
+        .failures
+        .sort_by(|a, b| a.region_id.cmp(&b.region_id));
+    Ok(report)
+}
+
+/// Quotes a CSV field when it has to be.
+///
+/// A field which a spreadsheet would take for a formula, such as a rule description
+/// starting with `=` or with a tab or carriage return before one, is prefixed with `'`
+/// to be shown as text.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{field}")
+    } else {
+        field.to_owned()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+impl AuditReport {
+    /// The report as JSON, or as CSV with a finding per line and the failures left out.
+    pub fn export(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self).unwrap(),
+            ExportFormat::Csv => {
+                let mut csv = String::from(
+                    "severity,finding,region_id,security_group_id,security_group_name,\
+                     security_group_rule_id,rule,message,affected_instances\r\n",
+                );
+                for finding in &self.findings {
+                    let instances = finding
+                        .affected_instances
+                        .iter()
+                        .map(|instance| instance.instance_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let fields = [
+                        format!("{:?}", finding.severity),
+                        finding.kind.name().to_owned(),
+                        finding.region_id.clone(),
+                        finding.security_group_id.clone(),
+                        finding.security_group_name.clone(),
+                        finding.security_group_rule_id.clone().unwrap_or_default(),
+                        finding.rule.as_ref().map(describe_rule).unwrap_or_default(),
+                        finding.message.clone(),
+                        instances,
+                    ];
+                    let line = fields
+                        .iter()
+                        .map(|field| csv_field(field))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    csv.push_str(&line);
+                    csv.push_str("\r\n");
+                }
+                csv
+            }
+        }
+    }
+
+    /// Writes the export to a new file of `dir` named after the time of the audit, e.g.
+    /// `security-audit-20261018T100000Z.csv`, and returns its path.
+    ///
+    /// An existing file is never overwritten.
+    pub fn export_to(&self, dir: &Path, format: ExportFormat) -> Result<PathBuf, EcsError> {
+        // The report comes from the frontend, its time can't be trusted as a file name.
+        let time = self
+            .audited_at
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>();
+        let path = dir.join(format!("security-audit-{time}.{}", format.extension()));
+        let failed = |err: std::io::Error| EcsError::ExportFailed {
+            path: path.display().to_string(),
+            message: err.to_string(),
+        };
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(failed)?;
+        file.write_all(self.export(format).as_bytes())
+            .map_err(failed)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        services::{ecs::security_group::SecurityGroupRule, fake::FAKE_REGIONS},
+        test_utils::start_fake_client,
+    };
+
+    use super::*;
+
+    fn rule(id: &str, ip_protocol: &str, port_range: &str, cidr_ip: &str) -> SecurityGroupRule {
+        SecurityGroupRule {
+            security_group_rule_id: id.to_owned(),
+            rule: RuleSpec {
+                direction: RuleDirection::Ingress,
+                ip_protocol: ip_protocol.to_owned(),
+                port_range: port_range.to_owned(),
+                peer: if cidr_ip.contains(':') {
+                    RulePeer::Ipv6Cidr {
+                        ipv6_cidr_ip: cidr_ip.to_owned(),
+                    }
+                } else {
+                    RulePeer::Cidr {
+                        cidr_ip: cidr_ip.to_owned(),
+                    }
+                },
+                policy: RulePolicy::Accept,
+                priority: 1,
+                description: String::new(),
+            },
+        }
+    }
+
+    fn instance(instance_id: &str, security_group_id: &str, public_ip: bool) -> InstanceSummary {
+        InstanceSummary {
+            instance_id: instance_id.to_owned(),
+            instance_name: instance_id.to_owned(),
+            region_id: "cn-hangzhou".to_owned(),
+            zone_id: "cn-hangzhou-h".to_owned(),
+            status: "Running".to_owned(),
+            start_time: None,
+            instance_type: "ecs.g7.large".to_owned(),
+            private_ips: vec!["10.0.0.1".to_owned()],
+            public_ips: if public_ip {
+                vec!["203.0.113.1".to_owned()]
+            } else {
+                Vec::new()
+            },
+            charge_type: "PostPaid".to_owned(),
+            expired_time: None,
+            tags: Vec::new(),
+            deletion_protection: false,
+            security_group_ids: vec![security_group_id.to_owned()],
+        }
+    }
+
+    #[test]
+    fn test_audit_groups() {
+        let groups = [
+            SecurityGroupRules {
+                security_group_id: "sg-web".to_owned(),
+                security_group_name: "web".to_owned(),
+                vpc_id: "vpc-1".to_owned(),
+                inner_access_policy: RulePolicy::Accept,
+                rules: vec![
+                    rule("sgr-ssh", "TCP", "22/22", "0.0.0.0/0"),
+                    rule("sgr-all", "ALL", "-1/-1", "::/0"),
+                    rule("sgr-private", "TCP", "1/65535", "10.0.0.0/8"),
+                    rule("sgr-ping", "ICMP", "-1/-1", "0.0.0.0/0"),
+                ],
+            },
+            SecurityGroupRules {
+                security_group_id: "sg-db".to_owned(),
+                security_group_name: "db".to_owned(),
+                vpc_id: "vpc-1".to_owned(),
+                inner_access_policy: RulePolicy::Accept,
+                rules: vec![rule("sgr-custom", "TCP", "9000/9001", "0.0.0.0/0")],
+            },
+        ];
+        let instances = [
+            instance("i-web", "sg-web", true),
+            instance("i-db", "sg-db", false),
+        ];
+        let options = AuditOptions {
+            extra_sensitive_ports: vec![9001],
+            ..AuditOptions::default()
+        };
+
+        let findings = audit_groups("cn-hangzhou", &groups, &instances, &options)
+            .into_iter()
+            .map(|finding| {
+                (
+                    finding.severity,
+                    finding.kind,
+                    finding.security_group_rule_id,
+                    finding
+                        .affected_instances
+                        .into_iter()
+                        .map(|instance| instance.instance_id)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let web = || vec!["i-web".to_owned()];
+        assert_eq!(
+            findings,
+            [
+                (
+                    Severity::Critical,
+                    FindingKind::SensitivePortOpen { ports: vec![22] },
+                    Some("sgr-ssh".to_owned()),
+                    web(),
+                ),
+                (
+                    Severity::Critical,
+                    FindingKind::SensitivePortOpen {
+                        ports: vec![22, 3306, 3389, 6379, 9001, 27017],
+                    },
+                    Some("sgr-all".to_owned()),
+                    web(),
+                ),
+                (
+                    Severity::Medium,
+                    FindingKind::BroadPortRange { port_count: 65535 },
+                    Some("sgr-all".to_owned()),
+                    web(),
+                ),
+                (
+                    Severity::Low,
+                    FindingKind::BroadPortRange { port_count: 65535 },
+                    Some("sgr-private".to_owned()),
+                    web(),
+                ),
+                (
+                    Severity::High,
+                    FindingKind::SensitivePortOpen { ports: vec![9001] },
+                    Some("sgr-custom".to_owned()),
+                    vec!["i-db".to_owned()],
+                ),
+            ]
+        );
+
+        let unused = audit_groups("cn-hangzhou", &groups[1..], &[], &AuditOptions::default());
+        assert_eq!(
+            unused
+                .iter()
+                .map(|finding| (finding.severity, &finding.kind))
+                .collect::<Vec<_>>(),
+            [(Severity::Low, &FindingKind::UnusedGroup)]
+        );
+
+        // A dropping rule of a higher priority closes the port again.
+        let mut drop_ssh = rule("sgr-drop-ssh", "TCP", "22/22", "0.0.0.0/0");
+        drop_ssh.rule.policy = RulePolicy::Drop;
+        let mut rules = vec![
+            drop_ssh,
+            rule("sgr-ssh", "TCP", "22/22", "0.0.0.0/0"),
+            rule("sgr-mysql", "TCP", "3306/3306", "0.0.0.0/0"),
+        ];
+        rules[1].rule.priority = 2;
+        let dropping = [SecurityGroupRules {
+            security_group_id: "sg-drop".to_owned(),
+            security_group_name: "drop".to_owned(),
+            vpc_id: "vpc-1".to_owned(),
+            inner_access_policy: RulePolicy::Accept,
+            rules,
+        }];
+        let instances = [instance("i-db", "sg-drop", false)];
+        let findings = audit_groups("cn-hangzhou", &dropping, &instances, &options);
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| (&finding.kind, finding.security_group_rule_id.as_deref()))
+                .collect::<Vec<_>>(),
+            [(
+                &FindingKind::SensitivePortOpen { ports: vec![3306] },
+                Some("sgr-mysql")
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_security_groups() {
+        let (server, client_service) = start_fake_client().await;
+        let options = AuditOptions {
+            region_ids: Some(vec![FAKE_REGIONS[0].0.to_owned()]),
+            ..AuditOptions::default()
+        };
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 10, 0, 0).unwrap();
+
+        let report = audit_security_groups(&client_service, options.clone(), now)
+            .await
+            .unwrap();
+        assert_eq!(report.audited_at, "2026-10-18T10:00:00Z");
+        assert_eq!(report.failures, []);
+        assert_eq!(
+            report
+                .findings
+                .iter()
+                .map(|finding| (
+                    finding.severity,
+                    finding.security_group_id.as_str(),
+                    finding.security_group_rule_id.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (Severity::Critical, "sg-fake0001", Some("sgr-fake0001")),
+                (Severity::Medium, "sg-fake0002", Some("sgr-fake0006")),
+                (Severity::Low, "sg-fake0002", None),
+            ]
+        );
+        assert!(!report.findings[0].affected_instances.is_empty());
+
+        // A group is used as well by the secondary network interfaces.
+        server
+            .fake()
+            .secondary_interfaces
+            .lock()
+            .unwrap()
+            .push(("i-fake0001".to_owned(), "sg-fake0002".to_owned()));
+        let with_secondary = audit_security_groups(&client_service, options.clone(), now)
+            .await
+            .unwrap();
+        assert_eq!(with_secondary.findings.len(), 2);
+        assert_eq!(with_secondary.findings[1].security_group_id, "sg-fake0002");
+        assert_eq!(
+            with_secondary.findings[1]
+                .affected_instances
+                .iter()
+                .map(|instance| instance.instance_id.as_str())
+                .collect::<Vec<_>>(),
+            ["i-fake0001"]
+        );
+        server.fake().secondary_interfaces.lock().unwrap().clear();
+
+        let csv = report.export(ExportFormat::Csv);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[1].starts_with("Critical,SensitivePortOpen,cn-hangzhou,sg-fake0001,"),
+            "{}",
+            lines[1]
+        );
+        let json = report.export(ExportFormat::Json);
+        assert_eq!(serde_json::from_str::<AuditReport>(&json).unwrap(), report);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = report.export_to(dir.path(), ExportFormat::Csv).unwrap();
+        assert_eq!(path, dir.path().join("security-audit-20261018T100000Z.csv"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), csv);
+        assert_matches!(
+            report.export_to(dir.path(), ExportFormat::Csv),
+            Err(EcsError::ExportFailed { .. })
+        );
+        let escaping = AuditReport {
+            audited_at: "../../evil".to_owned(),
+            ..report.clone()
+        };
+        let path = escaping.export_to(dir.path(), ExportFormat::Json).unwrap();
+        assert_eq!(path, dir.path().join("security-audit-evil.json"));
+
+        let options = AuditOptions {
+            region_ids: Some(vec!["cn-hangzhou.evil".to_owned()]),
+            ..AuditOptions::default()
+        };
+        let report = audit_security_groups(&client_service, options, now)
+            .await
+            .unwrap();
+        assert_eq!(report.groups_audited, 0);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].region_id, "cn-hangzhou.evil");
+
+        client_service.clear();
+        assert_matches!(
+            audit_security_groups(&client_service, AuditOptions::default(), now).await,
+            Err(AliyunRequestCommandError::Specific(EcsError::NoClient))
+        );
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("web"), "web");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("-1/-1"), "'-1/-1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("\t=1+2"), "'\t=1+2");
+        assert_eq!(csv_field("\r=1+2"), "\"'\r=1+2\"");
+    }
+}
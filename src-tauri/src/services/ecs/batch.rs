@@ -0,0 +1,525 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use alibabacloud::client::error::AliyunRejection;
+use serde::{Deserialize, Serialize};
+use tokio::task::{Id, JoinError, JoinSet};
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{ecs_client, power::PowerAction, EcsError},
+    error::{AliyunRequestCommandError, ClassifiedRejection, DetachedError},
+    region::RegionClient,
+};
+
+/// The code of the instances of a batch which went through.
+const SUCCESS_CODE: &str = "200";
+
+/// How often the cancellation of a batch is checked while waiting for a request.
+const CANCELLATION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many instances a batch request and its parallel requests take at most.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchLimits {
+    /// The instances of a single request, 100 at most for ECS.
+    pub batch_size: usize,
+    /// The requests sent at the same time, across all regions.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            max_concurrent_requests: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, specta::Type)]
+pub struct InstanceRef {
+    pub region_id: String,
+    pub instance_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+pub enum SkipReason {
+    /// The batch was cancelled before the request of the instance was sent.
+    Cancelled,
+    /// The instance already was in the status the action leads to.
+    AlreadyInStatus,
+}
+
+/// What happened to an instance of a batch.
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(tag = "type", content = "data")]
+pub enum InstanceOutcome {
+    /// Aliyun accepted the action, the instance now being in `status`.
+    Succeeded {
+        status: String,
+    },
+    Failed(AliyunRequestCommandError<EcsError>),
+    Skipped {
+        reason: SkipReason,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, specta::Type)]
+pub struct BatchSummary {
+    pub succeeded: u32,
+    pub failed: u32,
+    pub skipped: u32,
+}
+
+/// The outcome of each instance of a batch, by instance ID.
+#[derive(Debug, Serialize, specta::Type)]
+pub struct BatchReport {
+    pub results: BTreeMap<String, InstanceOutcome>,
+    pub summary: BatchSummary,
+}
+
+impl BatchReport {
+    fn new(outcomes: Vec<(String, InstanceOutcome)>) -> Self {
+        let mut summary = BatchSummary::default();
+        for (_, outcome) in &outcomes {
+            match outcome {
+                InstanceOutcome::Succeeded { .. } => summary.succeeded += 1,
+                InstanceOutcome::Failed(_) => summary.failed += 1,
+                InstanceOutcome::Skipped { .. } => summary.skipped += 1,
+            }
+        }
+        Self {
+            results: outcomes.into_iter().collect(),
+            summary,
+        }
+    }
+}
+
+/// The flags cancelling the running batches, by batch ID.
+#[derive(Default)]
+pub struct BatchCancellations {
+    flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl BatchCancellations {
+    /// Returns the flag cancelling the batch, raised by [`Self::cancel`].
+    pub fn register(&self, batch_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.flags
+            .lock()
+            .unwrap()
+            .insert(batch_id.to_owned(), flag.clone());
+        flag
+    }
+
+    /// Cancels the batch, returns whether it was running.
+    pub fn cancel(&self, batch_id: &str) -> bool {
+        let flags = self.flags.lock().unwrap();
+        let Some(flag) = flags.get(batch_id) else {
+            return false;
+        };
+        flag.store(true, Ordering::SeqCst);
+        true
+    }
+
+    pub fn finish(&self, batch_id: &str) {
+        self.flags.lock().unwrap().remove(batch_id);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceResponse {
+    instance_id: String,
+    code: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    current_status: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct InstanceResponseList {
+    instance_response: Vec<InstanceResponse>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BatchResponse {
+    #[serde(default)]
+    request_id: String,
+    #[serde(default)]
+    instance_responses: InstanceResponseList,
+}
+
+/// The outcome of an instance while it crosses tasks, see [`DetachedError`].
+enum DetachedOutcome {
+    Succeeded(String),
+    Failed(DetachedError<EcsError>),
+    Skipped(SkipReason),
+}
+
+impl From<DetachedOutcome> for InstanceOutcome {
+    fn from(outcome: DetachedOutcome) -> Self {
+        match outcome {
+            DetachedOutcome::Succeeded(status) => Self::Succeeded { status },
+            DetachedOutcome::Failed(error) => Self::Failed(error.into()),
+            DetachedOutcome::Skipped(reason) => Self::Skipped { reason },
+        }
+    }
+}
+
+/// Sends a single batch request, returns the outcome of each of its instances.
+///
+/// The instances are skipped when the batch is cancelled before the request is sent.
+async fn run_chunk(
+    client: RegionClient,
+    action: PowerAction,
+    instance_ids: Vec<String>,
+    cancelled: Arc<AtomicBool>,
+) -> Vec<(String, DetachedOutcome)> {
+    if cancelled.load(Ordering::SeqCst) {
+        return skip_cancelled(instance_ids);
+    }
+    let (name, params) = action.batch_request(&instance_ids);
+    let response = match client
+        .request::<BatchResponse, EcsError>(name, params)
+        .await
+    {
+        Ok(response) => response,
+        // The request failed as a whole, so did each of its instances.
+        Err(err) => {
+            let err = DetachedError::from(err);
+            return instance_ids
+                .into_iter()
+                .map(|instance_id| (instance_id, DetachedOutcome::Failed(err.duplicate())))
+                .collect();
+        }
+    };
+
+    let mut responses = response
+        .instance_responses
+        .instance_response
+        .into_iter()
+        .map(|r| (r.instance_id.clone(), r))
+        .collect::<HashMap<_, _>>();
+    instance_ids
+        .into_iter()
+        .map(|instance_id| {
+            let outcome = match responses.remove(&instance_id) {
+                Some(r) if r.code == SUCCESS_CODE => DetachedOutcome::Succeeded(r.current_status),
+                Some(r) => {
+                    let rejection = ClassifiedRejection::from(AliyunRejection {
+                        code: r.code,
+                        host_id: String::new(),
+                        message: r.message,
+                        request_id: response.request_id.clone(),
+                        recommend: String::new(),
+                    });
+                    let err = EcsError::of_instance(
+                        AliyunRequestCommandError::Rejected(Box::new(rejection)),
+                        &instance_id,
+                    );
+                    match err {
+                        AliyunRequestCommandError::Specific(
+                            EcsError::IncorrectInstanceStatus { .. },
+                        ) if r.current_status == action.target_status() => {
+                            DetachedOutcome::Skipped(SkipReason::AlreadyInStatus)
+                        }
+                        err => DetachedOutcome::Failed(err.into()),
+                    }
+                }
+                None => DetachedOutcome::Failed(DetachedError::InternalError {
+                    message: format!("Aliyun sent back no result for {instance_id}"),
+                }),
+            };
+            (instance_id, outcome)
+        })
+        .collect()
+}
+
+fn skip_cancelled(instance_ids: Vec<String>) -> Vec<(String, DetachedOutcome)> {
+    instance_ids
+        .into_iter()
+        .map(|instance_id| (instance_id, DetachedOutcome::Skipped(SkipReason::Cancelled)))
+        .collect()
+}
+
+/// Adds the outcomes of a finished request, `pending` holding the instances of the
+/// requests still running by task, so that those of a panicked one fail.
+fn collect_chunk(
+    joined: Result<(Id, Vec<(String, DetachedOutcome)>), JoinError>,
+    pending: &mut HashMap<Id, Vec<String>>,
+    outcomes: &mut Vec<(String, DetachedOutcome)>,
+) {
+    match joined {
+        Ok((id, chunk)) => {
+            pending.remove(&id);
+            outcomes.extend(chunk);
+        }
+        Err(err) => {
+            log::error!("A batch request panicked, its instances failed: {err}");
+            let message = format!("the batch request failed unexpectedly: {err}");
+            let failed = pending.remove(&err.id()).unwrap_or_default();
+            outcomes.extend(failed.into_iter().map(|instance_id| {
+                let err = DetachedError::InternalError {
+                    message: message.clone(),
+                };
+                (instance_id, DetachedOutcome::Failed(err))
+            }));
+        }
+    }
+}
+
+/// Applies the action to the instances, batched by region.
+///
+/// At most `limits.max_concurrent_requests` requests are sent at the same time. Once
+/// `cancelled` is raised, the requests not sent yet are dropped and their instances
+/// skipped, even those waiting for a request to end. Each instance succeeds or fails
+/// on its own, a request or a region failing as a whole failing each of its instances.
+pub async fn run_batch(
+    client_service: &AliyunClientService,
+    action: PowerAction,
+    instances: Vec<InstanceRef>,
+    limits: &BatchLimits,
+    cancelled: Arc<AtomicBool>,
+) -> Result<BatchReport, AliyunRequestCommandError<EcsError>> {
+    let mut by_region = BTreeMap::<String, BTreeSet<String>>::new();
+    for instance in instances {
+        by_region
+            .entry(instance.region_id)
+            .or_default()
+            .insert(instance.instance_id);
+    }
+
+    let mut outcomes = Vec::new();
+    let mut chunks = Vec::new();
+    for (region_id, instance_ids) in by_region {
+        let client =
+            match ecs_client(client_service, &region_id) {
+                Ok(client) => client,
+                Err(err) => {
+                    let err = DetachedError::from(err);
+                    outcomes.extend(instance_ids.into_iter().map(|instance_id| {
+                        (instance_id, DetachedOutcome::Failed(err.duplicate()))
+                    }));
+                    continue;
+                }
+            };
+        let instance_ids = instance_ids.into_iter().collect::<Vec<_>>();
+        for chunk in instance_ids.chunks(limits.batch_size.max(1)) {
+            chunks.push((client.clone(), chunk.to_vec()));
+        }
+    }
+
+    let mut tasks = JoinSet::new();
+    let mut pending = HashMap::new();
+    for (client, instance_ids) in chunks {
+        while tasks.len() >= limits.max_concurrent_requests.max(1)
+            && !cancelled.load(Ordering::SeqCst)
+        {
+            let next = tokio::time::timeout(CANCELLATION_CHECK_INTERVAL, tasks.join_next_with_id());
+            if let Ok(Some(joined)) = next.await {
+                collect_chunk(joined, &mut pending, &mut outcomes);
+            }
+        }
+        if cancelled.load(Ordering::SeqCst) {
+            outcomes.extend(skip_cancelled(instance_ids));
+            continue;
+        }
+        let task = run_chunk(client, action, instance_ids.clone(), cancelled.clone());
+        pending.insert(tasks.spawn(task).id(), instance_ids);
+    }
+    while let Some(joined) = tasks.join_next_with_id().await {
+        collect_chunk(joined, &mut pending, &mut outcomes);
+    }
+
+    let outcomes = outcomes
+        .into_iter()
+        .map(|(instance_id, outcome)| (instance_id, outcome.into()))
+        .collect();
+    Ok(BatchReport::new(outcomes))
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        services::{
+            ecs::power::StoppedMode,
+            fake::{FakeRejection, FAKE_REGIONS},
+        },
+        test_utils::start_fake_client,
+    };
+
+    use super::*;
+
+    fn instance_refs(region_id: &str, instance_ids: &[&str]) -> Vec<InstanceRef> {
+        instance_ids
+            .iter()
+            .map(|instance_id| InstanceRef {
+                region_id: region_id.to_owned(),
+                instance_id: (*instance_id).to_owned(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_batch() {
+        let (server, client_service) = start_fake_client().await;
+        let limits = BatchLimits {
+            batch_size: 2,
+            max_concurrent_requests: 1,
+        };
+        let mut instances = instance_refs(
+            FAKE_REGIONS[0].0,
+            &["i-fake0001", "i-fake0002", "i-fake0003", "i-none"],
+        );
+        instances.extend(instance_refs(FAKE_REGIONS[1].0, &["i-elsewhere"]));
+        let stop = PowerAction::Stop(StoppedMode::StopCharging);
+
+        let report = run_batch(
+            &client_service,
+            stop,
+            instances.clone(),
+            &limits,
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            report.summary,
+            BatchSummary {
+                succeeded: 2,
+                failed: 2,
+                skipped: 1,
+            }
+        );
+        assert_matches!(&report.results["i-fake0001"], InstanceOutcome::Succeeded { status } if status == "Stopped");
+        assert_matches!(
+            &report.results["i-fake0003"],
+            InstanceOutcome::Skipped {
+                reason: SkipReason::AlreadyInStatus
+            }
+        );
+        assert_matches!(
+            &report.results["i-none"],
+            InstanceOutcome::Failed(AliyunRequestCommandError::Specific(
+                EcsError::InstanceNotFound { .. }
+            ))
+        );
+        assert_eq!(
+            server
+                .fake()
+                .calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|call| *call == "StopInstances")
+                .count(),
+            3
+        );
+        assert_eq!(
+            server.fake().instance("i-fake0002").unwrap().stopped_mode,
+            "StopCharging"
+        );
+
+        // A request failing as a whole fails each of its instances.
+        server
+            .fake()
+            .reject("StartInstances", FakeRejection::new(403, "Forbidden.RAM"));
+        let report = run_batch(
+            &client_service,
+            PowerAction::Start,
+            instances.clone(),
+            &limits,
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.failed, 5);
+        assert_matches!(
+            &report.results["i-fake0002"],
+            InstanceOutcome::Failed(AliyunRequestCommandError::Rejected(r))
+                if r.missing_permission.is_some()
+        );
+
+        // So does a region without a client, without failing the other regions.
+        server.fake().clear_rejections();
+        let mut elsewhere = instance_refs(FAKE_REGIONS[0].0, &["i-fake0001"]);
+        elsewhere.extend(instance_refs("cn-hangzhou.evil", &["i-evil"]));
+        let report = run_batch(
+            &client_service,
+            PowerAction::Start,
+            elsewhere,
+            &limits,
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await
+        .unwrap();
+        assert_matches!(
+            &report.results["i-fake0001"],
+            InstanceOutcome::Succeeded { .. }
+        );
+        assert_matches!(
+            &report.results["i-evil"],
+            InstanceOutcome::Failed(AliyunRequestCommandError::Specific(
+                EcsError::InvalidRegion { region_id }
+            )) if region_id == "cn-hangzhou.evil"
+        );
+
+        let report = run_batch(
+            &client_service,
+            PowerAction::Reboot,
+            instances,
+            &limits,
+            Arc::new(AtomicBool::new(true)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.skipped, 5);
+    }
+
+    #[tokio::test]
+    async fn test_collect_panicked_chunk() {
+        let mut tasks = JoinSet::<Vec<(String, DetachedOutcome)>>::new();
+        let id = tasks.spawn(async { panic!("a bug") }).id();
+        let mut pending = HashMap::from([(id, vec!["i-fake0001".to_owned()])]);
+        let mut outcomes = Vec::new();
+
+        let joined = tasks.join_next_with_id().await.unwrap();
+        collect_chunk(joined, &mut pending, &mut outcomes);
+        assert!(pending.is_empty());
+        let report = BatchReport::new(
+            outcomes
+                .into_iter()
+                .map(|(instance_id, outcome)| (instance_id, outcome.into()))
+                .collect(),
+        );
+        assert_eq!(report.summary.failed, 1);
+        assert_matches!(
+            &report.results["i-fake0001"],
+            InstanceOutcome::Failed(AliyunRequestCommandError::InternalError { .. })
+        );
+    }
+
+    #[test]
+    fn test_cancellations() {
+        let cancellations = BatchCancellations::default();
+        let flag = cancellations.register("batch-1");
+        assert!(!cancellations.cancel("batch-2"));
+        assert!(cancellations.cancel("batch-1"));
+        assert!(flag.load(Ordering::SeqCst));
+        cancellations.finish("batch-1");
+        assert!(!cancellations.cancel("batch-1"));
+    }
+}
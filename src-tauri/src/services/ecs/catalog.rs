@@ -0,0 +1,698 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::services::{
+    ecs::{launch::ChargeType, EcsError},
+    error::AliyunRequestCommandError,
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The largest page of the ECS listings the catalog pages through.
+const ECS_PAGE_SIZE: u32 = 100;
+/// The largest page of the VPC listings.
+const VPC_PAGE_SIZE: u32 = 50;
+/// The largest page of DescribeKeyPairs, smaller than the other ECS listings.
+const KEY_PAIR_PAGE_SIZE: u32 = 50;
+
+/// A zone of a region, where instances and vSwitches live.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ZoneInfo {
+    pub zone_id: String,
+    pub local_name: String,
+}
+
+/// What DescribeAvailableResource tells about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum ResourceKind {
+    InstanceType,
+    /// The disk categories of the system disk, for an instance type.
+    SystemDisk,
+    /// The disk categories of the data disks, for an instance type.
+    DataDisk,
+}
+
+impl ResourceKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::InstanceType => "InstanceType",
+            Self::SystemDisk => "SystemDisk",
+            Self::DataDisk => "DataDisk",
+        }
+    }
+}
+
+/// An instance type or a disk category offered in a zone.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ZoneResource {
+    /// E.g. `ecs.g7.large` or `cloud_essd`.
+    pub value: String,
+    /// Whether it is in stock, sold out ones being listed too.
+    pub available: bool,
+    /// E.g. `WithStock`, `ClosedWithStock` or `WithoutStock`.
+    pub status_category: String,
+    /// The smallest and largest disk size, in GiB.
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+/// A bootable image.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ImageInfo {
+    pub image_id: String,
+    pub image_name: String,
+    pub os_name: String,
+    /// `x86_64` or `arm64`.
+    pub architecture: String,
+    /// `system`, `self`, `others` or `marketplace`.
+    pub owner_alias: String,
+    /// The size of the image, the least the system disk can be, in GiB.
+    pub size: i32,
+}
+
+/// Whose images to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum ImageOwner {
+    /// The public images of Aliyun.
+    System,
+    /// The custom images of the account.
+    OwnImages,
+    /// The images shared with the account.
+    Shared,
+    Marketplace,
+}
+
+impl ImageOwner {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::OwnImages => "self",
+            Self::Shared => "others",
+            Self::Marketplace => "marketplace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct VpcInfo {
+    pub vpc_id: String,
+    pub vpc_name: String,
+    pub cidr_block: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct VSwitchInfo {
+    pub vswitch_id: String,
+    pub vswitch_name: String,
+    pub vpc_id: String,
+    pub zone_id: String,
+    pub cidr_block: String,
+    pub available_ip_address_count: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+pub enum SecurityGroupType {
+    /// A basic group, whose instances can reach each other unless its inner access
+    /// policy drops it, and whose rules can name other groups as peers.
+    #[default]
+    #[serde(alias = "normal")]
+    Normal,
+    /// An enterprise group, whose instances can't reach each other and whose rules
+    /// can't name other groups as peers.
+    #[serde(alias = "enterprise")]
+    Enterprise,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct SecurityGroupInfo {
+    pub security_group_id: String,
+    pub security_group_name: String,
+    pub vpc_id: String,
+    pub description: String,
+    pub security_group_type: SecurityGroupType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct KeyPairInfo {
+    pub key_pair_name: String,
+    pub key_pair_finger_print: String,
+}
+
+/// An answer of a listing paged by page number.
+pub(super) trait Page: DeserializeOwned {
+    type Item;
+
+    fn total_count(&self) -> u32;
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Calls the listing page after page until every item is there.
+pub(super) async fn describe_pages<P: Page>(
+    client: &RegionClient,
+    action: &str,
+    mut params: RpcParams,
+    page_size: u32,
+) -> Result<Vec<P::Item>, AliyunRequestCommandError<EcsError>> {
+    let mut items = Vec::new();
+    params.insert("PageSize".to_owned(), page_size.to_string());
+    for page_number in 1.. {
+        params.insert("PageNumber".to_owned(), page_number.to_string());
+        let page: P = client.request(action, params.clone()).await?;
+        let total_count = page.total_count() as usize;
+        let page = page.into_items();
+        let page_len = page.len();
+        items.extend(page);
+        if page_len == 0 || items.len() >= total_count {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// Implements [`Page`] for the answer `$page`, its items being in `$list.$item`.
+macro_rules! page {
+    ($page:ident, $list:ident, $item:ident => $info:ty) => {
+        impl Page for $page {
+            type Item = $info;
+
+            fn total_count(&self) -> u32 {
+                self.total_count
+            }
+
+            fn into_items(self) -> Vec<$info> {
+                self.$list.$item.into_iter().map(Into::into).collect()
+            }
+        }
+    };
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedZone {
+    zone_id: String,
+    #[serde(default)]
+    local_name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct ZoneList {
+    zone: Vec<DescribedZone>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedZones {
+    #[serde(default)]
+    zones: ZoneList,
+}
+
+/// The zones of the region of `client`.
+pub async fn describe_zones(
+    client: &RegionClient,
+) -> Result<Vec<ZoneInfo>, AliyunRequestCommandError<EcsError>> {
+    let described: DescribedZones = client
+        .request(
+            "DescribeZones",
+            RpcParams::from([("Verbose".to_owned(), "false".to_owned())]),
+        )
+        .await?;
+    Ok(described
+        .zones
+        .zone
+        .into_iter()
+        .map(|zone| ZoneInfo {
+            zone_id: zone.zone_id,
+            local_name: zone.local_name,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SupportedResource {
+    value: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    status_category: String,
+    #[serde(default)]
+    min: Option<i32>,
+    #[serde(default)]
+    max: Option<i32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct SupportedResources {
+    supported_resource: Vec<SupportedResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AvailableResource {
+    #[serde(default)]
+    supported_resources: SupportedResources,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct AvailableResources {
+    available_resource: Vec<AvailableResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AvailableZone {
+    #[serde(default)]
+    available_resources: AvailableResources,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct AvailableZones {
+    available_zone: Vec<AvailableZone>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedAvailableResources {
+    #[serde(default)]
+    available_zones: AvailableZones,
+}
+
+/// The instance types or disk categories offered in the zone with the charge type.
+///
+/// Disk categories depend on the instance type, which then has to be given.
+pub async fn describe_available_resources(
+    client: &RegionClient,
+    zone_id: &str,
+    kind: ResourceKind,
+    charge_type: &ChargeType,
+    instance_type: Option<&str>,
+) -> Result<Vec<ZoneResource>, AliyunRequestCommandError<EcsError>> {
+    let mut params = RpcParams::from([
+        ("ZoneId".to_owned(), zone_id.to_owned()),
+        ("DestinationResource".to_owned(), kind.as_str().to_owned()),
+        (
+            "InstanceChargeType".to_owned(),
+            charge_type.as_str().to_owned(),
+        ),
+    ]);
+    if let Some(instance_type) = instance_type {
+        params.insert("InstanceType".to_owned(), instance_type.to_owned());
+    }
+    let described: DescribedAvailableResources =
+        client.request("DescribeAvailableResource", params).await?;
+    Ok(described
+        .available_zones
+        .available_zone
+        .into_iter()
+        .flat_map(|zone| zone.available_resources.available_resource)
+        .flat_map(|resource| resource.supported_resources.supported_resource)
+        .map(|resource| ZoneResource {
+            available: resource.status == "Available",
+            value: resource.value,
+            status_category: resource.status_category,
+            min: resource.min,
+            max: resource.max,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedImage {
+    image_id: String,
+    #[serde(default)]
+    image_name: String,
+    #[serde(default, rename = "OSName")]
+    os_name: String,
+    #[serde(default)]
+    architecture: String,
+    #[serde(default)]
+    image_owner_alias: String,
+    #[serde(default)]
+    size: i32,
+}
+
+impl From<DescribedImage> for ImageInfo {
+    fn from(image: DescribedImage) -> Self {
+        Self {
+            image_id: image.image_id,
+            image_name: image.image_name,
+            os_name: image.os_name,
+            architecture: image.architecture,
+            owner_alias: image.image_owner_alias,
+            size: image.size,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct ImageList {
+    image: Vec<DescribedImage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedImages {
+    #[serde(default)]
+    images: ImageList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+page!(DescribedImages, images, image => ImageInfo);
+
+/// The available images of the owner, only those the instance type can boot when given.
+pub async fn describe_images(
+    client: &RegionClient,
+    owner: ImageOwner,
+    instance_type: Option<&str>,
+) -> Result<Vec<ImageInfo>, AliyunRequestCommandError<EcsError>> {
+    let mut params = RpcParams::from([
+        ("ImageOwnerAlias".to_owned(), owner.as_str().to_owned()),
+        ("Status".to_owned(), "Available".to_owned()),
+    ]);
+    if let Some(instance_type) = instance_type {
+        params.insert("InstanceType".to_owned(), instance_type.to_owned());
+    }
+    describe_pages::<DescribedImages>(client, "DescribeImages", params, ECS_PAGE_SIZE).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVpc {
+    vpc_id: String,
+    #[serde(default)]
+    vpc_name: String,
+    #[serde(default)]
+    cidr_block: String,
+    #[serde(default)]
+    is_default: bool,
+}
+
+impl From<DescribedVpc> for VpcInfo {
+    fn from(vpc: DescribedVpc) -> Self {
+        Self {
+            vpc_id: vpc.vpc_id,
+            vpc_name: vpc.vpc_name,
+            cidr_block: vpc.cidr_block,
+            is_default: vpc.is_default,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct VpcList {
+    vpc: Vec<DescribedVpc>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVpcs {
+    #[serde(default)]
+    vpcs: VpcList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+page!(DescribedVpcs, vpcs, vpc => VpcInfo);
+
+/// The VPCs of the region of `client`, a client of the VPC API.
+pub async fn describe_vpcs(
+    client: &RegionClient,
+) -> Result<Vec<VpcInfo>, AliyunRequestCommandError<EcsError>> {
+    describe_pages::<DescribedVpcs>(client, "DescribeVpcs", RpcParams::new(), VPC_PAGE_SIZE).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVSwitch {
+    #[serde(rename = "VSwitchId")]
+    vswitch_id: String,
+    #[serde(default, rename = "VSwitchName")]
+    vswitch_name: String,
+    #[serde(default)]
+    vpc_id: String,
+    #[serde(default)]
+    zone_id: String,
+    #[serde(default)]
+    cidr_block: String,
+    #[serde(default)]
+    available_ip_address_count: i32,
+}
+
+impl From<DescribedVSwitch> for VSwitchInfo {
+    fn from(vswitch: DescribedVSwitch) -> Self {
+        Self {
+            vswitch_id: vswitch.vswitch_id,
+            vswitch_name: vswitch.vswitch_name,
+            vpc_id: vswitch.vpc_id,
+            zone_id: vswitch.zone_id,
+            cidr_block: vswitch.cidr_block,
+            available_ip_address_count: vswitch.available_ip_address_count,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct VSwitchList {
+    #[serde(rename = "VSwitch")]
+    vswitch: Vec<DescribedVSwitch>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVSwitches {
+    #[serde(default, rename = "VSwitches")]
+    vswitches: VSwitchList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+page!(DescribedVSwitches, vswitches, vswitch => VSwitchInfo);
+
+/// The vSwitches of the region of `client`, a client of the VPC API, narrowed down to a
+/// VPC, a zone or a single vSwitch.
+pub async fn describe_vswitches(
+    client: &RegionClient,
+    vpc_id: Option<&str>,
+    zone_id: Option<&str>,
+    vswitch_id: Option<&str>,
+) -> Result<Vec<VSwitchInfo>, AliyunRequestCommandError<EcsError>> {
+    let params = [
+        ("VpcId", vpc_id),
+        ("ZoneId", zone_id),
+        ("VSwitchId", vswitch_id),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name.to_owned(), value?.to_owned())))
+    .collect();
+    describe_pages::<DescribedVSwitches>(client, "DescribeVSwitches", params, VPC_PAGE_SIZE).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedSecurityGroup {
+    security_group_id: String,
+    #[serde(default)]
+    security_group_name: String,
+    #[serde(default)]
+    vpc_id: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    security_group_type: SecurityGroupType,
+}
+
+impl From<DescribedSecurityGroup> for SecurityGroupInfo {
+    fn from(group: DescribedSecurityGroup) -> Self {
+        Self {
+            security_group_id: group.security_group_id,
+            security_group_name: group.security_group_name,
+            vpc_id: group.vpc_id,
+            description: group.description,
+            security_group_type: group.security_group_type,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct SecurityGroupList {
+    security_group: Vec<DescribedSecurityGroup>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedSecurityGroups {
+    #[serde(default)]
+    security_groups: SecurityGroupList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+page!(DescribedSecurityGroups, security_groups, security_group => SecurityGroupInfo);
+
+/// The security groups of the region of `client`, of a VPC or with the given IDs.
+pub async fn describe_security_groups(
+    client: &RegionClient,
+    vpc_id: Option<&str>,
+    security_group_ids: Option<&[String]>,
+) -> Result<Vec<SecurityGroupInfo>, AliyunRequestCommandError<EcsError>> {
+    let mut params = RpcParams::new();
+    if let Some(vpc_id) = vpc_id {
+        params.insert("VpcId".to_owned(), vpc_id.to_owned());
+    }
+    if let Some(ids) = security_group_ids {
+        params.insert(
+            "SecurityGroupIds".to_owned(),
+            serde_json::to_string(ids).unwrap(),
+        );
+    }
+    describe_pages::<DescribedSecurityGroups>(
+        client,
+        "DescribeSecurityGroups",
+        params,
+        ECS_PAGE_SIZE,
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedKeyPair {
+    key_pair_name: String,
+    #[serde(default)]
+    key_pair_finger_print: String,
+}
+
+impl From<DescribedKeyPair> for KeyPairInfo {
+    fn from(key_pair: DescribedKeyPair) -> Self {
+        Self {
+            key_pair_name: key_pair.key_pair_name,
+            key_pair_finger_print: key_pair.key_pair_finger_print,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct KeyPairList {
+    key_pair: Vec<DescribedKeyPair>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedKeyPairs {
+    #[serde(default)]
+    key_pairs: KeyPairList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+page!(DescribedKeyPairs, key_pairs, key_pair => KeyPairInfo);
+
+/// The SSH key pairs of the region of `client`.
+pub async fn describe_key_pairs(
+    client: &RegionClient,
+) -> Result<Vec<KeyPairInfo>, AliyunRequestCommandError<EcsError>> {
+    describe_pages::<DescribedKeyPairs>(
+        client,
+        "DescribeKeyPairs",
+        RpcParams::new(),
+        KEY_PAIR_PAGE_SIZE,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::services::{
+        fake::{FakeAliyun, FakeAliyunServer},
+        region::RegionalApi,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_catalog() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let ecs = server.region_client();
+        let vpc = server.api_client(RegionalApi::Vpc);
+
+        let zones = describe_zones(&ecs).await.unwrap();
+        assert_eq!(
+            zones.iter().map(|zone| &zone.zone_id).collect::<Vec<_>>(),
+            ["cn-hangzhou-h", "cn-hangzhou-i"]
+        );
+
+        let instance_types = describe_available_resources(
+            &ecs,
+            "cn-hangzhou-h",
+            ResourceKind::InstanceType,
+            &ChargeType::PostPaid,
+            None,
+        )
+        .await
+        .unwrap();
+        let sold_out = instance_types
+            .iter()
+            .find(|resource| resource.value == "ecs.g7.xlarge")
+            .unwrap();
+        assert!(!sold_out.available);
+        assert_eq!(sold_out.status_category, "WithoutStock");
+        let categories = describe_available_resources(
+            &ecs,
+            "cn-hangzhou-h",
+            ResourceKind::SystemDisk,
+            &ChargeType::PostPaid,
+            Some("ecs.g7.large"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(categories[0].value, "cloud_essd");
+        assert_eq!(
+            (categories[0].min, categories[0].max),
+            (Some(20), Some(2048))
+        );
+
+        let images = describe_images(&ecs, ImageOwner::System, Some("ecs.g7.large"))
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].owner_alias, "system");
+        assert!(describe_images(&ecs, ImageOwner::OwnImages, None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let vpcs = describe_vpcs(&vpc).await.unwrap();
+        assert!(vpcs[0].is_default);
+        let vswitches = describe_vswitches(&vpc, Some("vpc-fake0001"), Some("cn-hangzhou-i"), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            vswitches
+                .iter()
+                .map(|vswitch| &vswitch.vswitch_id)
+                .collect::<Vec<_>>(),
+            ["vsw-fake0002"]
+        );
+        let groups = describe_security_groups(&ecs, Some("vpc-fake0002"), None)
+            .await
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].security_group_id, "sg-fake0002");
+        assert_eq!(groups[0].security_group_type, SecurityGroupType::Normal);
+        let key_pairs = describe_key_pairs(&ecs).await.unwrap();
+        assert_eq!(key_pairs[0].key_pair_name, "demo-key");
+    }
+}
@@ -0,0 +1,465 @@
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::{
+        default_region_client, describe_regions, ecs_client, EcsError, RegionFailure,
+        SecurityGroupIdList,
+    },
+    error::{AliyunRequestCommandError, NoOther, SpecificError},
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The largest page of DescribeInstances.
+pub const INSTANCE_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct InstanceTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// What the inventory shows of an instance.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct InstanceSummary {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub region_id: String,
+    pub zone_id: String,
+    /// E.g. `Running`, `Stopped`, `Starting` or `Stopping`.
+    pub status: String,
+    /// When the instance was last started or rebooted, `None` if it never was.
+    pub start_time: Option<String>,
+    pub instance_type: String,
+    /// The private addresses, in the VPC or the classic network.
+    pub private_ips: Vec<String>,
+    /// The public addresses, elastic ones included.
+    pub public_ips: Vec<String>,
+    /// `PostPaid` (pay-as-you-go) or `PrePaid` (subscription).
+    pub charge_type: String,
+    /// When the subscription ends, `None` for pay-as-you-go instances.
+    pub expired_time: Option<String>,
+    pub tags: Vec<InstanceTag>,
+    /// Whether the instance can't be released until the protection is turned off.
+    pub deletion_protection: bool,
+    /// The security groups of its primary network interface.
+    pub security_group_ids: Vec<String>,
+}
+
+/// The instances of several regions, and the regions which couldn't be listed.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct InstanceInventory {
+    pub instances: Vec<InstanceSummary>,
+    pub failures: Vec<RegionFailure>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct IpAddresses {
+    ip_address: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct EipAddress {
+    ip_address: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct VpcAttributes {
+    private_ip_address: IpAddresses,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedTag {
+    tag_key: String,
+    #[serde(default)]
+    tag_value: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct DescribedTags {
+    tag: Vec<DescribedTag>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedInstance {
+    instance_id: String,
+    #[serde(default)]
+    instance_name: String,
+    region_id: String,
+    #[serde(default)]
+    zone_id: String,
+    status: String,
+    #[serde(default)]
+    start_time: String,
+    #[serde(default)]
+    instance_type: String,
+    #[serde(default)]
+    vpc_attributes: VpcAttributes,
+    #[serde(default)]
+    inner_ip_address: IpAddresses,
+    #[serde(default)]
+    public_ip_address: IpAddresses,
+    #[serde(default)]
+    eip_address: EipAddress,
+    #[serde(default)]
+    instance_charge_type: String,
+    #[serde(default)]
+    expired_time: String,
+    #[serde(default)]
+    tags: DescribedTags,
+    #[serde(default)]
+    deletion_protection: bool,
+    #[serde(default)]
+    security_group_ids: SecurityGroupIdList,
+}
+
+impl From<DescribedInstance> for InstanceSummary {
+    fn from(instance: DescribedInstance) -> Self {
+        let private_ips = [
+            instance.vpc_attributes.private_ip_address.ip_address,
+            instance.inner_ip_address.ip_address,
+        ]
+        .concat();
+        let mut public_ips = instance.public_ip_address.ip_address;
+        if !instance.eip_address.ip_address.is_empty() {
+            public_ips.push(instance.eip_address.ip_address);
+        }
+        // Pay-as-you-go instances report an expiry far in the future, meaningless.
+        let expired_time = (instance.instance_charge_type == "PrePaid"
+            && !instance.expired_time.is_empty())
+        .then_some(instance.expired_time);
+
+        Self {
+            instance_id: instance.instance_id,
+            instance_name: instance.instance_name,
+            region_id: instance.region_id,
+            zone_id: instance.zone_id,
+            status: instance.status,
+            start_time: (!instance.start_time.is_empty()).then_some(instance.start_time),
+            instance_type: instance.instance_type,
+            private_ips,
+            public_ips,
+            charge_type: instance.instance_charge_type,
+            expired_time,
+            tags: instance
+                .tags
+                .tag
+                .into_iter()
+                .map(|tag| InstanceTag {
+                    key: tag.tag_key,
+                    value: tag.tag_value,
+                })
+                .collect(),
+            deletion_protection: instance.deletion_protection,
+            security_group_ids: instance.security_group_ids.security_group_id,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct DescribedInstanceList {
+    instance: Vec<DescribedInstance>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedInstances {
+    #[serde(default)]
+    instances: DescribedInstanceList,
+    #[serde(default)]
+    total_count: u32,
+    #[serde(default)]
+    next_token: Option<String>,
+}
+
+impl DescribedInstances {
+    /// Whether no instance is left after this page, `listed` being the instances of the
+    /// previous pages.
+    fn is_last_page(&self, listed: usize) -> bool {
+        let page_len = self.instances.instance.len();
+        page_len == 0 || listed + page_len >= self.total_count as usize
+    }
+}
+
+/// Lists every instance of the region of `client`, page after page.
+///
+/// Pages are asked for by `NextToken`. When the first answer has no token while more
+/// instances are left, the listing starts over by page number instead.
+pub async fn describe_region_instances(
+    client: &RegionClient,
+    page_size: u32,
+) -> Result<Vec<InstanceSummary>, AliyunRequestCommandError<NoOther>> {
+    let mut params = RpcParams::from([("MaxResults".to_owned(), page_size.to_string())]);
+    let mut described: DescribedInstances =
+        client.request("DescribeInstances", params.clone()).await?;
+    if described.next_token.is_none() && !described.is_last_page(0) {
+        return describe_numbered_pages(client, page_size).await;
+    }
+
+    let mut instances = Vec::new();
+    loop {
+        instances.extend(described.instances.instance.into_iter().map(Into::into));
+        match described.next_token {
+            Some(token) if !token.is_empty() => {
+                params.insert("NextToken".to_owned(), token);
+            }
+            _ => break,
+        }
+        described = client.request("DescribeInstances", params.clone()).await?;
+    }
+    Ok(instances)
+}
+
+/// Lists every instance of the region of `client` by page number.
+async fn describe_numbered_pages(
+    client: &RegionClient,
+    page_size: u32,
+) -> Result<Vec<InstanceSummary>, AliyunRequestCommandError<NoOther>> {
+    let mut instances = Vec::new();
+    for page_number in 1.. {
+        let params = RpcParams::from([
+            ("PageSize".to_owned(), page_size.to_string()),
+            ("PageNumber".to_owned(), page_number.to_string()),
+        ]);
+        let described: DescribedInstances = client.request("DescribeInstances", params).await?;
+        let last_page = described.is_last_page(instances.len());
+        instances.extend(described.instances.instance.into_iter().map(Into::into));
+        if last_page {
+            break;
+        }
+    }
+    Ok(instances)
+}
+
+/// Describes a single instance of the region of `client`, `None` when there is none with
+/// this ID.
+pub async fn describe_instance<E: SpecificError>(
+    client: &RegionClient,
+    instance_id: &str,
+) -> Result<Option<InstanceSummary>, AliyunRequestCommandError<E>> {
+    let instance_ids = serde_json::to_string(&[instance_id]).unwrap();
+    let params = RpcParams::from([("InstanceIds".to_owned(), instance_ids)]);
+    let described: DescribedInstances = client.request("DescribeInstances", params).await?;
+    Ok(described
+        .instances
+        .instance
+        .into_iter()
+        .next()
+        .map(Into::into))
+}
+
+/// Lists the instances of the given regions in parallel, of every region available to
+/// the account when `None`.
+///
+/// A region failing doesn't fail the others, it is reported in the failures. The
+/// instances are sorted by region then ID.
+pub async fn list_instances(
+    client_service: &AliyunClientService,
+    region_ids: Option<Vec<String>>,
+    page_size: u32,
+) -> Result<InstanceInventory, AliyunRequestCommandError<EcsError>> {
+    let region_ids = match region_ids {
+        Some(region_ids) => region_ids,
+        None => {
+            let client = default_region_client(client_service)?;
+            describe_regions(&client).await?
+        }
+    };
+
+    let mut inventory = InstanceInventory {
+        instances: Vec::new(),
+        failures: Vec::new(),
+    };
+    let mut tasks = JoinSet::new();
+    for region_id in region_ids {
+        let client = match ecs_client(client_service, &region_id) {
+            Ok(client) => client,
+            Err(err) => {
+                let err = AliyunRequestCommandError::from_any(err);
+                inventory
+                    .failures
+                    .push(RegionFailure::new(&region_id, &err));
+                continue;
+            }
+        };
+        // The error isn't `Send`, it is turned into a failure within the task.
+        tasks.spawn(async move {
+            describe_region_instances(&client, page_size)
+                .await
+                .map_err(|err| RegionFailure::new(&region_id, &err))
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(Ok(instances)) => inventory.instances.extend(instances),
+            Ok(Err(failure)) => inventory.failures.push(failure),
+            Err(err) => {
+                return Err(AliyunRequestCommandError::InternalError {
+                    message: format!("listing the instances of a region failed: {err}"),
+                    source: Box::new(err),
+                })
+            }
+        }
+    }
+    inventory
+        .instances
+        .sort_by(|a, b| (&a.region_id, &a.instance_id).cmp(&(&b.region_id, &b.instance_id)));
+    inventory
+        .failures
+        .sort_by(|a, b| a.region_id.cmp(&b.region_id));
+    Ok(inventory)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        services::{
+            auth::{chain::CredentialSource, types::AccessKeyCredentials},
+            error::RejectionCategory,
+            fake::{FakeRejection, FAKE_REGIONS},
+        },
+        test_utils::start_fake_client,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_describe_region_instances() {
+        let (server, client_service) = start_fake_client().await;
+        let client = client_service
+            .region_client(Some(FAKE_REGIONS[0].0))
+            .unwrap();
+
+        let instances = describe_region_instances(&client, 2).await.unwrap();
+        assert_eq!(instances.len(), 3);
+        assert_eq!(
+            server.fake().calls.lock().unwrap().as_slice(),
+            ["DescribeInstances", "DescribeInstances"]
+        );
+        assert_eq!(
+            instances[0],
+            InstanceSummary {
+                instance_id: "i-fake0001".to_owned(),
+                instance_name: "web-1".to_owned(),
+                region_id: "cn-hangzhou".to_owned(),
+                zone_id: "cn-hangzhou-h".to_owned(),
+                status: "Running".to_owned(),
+                start_time: Some("2026-01-01T00:01Z".to_owned()),
+                instance_type: "ecs.g7.large".to_owned(),
+                private_ips: vec!["172.16.0.10".to_owned()],
+                public_ips: vec!["47.96.0.10".to_owned()],
+                charge_type: "PostPaid".to_owned(),
+                expired_time: None,
+                tags: vec![InstanceTag {
+                    key: "app".to_owned(),
+                    value: "web".to_owned(),
+                }],
+                deletion_protection: false,
+                security_group_ids: vec!["sg-fake0001".to_owned()],
+            }
+        );
+        assert_eq!(
+            instances[2].expired_time.as_deref(),
+            Some("2027-01-01T16:00Z")
+        );
+        assert!(instances[2].public_ips.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_describe_region_instances_by_page_number() {
+        let (server, client_service) = start_fake_client().await;
+        *server.fake().without_next_token.lock().unwrap() = true;
+        let client = client_service
+            .region_client(Some(FAKE_REGIONS[0].0))
+            .unwrap();
+
+        let instances = describe_region_instances(&client, 2).await.unwrap();
+        let ids = instances
+            .iter()
+            .map(|instance| instance.instance_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["i-fake0001", "i-fake0002", "i-fake0003"]);
+        // The first page is asked for again by number.
+        assert_eq!(server.fake().calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_list_instances() {
+        let (server, client_service) = start_fake_client().await;
+        let mut moved = server.fake().instance("i-fake0002").unwrap();
+        moved.instance_id = "i-fake0004".to_owned();
+        moved.region_id = FAKE_REGIONS[2].0.to_owned();
+        server.fake().instances.lock().unwrap().push(moved);
+
+        let inventory = list_instances(&client_service, None, INSTANCE_PAGE_SIZE)
+            .await
+            .unwrap();
+        let ids = inventory
+            .instances
+            .iter()
+            .map(|instance| instance.instance_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            ["i-fake0004", "i-fake0001", "i-fake0002", "i-fake0003"]
+        );
+        assert!(inventory.failures.is_empty());
+
+        // An unknown region fails alone.
+        let regions = vec![FAKE_REGIONS[0].0.to_owned(), "cn-nowhere".to_owned()];
+        let inventory = list_instances(&client_service, Some(regions), INSTANCE_PAGE_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(inventory.instances.len(), 3);
+        assert_eq!(inventory.failures.len(), 1);
+        assert_eq!(inventory.failures[0].region_id, "cn-nowhere");
+        assert_eq!(
+            inventory.failures[0].category,
+            Some(RejectionCategory::InvalidRegion)
+        );
+
+        // So does a region ID this app refuses before asking Aliyun.
+        let regions = vec![FAKE_REGIONS[0].0.to_owned(), "cn-hangzhou.evil".to_owned()];
+        let inventory = list_instances(&client_service, Some(regions), INSTANCE_PAGE_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(inventory.instances.len(), 3);
+        assert_eq!(inventory.failures.len(), 1);
+        assert_eq!(inventory.failures[0].region_id, "cn-hangzhou.evil");
+
+        server
+            .fake()
+            .reject("DescribeRegions", FakeRejection::new(403, "Forbidden.RAM"));
+        let err = list_instances(&client_service, None, INSTANCE_PAGE_SIZE)
+            .await
+            .unwrap_err();
+        assert_matches!(err, AliyunRequestCommandError::Rejected(r) if r.missing_permission.is_some());
+
+        client_service.initialize(
+            AccessKeyCredentials::new("UNKNOWN", "UNKNOWN").into(),
+            CredentialSource::Environment,
+        );
+        server.fake().clear_rejections();
+        let regions = vec![FAKE_REGIONS[0].0.to_owned()];
+        let inventory = list_instances(&client_service, Some(regions), INSTANCE_PAGE_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(
+            inventory.failures[0].category,
+            Some(RejectionCategory::InvalidCredentials)
+        );
+    }
+}
@@ -0,0 +1,797 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    ecs::{
+        catalog::{
+            describe_available_resources, describe_security_groups, describe_vswitches,
+            ResourceKind, ZoneResource,
+        },
+        inventory::InstanceTag,
+        EcsError,
+    },
+    error::AliyunRequestCommandError,
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The disk categories RunInstances takes.
+const DISK_CATEGORIES: &[&str] = &[
+    "cloud",
+    "cloud_efficiency",
+    "cloud_ssd",
+    "cloud_essd",
+    "cloud_essd_entry",
+    "cloud_auto",
+];
+const MAX_SECURITY_GROUPS: usize = 5;
+const MAX_DATA_DISKS: usize = 16;
+const MAX_TAGS: usize = 20;
+const MAX_AMOUNT: u32 = 100;
+/// The largest pay-by-traffic bandwidth, in Mbit/s.
+const MAX_BANDWIDTH_OUT: u32 = 100;
+const MAX_CLIENT_TOKEN_LEN: usize = 64;
+
+/// The part of a launch an error is about, for the wizard to point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchField {
+    ZoneId,
+    InstanceType,
+    ImageId,
+    VswitchId,
+    SecurityGroupIds,
+    InstanceName,
+    KeyPairName,
+    SystemDisk,
+    DataDisks,
+    ChargeType,
+    InternetMaxBandwidthOut,
+    Amount,
+    Tags,
+    ClientToken,
+}
+
+impl LaunchField {
+    /// Guesses the field a rejection of RunInstances is about from its code, e.g.
+    /// `InvalidInstanceType.ValueNotSupported` or `InvalidVSwitchId.NotFound`.
+    fn of_code(code: &str) -> Option<Self> {
+        // Sold out instance types are rejected with codes naming the zone.
+        if code.contains("NoStock") {
+            return Some(Self::InstanceType);
+        }
+        const FIELDS: &[(&str, LaunchField)] = &[
+            ("InstanceType", LaunchField::InstanceType),
+            ("Image", LaunchField::ImageId),
+            ("VSwitch", LaunchField::VswitchId),
+            ("SecurityGroup", LaunchField::SecurityGroupIds),
+            ("KeyPair", LaunchField::KeyPairName),
+            ("InstanceName", LaunchField::InstanceName),
+            ("SystemDisk", LaunchField::SystemDisk),
+            ("DataDisk", LaunchField::DataDisks),
+            ("Zone", LaunchField::ZoneId),
+            ("Bandwidth", LaunchField::InternetMaxBandwidthOut),
+            ("Period", LaunchField::ChargeType),
+            ("ChargeType", LaunchField::ChargeType),
+            ("Amount", LaunchField::Amount),
+            ("Tag", LaunchField::Tags),
+        ];
+        FIELDS
+            .iter()
+            .find(|(name, _)| code.contains(name))
+            .map(|(_, field)| *field)
+    }
+}
+
+/// The unit of the term of a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum PeriodUnit {
+    Week,
+    Month,
+}
+
+impl PeriodUnit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Week => "Week",
+            Self::Month => "Month",
+        }
+    }
+}
+
+/// How instances are billed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum ChargeType {
+    /// Pay-as-you-go.
+    PostPaid,
+    /// A subscription for `period` weeks or months, paid upfront.
+    PrePaid {
+        period: u32,
+        period_unit: PeriodUnit,
+    },
+}
+
+impl ChargeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostPaid => "PostPaid",
+            Self::PrePaid { .. } => "PrePaid",
+        }
+    }
+}
+
+/// A disk created with the instances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct DiskSpec {
+    /// E.g. `cloud_essd`.
+    pub category: String,
+    /// In GiB.
+    pub size: u32,
+}
+
+/// The configuration of the instances to create, as picked in the wizard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct LaunchSpec {
+    pub zone_id: String,
+    pub instance_type: String,
+    pub image_id: String,
+    pub vswitch_id: String,
+    /// The security groups, all of them in the VPC of the vSwitch.
+    pub security_group_ids: Vec<String>,
+    pub instance_name: Option<String>,
+    pub key_pair_name: Option<String>,
+    pub system_disk: DiskSpec,
+    pub data_disks: Vec<DiskSpec>,
+    pub charge_type: ChargeType,
+    /// The public bandwidth, billed by traffic, no public IP when 0. In Mbit/s.
+    pub internet_max_bandwidth_out: u32,
+    /// How many instances to create.
+    pub amount: u32,
+    pub tags: Vec<InstanceTag>,
+}
+
+/// The instances created by RunInstances.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct LaunchResult {
+    pub instance_ids: Vec<String>,
+    /// The order of the subscription, `None` for pay-as-you-go instances.
+    pub order_id: Option<String>,
+}
+
+pub(super) fn invalid(
+    field: LaunchField,
+    reason: impl Into<String>,
+) -> AliyunRequestCommandError<EcsError> {
+    AliyunRequestCommandError::Specific(EcsError::InvalidLaunchSpec {
+        field,
+        reason: reason.into(),
+    })
+}
+
+/// Checks the name is one Aliyun takes: 2 to 128 characters, starting with a letter.
+pub(super) fn check_instance_name(
+    name: Option<&str>,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    let Some(name) = name else {
+        return Ok(());
+    };
+    let len = name.chars().count();
+    let valid = (2..=128).contains(&len)
+        && name.chars().next().is_some_and(char::is_alphabetic)
+        && !name.starts_with("http://")
+        && !name.starts_with("https://");
+    if !valid {
+        return Err(invalid(
+            LaunchField::InstanceName,
+            "names have 2 to 128 characters and start with a letter",
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn check_security_groups(
+    security_group_ids: &[String],
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if security_group_ids.is_empty() || security_group_ids.len() > MAX_SECURITY_GROUPS {
+        return Err(invalid(
+            LaunchField::SecurityGroupIds,
+            format!("pick from 1 to {MAX_SECURITY_GROUPS} security groups"),
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn check_bandwidth(bandwidth: u32) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if bandwidth > MAX_BANDWIDTH_OUT {
+        return Err(invalid(
+            LaunchField::InternetMaxBandwidthOut,
+            format!("the bandwidth is at most {MAX_BANDWIDTH_OUT} Mbit/s"),
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn check_amount(amount: u32) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if !(1..=MAX_AMOUNT).contains(&amount) {
+        return Err(invalid(
+            LaunchField::Amount,
+            format!("from 1 to {MAX_AMOUNT} instances can be created at once"),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks there aren't too many tags, and none of them is reserved to Aliyun.
+pub(super) fn check_tags(tags: &[InstanceTag]) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if tags.len() > MAX_TAGS {
+        return Err(invalid(
+            LaunchField::Tags,
+            format!("at most {MAX_TAGS} tags"),
+        ));
+    }
+    for tag in tags {
+        if tag.key.is_empty() || tag.key.chars().count() > 128 || is_system_tag(&tag.key) {
+            return Err(invalid(
+                LaunchField::Tags,
+                format!("{:?} can't be a tag key", tag.key),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the tag key is one of those Aliyun sets itself.
+pub(super) fn is_system_tag(key: &str) -> bool {
+    key.starts_with("aliyun") || key.starts_with("acs:")
+}
+
+pub(super) fn check_client_token(
+    client_token: &str,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if client_token.is_empty()
+        || client_token.len() > MAX_CLIENT_TOKEN_LEN
+        || !client_token.is_ascii()
+    {
+        return Err(invalid(
+            LaunchField::ClientToken,
+            format!("the token has 1 to {MAX_CLIENT_TOKEN_LEN} ASCII characters"),
+        ));
+    }
+    Ok(())
+}
+
+fn check_disk(
+    field: LaunchField,
+    disk: &DiskSpec,
+    sizes: std::ops::RangeInclusive<u32>,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    if !DISK_CATEGORIES.contains(&disk.category.as_str()) {
+        return Err(invalid(
+            field,
+            format!("{:?} is not a disk category", disk.category),
+        ));
+    }
+    if !sizes.contains(&disk.size) {
+        return Err(invalid(
+            field,
+            format!(
+                "the size must be from {} to {} GiB",
+                sizes.start(),
+                sizes.end()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+impl LaunchSpec {
+    /// Checks what can be checked without asking Aliyun, in the region of `region_id`.
+    pub fn check(&self, region_id: &str) -> Result<(), AliyunRequestCommandError<EcsError>> {
+        if !self.zone_id.starts_with(region_id) {
+            return Err(invalid(
+                LaunchField::ZoneId,
+                format!("the zone is not in {region_id}"),
+            ));
+        }
+        if !self.instance_type.starts_with("ecs.") {
+            return Err(invalid(
+                LaunchField::InstanceType,
+                "instance types start with `ecs.`",
+            ));
+        }
+        if self.image_id.is_empty() {
+            return Err(invalid(LaunchField::ImageId, "pick an image"));
+        }
+        if !self.vswitch_id.starts_with("vsw-") {
+            return Err(invalid(LaunchField::VswitchId, "pick a vSwitch"));
+        }
+        check_security_groups(&self.security_group_ids)?;
+        check_instance_name(self.instance_name.as_deref())?;
+        if self.key_pair_name.as_ref().is_some_and(String::is_empty) {
+            return Err(invalid(LaunchField::KeyPairName, "the name is empty"));
+        }
+        check_disk(LaunchField::SystemDisk, &self.system_disk, 20..=2048)?;
+        if self.data_disks.len() > MAX_DATA_DISKS {
+            return Err(invalid(
+                LaunchField::DataDisks,
+                format!("at most {MAX_DATA_DISKS} data disks can be attached"),
+            ));
+        }
+        for disk in &self.data_disks {
+            check_disk(LaunchField::DataDisks, disk, 20..=32768)?;
+        }
+        if let ChargeType::PrePaid {
+            period,
+            period_unit,
+        } = self.charge_type
+        {
+            let valid = match period_unit {
+                PeriodUnit::Week => (1..=4).contains(&period),
+                PeriodUnit::Month => {
+                    (1..=9).contains(&period) || [12, 24, 36, 48, 60].contains(&period)
+                }
+            };
+            if !valid {
+                return Err(invalid(
+                    LaunchField::ChargeType,
+                    "subscriptions last 1 to 4 weeks, 1 to 9 months, or 1 to 5 years",
+                ));
+            }
+        }
+        check_bandwidth(self.internet_max_bandwidth_out)?;
+        check_amount(self.amount)?;
+        check_tags(&self.tags)
+    }
+
+    /// The parameters of RunInstances.
+    fn params(&self) -> RpcParams {
+        let mut params = RpcParams::from([
+            ("ZoneId".to_owned(), self.zone_id.clone()),
+            ("InstanceType".to_owned(), self.instance_type.clone()),
+            ("ImageId".to_owned(), self.image_id.clone()),
+            ("VSwitchId".to_owned(), self.vswitch_id.clone()),
+            (
+                "SystemDisk.Category".to_owned(),
+                self.system_disk.category.clone(),
+            ),
+            (
+                "SystemDisk.Size".to_owned(),
+                self.system_disk.size.to_string(),
+            ),
+            (
+                "InstanceChargeType".to_owned(),
+                self.charge_type.as_str().to_owned(),
+            ),
+            (
+                "InternetMaxBandwidthOut".to_owned(),
+                self.internet_max_bandwidth_out.to_string(),
+            ),
+            ("Amount".to_owned(), self.amount.to_string()),
+        ]);
+        for (index, security_group_id) in self.security_group_ids.iter().enumerate() {
+            params.insert(
+                format!("SecurityGroupIds.{}", index + 1),
+                security_group_id.clone(),
+            );
+        }
+        if let Some(name) = &self.instance_name {
+            params.insert("InstanceName".to_owned(), name.clone());
+        }
+        if let Some(key_pair_name) = &self.key_pair_name {
+            params.insert("KeyPairName".to_owned(), key_pair_name.clone());
+        }
+        for (index, disk) in self.data_disks.iter().enumerate() {
+            let n = index + 1;
+            params.insert(format!("DataDisk.{n}.Category"), disk.category.clone());
+            params.insert(format!("DataDisk.{n}.Size"), disk.size.to_string());
+        }
+        if let ChargeType::PrePaid {
+            period,
+            period_unit,
+        } = self.charge_type
+        {
+            params.insert("Period".to_owned(), period.to_string());
+            params.insert("PeriodUnit".to_owned(), period_unit.as_str().to_owned());
+        }
+        if self.internet_max_bandwidth_out > 0 {
+            params.insert("InternetChargeType".to_owned(), "PayByTraffic".to_owned());
+        }
+        for (index, tag) in self.tags.iter().enumerate() {
+            let n = index + 1;
+            params.insert(format!("Tag.{n}.Key"), tag.key.clone());
+            params.insert(format!("Tag.{n}.Value"), tag.value.clone());
+        }
+        params
+    }
+}
+
+/// Whether the resource is in stock, `None` when it isn't offered at all.
+fn offered(resources: &[ZoneResource], value: &str) -> Option<bool> {
+    resources
+        .iter()
+        .find(|resource| resource.value == value)
+        .map(|resource| resource.available)
+}
+
+/// Checks the choices against each other with what Aliyun describes: the vSwitch is in
+/// the zone, the security groups in its VPC, and the instance type and system disk are
+/// offered there.
+async fn check_combination(
+    ecs: &RegionClient,
+    vpc: &RegionClient,
+    spec: &LaunchSpec,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    let vswitches = describe_vswitches(vpc, None, None, Some(&spec.vswitch_id)).await?;
+    let Some(vswitch) = vswitches.first() else {
+        return Err(invalid(
+            LaunchField::VswitchId,
+            format!("the vSwitch {} doesn't exist", spec.vswitch_id),
+        ));
+    };
+    if vswitch.zone_id != spec.zone_id {
+        return Err(invalid(
+            LaunchField::VswitchId,
+            format!(
+                "the vSwitch is in {}, not {}",
+                vswitch.zone_id, spec.zone_id
+            ),
+        ));
+    }
+
+    let groups = describe_security_groups(ecs, None, Some(&spec.security_group_ids)).await?;
+    for security_group_id in &spec.security_group_ids {
+        let Some(group) = groups
+            .iter()
+            .find(|group| &group.security_group_id == security_group_id)
+        else {
+            return Err(invalid(
+                LaunchField::SecurityGroupIds,
+                format!("the security group {security_group_id} doesn't exist"),
+            ));
+        };
+        if group.vpc_id != vswitch.vpc_id {
+            return Err(invalid(
+                LaunchField::SecurityGroupIds,
+                format!(
+                    "the security group {security_group_id} is in {}, not in the VPC {} of \
+                     the vSwitch",
+                    group.vpc_id, vswitch.vpc_id
+                ),
+            ));
+        }
+    }
+
+    let instance_types = describe_available_resources(
+        ecs,
+        &spec.zone_id,
+        ResourceKind::InstanceType,
+        &spec.charge_type,
+        Some(&spec.instance_type),
+    )
+    .await?;
+    match offered(&instance_types, &spec.instance_type) {
+        Some(true) => {}
+        Some(false) => {
+            return Err(invalid(
+                LaunchField::InstanceType,
+                format!("{} is sold out in {}", spec.instance_type, spec.zone_id),
+            ))
+        }
+        None => {
+            return Err(invalid(
+                LaunchField::InstanceType,
+                format!("{} is not offered in {}", spec.instance_type, spec.zone_id),
+            ))
+        }
+    }
+
+    let categories = describe_available_resources(
+        ecs,
+        &spec.zone_id,
+        ResourceKind::SystemDisk,
+        &spec.charge_type,
+        Some(&spec.instance_type),
+    )
+    .await?;
+    if offered(&categories, &spec.system_disk.category) != Some(true) {
+        return Err(invalid(
+            LaunchField::SystemDisk,
+            format!(
+                "{} system disks are not available for {} in {}",
+                spec.system_disk.category, spec.instance_type, spec.zone_id
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Turns the rejections of RunInstances naming a part of the launch into specific errors.
+pub(super) fn of_launch(
+    err: AliyunRequestCommandError<EcsError>,
+) -> AliyunRequestCommandError<EcsError> {
+    let AliyunRequestCommandError::Rejected(rejection) = err else {
+        return err;
+    };
+    match LaunchField::of_code(&rejection.data.code) {
+        Some(field) => AliyunRequestCommandError::Specific(EcsError::LaunchRejected {
+            field,
+            code: rejection.data.code,
+            message: rejection.data.message,
+        }),
+        None => AliyunRequestCommandError::Rejected(rejection),
+    }
+}
+
+/// Validates the launch locally, against what Aliyun describes, then with a dry run of
+/// RunInstances, which checks the rest: quotas, the balance, permissions...
+///
+/// `ecs` and `vpc` are clients of the ECS and VPC APIs in the region of the launch.
+pub async fn validate_launch(
+    ecs: &RegionClient,
+    vpc: &RegionClient,
+    spec: &LaunchSpec,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    spec.check(ecs.region_id())?;
+    check_combination(ecs, vpc, spec).await?;
+    dry_run_instances(ecs, spec.params()).await
+}
+
+/// Asks Aliyun whether RunInstances would go through with the parameters.
+pub(super) async fn dry_run_instances(
+    ecs: &RegionClient,
+    mut params: RpcParams,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    params.insert("DryRun".to_owned(), "true".to_owned());
+    match ecs
+        .request::<serde_json::Value, EcsError>("RunInstances", params)
+        .await
+    {
+        Err(AliyunRequestCommandError::Rejected(rejection))
+            if rejection.data.code == "DryRunOperation" =>
+        {
+            Ok(())
+        }
+        Err(err) => Err(of_launch(err)),
+        // A dry run is never expected to go through, yet it did nothing.
+        Ok(_) => Ok(()),
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct InstanceIdSet {
+    instance_id_set: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RunInstancesResponse {
+    #[serde(default)]
+    instance_id_sets: InstanceIdSet,
+    #[serde(default)]
+    order_id: Option<String>,
+}
+
+/// Validates the launch with [`validate_launch`], then creates the instances.
+///
+/// `client_token` is generated by the frontend for each launch: submitting the same
+/// launch twice with it, e.g. after a timeout, creates the instances once.
+pub async fn run_instances(
+    ecs: &RegionClient,
+    vpc: &RegionClient,
+    spec: &LaunchSpec,
+    client_token: &str,
+) -> Result<LaunchResult, AliyunRequestCommandError<EcsError>> {
+    check_client_token(client_token)?;
+    validate_launch(ecs, vpc, spec).await?;
+    create_instances(ecs, spec.params(), client_token).await
+}
+
+/// Calls RunInstances with the parameters, once per client token.
+pub(super) async fn create_instances(
+    ecs: &RegionClient,
+    mut params: RpcParams,
+    client_token: &str,
+) -> Result<LaunchResult, AliyunRequestCommandError<EcsError>> {
+    check_client_token(client_token)?;
+    params.insert("ClientToken".to_owned(), client_token.to_owned());
+    let response: RunInstancesResponse = ecs
+        .request("RunInstances", params)
+        .await
+        .map_err(of_launch)?;
+    Ok(LaunchResult {
+        instance_ids: response.instance_id_sets.instance_id_set,
+        order_id: response.order_id.filter(|order_id| !order_id.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::{
+        fake::{FakeAliyun, FakeAliyunServer, FakeRejection},
+        region::RegionalApi,
+    };
+
+    use super::*;
+
+    fn spec() -> LaunchSpec {
+        LaunchSpec {
+            zone_id: "cn-hangzhou-h".to_owned(),
+            instance_type: "ecs.g7.large".to_owned(),
+            image_id: "aliyun_3_x64_20G_alibase_20250101.vhd".to_owned(),
+            vswitch_id: "vsw-fake0001".to_owned(),
+            security_group_ids: vec!["sg-fake0001".to_owned()],
+            instance_name: Some("web-3".to_owned()),
+            key_pair_name: Some("demo-key".to_owned()),
+            system_disk: DiskSpec {
+                category: "cloud_essd".to_owned(),
+                size: 40,
+            },
+            data_disks: vec![],
+            charge_type: ChargeType::PostPaid,
+            internet_max_bandwidth_out: 5,
+            amount: 2,
+            tags: vec![InstanceTag {
+                key: "app".to_owned(),
+                value: "web".to_owned(),
+            }],
+        }
+    }
+
+    type SpecChange = fn(&mut LaunchSpec);
+
+    fn invalid_field(err: AliyunRequestCommandError<EcsError>) -> LaunchField {
+        match err {
+            AliyunRequestCommandError::Specific(EcsError::InvalidLaunchSpec { field, .. }) => field,
+            err => panic!("not an invalid launch: {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        spec().check("cn-hangzhou").unwrap();
+        assert_eq!(
+            invalid_field(spec().check("cn-shanghai").unwrap_err()),
+            LaunchField::ZoneId
+        );
+
+        let cases: Vec<(SpecChange, LaunchField)> = vec![
+            (
+                |s| s.security_group_ids.clear(),
+                LaunchField::SecurityGroupIds,
+            ),
+            (
+                |s| s.instance_name = Some("1st".to_owned()),
+                LaunchField::InstanceName,
+            ),
+            (|s| s.system_disk.size = 10, LaunchField::SystemDisk),
+            (
+                |s| {
+                    s.data_disks.push(DiskSpec {
+                        category: "nvme".to_owned(),
+                        size: 100,
+                    })
+                },
+                LaunchField::DataDisks,
+            ),
+            (
+                |s| {
+                    s.charge_type = ChargeType::PrePaid {
+                        period: 10,
+                        period_unit: PeriodUnit::Month,
+                    }
+                },
+                LaunchField::ChargeType,
+            ),
+            (
+                |s| s.internet_max_bandwidth_out = 200,
+                LaunchField::InternetMaxBandwidthOut,
+            ),
+            (|s| s.amount = 0, LaunchField::Amount),
+            (
+                |s| s.tags[0].key = "aliyun-app".to_owned(),
+                LaunchField::Tags,
+            ),
+        ];
+        for (change, field) in cases {
+            let mut spec = spec();
+            change(&mut spec);
+            assert_eq!(invalid_field(spec.check("cn-hangzhou").unwrap_err()), field);
+        }
+
+        assert_eq!(
+            LaunchField::of_code("InvalidVSwitchId.NotFound"),
+            Some(LaunchField::VswitchId)
+        );
+        assert_eq!(
+            LaunchField::of_code("Zone.NoStock"),
+            Some(LaunchField::InstanceType)
+        );
+        assert_eq!(LaunchField::of_code("QuotaExceed.PostPaidInstance"), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_instances() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let fake = server.fake();
+        let ecs = server.region_client();
+        let vpc = server.api_client(RegionalApi::Vpc);
+
+        validate_launch(&ecs, &vpc, &spec()).await.unwrap();
+        assert!(fake.launches.lock().unwrap().is_empty());
+
+        // Combinations only Aliyun knows about.
+        let cases: Vec<(SpecChange, LaunchField)> = vec![
+            (
+                |s| s.vswitch_id = "vsw-fake0002".to_owned(),
+                LaunchField::VswitchId,
+            ),
+            (
+                |s| s.vswitch_id = "vsw-none".to_owned(),
+                LaunchField::VswitchId,
+            ),
+            (
+                |s| s.security_group_ids = vec!["sg-fake0002".to_owned()],
+                LaunchField::SecurityGroupIds,
+            ),
+            (
+                |s| s.instance_type = "ecs.g7.xlarge".to_owned(),
+                LaunchField::InstanceType,
+            ),
+            (
+                |s| s.instance_type = "ecs.c7.large".to_owned(),
+                LaunchField::InstanceType,
+            ),
+            (
+                |s| s.system_disk.category = "cloud_ssd".to_owned(),
+                LaunchField::SystemDisk,
+            ),
+        ];
+        for (change, field) in cases {
+            let mut spec = spec();
+            change(&mut spec);
+            let err = validate_launch(&ecs, &vpc, &spec).await.unwrap_err();
+            assert_eq!(invalid_field(err), field);
+        }
+
+        // Left to the dry run.
+        let mut unknown_image = spec();
+        unknown_image.image_id = "m-none".to_owned();
+        let err = validate_launch(&ecs, &vpc, &unknown_image)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::LaunchRejected { field: LaunchField::ImageId, code, .. })
+                if code == "InvalidImageId.NotFound"
+        );
+        fake.reject(
+            "RunInstances",
+            FakeRejection::new(403, "QuotaExceed.PostPaidInstance").times(1),
+        );
+        let err = validate_launch(&ecs, &vpc, &spec()).await.unwrap_err();
+        assert_matches!(err, AliyunRequestCommandError::Rejected(r) if r.data.code == "QuotaExceed.PostPaidInstance");
+
+        let err = run_instances(&ecs, &vpc, &spec(), "").await.unwrap_err();
+        assert_eq!(invalid_field(err), LaunchField::ClientToken);
+        let result = run_instances(&ecs, &vpc, &spec(), "launch-1")
+            .await
+            .unwrap();
+        assert_eq!(result.instance_ids, ["i-fake0004", "i-fake0005"]);
+        assert_eq!(result.order_id, None);
+        let instance = fake.instance("i-fake0005").unwrap();
+        assert_eq!(instance.instance_name, "web-3");
+        assert_eq!(instance.tags, [("app".to_owned(), "web".to_owned())]);
+        assert!(instance.public_ip.is_some());
+
+        // Sent again with the same token, nothing more is created.
+        let again = run_instances(&ecs, &vpc, &spec(), "launch-1")
+            .await
+            .unwrap();
+        assert_eq!(again, result);
+        assert_eq!(fake.instances.lock().unwrap().len(), 5);
+    }
+}
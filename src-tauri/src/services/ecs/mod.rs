@@ -0,0 +1,261 @@
+pub mod audit;
+pub mod batch;
+pub mod catalog;
+pub mod inventory;
+pub mod launch;
+pub mod power;
+pub mod reachability;
+pub mod release;
+pub mod security_group;
+pub mod template;
+pub mod temporary_access;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    client::AliyunClientService,
+    ecs::launch::LaunchField,
+    error::{AliyunRequestCommandError, NoOther, RamPermission, RejectionCategory},
+    region::{is_valid_region_id, RegionClient},
+    rpc::RpcParams,
+};
+
+pub type EcsCommandError = AliyunRequestCommandError<EcsError>;
+
+#[derive(Debug, Clone, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum EcsError {
+    #[error("no Aliyun client is available, sign in or pick a profile first")]
+    NoClient,
+    #[error("{region_id:?} is not a valid region ID")]
+    InvalidRegion { region_id: String },
+    #[error("the instance {instance_id} doesn't exist in this region")]
+    InstanceNotFound { instance_id: String },
+    /// The instance is not in a status allowing the action, e.g. stopping a stopped one.
+    #[error("the instance {instance_id} can't do this in its current status: {message}")]
+    IncorrectInstanceStatus {
+        instance_id: String,
+        code: String,
+        message: String,
+    },
+    #[error("the confirmation doesn't match the name of the instance {instance_id}")]
+    ConfirmationMismatch { instance_id: String },
+    #[error("the instance {instance_id} has deletion protection on, turn it off first")]
+    DeletionProtected { instance_id: String },
+    /// A subscription instance can't be released before its term ends.
+    #[error("the subscription of the instance {instance_id} runs until {expired_time}")]
+    SubscriptionNotExpired {
+        instance_id: String,
+        expired_time: String,
+    },
+    /// A snapshot didn't complete, `status` being where it stopped.
+    #[error("the snapshot {snapshot_id} didn't complete, it is {status}")]
+    SnapshotFailed { snapshot_id: String, status: String },
+    /// A choice of the launch wizard is wrong on its own or with the others.
+    #[error("{reason}")]
+    InvalidLaunchSpec { field: LaunchField, reason: String },
+    /// Aliyun rejected a launch because of `field`, when checking it or creating it.
+    #[error("the launch was rejected because of its {field:?}: {message}")]
+    LaunchRejected {
+        field: LaunchField,
+        code: String,
+        message: String,
+    },
+    #[error("the launch template {launch_template_id} doesn't exist in this region")]
+    TemplateNotFound { launch_template_id: String },
+    #[error("the launch template {launch_template_id} has no version {version_number}")]
+    TemplateVersionNotFound {
+        launch_template_id: String,
+        version_number: u32,
+    },
+    #[error("the security group {security_group_id} doesn't exist in this region")]
+    SecurityGroupNotFound { security_group_id: String },
+    #[error("the security group {security_group_id} has no rule {security_group_rule_id}")]
+    RuleNotFound {
+        security_group_id: String,
+        security_group_rule_id: String,
+    },
+    /// A change to the rules of a security group is wrong on its own or with the others.
+    #[error("{reason}")]
+    InvalidRule { reason: String },
+    /// The rules changed since the change was previewed, it has to be previewed again.
+    #[error("the rules of the security group {security_group_id} changed since the preview")]
+    RulesChanged { security_group_id: String },
+    #[error("no change to the rules of the security group {security_group_id} can be rolled back")]
+    NoRuleBackup { security_group_id: String },
+    #[error("failed to back up the rules of the security group: {message}")]
+    RuleBackupFailed { message: String },
+    #[error("failed to find out the public IP of this machine: {message}")]
+    PublicIpUnknown { message: String },
+    #[error("failed to record the temporary access: {message}")]
+    TemporaryAccessNotRecorded { message: String },
+    /// The traffic to simulate is wrong, e.g. TCP without a port.
+    #[error("{reason}")]
+    InvalidTrafficQuery { reason: String },
+    #[error("failed to write {path}: {message}")]
+    ExportFailed { path: String, message: String },
+    /// The instance was still in `status` when the wait gave up.
+    #[error("the instance {instance_id} didn't become {expected} in time, it is {status}")]
+    WaitTimedOut {
+        instance_id: String,
+        expected: String,
+        status: String,
+    },
+}
+
+impl EcsError {
+    /// Turns the rejections of an action on an instance which the frontend handles on
+    /// their own into specific errors.
+    pub fn of_instance(
+        err: AliyunRequestCommandError<Self>,
+        instance_id: &str,
+    ) -> AliyunRequestCommandError<Self> {
+        let AliyunRequestCommandError::Rejected(rejection) = err else {
+            return err;
+        };
+        let instance_id = instance_id.to_owned();
+        match rejection.category {
+            RejectionCategory::IncorrectInstanceStatus => {
+                AliyunRequestCommandError::Specific(Self::IncorrectInstanceStatus {
+                    instance_id,
+                    code: rejection.data.code,
+                    message: rejection.data.message,
+                })
+            }
+            RejectionCategory::NotFound if rejection.data.code.starts_with("InvalidInstanceId") => {
+                AliyunRequestCommandError::Specific(Self::InstanceNotFound { instance_id })
+            }
+            _ => AliyunRequestCommandError::Rejected(rejection),
+        }
+    }
+}
+
+/// A failure of a region, while the other regions went through.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RegionFailure {
+    pub region_id: String,
+    pub message: String,
+    /// The category of the rejection, `None` when the request didn't get an answer.
+    pub category: Option<RejectionCategory>,
+    pub missing_permission: Option<RamPermission>,
+}
+
+impl RegionFailure {
+    pub fn new(region_id: &str, error: &AliyunRequestCommandError<NoOther>) -> Self {
+        let (category, missing_permission) = match error {
+            AliyunRequestCommandError::Rejected(rejection) => (
+                Some(rejection.category),
+                rejection.missing_permission.clone(),
+            ),
+            _ => (None, None),
+        };
+        Self {
+            region_id: region_id.to_owned(),
+            message: error.to_string(),
+            category,
+            missing_permission,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedRegions {
+    regions: RegionList,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RegionList {
+    region: Vec<DescribedRegion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedRegion {
+    region_id: String,
+    #[serde(default)]
+    local_name: String,
+}
+
+/// The `SecurityGroupIds` of an instance, a network interface or a launch template.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub(super) struct SecurityGroupIdList {
+    pub(super) security_group_id: Vec<String>,
+}
+
+/// A region where ECS is available.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct RegionInfo {
+    pub region_id: String,
+    /// The name of the region, e.g. `华东1（杭州）`.
+    pub local_name: String,
+}
+
+/// The region client of the default region, failing without credentials.
+pub fn default_region_client(
+    client_service: &AliyunClientService,
+) -> Result<RegionClient, EcsCommandError> {
+    client_service
+        .region_client(None)
+        .ok_or(AliyunRequestCommandError::Specific(EcsError::NoClient))
+}
+
+/// The ECS client of the region, failing for an invalid region ID or without
+/// credentials.
+pub fn ecs_client(
+    client_service: &AliyunClientService,
+    region_id: &str,
+) -> Result<RegionClient, EcsCommandError> {
+    check_region_id(region_id)?;
+    client_service
+        .region_client(Some(region_id))
+        .ok_or(AliyunRequestCommandError::Specific(EcsError::NoClient))
+}
+
+/// Like [`ecs_client`], for the VPC API.
+pub fn vpc_client(
+    client_service: &AliyunClientService,
+    region_id: &str,
+) -> Result<RegionClient, EcsCommandError> {
+    check_region_id(region_id)?;
+    client_service
+        .vpc_client(Some(region_id))
+        .ok_or(AliyunRequestCommandError::Specific(EcsError::NoClient))
+}
+
+fn check_region_id(region_id: &str) -> Result<(), EcsCommandError> {
+    if is_valid_region_id(region_id) {
+        Ok(())
+    } else {
+        Err(AliyunRequestCommandError::Specific(
+            EcsError::InvalidRegion {
+                region_id: region_id.to_owned(),
+            },
+        ))
+    }
+}
+
+/// The regions where ECS is available to the account, with their names, asked to
+/// `client`.
+pub async fn describe_region_infos(
+    client: &RegionClient,
+) -> Result<Vec<RegionInfo>, EcsCommandError> {
+    let described: DescribedRegions = client.request("DescribeRegions", RpcParams::new()).await?;
+    Ok(described
+        .regions
+        .region
+        .into_iter()
+        .map(|region| RegionInfo {
+            region_id: region.region_id,
+            local_name: region.local_name,
+        })
+        .collect())
+}
+
+/// The IDs of the regions where ECS is available to the account, asked to `client`.
+pub async fn describe_regions(client: &RegionClient) -> Result<Vec<String>, EcsCommandError> {
+    let regions = describe_region_infos(client).await?;
+    Ok(regions.into_iter().map(|region| region.region_id).collect())
+}
@@ -0,0 +1,364 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::services::{
+    ecs::{
+        inventory::{describe_instance, InstanceSummary},
+        EcsCommandError, EcsError,
+    },
+    error::AliyunRequestCommandError,
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// Whether a stopped pay-as-you-go instance keeps being billed for its vCPUs and memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+pub enum StoppedMode {
+    /// Keeps the resources, so the instance is sure to start again.
+    #[default]
+    KeepCharging,
+    /// Releases the vCPUs and memory, which may be out of stock when starting again.
+    StopCharging,
+}
+
+impl StoppedMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::KeepCharging => "KeepCharging",
+            Self::StopCharging => "StopCharging",
+        }
+    }
+}
+
+/// A change of the power state of an instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type", content = "stopped_mode")]
+pub enum PowerAction {
+    Start,
+    Stop(StoppedMode),
+    /// Stops without waiting for the OS to shut down, like pulling the plug.
+    ForceStop(StoppedMode),
+    Reboot,
+}
+
+impl PowerAction {
+    /// The status the instance ends up in.
+    pub fn target_status(&self) -> &'static str {
+        match self {
+            Self::Start | Self::Reboot => "Running",
+            Self::Stop(_) | Self::ForceStop(_) => "Stopped",
+        }
+    }
+
+    /// The parameters of the action besides the instances.
+    fn params(&self) -> RpcParams {
+        let mut params = RpcParams::new();
+        if let Self::Stop(mode) | Self::ForceStop(mode) = self {
+            let force = matches!(self, Self::ForceStop(_));
+            params.insert("ForceStop".to_owned(), force.to_string());
+            params.insert("StoppedMode".to_owned(), mode.as_str().to_owned());
+        }
+        params
+    }
+
+    fn request(&self, instance_id: &str) -> (&'static str, RpcParams) {
+        let action = match self {
+            Self::Start => "StartInstance",
+            Self::Stop(_) | Self::ForceStop(_) => "StopInstance",
+            Self::Reboot => "RebootInstance",
+        };
+        let mut params = self.params();
+        params.insert("InstanceId".to_owned(), instance_id.to_owned());
+        (action, params)
+    }
+
+    /// The request applying the action to several instances of a region at once, each
+    /// one succeeding or failing on its own.
+    pub fn batch_request(&self, instance_ids: &[String]) -> (&'static str, RpcParams) {
+        let action = match self {
+            Self::Start => "StartInstances",
+            Self::Stop(_) | Self::ForceStop(_) => "StopInstances",
+            Self::Reboot => "RebootInstances",
+        };
+        let mut params = self.params();
+        params.insert("BatchOptimization".to_owned(), "SuccessFirst".to_owned());
+        for (index, instance_id) in instance_ids.iter().enumerate() {
+            params.insert(format!("InstanceId.{}", index + 1), instance_id.clone());
+        }
+        (action, params)
+    }
+}
+
+/// How often and how long the status of an instance is polled after an action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitPolicy {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for WaitPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(3),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+/// The progress of a power action, streamed to the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum PowerProgress {
+    /// Aliyun accepted the action, the instance is on its way to `target_status`.
+    Requested {
+        instance_id: String,
+        target_status: String,
+    },
+    /// The status of the instance while waiting.
+    Polled {
+        instance_id: String,
+        status: String,
+        elapsed_secs: u32,
+    },
+    Reached {
+        instance_id: String,
+        status: String,
+    },
+}
+
+/// Sends the action, then polls the instance until it reaches the target status.
+///
+/// A reboot ends in the status it started from, so it is only over once the instance
+/// has been seen out of it or started again since the action.
+///
+/// Returns the status reached. `on_progress` is called once the action has been
+/// accepted, after each poll, and once the target status is reached.
+pub async fn power<F: Fn(PowerProgress)>(
+    client: &RegionClient,
+    instance_id: &str,
+    action: PowerAction,
+    wait: &WaitPolicy,
+    on_progress: F,
+) -> Result<String, EcsCommandError> {
+    let start_time = match action {
+        PowerAction::Reboot => Some(describe_existing(client, instance_id).await?.start_time),
+        _ => None,
+    };
+    let (name, params) = action.request(instance_id);
+    client
+        .request::<Value, EcsError>(name, params)
+        .await
+        .map_err(|err| EcsError::of_instance(err, instance_id))?;
+    let target_status = action.target_status();
+    on_progress(PowerProgress::Requested {
+        instance_id: instance_id.to_owned(),
+        target_status: target_status.to_owned(),
+    });
+
+    let started = Instant::now();
+    let mut under_way = start_time.is_none();
+    loop {
+        // The status may not have changed yet right after the action.
+        tokio::time::sleep(wait.interval).await;
+        let instance = describe_existing(client, instance_id).await?;
+        let status = instance.status;
+        let elapsed = started.elapsed();
+        on_progress(PowerProgress::Polled {
+            instance_id: instance_id.to_owned(),
+            status: status.clone(),
+            elapsed_secs: elapsed.as_secs() as u32,
+        });
+
+        under_way |= status != target_status
+            || start_time
+                .as_ref()
+                .is_some_and(|start_time| *start_time != instance.start_time);
+        if under_way && status == target_status {
+            on_progress(PowerProgress::Reached {
+                instance_id: instance_id.to_owned(),
+                status: status.clone(),
+            });
+            return Ok(status);
+        }
+        if elapsed + wait.interval > wait.timeout {
+            return Err(AliyunRequestCommandError::Specific(
+                EcsError::WaitTimedOut {
+                    instance_id: instance_id.to_owned(),
+                    expected: target_status.to_owned(),
+                    status,
+                },
+            ));
+        }
+    }
+}
+
+/// Describes the instance, failing when it doesn't exist.
+async fn describe_existing(
+    client: &RegionClient,
+    instance_id: &str,
+) -> Result<InstanceSummary, EcsCommandError> {
+    describe_instance::<EcsError>(client, instance_id)
+        .await?
+        .ok_or_else(|| {
+            AliyunRequestCommandError::Specific(EcsError::InstanceNotFound {
+                instance_id: instance_id.to_owned(),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::fake::{FakeAliyun, FakeAliyunServer, FakeRejection};
+
+    use super::*;
+
+    fn fast_wait() -> WaitPolicy {
+        WaitPolicy {
+            interval: Duration::from_millis(1),
+            timeout: Duration::from_millis(50),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_power() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let fake = server.fake();
+        let client = server.region_client();
+        let progress = Mutex::new(Vec::new());
+        let record = |p: PowerProgress| progress.lock().unwrap().push(p);
+
+        let stop = PowerAction::Stop(StoppedMode::StopCharging);
+        let status = power(&client, "i-fake0001", stop, &fast_wait(), record)
+            .await
+            .unwrap();
+        assert_eq!(status, "Stopped");
+        assert_eq!(
+            fake.instance("i-fake0001").unwrap().stopped_mode,
+            "StopCharging"
+        );
+        assert_eq!(
+            progress.lock().unwrap().as_slice(),
+            [
+                PowerProgress::Requested {
+                    instance_id: "i-fake0001".to_owned(),
+                    target_status: "Stopped".to_owned(),
+                },
+                PowerProgress::Polled {
+                    instance_id: "i-fake0001".to_owned(),
+                    status: "Stopped".to_owned(),
+                    elapsed_secs: 0,
+                },
+                PowerProgress::Reached {
+                    instance_id: "i-fake0001".to_owned(),
+                    status: "Stopped".to_owned(),
+                },
+            ]
+        );
+
+        let err = power(&client, "i-fake0001", stop, &fast_wait(), |_| {})
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::IncorrectInstanceStatus { instance_id, .. })
+                if instance_id == "i-fake0001"
+        );
+        let err = power(&client, "i-none", PowerAction::Start, &fast_wait(), |_| {})
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::InstanceNotFound { .. })
+        );
+
+        for action in [PowerAction::Start, PowerAction::Reboot] {
+            let status = power(&client, "i-fake0001", action, &fast_wait(), |_| {})
+                .await
+                .unwrap();
+            assert_eq!(status, "Running");
+        }
+        let force_stop = PowerAction::ForceStop(StoppedMode::KeepCharging);
+        power(&client, "i-fake0001", force_stop, &fast_wait(), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(
+            fake.instance("i-fake0001").unwrap().stopped_mode,
+            "KeepCharging"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_power_timeout() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let client = server.region_client();
+        // The fake changes status right away, an instance stuck in `Stopping` is
+        // simulated by putting it back into a transitional status once requested.
+        let stuck = |p: PowerProgress| {
+            if let PowerProgress::Requested { instance_id, .. } = p {
+                let mut instances = server.fake().instances.lock().unwrap();
+                let instance = instances
+                    .iter_mut()
+                    .find(|instance| instance.instance_id == instance_id)
+                    .unwrap();
+                instance.status = "Stopping".to_owned();
+            }
+        };
+        let stop = PowerAction::Stop(StoppedMode::KeepCharging);
+        let err = power(&client, "i-fake0002", stop, &fast_wait(), stuck)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::WaitTimedOut { status, .. })
+                if status == "Stopping"
+        );
+
+        // A reboot still running at the start time it had is not over yet.
+        let not_rebooted = |p: PowerProgress| {
+            if let PowerProgress::Requested { instance_id, .. } = p {
+                let mut instances = server.fake().instances.lock().unwrap();
+                let instance = instances
+                    .iter_mut()
+                    .find(|instance| instance.instance_id == instance_id)
+                    .unwrap();
+                instance.start_time = "2026-01-01T00:01Z".to_owned();
+            }
+        };
+        let err = power(
+            &client,
+            "i-fake0001",
+            PowerAction::Reboot,
+            &fast_wait(),
+            not_rebooted,
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::WaitTimedOut { status, .. })
+                if status == "Running"
+        );
+
+        server
+            .fake()
+            .reject("StartInstance", FakeRejection::new(403, "Forbidden.RAM"));
+        let err = power(
+            &client,
+            "i-fake0003",
+            PowerAction::Start,
+            &fast_wait(),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(err, AliyunRequestCommandError::Rejected(r) if r.missing_permission.is_some());
+    }
+}
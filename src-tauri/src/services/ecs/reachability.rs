@@ -0,0 +1,1145 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    ecs::{
+        catalog::{describe_security_groups, SecurityGroupType},
+        security_group::{
+            describe_security_group_rules, parse_port_range, RuleDirection, RulePeer, RulePolicy,
+            RuleSpec, SecurityGroupRules, IP_PROTOCOLS,
+        },
+        EcsCommandError, EcsError, SecurityGroupIdList,
+    },
+    error::AliyunRequestCommandError,
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The network interfaces asked for per page, an instance having a few of them.
+const INTERFACE_PAGE_SIZE: u32 = 100;
+
+/// Where the simulated traffic comes from.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum TrafficSource {
+    /// An IPv4 or IPv6 address or CIDR block, outside of any security group.
+    Cidr { cidr: String },
+    /// The primary network interface of an instance of the region, with its address and
+    /// security groups.
+    Instance { instance_id: String },
+    /// Any instance of a security group, whose address isn't known.
+    SecurityGroup { security_group_id: String },
+}
+
+/// The traffic simulated, entering an instance.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+pub struct TrafficQuery {
+    pub source: TrafficSource,
+    pub instance_id: String,
+    /// `TCP`, `UDP`, `ICMP`, `ICMPv6` or `GRE`.
+    pub ip_protocol: String,
+    /// Required for TCP and UDP, ignored otherwise.
+    pub port: Option<u16>,
+}
+
+/// An IPv4 or IPv6 network, a single address being a network of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(cidr: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{cidr} is not an IP address or CIDR block");
+        let (address, prefix_len) = match cidr.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (cidr, None),
+        };
+        let address = address.trim().parse::<IpAddr>().map_err(|_| invalid())?;
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max_prefix_len,
+        };
+        if prefix_len > max_prefix_len {
+            return Err(invalid());
+        }
+        Ok(Self {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+impl IpNetwork {
+    /// The address as bits, with the length of the addresses of its version.
+    fn bits(&self) -> (u128, u8) {
+        match self.address {
+            IpAddr::V4(address) => (u32::from(address).into(), 32),
+            IpAddr::V6(address) => (u128::from(address), 128),
+        }
+    }
+
+    /// The first `prefix_len` bits of the address.
+    fn prefix(&self, prefix_len: u8) -> u128 {
+        let (bits, len) = self.bits();
+        match prefix_len {
+            0 => 0,
+            _ => bits >> (len - prefix_len),
+        }
+    }
+
+    fn same_version(&self, other: &Self) -> bool {
+        self.address.is_ipv4() == other.address.is_ipv4()
+    }
+
+    /// Whether every address of `other` is in the network.
+    pub fn contains(&self, other: &Self) -> bool {
+        self.same_version(other)
+            && other.prefix_len >= self.prefix_len
+            && self.prefix(self.prefix_len) == other.prefix(self.prefix_len)
+    }
+
+    /// Whether some address of `other` is in the network.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let prefix_len = self.prefix_len.min(other.prefix_len);
+        self.same_version(other) && self.prefix(prefix_len) == other.prefix(prefix_len)
+    }
+}
+
+/// The source as the security groups see it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceEndpoint {
+    /// `None` when only the security groups of the source are known.
+    pub network: Option<IpNetwork>,
+    pub security_group_ids: Vec<String>,
+}
+
+/// A security group of the destination with its type, which the rules don't tell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedSecurityGroup {
+    pub security_group_type: SecurityGroupType,
+    pub group: SecurityGroupRules,
+}
+
+/// What decided whether the traffic enters.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum Decision {
+    /// The first rule matching the traffic, by priority.
+    Rule {
+        security_group_id: String,
+        security_group_rule_id: String,
+        rule: RuleSpec,
+    },
+    /// No rule matched, and the source is in the same group as the destination.
+    InnerAccessPolicy {
+        security_group_id: String,
+        policy: RulePolicy,
+    },
+    /// No rule matched, and ingress traffic is denied by default.
+    NoMatchingRule,
+}
+
+/// A rule which might have decided otherwise, but can't be evaluated locally.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct UnevaluatedRule {
+    pub security_group_id: String,
+    pub security_group_rule_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct Verdict {
+    pub allowed: bool,
+    pub decided_by: Decision,
+    /// The rules evaluated before the decision but skipped, the verdict assuming they
+    /// don't match.
+    pub unevaluated_rules: Vec<UnevaluatedRule>,
+}
+
+/// The verdict for the traffic entering a network interface of the destination.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct InterfaceVerdict {
+    pub network_interface_id: String,
+    /// Whether it is the primary network interface of the instance.
+    pub primary: bool,
+    pub private_ip_address: String,
+    pub security_group_ids: Vec<String>,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ReachabilityReport {
+    pub instance_id: String,
+    /// The primary network interface first.
+    pub interfaces: Vec<InterfaceVerdict>,
+}
+
+/// Whether the peer of a rule matches the source.
+enum PeerMatch {
+    Yes,
+    No,
+    Unknown(String),
+}
+
+fn match_network(peer: &str, source: &SourceEndpoint) -> PeerMatch {
+    let Ok(peer) = peer.parse::<IpNetwork>() else {
+        return PeerMatch::Unknown(format!("{peer} is not a CIDR block"));
+    };
+    match &source.network {
+        None => PeerMatch::Unknown("the address of the source isn't known".to_owned()),
+        Some(network) if peer.contains(network) => PeerMatch::Yes,
+        Some(network) if peer.overlaps(network) => {
+            PeerMatch::Unknown("it covers only a part of the source".to_owned())
+        }
+        Some(_) => PeerMatch::No,
+    }
+}
+
+fn match_peer(
+    peer: &RulePeer,
+    security_group_type: SecurityGroupType,
+    source: &SourceEndpoint,
+) -> PeerMatch {
+    match peer {
+        RulePeer::Cidr { cidr_ip } => match_network(cidr_ip, source),
+        RulePeer::Ipv6Cidr { ipv6_cidr_ip } => match_network(ipv6_cidr_ip, source),
+        RulePeer::SecurityGroup { .. } if security_group_type == SecurityGroupType::Enterprise => {
+            PeerMatch::Unknown("enterprise security groups don't support group peers".to_owned())
+        }
+        RulePeer::SecurityGroup { security_group_id } => {
+            if source.security_group_ids.contains(security_group_id) {
+                PeerMatch::Yes
+            } else {
+                PeerMatch::No
+            }
+        }
+        RulePeer::PrefixList { prefix_list_id } => PeerMatch::Unknown(format!(
+            "the entries of the prefix list {prefix_list_id} aren't known"
+        )),
+    }
+}
+
+/// Whether the protocol and ports of the rule cover the traffic.
+fn covers_traffic(rule: &RuleSpec, ip_protocol: &str, port: Option<u16>) -> bool {
+    if rule.ip_protocol.eq_ignore_ascii_case("ALL") {
+        return true;
+    }
+    if !rule.ip_protocol.eq_ignore_ascii_case(ip_protocol) {
+        return false;
+    }
+    match ip_protocol.to_ascii_uppercase().as_str() {
+        "TCP" | "UDP" => parse_port_range(&rule.port_range)
+            .zip(port)
+            .is_some_and(|((start, end), port)| (start..=end).contains(&port)),
+        _ => true,
+    }
+}
+
+/// Evaluates the traffic entering a network interface in the security groups, locally.
+///
+/// The ingress rules of all the groups are evaluated together, by priority from 1,
+/// dropping rules before accepting ones of the same priority, and the first rule
+/// matching decides. Without any, traffic from an instance of one of the groups is
+/// decided by the inner access policy of the group, enterprise groups always dropping
+/// it, and other traffic is denied.
+pub fn evaluate_ingress(
+    source: &SourceEndpoint,
+    groups: &[TypedSecurityGroup],
+    ip_protocol: &str,
+    port: Option<u16>,
+) -> Verdict {
+    let mut rules = groups
+        .iter()
+        .flat_map(|typed| {
+            typed
+                .group
+                .rules
+                .iter()
+                .filter(|rule| rule.rule.direction == RuleDirection::Ingress)
+                .map(move |rule| (typed, rule))
+        })
+        .collect::<Vec<_>>();
+    // Stable, the groups and rules keep their order within a priority.
+    rules.sort_by_key(|(_, rule)| (rule.rule.priority, rule.rule.policy != RulePolicy::Drop));
+
+    let mut unevaluated_rules = Vec::new();
+    for (typed, rule) in rules {
+        if !covers_traffic(&rule.rule, ip_protocol, port) {
+            continue;
+        }
+        match match_peer(&rule.rule.peer, typed.security_group_type, source) {
+            PeerMatch::Yes => {
+                return Verdict {
+                    allowed: rule.rule.policy == RulePolicy::Accept,
+                    decided_by: Decision::Rule {
+                        security_group_id: typed.group.security_group_id.clone(),
+                        security_group_rule_id: rule.security_group_rule_id.clone(),
+                        rule: rule.rule.clone(),
+                    },
+                    unevaluated_rules,
+                }
+            }
+            PeerMatch::No => {}
+            PeerMatch::Unknown(reason) => unevaluated_rules.push(UnevaluatedRule {
+                security_group_id: typed.group.security_group_id.clone(),
+                security_group_rule_id: rule.security_group_rule_id.clone(),
+                reason,
+            }),
+        }
+    }
+
+    let shared = groups
+        .iter()
+        .filter(|typed| {
+            source
+                .security_group_ids
+                .contains(&typed.group.security_group_id)
+        })
+        .map(|typed| {
+            let policy = match typed.security_group_type {
+                SecurityGroupType::Normal => typed.group.inner_access_policy,
+                SecurityGroupType::Enterprise => RulePolicy::Drop,
+            };
+            (&typed.group.security_group_id, policy)
+        })
+        .collect::<Vec<_>>();
+    let inner = shared
+        .iter()
+        .find(|(_, policy)| *policy == RulePolicy::Accept)
+        .or(shared.first());
+    let (allowed, decided_by) = match inner {
+        Some((security_group_id, policy)) => (
+            *policy == RulePolicy::Accept,
+            Decision::InnerAccessPolicy {
+                security_group_id: (*security_group_id).clone(),
+                policy: *policy,
+            },
+        ),
+        None => (false, Decision::NoMatchingRule),
+    };
+    Verdict {
+        allowed,
+        decided_by,
+        unevaluated_rules,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedInterfaces {
+    #[serde(default)]
+    network_interface_sets: InterfaceList,
+    #[serde(default)]
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct InterfaceList {
+    network_interface_set: Vec<DescribedInterface>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedInterface {
+    network_interface_id: String,
+    #[serde(default)]
+    instance_id: String,
+    /// `Primary` or `Secondary`.
+    #[serde(default)]
+    r#type: String,
+    #[serde(default)]
+    private_ip_address: String,
+    #[serde(default)]
+    security_group_ids: SecurityGroupIdList,
+}
+
+/// The network interfaces of the region matching the filters of `params`, page after
+/// page.
+async fn describe_network_interfaces(
+    client: &RegionClient,
+    mut params: RpcParams,
+) -> Result<Vec<DescribedInterface>, EcsCommandError> {
+    let mut interfaces = Vec::new();
+    params.insert("MaxResults".to_owned(), INTERFACE_PAGE_SIZE.to_string());
+    loop {
+        let described: DescribedInterfaces = client
+            .request("DescribeNetworkInterfaces", params.clone())
+            .await?;
+        interfaces.extend(described.network_interface_sets.network_interface_set);
+        match described.next_token {
+            Some(token) if !token.is_empty() => {
+                params.insert("NextToken".to_owned(), token);
+            }
+            _ => break,
+        }
+    }
+    Ok(interfaces)
+}
+
+/// The security groups of every network interface of the instances of the region, by
+/// instance ID.
+pub(super) async fn describe_instance_security_groups(
+    client: &RegionClient,
+) -> Result<HashMap<String, BTreeSet<String>>, EcsCommandError> {
+    let mut groups = HashMap::<String, BTreeSet<String>>::new();
+    for interface in describe_network_interfaces(client, RpcParams::new()).await? {
+        if !interface.instance_id.is_empty() {
+            groups
+                .entry(interface.instance_id)
+                .or_default()
+                .extend(interface.security_group_ids.security_group_id);
+        }
+    }
+    Ok(groups)
+}
+
+/// The network interfaces of an instance, the primary one first, failing when there is
+/// none.
+async fn describe_interfaces(
+    client: &RegionClient,
+    instance_id: &str,
+) -> Result<Vec<DescribedInterface>, EcsCommandError> {
+    let params = RpcParams::from([("InstanceId".to_owned(), instance_id.to_owned())]);
+    let mut interfaces = describe_network_interfaces(client, params).await?;
+    if interfaces.is_empty() {
+        return Err(AliyunRequestCommandError::Specific(
+            EcsError::InstanceNotFound {
+                instance_id: instance_id.to_owned(),
+            },
+        ));
+    }
+    interfaces.sort_by_key(|interface| interface.r#type != "Primary");
+    Ok(interfaces)
+}
+
+fn invalid_query(reason: String) -> EcsCommandError {
+    AliyunRequestCommandError::Specific(EcsError::InvalidTrafficQuery { reason })
+}
+
+/// The protocol as Aliyun spells it, checking the port is there when it is needed.
+fn check_traffic(ip_protocol: &str, port: Option<u16>) -> Result<String, EcsCommandError> {
+    let ip_protocol = IP_PROTOCOLS
+        .iter()
+        .find(|protocol| protocol.eq_ignore_ascii_case(ip_protocol) && **protocol != "ALL")
+        .ok_or_else(|| invalid_query(format!("{ip_protocol} is not a protocol of the rules")))?;
+    if matches!(*ip_protocol, "TCP" | "UDP") && port.is_none_or(|port| port == 0) {
+        return Err(invalid_query(format!("{ip_protocol} traffic needs a port")));
+    }
+    Ok((*ip_protocol).to_owned())
+}
+
+async fn resolve_source(
+    client: &RegionClient,
+    source: &TrafficSource,
+) -> Result<SourceEndpoint, EcsCommandError> {
+    match source {
+        TrafficSource::Cidr { cidr } => Ok(SourceEndpoint {
+            network: Some(cidr.parse().map_err(invalid_query)?),
+            security_group_ids: Vec::new(),
+        }),
+        TrafficSource::Instance { instance_id } => {
+            let primary = describe_interfaces(client, instance_id).await?.remove(0);
+            Ok(SourceEndpoint {
+                network: primary.private_ip_address.parse().ok(),
+                security_group_ids: primary.security_group_ids.security_group_id,
+            })
+        }
+        TrafficSource::SecurityGroup { security_group_id } => Ok(SourceEndpoint {
+            network: None,
+            security_group_ids: vec![security_group_id.clone()],
+        }),
+    }
+}
+
+/// Simulates the traffic entering each network interface of the destination, with the
+/// security groups of the interface, without changing anything.
+///
+/// Only the security groups of the destination are evaluated, not the egress rules of
+/// the source.
+pub async fn simulate_reachability(
+    client: &RegionClient,
+    query: &TrafficQuery,
+) -> Result<ReachabilityReport, EcsCommandError> {
+    let ip_protocol = check_traffic(&query.ip_protocol, query.port)?;
+    let source = resolve_source(client, &query.source).await?;
+    let interfaces = describe_interfaces(client, &query.instance_id).await?;
+
+    let mut group_ids = interfaces
+        .iter()
+        .flat_map(|interface| {
+            interface
+                .security_group_ids
+                .security_group_id
+                .iter()
+                .cloned()
+        })
+        .collect::<Vec<_>>();
+    group_ids.sort();
+    group_ids.dedup();
+    let infos = describe_security_groups(client, None, Some(&group_ids)).await?;
+    let mut groups = Vec::new();
+    for security_group_id in &group_ids {
+        let security_group_type = infos
+            .iter()
+            .find(|info| &info.security_group_id == security_group_id)
+            .map(|info| info.security_group_type)
+            .unwrap_or_default();
+        groups.push(TypedSecurityGroup {
+            security_group_type,
+            group: describe_security_group_rules(client, security_group_id).await?,
+        });
+    }
+
+    let interfaces = interfaces
+        .into_iter()
+        .map(|interface| {
+            let security_group_ids = interface.security_group_ids.security_group_id;
+            let groups = groups
+                .iter()
+                .filter(|typed| security_group_ids.contains(&typed.group.security_group_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            InterfaceVerdict {
+                network_interface_id: interface.network_interface_id,
+                primary: interface.r#type == "Primary",
+                private_ip_address: interface.private_ip_address,
+                verdict: evaluate_ingress(&source, &groups, &ip_protocol, query.port),
+                security_group_ids,
+            }
+        })
+        .collect();
+    Ok(ReachabilityReport {
+        instance_id: query.instance_id.clone(),
+        interfaces,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::{
+        ecs::security_group::SecurityGroupRule,
+        fake::{FakeAliyun, FakeAliyunServer},
+    };
+
+    use super::*;
+
+    fn rule(
+        id: &str,
+        ip_protocol: &str,
+        port_range: &str,
+        peer: RulePeer,
+        policy: RulePolicy,
+        priority: u32,
+    ) -> SecurityGroupRule {
+        SecurityGroupRule {
+            security_group_rule_id: id.to_owned(),
+            rule: RuleSpec {
+                direction: RuleDirection::Ingress,
+                ip_protocol: ip_protocol.to_owned(),
+                port_range: port_range.to_owned(),
+                peer,
+                policy,
+                priority,
+                description: String::new(),
+            },
+        }
+    }
+
+    fn cidr(cidr_ip: &str) -> RulePeer {
+        RulePeer::Cidr {
+            cidr_ip: cidr_ip.to_owned(),
+        }
+    }
+
+    fn group_peer(security_group_id: &str) -> RulePeer {
+        RulePeer::SecurityGroup {
+            security_group_id: security_group_id.to_owned(),
+        }
+    }
+
+    fn group(
+        security_group_id: &str,
+        security_group_type: SecurityGroupType,
+        inner_access_policy: RulePolicy,
+        rules: Vec<SecurityGroupRule>,
+    ) -> TypedSecurityGroup {
+        TypedSecurityGroup {
+            security_group_type,
+            group: SecurityGroupRules {
+                security_group_id: security_group_id.to_owned(),
+                security_group_name: security_group_id.to_owned(),
+                vpc_id: "vpc-1".to_owned(),
+                inner_access_policy,
+                rules,
+            },
+        }
+    }
+
+    fn from_address(address: &str, security_group_ids: &[&str]) -> SourceEndpoint {
+        SourceEndpoint {
+            network: Some(address.parse().unwrap()),
+            security_group_ids: security_group_ids.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    /// The ID of the rule which decided, or the inner access policy, or `None`.
+    fn decided_by(verdict: &Verdict) -> Option<String> {
+        match &verdict.decided_by {
+            Decision::Rule {
+                security_group_rule_id,
+                ..
+            } => Some(security_group_rule_id.clone()),
+            Decision::InnerAccessPolicy {
+                security_group_id, ..
+            } => Some(format!("inner:{security_group_id}")),
+            Decision::NoMatchingRule => None,
+        }
+    }
+
+    #[test]
+    fn test_ip_network() {
+        let network = "10.0.0.0/8".parse::<IpNetwork>().unwrap();
+        assert!(network.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(network.contains(&"10.1.0.0/16".parse().unwrap()));
+        assert!(!network.contains(&"11.0.0.1".parse().unwrap()));
+        assert!(!network.contains(&"0.0.0.0/0".parse().unwrap()));
+        assert!(network.overlaps(&"0.0.0.0/0".parse().unwrap()));
+        assert!(!network.overlaps(&"192.168.0.0/16".parse().unwrap()));
+        assert!("0.0.0.0/0"
+            .parse::<IpNetwork>()
+            .unwrap()
+            .contains(&"203.0.113.7".parse().unwrap()));
+        assert!(!"0.0.0.0/0"
+            .parse::<IpNetwork>()
+            .unwrap()
+            .contains(&"2001:db8::1".parse().unwrap()));
+        assert!("2001:db8::/32"
+            .parse::<IpNetwork>()
+            .unwrap()
+            .contains(&"2001:db8::1".parse().unwrap()));
+        assert!("::/0"
+            .parse::<IpNetwork>()
+            .unwrap()
+            .contains(&"2001:db8::1".parse().unwrap()));
+        assert_eq!(
+            "172.16.0.10".parse::<IpNetwork>(),
+            "172.16.0.10/32".parse::<IpNetwork>()
+        );
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_priorities_and_policies() {
+        let normal = |rules| {
+            vec![group(
+                "sg-1",
+                SecurityGroupType::Normal,
+                RulePolicy::Accept,
+                rules,
+            )]
+        };
+        let source = from_address("203.0.113.7", &[]);
+
+        // The lowest priority number wins, whatever the order of the rules.
+        let groups = normal(vec![
+            rule(
+                "accept",
+                "TCP",
+                "22/22",
+                cidr("0.0.0.0/0"),
+                RulePolicy::Accept,
+                10,
+            ),
+            rule(
+                "drop",
+                "TCP",
+                "22/22",
+                cidr("203.0.113.0/24"),
+                RulePolicy::Drop,
+                1,
+            ),
+        ]);
+        let verdict = evaluate_ingress(&source, &groups, "TCP", Some(22));
+        assert!(!verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("drop"));
+
+        // Dropping wins within a priority.
+        let groups = normal(vec![
+            rule(
+                "accept",
+                "TCP",
+                "1/1024",
+                cidr("0.0.0.0/0"),
+                RulePolicy::Accept,
+                5,
+            ),
+            rule(
+                "drop",
+                "ALL",
+                "-1/-1",
+                cidr("203.0.113.7"),
+                RulePolicy::Drop,
+                5,
+            ),
+        ]);
+        let verdict = evaluate_ingress(&source, &groups, "TCP", Some(80));
+        assert!(!verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("drop"));
+
+        // Rules not covering the traffic are skipped.
+        let groups = normal(vec![
+            rule(
+                "other-port",
+                "TCP",
+                "443/443",
+                cidr("0.0.0.0/0"),
+                RulePolicy::Drop,
+                1,
+            ),
+            rule(
+                "udp",
+                "UDP",
+                "22/22",
+                cidr("0.0.0.0/0"),
+                RulePolicy::Drop,
+                1,
+            ),
+            rule(
+                "other-source",
+                "TCP",
+                "22/22",
+                cidr("10.0.0.0/8"),
+                RulePolicy::Drop,
+                1,
+            ),
+            rule(
+                "ssh",
+                "TCP",
+                "20/30",
+                cidr("0.0.0.0/0"),
+                RulePolicy::Accept,
+                100,
+            ),
+        ]);
+        let verdict = evaluate_ingress(&source, &groups, "TCP", Some(22));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("ssh"));
+        assert_eq!(verdict.unevaluated_rules, []);
+
+        // Ingress traffic is denied by default.
+        let verdict = evaluate_ingress(&source, &groups, "TCP", Some(3306));
+        assert_eq!(verdict.decided_by, Decision::NoMatchingRule);
+        assert!(!verdict.allowed);
+
+        // Protocols without ports ignore them.
+        let groups = normal(vec![rule(
+            "ping",
+            "ICMP",
+            "-1/-1",
+            cidr("0.0.0.0/0"),
+            RulePolicy::Accept,
+            1,
+        )]);
+        assert!(evaluate_ingress(&source, &groups, "ICMP", None).allowed);
+        assert!(!evaluate_ingress(&source, &groups, "ICMPv6", None).allowed);
+    }
+
+    #[test]
+    fn test_rules_of_several_groups() {
+        let groups = [
+            group(
+                "sg-web",
+                SecurityGroupType::Normal,
+                RulePolicy::Accept,
+                vec![rule(
+                    "web",
+                    "TCP",
+                    "80/80",
+                    cidr("0.0.0.0/0"),
+                    RulePolicy::Accept,
+                    50,
+                )],
+            ),
+            group(
+                "sg-block",
+                SecurityGroupType::Normal,
+                RulePolicy::Accept,
+                vec![rule(
+                    "block",
+                    "ALL",
+                    "-1/-1",
+                    cidr("198.51.100.0/24"),
+                    RulePolicy::Drop,
+                    10,
+                )],
+            ),
+        ];
+
+        let verdict =
+            evaluate_ingress(&from_address("198.51.100.9", &[]), &groups, "TCP", Some(80));
+        assert!(!verdict.allowed);
+        assert_matches!(
+            verdict.decided_by,
+            Decision::Rule { ref security_group_id, .. } if security_group_id == "sg-block"
+        );
+        let verdict = evaluate_ingress(&from_address("192.0.2.1", &[]), &groups, "TCP", Some(80));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn test_group_peers_and_inner_access() {
+        let rules = vec![
+            rule(
+                "from-app",
+                "TCP",
+                "3306/3306",
+                group_peer("sg-app"),
+                RulePolicy::Accept,
+                1,
+            ),
+            rule(
+                "no-redis",
+                "TCP",
+                "6379/6379",
+                group_peer("sg-db"),
+                RulePolicy::Drop,
+                1,
+            ),
+        ];
+        let basic = |inner_access_policy| {
+            vec![group(
+                "sg-db",
+                SecurityGroupType::Normal,
+                inner_access_policy,
+                rules.clone(),
+            )]
+        };
+
+        // A group peer matches the instances of the group, their address unknown.
+        let app = SourceEndpoint {
+            network: None,
+            security_group_ids: vec!["sg-app".to_owned()],
+        };
+        let verdict = evaluate_ingress(&app, &basic(RulePolicy::Accept), "TCP", Some(3306));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("from-app"));
+        let verdict = evaluate_ingress(&app, &basic(RulePolicy::Accept), "TCP", Some(6379));
+        assert_eq!(verdict.decided_by, Decision::NoMatchingRule);
+
+        // The instances of a group reach each other unless the policy drops it, the rules
+        // first.
+        let db = from_address("10.0.0.5", &["sg-db"]);
+        let verdict = evaluate_ingress(&db, &basic(RulePolicy::Accept), "TCP", Some(5432));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("inner:sg-db"));
+        let verdict = evaluate_ingress(&db, &basic(RulePolicy::Accept), "TCP", Some(6379));
+        assert!(!verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("no-redis"));
+        let verdict = evaluate_ingress(&db, &basic(RulePolicy::Drop), "TCP", Some(5432));
+        assert!(!verdict.allowed);
+        assert_eq!(
+            verdict.decided_by,
+            Decision::InnerAccessPolicy {
+                security_group_id: "sg-db".to_owned(),
+                policy: RulePolicy::Drop,
+            }
+        );
+
+        // An accepting group wins over a dropping one, both shared.
+        let groups = [
+            group(
+                "sg-a",
+                SecurityGroupType::Normal,
+                RulePolicy::Drop,
+                Vec::new(),
+            ),
+            group(
+                "sg-b",
+                SecurityGroupType::Normal,
+                RulePolicy::Accept,
+                Vec::new(),
+            ),
+        ];
+        let both = from_address("10.0.0.5", &["sg-a", "sg-b"]);
+        let verdict = evaluate_ingress(&both, &groups, "UDP", Some(53));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("inner:sg-b"));
+    }
+
+    #[test]
+    fn test_enterprise_groups() {
+        let groups = [group(
+            "sg-ent",
+            SecurityGroupType::Enterprise,
+            // Reported as accepting, enterprise groups drop it anyway.
+            RulePolicy::Accept,
+            vec![
+                rule(
+                    "peer",
+                    "TCP",
+                    "22/22",
+                    group_peer("sg-ent"),
+                    RulePolicy::Accept,
+                    1,
+                ),
+                rule(
+                    "ssh",
+                    "TCP",
+                    "22/22",
+                    cidr("10.0.0.0/8"),
+                    RulePolicy::Accept,
+                    2,
+                ),
+            ],
+        )];
+        let member = from_address("172.16.0.9", &["sg-ent"]);
+
+        let verdict = evaluate_ingress(&member, &groups, "TCP", Some(22));
+        assert!(!verdict.allowed);
+        assert_eq!(
+            verdict.decided_by,
+            Decision::InnerAccessPolicy {
+                security_group_id: "sg-ent".to_owned(),
+                policy: RulePolicy::Drop,
+            }
+        );
+        assert_eq!(
+            verdict
+                .unevaluated_rules
+                .iter()
+                .map(|rule| rule.security_group_rule_id.as_str())
+                .collect::<Vec<_>>(),
+            ["peer"]
+        );
+
+        let verdict = evaluate_ingress(
+            &from_address("10.1.1.1", &["sg-ent"]),
+            &groups,
+            "TCP",
+            Some(22),
+        );
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("ssh"));
+    }
+
+    #[test]
+    fn test_unevaluated_rules() {
+        let groups = [group(
+            "sg-1",
+            SecurityGroupType::Normal,
+            RulePolicy::Accept,
+            vec![
+                rule(
+                    "prefix-list",
+                    "TCP",
+                    "443/443",
+                    RulePeer::PrefixList {
+                        prefix_list_id: "pl-1".to_owned(),
+                    },
+                    RulePolicy::Drop,
+                    1,
+                ),
+                rule(
+                    "office",
+                    "TCP",
+                    "443/443",
+                    cidr("192.0.2.0/25"),
+                    RulePolicy::Accept,
+                    2,
+                ),
+                rule(
+                    "v6",
+                    "TCP",
+                    "443/443",
+                    RulePeer::Ipv6Cidr {
+                        ipv6_cidr_ip: "::/0".to_owned(),
+                    },
+                    RulePolicy::Accept,
+                    3,
+                ),
+            ],
+        )];
+
+        // A source partly covered by a rule.
+        let verdict = evaluate_ingress(
+            &from_address("192.0.2.0/24", &[]),
+            &groups,
+            "TCP",
+            Some(443),
+        );
+        assert_eq!(verdict.decided_by, Decision::NoMatchingRule);
+        assert_eq!(
+            verdict
+                .unevaluated_rules
+                .iter()
+                .map(|rule| rule.security_group_rule_id.as_str())
+                .collect::<Vec<_>>(),
+            ["prefix-list", "office"]
+        );
+
+        // The rules after the decision aren't reported.
+        let verdict =
+            evaluate_ingress(&from_address("2001:db8::1", &[]), &groups, "TCP", Some(443));
+        assert!(verdict.allowed);
+        assert_eq!(decided_by(&verdict).as_deref(), Some("v6"));
+        assert_eq!(verdict.unevaluated_rules.len(), 1);
+
+        // A group source has no address for the CIDR rules.
+        let group_source = SourceEndpoint {
+            network: None,
+            security_group_ids: vec!["sg-2".to_owned()],
+        };
+        let verdict = evaluate_ingress(&group_source, &groups, "TCP", Some(443));
+        assert!(!verdict.allowed);
+        assert_eq!(verdict.unevaluated_rules.len(), 3);
+    }
+
+    #[test]
+    fn test_check_traffic() {
+        assert_eq!(check_traffic("tcp", Some(22)).unwrap(), "TCP");
+        assert_eq!(check_traffic("icmpv6", None).unwrap(), "ICMPv6");
+        assert_matches!(
+            check_traffic("UDP", None),
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::InvalidTrafficQuery { .. }
+            ))
+        );
+        assert_matches!(
+            check_traffic("ALL", None),
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::InvalidTrafficQuery { .. }
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_reachability() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let client = server.region_client();
+        let query = |source, ip_protocol: &str, port| TrafficQuery {
+            source,
+            instance_id: "i-fake0001".to_owned(),
+            ip_protocol: ip_protocol.to_owned(),
+            port,
+        };
+
+        let report = simulate_reachability(
+            &client,
+            &query(
+                TrafficSource::Cidr {
+                    cidr: "203.0.113.7".to_owned(),
+                },
+                "TCP",
+                Some(22),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.interfaces.len(), 1);
+        let interface = &report.interfaces[0];
+        assert_eq!(interface.network_interface_id, "eni-fake0001");
+        assert!(interface.primary);
+        assert_eq!(interface.security_group_ids, ["sg-fake0001"]);
+        assert!(interface.verdict.allowed);
+        assert_eq!(
+            decided_by(&interface.verdict).as_deref(),
+            Some("sgr-fake0001")
+        );
+
+        let report = simulate_reachability(
+            &client,
+            &query(
+                TrafficSource::Cidr {
+                    cidr: "203.0.113.7".to_owned(),
+                },
+                "TCP",
+                Some(3306),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            report.interfaces[0].verdict.decided_by,
+            Decision::NoMatchingRule
+        );
+
+        // Both instances are in `sg-fake0001`, which lets its instances reach each other.
+        let report = simulate_reachability(
+            &client,
+            &query(
+                TrafficSource::Instance {
+                    instance_id: "i-fake0002".to_owned(),
+                },
+                "TCP",
+                Some(3306),
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(report.interfaces[0].verdict.allowed);
+        assert_eq!(
+            decided_by(&report.interfaces[0].verdict).as_deref(),
+            Some("inner:sg-fake0001")
+        );
+
+        assert_matches!(
+            simulate_reachability(
+                &client,
+                &TrafficQuery {
+                    instance_id: "i-missing".to_owned(),
+                    ..query(
+                        TrafficSource::SecurityGroup {
+                            security_group_id: "sg-fake0002".to_owned(),
+                        },
+                        "ICMP",
+                        None,
+                    )
+                },
+            )
+            .await,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::InstanceNotFound { .. }
+            ))
+        );
+        assert_matches!(
+            simulate_reachability(
+                &client,
+                &query(
+                    TrafficSource::Cidr {
+                        cidr: "not an address".to_owned(),
+                    },
+                    "TCP",
+                    Some(22),
+                ),
+            )
+            .await,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::InvalidTrafficQuery { .. }
+            ))
+        );
+        // Nothing was changed.
+        assert!(server
+            .fake()
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|action| action.starts_with("Describe")));
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::services::{
+    ecs::{
+        inventory::{describe_instance, InstanceSummary},
+        power::WaitPolicy,
+        EcsError,
+    },
+    error::AliyunRequestCommandError,
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The code of the rejection of a dry run which would have succeeded.
+const DRY_RUN_SUCCESS_CODE: &str = "DryRunOperation";
+
+/// How a release is carried out.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+pub struct ReleaseOptions {
+    /// Typed by the user: the name of the instance, its ID when it has no name.
+    pub confirmation: String,
+    /// Takes a snapshot of each disk before releasing, kept after the disks are gone.
+    #[serde(default)]
+    pub snapshot_disks: bool,
+    /// Releases the instance even when it is running.
+    #[serde(default)]
+    pub force: bool,
+    /// Only checks whether the release would go through, nothing is changed.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What a release did, or would do in a dry run.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ReleaseReport {
+    pub instance_id: String,
+    pub dry_run: bool,
+    /// The disks snapshotted before releasing, or which would be.
+    pub disk_ids: Vec<String>,
+    /// The final snapshots taken, none in a dry run.
+    pub snapshot_ids: Vec<String>,
+    /// Whether the instance has been released, never in a dry run.
+    pub released: bool,
+}
+
+/// What the user has to type to confirm the release of the instance.
+pub fn confirmation_token(instance: &InstanceSummary) -> &str {
+    if instance.instance_name.is_empty() {
+        &instance.instance_id
+    } else {
+        &instance.instance_name
+    }
+}
+
+/// Parses an expiry of ECS, e.g. `2027-01-01T16:00Z`.
+fn parse_expired_time(expired_time: &str) -> Option<DateTime<Utc>> {
+    ["%Y-%m-%dT%H:%MZ", "%Y-%m-%dT%H:%M:%SZ"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(expired_time, format).ok())
+        .map(|expired_time| expired_time.and_utc())
+}
+
+/// Checks that the release has been confirmed and that nothing protects the instance.
+///
+/// Subscription instances are refused until they have expired, their expiry being
+/// taken as in the future when it can't be read.
+pub fn check_releasable(
+    instance: &InstanceSummary,
+    confirmation: &str,
+    now: DateTime<Utc>,
+) -> Result<(), EcsError> {
+    let instance_id = instance.instance_id.clone();
+    if confirmation != confirmation_token(instance) {
+        return Err(EcsError::ConfirmationMismatch { instance_id });
+    }
+    if instance.deletion_protection {
+        return Err(EcsError::DeletionProtected { instance_id });
+    }
+    if instance.charge_type == "PrePaid" {
+        let expired_time = instance.expired_time.clone().unwrap_or_default();
+        if parse_expired_time(&expired_time).is_none_or(|expired_time| expired_time > now) {
+            return Err(EcsError::SubscriptionNotExpired {
+                instance_id,
+                expired_time,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedDisk {
+    disk_id: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct DiskList {
+    disk: Vec<DescribedDisk>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedDisks {
+    #[serde(default)]
+    disks: DiskList,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreatedSnapshot {
+    snapshot_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedSnapshot {
+    snapshot_id: String,
+    status: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct SnapshotList {
+    snapshot: Vec<DescribedSnapshot>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedSnapshots {
+    #[serde(default)]
+    snapshots: SnapshotList,
+}
+
+async fn describe_disk_ids(
+    client: &RegionClient,
+    instance_id: &str,
+) -> Result<Vec<String>, AliyunRequestCommandError<EcsError>> {
+    let params = RpcParams::from([
+        ("InstanceId".to_owned(), instance_id.to_owned()),
+        ("PageSize".to_owned(), "100".to_owned()),
+    ]);
+    let described: DescribedDisks = client.request("DescribeDisks", params).await?;
+    Ok(described
+        .disks
+        .disk
+        .into_iter()
+        .map(|disk| disk.disk_id)
+        .collect())
+}
+
+/// Takes a snapshot of each disk, then waits until all of them are complete.
+async fn snapshot_disks(
+    client: &RegionClient,
+    instance_id: &str,
+    disk_ids: &[String],
+    wait: &WaitPolicy,
+) -> Result<Vec<String>, AliyunRequestCommandError<EcsError>> {
+    let mut snapshot_ids = Vec::new();
+    for disk_id in disk_ids {
+        let params = RpcParams::from([
+            ("DiskId".to_owned(), disk_id.clone()),
+            (
+                "SnapshotName".to_owned(),
+                format!("final-{instance_id}-{disk_id}"),
+            ),
+            (
+                "Description".to_owned(),
+                format!("Taken before releasing {instance_id}"),
+            ),
+            // Sent again safely when throttled.
+            ("ClientToken".to_owned(), uuid::Uuid::new_v4().to_string()),
+        ]);
+        let created: CreatedSnapshot = client.request("CreateSnapshot", params).await?;
+        log::info!("Taking the snapshot {} of {disk_id}", created.snapshot_id);
+        snapshot_ids.push(created.snapshot_id);
+    }
+
+    if snapshot_ids.is_empty() {
+        return Ok(snapshot_ids);
+    }
+    let started = Instant::now();
+    let params = RpcParams::from([(
+        "SnapshotIds".to_owned(),
+        serde_json::to_string(&snapshot_ids).unwrap(),
+    )]);
+    loop {
+        let described: DescribedSnapshots =
+            client.request("DescribeSnapshots", params.clone()).await?;
+        let statuses = described
+            .snapshots
+            .snapshot
+            .into_iter()
+            .map(|snapshot| (snapshot.snapshot_id, snapshot.status))
+            .collect::<BTreeMap<_, _>>();
+        let pending = snapshot_ids.iter().find_map(|snapshot_id| {
+            let status = statuses.get(snapshot_id).map_or("missing", String::as_str);
+            (status != "accomplished").then(|| (snapshot_id, status.to_owned()))
+        });
+        let Some((snapshot_id, status)) = pending else {
+            return Ok(snapshot_ids);
+        };
+        // A snapshot just taken may not be listed yet, it is waited for like the others.
+        if status == "failed" || started.elapsed() + wait.interval > wait.timeout {
+            return Err(AliyunRequestCommandError::Specific(
+                EcsError::SnapshotFailed {
+                    snapshot_id: snapshot_id.clone(),
+                    status,
+                },
+            ));
+        }
+        tokio::time::sleep(wait.interval).await;
+    }
+}
+
+/// Releases the instance, once confirmed and checked with [`check_releasable`].
+///
+/// The disks are snapshotted first when asked to, the release being given up when a
+/// snapshot fails. A dry run makes the checks and asks Aliyun whether the release would
+/// go through, without changing anything.
+pub async fn release_instance(
+    client: &RegionClient,
+    instance_id: &str,
+    options: &ReleaseOptions,
+    snapshot_wait: &WaitPolicy,
+) -> Result<ReleaseReport, AliyunRequestCommandError<EcsError>> {
+    let instance = describe_instance::<EcsError>(client, instance_id)
+        .await?
+        .ok_or_else(|| {
+            AliyunRequestCommandError::Specific(EcsError::InstanceNotFound {
+                instance_id: instance_id.to_owned(),
+            })
+        })?;
+    check_releasable(&instance, &options.confirmation, Utc::now())
+        .map_err(AliyunRequestCommandError::Specific)?;
+
+    let disk_ids = if options.snapshot_disks {
+        describe_disk_ids(client, instance_id).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut params = RpcParams::from([
+        ("InstanceId".to_owned(), instance_id.to_owned()),
+        ("Force".to_owned(), options.force.to_string()),
+    ]);
+    let mut report = ReleaseReport {
+        instance_id: instance_id.to_owned(),
+        dry_run: options.dry_run,
+        disk_ids,
+        snapshot_ids: Vec::new(),
+        released: false,
+    };
+
+    if options.dry_run {
+        params.insert("DryRun".to_owned(), "true".to_owned());
+        return match client
+            .request::<Value, EcsError>("DeleteInstance", params)
+            .await
+        {
+            Ok(_) => Ok(report),
+            Err(AliyunRequestCommandError::Rejected(rejection))
+                if rejection.data.code == DRY_RUN_SUCCESS_CODE =>
+            {
+                Ok(report)
+            }
+            Err(err) => Err(EcsError::of_instance(err, instance_id)),
+        };
+    }
+
+    report.snapshot_ids =
+        snapshot_disks(client, instance_id, &report.disk_ids, snapshot_wait).await?;
+    client
+        .request::<Value, EcsError>("DeleteInstance", params)
+        .await
+        .map_err(|err| EcsError::of_instance(err, instance_id))?;
+    log::info!("Released the instance {instance_id}");
+    report.released = true;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::fake::{FakeAliyun, FakeAliyunServer};
+
+    use super::*;
+
+    fn options(confirmation: &str) -> ReleaseOptions {
+        ReleaseOptions {
+            confirmation: confirmation.to_owned(),
+            snapshot_disks: true,
+            force: false,
+            dry_run: true,
+        }
+    }
+
+    #[test]
+    fn test_check_releasable() {
+        let subscription = FakeAliyun::demo().instance("i-fake0003").unwrap();
+        let mut instance = InstanceSummary {
+            instance_id: subscription.instance_id,
+            instance_name: subscription.instance_name,
+            region_id: subscription.region_id,
+            zone_id: subscription.zone_id,
+            status: subscription.status,
+            start_time: Some(subscription.start_time),
+            instance_type: subscription.instance_type,
+            private_ips: Vec::new(),
+            public_ips: Vec::new(),
+            charge_type: subscription.instance_charge_type,
+            expired_time: Some(subscription.expired_time),
+            tags: Vec::new(),
+            deletion_protection: false,
+            security_group_ids: Vec::new(),
+        };
+        let before = "2026-12-31T00:00:00Z".parse().unwrap();
+        let after = "2027-01-02T00:00:00Z".parse().unwrap();
+
+        assert_matches!(
+            check_releasable(&instance, "db-1", before),
+            Err(EcsError::SubscriptionNotExpired { .. })
+        );
+        check_releasable(&instance, "db-1", after).unwrap();
+        assert_matches!(
+            check_releasable(&instance, "i-fake0003", after),
+            Err(EcsError::ConfirmationMismatch { .. })
+        );
+
+        instance.instance_name = String::new();
+        check_releasable(&instance, "i-fake0003", after).unwrap();
+        instance.deletion_protection = true;
+        assert_matches!(
+            check_releasable(&instance, "i-fake0003", after),
+            Err(EcsError::DeletionProtected { .. })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release_instance() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let fake = server.fake();
+        let client = server.region_client();
+        let wait = WaitPolicy {
+            interval: Duration::from_millis(1),
+            timeout: Duration::from_millis(50),
+        };
+
+        // Running, so it can only be released by force.
+        let err = release_instance(&client, "i-fake0001", &options("web-1"), &wait)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::IncorrectInstanceStatus { .. })
+        );
+        let forced = ReleaseOptions {
+            force: true,
+            ..options("web-1")
+        };
+        let report = release_instance(&client, "i-fake0001", &forced, &wait)
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            ReleaseReport {
+                instance_id: "i-fake0001".to_owned(),
+                dry_run: true,
+                disk_ids: vec!["d-fake0001".to_owned()],
+                snapshot_ids: Vec::new(),
+                released: false,
+            }
+        );
+        assert!(fake.instance("i-fake0001").is_some());
+        assert!(fake.snapshots.lock().unwrap().is_empty());
+
+        let released = ReleaseOptions {
+            dry_run: false,
+            ..forced
+        };
+        let report = release_instance(&client, "i-fake0001", &released, &wait)
+            .await
+            .unwrap();
+        assert!(report.released);
+        assert_eq!(report.snapshot_ids, vec!["s-fake0001"]);
+        assert_eq!(
+            fake.snapshots.lock().unwrap()[0],
+            (
+                "s-fake0001".to_owned(),
+                "d-fake0001".to_owned(),
+                "final-i-fake0001-d-fake0001".to_owned()
+            )
+        );
+        assert!(fake.instance("i-fake0001").is_none());
+
+        fake.instances.lock().unwrap()[0].deletion_protection = true;
+        let protected = ReleaseOptions {
+            confirmation: "web-2".to_owned(),
+            ..released
+        };
+        let err = release_instance(&client, "i-fake0002", &protected, &wait)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::DeletionProtected { .. })
+        );
+        assert!(fake.instance("i-fake0002").is_some());
+    }
+}
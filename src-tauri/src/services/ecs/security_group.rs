@@ -0,0 +1,1047 @@
+use std::{
+    collections::HashSet,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    services::{
+        ecs::{EcsCommandError, EcsError},
+        error::{AliyunRequestCommandError, RejectionCategory},
+        region::RegionClient,
+        rpc::RpcParams,
+    },
+    types::Store,
+};
+
+/// The key of the rules of each security group before the last change made from the app,
+/// in the store.
+pub(crate) const RULE_BACKUPS_STORE_KEY: &str = "security_group_backups";
+
+/// The protocols of the rules, as Aliyun spells them.
+pub(super) const IP_PROTOCOLS: &[&str] = &["TCP", "UDP", "ICMP", "ICMPv6", "GRE", "ALL"];
+
+/// The longest description of a rule.
+const MAX_RULE_DESCRIPTION_LEN: usize = 512;
+
+/// Whether a rule is about the traffic entering or leaving the instances.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleDirection {
+    Ingress,
+    Egress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+pub enum RulePolicy {
+    Accept,
+    Drop,
+}
+
+impl RulePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accept => "Accept",
+            Self::Drop => "Drop",
+        }
+    }
+
+    fn of(policy: &str) -> Self {
+        if policy.eq_ignore_ascii_case("drop") {
+            Self::Drop
+        } else {
+            Self::Accept
+        }
+    }
+}
+
+/// Where the traffic of an ingress rule comes from, or where the traffic of an egress
+/// rule goes to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum RulePeer {
+    /// An IPv4 address or CIDR block, e.g. `0.0.0.0/0`.
+    Cidr {
+        cidr_ip: String,
+    },
+    Ipv6Cidr {
+        ipv6_cidr_ip: String,
+    },
+    /// The instances of another security group, or of the same one.
+    SecurityGroup {
+        security_group_id: String,
+    },
+    PrefixList {
+        prefix_list_id: String,
+    },
+}
+
+/// What a security group rule allows or denies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+pub struct RuleSpec {
+    pub direction: RuleDirection,
+    /// `TCP`, `UDP`, `ICMP`, `ICMPv6`, `GRE` or `ALL`.
+    pub ip_protocol: String,
+    /// `start/end`, e.g. `22/22`, always `-1/-1` for the protocols without ports.
+    pub port_range: String,
+    pub peer: RulePeer,
+    pub policy: RulePolicy,
+    /// From 1, the first one evaluated, to 100.
+    pub priority: u32,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A rule of a security group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub struct SecurityGroupRule {
+    pub security_group_rule_id: String,
+    pub rule: RuleSpec,
+}
+
+/// A security group with its ingress and egress rules.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct SecurityGroupRules {
+    pub security_group_id: String,
+    pub security_group_name: String,
+    pub vpc_id: String,
+    /// Whether the instances of the group can reach each other.
+    pub inner_access_policy: RulePolicy,
+    pub rules: Vec<SecurityGroupRule>,
+}
+
+/// A change to the rules of a security group.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+#[serde(tag = "type")]
+pub enum RuleEdit {
+    Add {
+        rule: RuleSpec,
+    },
+    /// Replaces a rule, in the same direction.
+    Modify {
+        security_group_rule_id: String,
+        rule: RuleSpec,
+    },
+    Revoke {
+        security_group_rule_id: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RuleModification {
+    pub security_group_rule_id: String,
+    pub from: RuleSpec,
+    pub to: RuleSpec,
+}
+
+/// How the rules of a security group change, or would change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RuleSetDiff {
+    pub security_group_id: String,
+    pub added: Vec<RuleSpec>,
+    pub modified: Vec<RuleModification>,
+    pub revoked: Vec<SecurityGroupRule>,
+}
+
+impl RuleSetDiff {
+    pub(super) fn new(security_group_id: &str) -> Self {
+        Self {
+            security_group_id: security_group_id.to_owned(),
+            added: Vec::new(),
+            modified: Vec::new(),
+            revoked: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.revoked.is_empty()
+    }
+}
+
+/// The rules of a security group before the last change made from the app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RuleBackup {
+    /// When the change was applied, in RFC 3339.
+    pub taken_at: String,
+    pub rules: Vec<RuleSpec>,
+}
+
+fn invalid(reason: impl Into<String>) -> EcsError {
+    EcsError::InvalidRule {
+        reason: reason.into(),
+    }
+}
+
+/// Whether the text is an address of the family, with an optional prefix length.
+fn is_cidr<A: std::str::FromStr>(cidr: &str, max_prefix_len: u8) -> bool {
+    let (address, prefix_len) = match cidr.split_once('/') {
+        Some((address, prefix_len)) => (address, Some(prefix_len)),
+        None => (cidr, None),
+    };
+    address.parse::<A>().is_ok()
+        && prefix_len.is_none_or(|prefix_len| {
+            prefix_len
+                .parse::<u8>()
+                .is_ok_and(|prefix_len| prefix_len <= max_prefix_len)
+        })
+}
+
+/// Parses `start/end`, or a single port.
+pub(super) fn parse_port_range(port_range: &str) -> Option<(u16, u16)> {
+    let (start, end) = port_range
+        .split_once('/')
+        .unwrap_or((port_range, port_range));
+    let start = start.trim().parse::<u16>().ok()?;
+    let end = end.trim().parse::<u16>().ok()?;
+    (1 <= start && start <= end).then_some((start, end))
+}
+
+impl RuleSpec {
+    /// The rule spelled the way Aliyun does, after checking each of its settings.
+    pub fn normalized(&self) -> Result<Self, EcsError> {
+        let ip_protocol = IP_PROTOCOLS
+            .iter()
+            .find(|protocol| protocol.eq_ignore_ascii_case(self.ip_protocol.trim()))
+            .ok_or_else(|| invalid(format!("{:?} is not a protocol", self.ip_protocol)))?;
+
+        let port_range = match *ip_protocol {
+            "TCP" | "UDP" => {
+                let (start, end) = parse_port_range(&self.port_range).ok_or_else(|| {
+                    invalid(format!(
+                        "{:?} is not a port range, e.g. 22/22 or 8000/8080",
+                        self.port_range
+                    ))
+                })?;
+                format!("{start}/{end}")
+            }
+            _ => match self.port_range.trim() {
+                "" | "-1/-1" => "-1/-1".to_owned(),
+                port_range => {
+                    return Err(invalid(format!(
+                    "{ip_protocol} has no ports, the port range must be -1/-1, not {port_range:?}"
+                )))
+                }
+            },
+        };
+
+        let peer = match &self.peer {
+            RulePeer::Cidr { cidr_ip } if is_cidr::<Ipv4Addr>(cidr_ip.trim(), 32) => {
+                RulePeer::Cidr {
+                    cidr_ip: cidr_ip.trim().to_owned(),
+                }
+            }
+            RulePeer::Ipv6Cidr { ipv6_cidr_ip }
+                if is_cidr::<Ipv6Addr>(ipv6_cidr_ip.trim(), 128) =>
+            {
+                RulePeer::Ipv6Cidr {
+                    ipv6_cidr_ip: ipv6_cidr_ip.trim().to_owned(),
+                }
+            }
+            RulePeer::SecurityGroup { security_group_id }
+                if security_group_id.trim().starts_with("sg-") =>
+            {
+                RulePeer::SecurityGroup {
+                    security_group_id: security_group_id.trim().to_owned(),
+                }
+            }
+            RulePeer::PrefixList { prefix_list_id } if prefix_list_id.trim().starts_with("pl-") => {
+                RulePeer::PrefixList {
+                    prefix_list_id: prefix_list_id.trim().to_owned(),
+                }
+            }
+            peer => return Err(invalid(format!("{peer:?} is not a valid peer"))),
+        };
+
+        if !(1..=100).contains(&self.priority) {
+            return Err(invalid(format!(
+                "the priority must be from 1 to 100, not {}",
+                self.priority
+            )));
+        }
+        if self.description.chars().count() > MAX_RULE_DESCRIPTION_LEN {
+            return Err(invalid(format!(
+                "the description is longer than {MAX_RULE_DESCRIPTION_LEN} characters"
+            )));
+        }
+
+        Ok(Self {
+            direction: self.direction,
+            ip_protocol: (*ip_protocol).to_owned(),
+            port_range,
+            peer,
+            policy: self.policy,
+            priority: self.priority,
+            description: self.description.clone(),
+        })
+    }
+
+    /// Whether both rules match the same traffic the same way, whatever their
+    /// descriptions.
+    pub(super) fn same_traffic(&self, other: &Self) -> bool {
+        Self {
+            description: String::new(),
+            ..self.clone()
+        } == Self {
+            description: String::new(),
+            ..other.clone()
+        }
+    }
+
+    /// The parameters of AuthorizeSecurityGroup and ModifySecurityGroupRule, or of their
+    /// egress counterparts, each name starting with `prefix`.
+    fn params(&self, prefix: &str) -> RpcParams {
+        let ingress = self.direction == RuleDirection::Ingress;
+        let peer = match &self.peer {
+            RulePeer::Cidr { cidr_ip } => (
+                if ingress {
+                    "SourceCidrIp"
+                } else {
+                    "DestCidrIp"
+                },
+                cidr_ip,
+            ),
+            RulePeer::Ipv6Cidr { ipv6_cidr_ip } => (
+                if ingress {
+                    "Ipv6SourceCidrIp"
+                } else {
+                    "Ipv6DestCidrIp"
+                },
+                ipv6_cidr_ip,
+            ),
+            RulePeer::SecurityGroup { security_group_id } => (
+                if ingress {
+                    "SourceGroupId"
+                } else {
+                    "DestGroupId"
+                },
+                security_group_id,
+            ),
+            RulePeer::PrefixList { prefix_list_id } => (
+                if ingress {
+                    "SourcePrefixListId"
+                } else {
+                    "DestPrefixListId"
+                },
+                prefix_list_id,
+            ),
+        };
+        [
+            ("IpProtocol", self.ip_protocol.clone()),
+            ("PortRange", self.port_range.clone()),
+            ("Policy", self.policy.as_str().to_owned()),
+            ("Priority", self.priority.to_string()),
+            ("Description", self.description.clone()),
+            (peer.0, peer.1.clone()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (format!("{prefix}{name}"), value))
+        .collect()
+    }
+}
+
+/// A number Aliyun sends either as such or as text.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(u32),
+    Text(String),
+}
+
+impl NumberOrText {
+    fn value(&self) -> Option<u32> {
+        match self {
+            Self::Number(number) => Some(*number),
+            Self::Text(text) => text.parse().ok(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedPermission {
+    #[serde(default)]
+    security_group_rule_id: String,
+    direction: String,
+    ip_protocol: String,
+    #[serde(default)]
+    port_range: String,
+    #[serde(default)]
+    source_cidr_ip: String,
+    #[serde(default)]
+    dest_cidr_ip: String,
+    #[serde(default)]
+    ipv6_source_cidr_ip: String,
+    #[serde(default)]
+    ipv6_dest_cidr_ip: String,
+    #[serde(default)]
+    source_group_id: String,
+    #[serde(default)]
+    dest_group_id: String,
+    #[serde(default)]
+    source_prefix_list_id: String,
+    #[serde(default)]
+    dest_prefix_list_id: String,
+    #[serde(default)]
+    policy: String,
+    priority: Option<NumberOrText>,
+    #[serde(default)]
+    description: String,
+}
+
+impl From<DescribedPermission> for SecurityGroupRule {
+    fn from(permission: DescribedPermission) -> Self {
+        let direction = if permission.direction.eq_ignore_ascii_case("egress") {
+            RuleDirection::Egress
+        } else {
+            RuleDirection::Ingress
+        };
+        let (cidr_ip, ipv6_cidr_ip, security_group_id, prefix_list_id) = match direction {
+            RuleDirection::Ingress => (
+                permission.source_cidr_ip,
+                permission.ipv6_source_cidr_ip,
+                permission.source_group_id,
+                permission.source_prefix_list_id,
+            ),
+            RuleDirection::Egress => (
+                permission.dest_cidr_ip,
+                permission.ipv6_dest_cidr_ip,
+                permission.dest_group_id,
+                permission.dest_prefix_list_id,
+            ),
+        };
+        let peer = if !cidr_ip.is_empty() {
+            RulePeer::Cidr { cidr_ip }
+        } else if !ipv6_cidr_ip.is_empty() {
+            RulePeer::Ipv6Cidr { ipv6_cidr_ip }
+        } else if !security_group_id.is_empty() {
+            RulePeer::SecurityGroup { security_group_id }
+        } else {
+            RulePeer::PrefixList { prefix_list_id }
+        };
+        let ip_protocol = IP_PROTOCOLS
+            .iter()
+            .find(|protocol| protocol.eq_ignore_ascii_case(&permission.ip_protocol))
+            .map_or(permission.ip_protocol, |protocol| (*protocol).to_owned());
+        Self {
+            security_group_rule_id: permission.security_group_rule_id,
+            rule: RuleSpec {
+                direction,
+                ip_protocol,
+                port_range: permission.port_range,
+                peer,
+                policy: RulePolicy::of(&permission.policy),
+                priority: permission
+                    .priority
+                    .and_then(|priority| priority.value())
+                    .unwrap_or(1),
+                description: permission.description,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct PermissionList {
+    permission: Vec<DescribedPermission>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedAttribute {
+    security_group_id: String,
+    #[serde(default)]
+    security_group_name: String,
+    #[serde(default)]
+    vpc_id: String,
+    #[serde(default)]
+    inner_access_policy: String,
+    #[serde(default)]
+    permissions: PermissionList,
+}
+
+/// Turns the rejections naming a missing security group into a specific error.
+fn of_security_group(err: EcsCommandError, security_group_id: &str) -> EcsCommandError {
+    match &err {
+        AliyunRequestCommandError::Rejected(rejection)
+            if rejection.category == RejectionCategory::NotFound
+                && rejection.data.code.starts_with("InvalidSecurityGroupId") =>
+        {
+            AliyunRequestCommandError::Specific(EcsError::SecurityGroupNotFound {
+                security_group_id: security_group_id.to_owned(),
+            })
+        }
+        _ => err,
+    }
+}
+
+/// The security group with its rules, in both directions.
+pub async fn describe_security_group_rules(
+    client: &RegionClient,
+    security_group_id: &str,
+) -> Result<SecurityGroupRules, EcsCommandError> {
+    let params = RpcParams::from([
+        ("SecurityGroupId".to_owned(), security_group_id.to_owned()),
+        ("Direction".to_owned(), "all".to_owned()),
+    ]);
+    let described: DescribedAttribute = match client
+        .request("DescribeSecurityGroupAttribute", params)
+        .await
+    {
+        Ok(described) => described,
+        Err(err) => return Err(of_security_group(err, security_group_id)),
+    };
+    Ok(SecurityGroupRules {
+        security_group_id: described.security_group_id,
+        security_group_name: described.security_group_name,
+        vpc_id: described.vpc_id,
+        inner_access_policy: RulePolicy::of(&described.inner_access_policy),
+        rules: described
+            .permissions
+            .permission
+            .into_iter()
+            .map(SecurityGroupRule::from)
+            .collect(),
+    })
+}
+
+/// How the edits would change the rules of the group, after checking them.
+///
+/// A rule can only be edited once, and an added rule must not match the same traffic
+/// as another one.
+pub fn diff_rule_edits(
+    current: &SecurityGroupRules,
+    edits: &[RuleEdit],
+) -> Result<RuleSetDiff, EcsError> {
+    let mut diff = RuleSetDiff::new(&current.security_group_id);
+    let mut edited = HashSet::new();
+    let mut existing = |security_group_rule_id: &str| {
+        if !edited.insert(security_group_rule_id.to_owned()) {
+            return Err(invalid(format!(
+                "the rule {security_group_rule_id} is edited more than once"
+            )));
+        }
+        current
+            .rules
+            .iter()
+            .find(|rule| rule.security_group_rule_id == security_group_rule_id)
+            .ok_or_else(|| EcsError::RuleNotFound {
+                security_group_id: current.security_group_id.clone(),
+                security_group_rule_id: security_group_rule_id.to_owned(),
+            })
+    };
+
+    for edit in edits {
+        match edit {
+            RuleEdit::Add { rule } => {
+                let rule = rule.normalized()?;
+                let rules = current.rules.iter().map(|existing| &existing.rule);
+                if rules
+                    .chain(&diff.added)
+                    .any(|other| other.same_traffic(&rule))
+                {
+                    return Err(invalid("the same rule already exists"));
+                }
+                diff.added.push(rule);
+            }
+            RuleEdit::Modify {
+                security_group_rule_id,
+                rule,
+            } => {
+                let existing = existing(security_group_rule_id)?;
+                let rule = rule.normalized()?;
+                if rule.direction != existing.rule.direction {
+                    return Err(invalid(
+                        "the direction of a rule can't change, revoke it and add another one",
+                    ));
+                }
+                if rule != existing.rule {
+                    diff.modified.push(RuleModification {
+                        security_group_rule_id: security_group_rule_id.clone(),
+                        from: existing.rule.clone(),
+                        to: rule,
+                    });
+                }
+            }
+            RuleEdit::Revoke {
+                security_group_rule_id,
+            } => {
+                let existing = existing(security_group_rule_id)?;
+                diff.revoked.push(existing.clone());
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// How the rules of the group change when brought back to `rules`: the rules not among
+/// them are revoked and the missing ones added again.
+pub fn diff_to_rules(current: &SecurityGroupRules, rules: &[RuleSpec]) -> RuleSetDiff {
+    let mut diff = RuleSetDiff::new(&current.security_group_id);
+    let mut missing = rules.to_vec();
+    for rule in &current.rules {
+        match missing.iter().position(|spec| *spec == rule.rule) {
+            Some(index) => {
+                missing.remove(index);
+            }
+            None => diff.revoked.push(rule.clone()),
+        }
+    }
+    diff.added = missing;
+    diff
+}
+
+/// Sends the changes of the diff, the revocations first so that added rules never
+/// collide with revoked ones.
+pub(super) async fn send_diff(
+    client: &RegionClient,
+    diff: &RuleSetDiff,
+) -> Result<(), EcsCommandError> {
+    let security_group_id = &diff.security_group_id;
+    let group_params =
+        || RpcParams::from([("SecurityGroupId".to_owned(), security_group_id.clone())]);
+
+    for direction in [RuleDirection::Ingress, RuleDirection::Egress] {
+        let mut params = group_params();
+        let revoked = diff
+            .revoked
+            .iter()
+            .filter(|revoked| revoked.rule.direction == direction);
+        for (index, revoked) in revoked.enumerate() {
+            params.insert(
+                format!("SecurityGroupRuleId.{}", index + 1),
+                revoked.security_group_rule_id.clone(),
+            );
+        }
+        if params.len() == 1 {
+            continue;
+        }
+        let action = match direction {
+            RuleDirection::Ingress => "RevokeSecurityGroup",
+            RuleDirection::Egress => "RevokeSecurityGroupEgress",
+        };
+        let result = client.request::<Value, EcsError>(action, params).await;
+        result.map_err(|err| of_security_group(err, security_group_id))?;
+    }
+
+    for modification in &diff.modified {
+        let mut params = group_params();
+        params.insert(
+            "SecurityGroupRuleId".to_owned(),
+            modification.security_group_rule_id.clone(),
+        );
+        params.extend(modification.to.params(""));
+        let action = match modification.to.direction {
+            RuleDirection::Ingress => "ModifySecurityGroupRule",
+            RuleDirection::Egress => "ModifySecurityGroupEgressRule",
+        };
+        let result = client.request::<Value, EcsError>(action, params).await;
+        result.map_err(|err| of_security_group(err, security_group_id))?;
+    }
+
+    for direction in [RuleDirection::Ingress, RuleDirection::Egress] {
+        let mut params = group_params();
+        let added = diff
+            .added
+            .iter()
+            .filter(|added| added.direction == direction);
+        for (index, added) in added.enumerate() {
+            params.extend(added.params(&format!("Permissions.{}.", index + 1)));
+        }
+        if params.len() == 1 {
+            continue;
+        }
+        let action = match direction {
+            RuleDirection::Ingress => "AuthorizeSecurityGroup",
+            RuleDirection::Egress => "AuthorizeSecurityGroupEgress",
+        };
+        let result = client.request::<Value, EcsError>(action, params).await;
+        result.map_err(|err| of_security_group(err, security_group_id))?;
+    }
+    Ok(())
+}
+
+fn backup_key(region_id: &str, security_group_id: &str) -> String {
+    format!("{region_id}/{security_group_id}")
+}
+
+fn load_backups<R: tauri::Runtime>(store: &Store<R>) -> Map<String, Value> {
+    match store.get(RULE_BACKUPS_STORE_KEY) {
+        Some(Value::Object(backups)) => backups,
+        _ => Map::new(),
+    }
+}
+
+fn save_backups<R: tauri::Runtime>(
+    store: &Store<R>,
+    backups: Map<String, Value>,
+) -> Result<(), EcsError> {
+    store.set(RULE_BACKUPS_STORE_KEY, Value::Object(backups));
+    store.save().map_err(|err| EcsError::RuleBackupFailed {
+        message: err.to_string(),
+    })
+}
+
+/// The rules of the group before the last change made from the app, if any.
+pub fn rule_backup<R: tauri::Runtime>(
+    store: &Store<R>,
+    region_id: &str,
+    security_group_id: &str,
+) -> Option<RuleBackup> {
+    let backup = load_backups(store).remove(&backup_key(region_id, security_group_id))?;
+    serde_json::from_value(backup)
+        .inspect_err(|err| log::warn!("Ignoring the rule backup of {security_group_id}: {err}"))
+        .ok()
+}
+
+fn save_rule_backup<R: tauri::Runtime>(
+    store: &Store<R>,
+    region_id: &str,
+    current: &SecurityGroupRules,
+) -> Result<(), EcsError> {
+    let backup = RuleBackup {
+        taken_at: Utc::now().to_rfc3339(),
+        rules: current.rules.iter().map(|rule| rule.rule.clone()).collect(),
+    };
+    let mut backups = load_backups(store);
+    backups.insert(
+        backup_key(region_id, &current.security_group_id),
+        serde_json::to_value(backup).expect("a rule backup is always serializable"),
+    );
+    save_backups(store, backups)
+}
+
+/// Checks the edits against the current rules of the group and tells how they would
+/// change them, nothing is changed.
+pub async fn preview_rule_edits(
+    client: &RegionClient,
+    security_group_id: &str,
+    edits: &[RuleEdit],
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let current = describe_security_group_rules(client, security_group_id).await?;
+    diff_rule_edits(&current, edits).map_err(AliyunRequestCommandError::Specific)
+}
+
+/// Applies the edits, provided they still change the rules the way `preview` says.
+///
+/// The rules are backed up in the store before anything is sent, replacing the previous
+/// backup of the group, so that a change which only partly went through can be rolled
+/// back too.
+pub async fn apply_rule_edits<R: tauri::Runtime>(
+    client: &RegionClient,
+    store: &Store<R>,
+    security_group_id: &str,
+    edits: &[RuleEdit],
+    preview: &RuleSetDiff,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let current = describe_security_group_rules(client, security_group_id).await?;
+    let diff = diff_rule_edits(&current, edits).map_err(AliyunRequestCommandError::Specific)?;
+    if diff != *preview {
+        return Err(AliyunRequestCommandError::Specific(
+            EcsError::RulesChanged {
+                security_group_id: security_group_id.to_owned(),
+            },
+        ));
+    }
+    if diff.is_empty() {
+        return Ok(diff);
+    }
+
+    save_rule_backup(store, client.region_id(), &current)
+        .map_err(AliyunRequestCommandError::Specific)?;
+    send_diff(client, &diff).await?;
+    log::info!(
+        "Changed the rules of {security_group_id}: {} added, {} modified, {} revoked",
+        diff.added.len(),
+        diff.modified.len(),
+        diff.revoked.len()
+    );
+    Ok(diff)
+}
+
+/// Tells how rolling the group back to its backup would change its rules, nothing is
+/// changed.
+pub async fn preview_rollback<R: tauri::Runtime>(
+    client: &RegionClient,
+    store: &Store<R>,
+    security_group_id: &str,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let backup = rule_backup(store, client.region_id(), security_group_id).ok_or_else(|| {
+        AliyunRequestCommandError::Specific(EcsError::NoRuleBackup {
+            security_group_id: security_group_id.to_owned(),
+        })
+    })?;
+    let current = describe_security_group_rules(client, security_group_id).await?;
+    Ok(diff_to_rules(&current, &backup.rules))
+}
+
+/// Brings the rules of the group back to how they were before the last change made from
+/// the app, provided the rollback still does what `preview` says.
+///
+/// The backup is dropped once the rules are back, so a rollback can't be repeated.
+pub async fn rollback_rules<R: tauri::Runtime>(
+    client: &RegionClient,
+    store: &Store<R>,
+    security_group_id: &str,
+    preview: &RuleSetDiff,
+) -> Result<RuleSetDiff, EcsCommandError> {
+    let diff = preview_rollback(client, store, security_group_id).await?;
+    if diff != *preview {
+        return Err(AliyunRequestCommandError::Specific(
+            EcsError::RulesChanged {
+                security_group_id: security_group_id.to_owned(),
+            },
+        ));
+    }
+    send_diff(client, &diff).await?;
+
+    let mut backups = load_backups(store);
+    backups.remove(&backup_key(client.region_id(), security_group_id));
+    save_backups(store, backups).map_err(AliyunRequestCommandError::Specific)?;
+    log::info!("Rolled the rules of {security_group_id} back");
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::{
+        auth::store::store_test_utils,
+        fake::{FakeAliyun, FakeAliyunServer, FAKE_REGIONS},
+    };
+
+    use super::*;
+
+    fn ingress(ip_protocol: &str, port_range: &str, cidr_ip: &str) -> RuleSpec {
+        RuleSpec {
+            direction: RuleDirection::Ingress,
+            ip_protocol: ip_protocol.to_owned(),
+            port_range: port_range.to_owned(),
+            peer: RulePeer::Cidr {
+                cidr_ip: cidr_ip.to_owned(),
+            },
+            policy: RulePolicy::Accept,
+            priority: 1,
+            description: String::new(),
+        }
+    }
+
+    fn specs(rules: &SecurityGroupRules) -> Vec<RuleSpec> {
+        let mut specs = rules
+            .rules
+            .iter()
+            .map(|rule| rule.rule.clone())
+            .collect::<Vec<_>>();
+        specs.sort_by_key(|spec| format!("{spec:?}"));
+        specs
+    }
+
+    #[test]
+    fn test_diff_rule_edits() {
+        let current = SecurityGroupRules {
+            security_group_id: "sg-1".to_owned(),
+            security_group_name: "web".to_owned(),
+            vpc_id: "vpc-1".to_owned(),
+            inner_access_policy: RulePolicy::Accept,
+            rules: vec![SecurityGroupRule {
+                security_group_rule_id: "sgr-1".to_owned(),
+                rule: ingress("TCP", "22/22", "0.0.0.0/0"),
+            }],
+        };
+
+        let diff = diff_rule_edits(
+            &current,
+            &[
+                RuleEdit::Add {
+                    rule: ingress("tcp", "8080", " 10.0.0.0/8 "),
+                },
+                RuleEdit::Add {
+                    rule: ingress("icmp", "", "10.0.0.0/8"),
+                },
+                RuleEdit::Modify {
+                    security_group_rule_id: "sgr-1".to_owned(),
+                    rule: ingress("TCP", "22/22", "0.0.0.0/0"),
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            diff.added,
+            vec![
+                ingress("TCP", "8080/8080", "10.0.0.0/8"),
+                ingress("ICMP", "-1/-1", "10.0.0.0/8"),
+            ]
+        );
+        assert!(diff.modified.is_empty());
+
+        for rule in [
+            ingress("SCTP", "22/22", "0.0.0.0/0"),
+            ingress("TCP", "22/21", "0.0.0.0/0"),
+            ingress("TCP", "0/22", "0.0.0.0/0"),
+            ingress("ICMP", "22/22", "0.0.0.0/0"),
+            ingress("TCP", "22/22", "0.0.0.0/33"),
+            ingress("TCP", "22/22", "::/0"),
+            RuleSpec {
+                priority: 101,
+                ..ingress("TCP", "80/80", "0.0.0.0/0")
+            },
+            RuleSpec {
+                description: "SSH from anywhere".to_owned(),
+                ..ingress("TCP", "22/22", "0.0.0.0/0")
+            },
+        ] {
+            let result = diff_rule_edits(&current, &[RuleEdit::Add { rule }]);
+            assert_matches!(result, Err(EcsError::InvalidRule { .. }));
+        }
+
+        let egress = RuleSpec {
+            direction: RuleDirection::Egress,
+            ..ingress("TCP", "22/22", "0.0.0.0/0")
+        };
+        let result = diff_rule_edits(
+            &current,
+            &[RuleEdit::Modify {
+                security_group_rule_id: "sgr-1".to_owned(),
+                rule: egress,
+            }],
+        );
+        assert_matches!(result, Err(EcsError::InvalidRule { .. }));
+        let revoke = || RuleEdit::Revoke {
+            security_group_rule_id: "sgr-1".to_owned(),
+        };
+        assert_matches!(
+            diff_rule_edits(&current, &[revoke(), revoke()]),
+            Err(EcsError::InvalidRule { .. })
+        );
+        let result = diff_rule_edits(
+            &current,
+            &[RuleEdit::Revoke {
+                security_group_rule_id: "sgr-2".to_owned(),
+            }],
+        );
+        assert_matches!(result, Err(EcsError::RuleNotFound { .. }));
+
+        let diff = diff_to_rules(&current, &[ingress("TCP", "80/80", "0.0.0.0/0")]);
+        assert_eq!(diff.revoked, current.rules);
+        assert_eq!(diff.added, vec![ingress("TCP", "80/80", "0.0.0.0/0")]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_and_rollback() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let client = server.region_client();
+        let store = store_test_utils::init_store();
+
+        let result = describe_security_group_rules(&client, "sg-missing").await;
+        assert_matches!(
+            result,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::SecurityGroupNotFound { .. }
+            ))
+        );
+        let original = describe_security_group_rules(&client, "sg-fake0001")
+            .await
+            .unwrap();
+        assert_eq!(original.vpc_id, "vpc-fake0001");
+        assert_eq!(original.rules.len(), 4);
+        assert_eq!(
+            original.rules[0].rule,
+            RuleSpec {
+                description: "SSH".to_owned(),
+                ..ingress("TCP", "22/22", "0.0.0.0/0")
+            }
+        );
+        assert_eq!(original.rules[3].rule.direction, RuleDirection::Egress);
+
+        let https = original.rules[1].rule.clone();
+        let edits = [
+            RuleEdit::Revoke {
+                security_group_rule_id: "sgr-fake0001".to_owned(),
+            },
+            RuleEdit::Add {
+                rule: RuleSpec {
+                    description: "office".to_owned(),
+                    ..ingress("TCP", "22", "203.0.113.0/24")
+                },
+            },
+            RuleEdit::Modify {
+                security_group_rule_id: "sgr-fake0002".to_owned(),
+                rule: RuleSpec {
+                    priority: 10,
+                    ..https.clone()
+                },
+            },
+        ];
+        let preview = preview_rule_edits(&client, "sg-fake0001", &edits)
+            .await
+            .unwrap();
+        assert_eq!(preview.revoked, vec![original.rules[0].clone()]);
+        assert_eq!(preview.added[0].port_range, "22/22");
+        assert_eq!(preview.modified[0].from, https);
+        assert!(rule_backup(&store, FAKE_REGIONS[0].0, "sg-fake0001").is_none());
+
+        let stale = RuleSetDiff::new("sg-fake0001");
+        let result = apply_rule_edits(&client, &store, "sg-fake0001", &edits, &stale).await;
+        assert_matches!(
+            result,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::RulesChanged { .. }
+            ))
+        );
+        let applied = apply_rule_edits(&client, &store, "sg-fake0001", &edits, &preview)
+            .await
+            .unwrap();
+        assert_eq!(applied, preview);
+        let changed = describe_security_group_rules(&client, "sg-fake0001")
+            .await
+            .unwrap();
+        let mut expected = specs(&original);
+        expected.retain(|spec| spec.port_range != "22/22" && spec.port_range != "443/443");
+        expected.extend([
+            preview.added[0].clone(),
+            RuleSpec {
+                priority: 10,
+                ..https.clone()
+            },
+        ]);
+        expected.sort_by_key(|spec| format!("{spec:?}"));
+        assert_eq!(specs(&changed), expected);
+        let backup = rule_backup(&store, FAKE_REGIONS[0].0, "sg-fake0001").unwrap();
+        assert_eq!(backup.rules.len(), 4);
+
+        let preview = preview_rollback(&client, &store, "sg-fake0001")
+            .await
+            .unwrap();
+        assert_eq!(preview.revoked.len(), 2);
+        assert_eq!(preview.added.len(), 2);
+        let result = rollback_rules(&client, &store, "sg-fake0001", &stale).await;
+        assert_matches!(
+            result,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::RulesChanged { .. }
+            ))
+        );
+        rollback_rules(&client, &store, "sg-fake0001", &preview)
+            .await
+            .unwrap();
+        let rolled_back = describe_security_group_rules(&client, "sg-fake0001")
+            .await
+            .unwrap();
+        assert_eq!(specs(&rolled_back), specs(&original));
+
+        let result = preview_rollback(&client, &store, "sg-fake0001").await;
+        assert_matches!(
+            result,
+            Err(AliyunRequestCommandError::Specific(
+                EcsError::NoRuleBackup { .. }
+            ))
+        );
+    }
+}
@@ -0,0 +1,959 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    ecs::{
+        catalog::{describe_pages, Page},
+        inventory::InstanceTag,
+        launch::{
+            check_amount, check_bandwidth, check_client_token, check_instance_name,
+            check_security_groups, check_tags, create_instances, dry_run_instances, is_system_tag,
+            of_launch, DiskSpec, LaunchResult,
+        },
+        EcsError, SecurityGroupIdList,
+    },
+    error::{AliyunRequestCommandError, RejectionCategory},
+    region::RegionClient,
+    rpc::RpcParams,
+};
+
+/// The largest page of the launch template listings.
+const TEMPLATE_PAGE_SIZE: u32 = 50;
+
+/// A launch template, without its versions.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct LaunchTemplateSummary {
+    pub launch_template_id: String,
+    pub launch_template_name: String,
+    /// The version used when launching without naming one.
+    pub default_version_number: u32,
+    pub latest_version_number: u32,
+    pub modified_time: String,
+}
+
+/// What a version of a launch template sets, everything being optional.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, specta::Type)]
+pub struct TemplateConfig {
+    pub image_id: Option<String>,
+    pub instance_type: Option<String>,
+    pub zone_id: Option<String>,
+    pub vswitch_id: Option<String>,
+    pub security_group_ids: Vec<String>,
+    pub instance_name: Option<String>,
+    pub key_pair_name: Option<String>,
+    pub system_disk: Option<DiskSpec>,
+    pub data_disks: Vec<DiskSpec>,
+    /// `PostPaid` or `PrePaid`.
+    pub charge_type: Option<String>,
+    /// In Mbit/s.
+    pub internet_max_bandwidth_out: Option<u32>,
+    pub tags: Vec<InstanceTag>,
+}
+
+impl TemplateConfig {
+    /// Each setting shown as text, by name, for diffs.
+    fn fields(&self) -> Vec<(&'static str, Option<String>)> {
+        let disk = |disk: &DiskSpec| format!("{} {} GiB", disk.category, disk.size);
+        let list = |items: Vec<String>| (!items.is_empty()).then(|| items.join(", "));
+        vec![
+            ("image_id", self.image_id.clone()),
+            ("instance_type", self.instance_type.clone()),
+            ("zone_id", self.zone_id.clone()),
+            ("vswitch_id", self.vswitch_id.clone()),
+            ("security_group_ids", list(self.security_group_ids.clone())),
+            ("instance_name", self.instance_name.clone()),
+            ("key_pair_name", self.key_pair_name.clone()),
+            ("system_disk", self.system_disk.as_ref().map(disk)),
+            (
+                "data_disks",
+                list(self.data_disks.iter().map(disk).collect()),
+            ),
+            ("charge_type", self.charge_type.clone()),
+            (
+                "internet_max_bandwidth_out",
+                self.internet_max_bandwidth_out
+                    .map(|bandwidth| bandwidth.to_string()),
+            ),
+            (
+                "tags",
+                list(
+                    self.tags
+                        .iter()
+                        .map(|tag| format!("{}={}", tag.key, tag.value))
+                        .collect(),
+                ),
+            ),
+        ]
+    }
+
+    /// The parameters of CreateLaunchTemplateVersion, named like those of RunInstances.
+    fn params(&self) -> RpcParams {
+        let mut params = RpcParams::new();
+        let settings = [
+            ("ImageId", &self.image_id),
+            ("InstanceType", &self.instance_type),
+            ("ZoneId", &self.zone_id),
+            ("VSwitchId", &self.vswitch_id),
+            ("InstanceName", &self.instance_name),
+            ("KeyPairName", &self.key_pair_name),
+            ("InstanceChargeType", &self.charge_type),
+        ];
+        for (name, value) in settings {
+            if let Some(value) = value {
+                params.insert(name.to_owned(), value.clone());
+            }
+        }
+        for (index, security_group_id) in self.security_group_ids.iter().enumerate() {
+            params.insert(
+                format!("SecurityGroupIds.{}", index + 1),
+                security_group_id.clone(),
+            );
+        }
+        if let Some(disk) = &self.system_disk {
+            params.insert("SystemDisk.Category".to_owned(), disk.category.clone());
+            params.insert("SystemDisk.Size".to_owned(), disk.size.to_string());
+        }
+        for (index, disk) in self.data_disks.iter().enumerate() {
+            let n = index + 1;
+            params.insert(format!("DataDisk.{n}.Category"), disk.category.clone());
+            params.insert(format!("DataDisk.{n}.Size"), disk.size.to_string());
+        }
+        if let Some(bandwidth) = self.internet_max_bandwidth_out {
+            params.insert("InternetMaxBandwidthOut".to_owned(), bandwidth.to_string());
+            if bandwidth > 0 {
+                params.insert("InternetChargeType".to_owned(), "PayByTraffic".to_owned());
+            }
+        }
+        for (index, tag) in self.tags.iter().enumerate() {
+            let n = index + 1;
+            params.insert(format!("Tag.{n}.Key"), tag.key.clone());
+            params.insert(format!("Tag.{n}.Value"), tag.value.clone());
+        }
+        params
+    }
+}
+
+/// A version of a launch template.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct LaunchTemplateVersion {
+    pub version_number: u32,
+    pub description: String,
+    pub default_version: bool,
+    pub create_time: String,
+    pub config: TemplateConfig,
+}
+
+/// A setting differing between two versions, `None` when a version doesn't set it.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct ConfigChange {
+    /// The name of the setting in [`TemplateConfig`].
+    pub field: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// How a version of a launch template differs from another.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct VersionDiff {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// The settings which changed, in the order of [`TemplateConfig`].
+    pub changes: Vec<ConfigChange>,
+}
+
+/// The settings differing from `from` to `to`.
+pub fn diff_configs(from: &TemplateConfig, to: &TemplateConfig) -> Vec<ConfigChange> {
+    from.fields()
+        .into_iter()
+        .zip(to.fields())
+        .filter(|((_, from), (_, to))| from != to)
+        .map(|((field, from), (_, to))| ConfigChange {
+            field: field.to_owned(),
+            from,
+            to,
+        })
+        .collect()
+}
+
+/// Launches instances from a version of a template, overriding some of its settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct TemplateLaunch {
+    pub launch_template_id: String,
+    /// The default version when `None`.
+    pub version_number: Option<u32>,
+    pub instance_name: Option<String>,
+    pub instance_type: Option<String>,
+    pub vswitch_id: Option<String>,
+    /// The security groups replacing those of the template, when not empty.
+    pub security_group_ids: Vec<String>,
+    pub key_pair_name: Option<String>,
+    /// In Mbit/s.
+    pub internet_max_bandwidth_out: Option<u32>,
+    /// How many instances to create.
+    pub amount: u32,
+    /// The tags replacing those of the template, when not empty.
+    pub tags: Vec<InstanceTag>,
+}
+
+impl TemplateLaunch {
+    fn check(&self) -> Result<(), AliyunRequestCommandError<EcsError>> {
+        check_instance_name(self.instance_name.as_deref())?;
+        if !self.security_group_ids.is_empty() {
+            check_security_groups(&self.security_group_ids)?;
+        }
+        if let Some(bandwidth) = self.internet_max_bandwidth_out {
+            check_bandwidth(bandwidth)?;
+        }
+        check_amount(self.amount)?;
+        check_tags(&self.tags)
+    }
+
+    /// The parameters of RunInstances, the overrides coming on top of the template.
+    fn params(&self) -> RpcParams {
+        let overrides = TemplateConfig {
+            instance_type: self.instance_type.clone(),
+            vswitch_id: self.vswitch_id.clone(),
+            security_group_ids: self.security_group_ids.clone(),
+            instance_name: self.instance_name.clone(),
+            key_pair_name: self.key_pair_name.clone(),
+            internet_max_bandwidth_out: self.internet_max_bandwidth_out,
+            tags: self.tags.clone(),
+            ..TemplateConfig::default()
+        };
+        let mut params = overrides.params();
+        params.insert(
+            "LaunchTemplateId".to_owned(),
+            self.launch_template_id.clone(),
+        );
+        if let Some(version_number) = self.version_number {
+            params.insert(
+                "LaunchTemplateVersion".to_owned(),
+                version_number.to_string(),
+            );
+        }
+        params.insert("Amount".to_owned(), self.amount.to_string());
+        params
+    }
+}
+
+/// Turns the rejections naming a missing template or version into specific errors.
+fn of_template(
+    err: AliyunRequestCommandError<EcsError>,
+    launch_template_id: &str,
+    version_number: Option<u32>,
+) -> AliyunRequestCommandError<EcsError> {
+    let AliyunRequestCommandError::Rejected(rejection) = &err else {
+        return err;
+    };
+    if rejection.category != RejectionCategory::NotFound {
+        return err;
+    }
+    let launch_template_id = launch_template_id.to_owned();
+    match (rejection.data.code.as_str(), version_number) {
+        ("InvalidLaunchTemplate.NotFound", _) => {
+            AliyunRequestCommandError::Specific(EcsError::TemplateNotFound { launch_template_id })
+        }
+        ("InvalidLaunchTemplateVersion.NotFound", Some(version_number)) => {
+            AliyunRequestCommandError::Specific(EcsError::TemplateVersionNotFound {
+                launch_template_id,
+                version_number,
+            })
+        }
+        _ => err,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedTemplate {
+    launch_template_id: String,
+    #[serde(default)]
+    launch_template_name: String,
+    #[serde(default)]
+    default_version_number: u32,
+    #[serde(default)]
+    latest_version_number: u32,
+    #[serde(default)]
+    modified_time: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct TemplateList {
+    launch_template_set: Vec<DescribedTemplate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedTemplates {
+    #[serde(default)]
+    launch_template_sets: TemplateList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+impl Page for DescribedTemplates {
+    type Item = LaunchTemplateSummary;
+
+    fn total_count(&self) -> u32 {
+        self.total_count
+    }
+
+    fn into_items(self) -> Vec<LaunchTemplateSummary> {
+        self.launch_template_sets
+            .launch_template_set
+            .into_iter()
+            .map(|template| LaunchTemplateSummary {
+                launch_template_id: template.launch_template_id,
+                launch_template_name: template.launch_template_name,
+                default_version_number: template.default_version_number,
+                latest_version_number: template.latest_version_number,
+                modified_time: template.modified_time,
+            })
+            .collect()
+    }
+}
+
+/// The launch templates of the region of `client`.
+pub async fn describe_launch_templates(
+    client: &RegionClient,
+) -> Result<Vec<LaunchTemplateSummary>, AliyunRequestCommandError<EcsError>> {
+    describe_pages::<DescribedTemplates>(
+        client,
+        "DescribeLaunchTemplates",
+        RpcParams::new(),
+        TEMPLATE_PAGE_SIZE,
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TemplateDisk {
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    size: u32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct TemplateDiskList {
+    data_disk: Vec<TemplateDisk>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TemplateTag {
+    key: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct TemplateTagList {
+    instance_tag: Vec<TemplateTag>,
+}
+
+/// The `LaunchTemplateData` of a version, where unset settings are missing or empty.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct TemplateData {
+    image_id: Option<String>,
+    instance_type: Option<String>,
+    zone_id: Option<String>,
+    #[serde(rename = "VSwitchId")]
+    vswitch_id: Option<String>,
+    /// The single security group of older versions.
+    security_group_id: Option<String>,
+    security_group_ids: SecurityGroupIdList,
+    instance_name: Option<String>,
+    key_pair_name: Option<String>,
+    #[serde(rename = "SystemDisk.Category")]
+    system_disk_category: Option<String>,
+    #[serde(rename = "SystemDisk.Size")]
+    system_disk_size: Option<u32>,
+    data_disks: TemplateDiskList,
+    instance_charge_type: Option<String>,
+    internet_max_bandwidth_out: Option<u32>,
+    tags: TemplateTagList,
+}
+
+impl From<TemplateData> for TemplateConfig {
+    fn from(data: TemplateData) -> Self {
+        let set = |value: Option<String>| value.filter(|value| !value.is_empty());
+        let mut security_group_ids = data.security_group_ids.security_group_id;
+        if let Some(security_group_id) = set(data.security_group_id) {
+            if !security_group_ids.contains(&security_group_id) {
+                security_group_ids.insert(0, security_group_id);
+            }
+        }
+        let system_disk = set(data.system_disk_category).map(|category| DiskSpec {
+            category,
+            size: data.system_disk_size.unwrap_or_default(),
+        });
+        Self {
+            image_id: set(data.image_id),
+            instance_type: set(data.instance_type),
+            zone_id: set(data.zone_id),
+            vswitch_id: set(data.vswitch_id),
+            security_group_ids,
+            instance_name: set(data.instance_name),
+            key_pair_name: set(data.key_pair_name),
+            system_disk,
+            data_disks: data
+                .data_disks
+                .data_disk
+                .into_iter()
+                .map(|disk| DiskSpec {
+                    category: disk.category,
+                    size: disk.size,
+                })
+                .collect(),
+            charge_type: set(data.instance_charge_type),
+            internet_max_bandwidth_out: data.internet_max_bandwidth_out,
+            tags: data
+                .tags
+                .instance_tag
+                .into_iter()
+                .map(|tag| InstanceTag {
+                    key: tag.key,
+                    value: tag.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVersion {
+    version_number: u32,
+    #[serde(default)]
+    version_description: String,
+    #[serde(default)]
+    default_version: bool,
+    #[serde(default)]
+    create_time: String,
+    #[serde(default)]
+    launch_template_data: TemplateData,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct VersionList {
+    launch_template_version_set: Vec<DescribedVersion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedVersions {
+    #[serde(default)]
+    launch_template_version_sets: VersionList,
+    #[serde(default)]
+    total_count: u32,
+}
+
+impl Page for DescribedVersions {
+    type Item = LaunchTemplateVersion;
+
+    fn total_count(&self) -> u32 {
+        self.total_count
+    }
+
+    fn into_items(self) -> Vec<LaunchTemplateVersion> {
+        self.launch_template_version_sets
+            .launch_template_version_set
+            .into_iter()
+            .map(|version| LaunchTemplateVersion {
+                version_number: version.version_number,
+                description: version.version_description,
+                default_version: version.default_version,
+                create_time: version.create_time,
+                config: version.launch_template_data.into(),
+            })
+            .collect()
+    }
+}
+
+/// The versions of the template, only the given ones when `version_numbers` isn't empty,
+/// sorted by number.
+pub async fn describe_template_versions(
+    client: &RegionClient,
+    launch_template_id: &str,
+    version_numbers: &[u32],
+) -> Result<Vec<LaunchTemplateVersion>, AliyunRequestCommandError<EcsError>> {
+    let mut params = RpcParams::from([
+        ("LaunchTemplateId".to_owned(), launch_template_id.to_owned()),
+        ("DetailFlag".to_owned(), "true".to_owned()),
+    ]);
+    for (index, version_number) in version_numbers.iter().enumerate() {
+        params.insert(
+            format!("LaunchTemplateVersion.{}", index + 1),
+            version_number.to_string(),
+        );
+    }
+    let mut versions = describe_pages::<DescribedVersions>(
+        client,
+        "DescribeLaunchTemplateVersions",
+        params,
+        TEMPLATE_PAGE_SIZE,
+    )
+    .await
+    .map_err(|err| of_template(err, launch_template_id, None))?;
+    versions.sort_by_key(|version| version.version_number);
+    Ok(versions)
+}
+
+/// How version `to` of the template differs from version `from`.
+pub async fn diff_template_versions(
+    client: &RegionClient,
+    launch_template_id: &str,
+    from: u32,
+    to: u32,
+) -> Result<VersionDiff, AliyunRequestCommandError<EcsError>> {
+    let versions = describe_template_versions(client, launch_template_id, &[from, to]).await?;
+    let config = |version_number: u32| {
+        versions
+            .iter()
+            .find(|version| version.version_number == version_number)
+            .map(|version| &version.config)
+            .ok_or_else(|| {
+                AliyunRequestCommandError::Specific(EcsError::TemplateVersionNotFound {
+                    launch_template_id: launch_template_id.to_owned(),
+                    version_number,
+                })
+            })
+    };
+    Ok(VersionDiff {
+        from_version: from,
+        to_version: to,
+        changes: diff_configs(config(from)?, config(to)?),
+    })
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct VpcAttributes {
+    #[serde(rename = "VSwitchId")]
+    vswitch_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceTagData {
+    tag_key: String,
+    #[serde(default)]
+    tag_value: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct InstanceTagList {
+    tag: Vec<InstanceTagData>,
+}
+
+/// What an instance was launched with, as far as templates go.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceData {
+    #[serde(default)]
+    image_id: String,
+    #[serde(default)]
+    instance_type: String,
+    #[serde(default)]
+    zone_id: String,
+    #[serde(default)]
+    instance_name: String,
+    #[serde(default)]
+    key_pair_name: String,
+    #[serde(default)]
+    vpc_attributes: VpcAttributes,
+    #[serde(default)]
+    security_group_ids: SecurityGroupIdList,
+    #[serde(default)]
+    instance_charge_type: String,
+    #[serde(default)]
+    internet_max_bandwidth_out: u32,
+    #[serde(default)]
+    tags: InstanceTagList,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct InstanceDataList {
+    instance: Vec<InstanceData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedInstanceData {
+    #[serde(default)]
+    instances: InstanceDataList,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DiskData {
+    #[serde(rename = "Type")]
+    disk_type: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    size: u32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct DiskDataList {
+    disk: Vec<DiskData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribedDiskData {
+    #[serde(default)]
+    disks: DiskDataList,
+}
+
+/// The configuration of an instance, its disks included, as a template would set it.
+///
+/// The tags Aliyun set itself are left out, templates can't set them.
+pub async fn instance_config(
+    client: &RegionClient,
+    instance_id: &str,
+) -> Result<TemplateConfig, AliyunRequestCommandError<EcsError>> {
+    let params = RpcParams::from([(
+        "InstanceIds".to_owned(),
+        serde_json::to_string(&[instance_id]).unwrap(),
+    )]);
+    let described: DescribedInstanceData = client.request("DescribeInstances", params).await?;
+    let Some(instance) = described.instances.instance.into_iter().next() else {
+        return Err(AliyunRequestCommandError::Specific(
+            EcsError::InstanceNotFound {
+                instance_id: instance_id.to_owned(),
+            },
+        ));
+    };
+
+    let params = RpcParams::from([("InstanceId".to_owned(), instance_id.to_owned())]);
+    let disks: DescribedDiskData = client.request("DescribeDisks", params).await?;
+    let (system_disks, data_disks): (Vec<_>, Vec<_>) = disks
+        .disks
+        .disk
+        .into_iter()
+        .partition(|disk| disk.disk_type == "system");
+    let spec = |disk: DiskData| DiskSpec {
+        category: disk.category,
+        size: disk.size,
+    };
+
+    let set = |value: String| (!value.is_empty()).then_some(value);
+    Ok(TemplateConfig {
+        image_id: set(instance.image_id),
+        instance_type: set(instance.instance_type),
+        zone_id: set(instance.zone_id),
+        vswitch_id: set(instance.vpc_attributes.vswitch_id),
+        security_group_ids: instance.security_group_ids.security_group_id,
+        instance_name: set(instance.instance_name),
+        key_pair_name: set(instance.key_pair_name),
+        system_disk: system_disks.into_iter().next().map(spec),
+        data_disks: data_disks.into_iter().map(spec).collect(),
+        charge_type: set(instance.instance_charge_type),
+        internet_max_bandwidth_out: Some(instance.internet_max_bandwidth_out),
+        tags: instance
+            .tags
+            .tag
+            .into_iter()
+            .filter(|tag| !is_system_tag(&tag.tag_key))
+            .map(|tag| InstanceTag {
+                key: tag.tag_key,
+                value: tag.tag_value,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreatedVersion {
+    launch_template_version_number: u32,
+}
+
+/// Adds a version to the template with the configuration of the instance, returns its
+/// number.
+///
+/// The instance name is left out, instances launched from the version would all be
+/// named after it otherwise.
+pub async fn create_version_from_instance(
+    client: &RegionClient,
+    launch_template_id: &str,
+    instance_id: &str,
+    description: &str,
+) -> Result<u32, AliyunRequestCommandError<EcsError>> {
+    let config = TemplateConfig {
+        instance_name: None,
+        ..instance_config(client, instance_id).await?
+    };
+    let mut params = config.params();
+    params.insert("LaunchTemplateId".to_owned(), launch_template_id.to_owned());
+    params.insert("VersionDescription".to_owned(), description.to_owned());
+    let created: CreatedVersion = client
+        .request("CreateLaunchTemplateVersion", params)
+        .await
+        .map_err(|err| of_template(err, launch_template_id, None))?;
+    Ok(created.launch_template_version_number)
+}
+
+/// Makes the version the one launched when none is named.
+pub async fn set_default_version(
+    client: &RegionClient,
+    launch_template_id: &str,
+    version_number: u32,
+) -> Result<(), AliyunRequestCommandError<EcsError>> {
+    let params = RpcParams::from([
+        ("LaunchTemplateId".to_owned(), launch_template_id.to_owned()),
+        (
+            "DefaultVersionNumber".to_owned(),
+            version_number.to_string(),
+        ),
+    ]);
+    client
+        .request::<serde_json::Value, EcsError>("ModifyLaunchTemplateDefaultVersion", params)
+        .await
+        .map_err(|err| of_template(err, launch_template_id, Some(version_number)))?;
+    Ok(())
+}
+
+/// Checks the overrides, asks Aliyun with a dry run, then creates the instances from the
+/// template, once per `client_token` like [`super::launch::run_instances`].
+pub async fn launch_from_template(
+    client: &RegionClient,
+    launch: &TemplateLaunch,
+    client_token: &str,
+) -> Result<LaunchResult, AliyunRequestCommandError<EcsError>> {
+    launch.check()?;
+    check_client_token(client_token)?;
+    let map_err = |err| {
+        of_launch(of_template(
+            err,
+            &launch.launch_template_id,
+            launch.version_number,
+        ))
+    };
+    dry_run_instances(client, launch.params())
+        .await
+        .map_err(map_err)?;
+    create_instances(client, launch.params(), client_token)
+        .await
+        .map_err(map_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::{
+        ecs::launch::LaunchField,
+        fake::{FakeAliyun, FakeAliyunServer},
+    };
+
+    use super::*;
+
+    fn launch() -> TemplateLaunch {
+        TemplateLaunch {
+            launch_template_id: "lt-fake0001".to_owned(),
+            version_number: None,
+            instance_name: Some("web-4".to_owned()),
+            instance_type: None,
+            vswitch_id: None,
+            security_group_ids: vec![],
+            key_pair_name: None,
+            internet_max_bandwidth_out: None,
+            amount: 1,
+            tags: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_template_versions() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let client = server.region_client();
+
+        let templates = describe_launch_templates(&client).await.unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].default_version_number, 1);
+        assert_eq!(templates[0].latest_version_number, 2);
+
+        let versions = describe_template_versions(&client, "lt-fake0001", &[])
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0].default_version);
+        let config = &versions[0].config;
+        assert_eq!(config.security_group_ids, ["sg-fake0001"]);
+        assert_eq!(
+            config.system_disk,
+            Some(DiskSpec {
+                category: "cloud_essd".to_owned(),
+                size: 40,
+            })
+        );
+        assert_eq!(config.internet_max_bandwidth_out, Some(5));
+
+        let diff = diff_template_versions(&client, "lt-fake0001", 1, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            diff.changes,
+            [ConfigChange {
+                field: "instance_type".to_owned(),
+                from: Some("ecs.g7.large".to_owned()),
+                to: Some("ecs.r7.xlarge".to_owned()),
+            }]
+        );
+        let err = diff_template_versions(&client, "lt-fake0001", 1, 7)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::TemplateVersionNotFound {
+                version_number: 7,
+                ..
+            })
+        );
+        let err = describe_template_versions(&client, "lt-none", &[])
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::TemplateNotFound { .. })
+        );
+
+        // db-1 differs from the second version by its zone, key pair, billing and tags.
+        let version_number =
+            create_version_from_instance(&client, "lt-fake0001", "i-fake0003", "from db-1")
+                .await
+                .unwrap();
+        assert_eq!(version_number, 3);
+        let diff = diff_template_versions(&client, "lt-fake0001", 2, 3)
+            .await
+            .unwrap();
+        let changed = diff
+            .changes
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed,
+            [
+                "zone_id",
+                "key_pair_name",
+                "charge_type",
+                "internet_max_bandwidth_out",
+                "tags"
+            ]
+        );
+
+        set_default_version(&client, "lt-fake0001", 3)
+            .await
+            .unwrap();
+        let versions = describe_template_versions(&client, "lt-fake0001", &[3])
+            .await
+            .unwrap();
+        assert!(versions[0].default_version);
+        let err = set_default_version(&client, "lt-fake0001", 9)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::TemplateVersionNotFound {
+                version_number: 9,
+                ..
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launch_from_template() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let fake = server.fake();
+        let client = server.region_client();
+
+        let result = launch_from_template(&client, &launch(), "template-1")
+            .await
+            .unwrap();
+        assert_eq!(result.instance_ids, ["i-fake0004"]);
+        let instance = fake.instance("i-fake0004").unwrap();
+        assert_eq!(instance.instance_name, "web-4");
+        assert_eq!(instance.instance_type, "ecs.g7.large");
+        assert_eq!(instance.zone_id, "cn-hangzhou-h");
+
+        let bigger = TemplateLaunch {
+            version_number: Some(2),
+            instance_name: None,
+            amount: 2,
+            tags: vec![InstanceTag {
+                key: "app".to_owned(),
+                value: "batch".to_owned(),
+            }],
+            ..launch()
+        };
+        let result = launch_from_template(&client, &bigger, "template-2")
+            .await
+            .unwrap();
+        assert_eq!(result.instance_ids, ["i-fake0005", "i-fake0006"]);
+        let instance = fake.instance("i-fake0006").unwrap();
+        assert_eq!(instance.instance_type, "ecs.r7.xlarge");
+        assert_eq!(instance.tags, [("app".to_owned(), "batch".to_owned())]);
+        let again = launch_from_template(&client, &bigger, "template-2")
+            .await
+            .unwrap();
+        assert_eq!(again, result);
+
+        let overridden = TemplateLaunch {
+            instance_type: Some("ecs.g7.xlarge".to_owned()),
+            ..launch()
+        };
+        let err = launch_from_template(&client, &overridden, "template-3")
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::LaunchRejected {
+                field: LaunchField::InstanceType,
+                ..
+            })
+        );
+        let missing = TemplateLaunch {
+            version_number: Some(5),
+            ..launch()
+        };
+        let err = launch_from_template(&client, &missing, "template-4")
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::TemplateVersionNotFound {
+                version_number: 5,
+                ..
+            })
+        );
+        let invalid = TemplateLaunch {
+            amount: 0,
+            ..launch()
+        };
+        let err = launch_from_template(&client, &invalid, "template-5")
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            AliyunRequestCommandError::Specific(EcsError::InvalidLaunchSpec {
+                field: LaunchField::Amount,
+                ..
+            })
+        );
+        assert_eq!(fake.instances.lock().unwrap().len(), 6);
+    }
+}
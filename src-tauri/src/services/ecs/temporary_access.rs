@@ -0,0 +1,558 @@
+use std::{
+    net::IpAddr,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    services::{
+        auth::chain::CredentialSource,
+        client::AliyunClientService,
+        ecs::{
+            security_group::{
+                describe_security_group_rules, send_diff, RuleDirection, RuleModification,
+                RulePeer, RulePolicy, RuleSetDiff, RuleSpec,
+            },
+            EcsCommandError, EcsError,
+        },
+        error::AliyunRequestCommandError,
+        region::RegionClient,
+    },
+    types::Store,
+};
+
+/// The key of the security groups which may have temporary access rules, in the store.
+pub(crate) const TEMPORARY_ACCESS_STORE_KEY: &str = "temporary_access_groups";
+
+/// Starts the description of the temporary access rules, followed by their expiry.
+pub const TEMPORARY_ACCESS_TAG: &str = "[temporary access]";
+
+/// The longest a temporary access rule lasts.
+pub const MAX_ACCESS_MINUTES: u32 = 24 * 60;
+
+const PUBLIC_IP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Held while the tracked groups are read, changed and saved, so that a grant and a sweep
+/// don't overwrite each other.
+static TRACKED_GROUPS_LOCK: Mutex<()> = Mutex::new(());
+
+/// An ingress rule letting an IP reach a port until it expires.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct TemporaryAccess {
+    pub region_id: String,
+    pub security_group_id: String,
+    pub rule: RuleSpec,
+    /// In RFC 3339.
+    pub expires_at: String,
+}
+
+/// Lets an IP reach a TCP port of the instances of a group for a while.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessGrant {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub minutes: u32,
+}
+
+/// A security group where temporary access rules have been added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TrackedGroup {
+    region_id: String,
+    security_group_id: String,
+    /// The credentials the rules were added with, the only ones they are revoked with.
+    /// `None` for the groups recorded before it was.
+    #[serde(default)]
+    owner: Option<CredentialSource>,
+    /// When the last access granted expires, `None` for the groups recorded before it was.
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl TrackedGroup {
+    fn is_owned_by(&self, source: &CredentialSource) -> bool {
+        self.owner.as_ref().is_none_or(|owner| owner == source)
+    }
+
+    /// Whether both are the same group recorded with the same credentials.
+    fn is_same(&self, other: &Self) -> bool {
+        self.region_id == other.region_id
+            && self.security_group_id == other.security_group_id
+            && self.owner == other.owner
+    }
+}
+
+/// Asks the public IP of the machine to a service answering it as plain text.
+#[derive(Clone)]
+pub struct PublicIpProvider {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl PublicIpProvider {
+    pub fn new<U: Into<String>>(url: U) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(PUBLIC_IP_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            url: url.into(),
+        }
+    }
+
+    pub async fn public_ip(&self) -> Result<IpAddr, EcsError> {
+        let unknown = |message: String| EcsError::PublicIpUnknown { message };
+        let response = self
+            .http
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|err| unknown(format!("{} didn't answer: {err}", self.url)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(unknown(format!("{} responded with {status}", self.url)));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|err| unknown(format!("failed to read the answer of {}: {err}", self.url)))?;
+        body.trim().parse().map_err(|_| {
+            unknown(format!(
+                "{} answered {:?}, not an IP",
+                self.url,
+                body.trim()
+            ))
+        })
+    }
+}
+
+/// The description of a temporary access rule expiring at `expires_at`.
+pub fn temporary_description(expires_at: DateTime<Utc>) -> String {
+    format!(
+        "{TEMPORARY_ACCESS_TAG} until {}",
+        expires_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+    )
+}
+
+/// When the rule expires, `None` when it is not a temporary access rule.
+pub fn expiry_of(description: &str) -> Option<DateTime<Utc>> {
+    let expires_at = description
+        .strip_prefix(TEMPORARY_ACCESS_TAG)?
+        .trim()
+        .strip_prefix("until ")?;
+    DateTime::parse_from_rfc3339(expires_at.trim())
+        .ok()
+        .map(|expires_at| expires_at.with_timezone(&Utc))
+}
+
+/// The rule letting `ip`, and only it, reach the TCP port.
+fn temporary_rule(ip: IpAddr, port: u16, expires_at: DateTime<Utc>) -> RuleSpec {
+    let peer = match ip {
+        IpAddr::V4(ip) => RulePeer::Cidr {
+            cidr_ip: format!("{ip}/32"),
+        },
+        IpAddr::V6(ip) => RulePeer::Ipv6Cidr {
+            ipv6_cidr_ip: format!("{ip}/128"),
+        },
+    };
+    RuleSpec {
+        direction: RuleDirection::Ingress,
+        ip_protocol: "TCP".to_owned(),
+        port_range: format!("{port}/{port}"),
+        peer,
+        policy: RulePolicy::Accept,
+        priority: 1,
+        description: temporary_description(expires_at),
+    }
+}
+
+fn tracked_groups<R: tauri::Runtime>(store: &Store<R>) -> Vec<TrackedGroup> {
+    store
+        .get(TEMPORARY_ACCESS_STORE_KEY)
+        .and_then(|groups| {
+            serde_json::from_value(groups)
+                .inspect_err(|err| log::warn!("Ignoring the temporary access groups: {err}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the tracked groups, changes them with `update` and saves them, under
+/// [`TRACKED_GROUPS_LOCK`].
+fn update_tracked_groups<R: tauri::Runtime, F: FnOnce(&mut Vec<TrackedGroup>)>(
+    store: &Store<R>,
+    update: F,
+) -> Result<(), EcsError> {
+    let _guard = TRACKED_GROUPS_LOCK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut groups = tracked_groups(store);
+    update(&mut groups);
+    save_tracked_groups(store, &groups)
+}
+
+fn save_tracked_groups<R: tauri::Runtime>(
+    store: &Store<R>,
+    groups: &[TrackedGroup],
+) -> Result<(), EcsError> {
+    let groups = serde_json::to_value(groups).expect("the groups are always serializable");
+    store.set(TEMPORARY_ACCESS_STORE_KEY, groups);
+    store
+        .save()
+        .map_err(|err| EcsError::TemporaryAccessNotRecorded {
+            message: err.to_string(),
+        })
+}
+
+/// Grants the access to the instances of the group, `client` using the credentials of
+/// `owner`.
+///
+/// A temporary access rule of the IP to the port is extended rather than duplicated. The
+/// group and the expiry of the access are recorded in the store before the rule is added,
+/// so that the rule is revoked once expired even after the app restarts.
+pub async fn allow_ip<R: tauri::Runtime>(
+    client: &RegionClient,
+    owner: &CredentialSource,
+    store: &Store<R>,
+    security_group_id: &str,
+    grant: &AccessGrant,
+    now: DateTime<Utc>,
+) -> Result<TemporaryAccess, EcsCommandError> {
+    let AccessGrant { ip, port, minutes } = *grant;
+    let invalid =
+        |reason: String| AliyunRequestCommandError::Specific(EcsError::InvalidRule { reason });
+    if port == 0 {
+        return Err(invalid("the port must be from 1 to 65535".to_owned()));
+    }
+    if !(1..=MAX_ACCESS_MINUTES).contains(&minutes) {
+        return Err(invalid(format!(
+            "the access must last from 1 to {MAX_ACCESS_MINUTES} minutes, not {minutes}"
+        )));
+    }
+
+    let expires_at = now + TimeDelta::minutes(minutes.into());
+    let rule = temporary_rule(ip, port, expires_at)
+        .normalized()
+        .map_err(AliyunRequestCommandError::Specific)?;
+    let current = describe_security_group_rules(client, security_group_id).await?;
+    let mut diff = RuleSetDiff::new(security_group_id);
+    match current
+        .rules
+        .iter()
+        .find(|existing| existing.rule.same_traffic(&rule))
+    {
+        Some(existing) if expiry_of(&existing.rule.description).is_some() => {
+            diff.modified.push(RuleModification {
+                security_group_rule_id: existing.security_group_rule_id.clone(),
+                from: existing.rule.clone(),
+                to: rule.clone(),
+            });
+        }
+        Some(_) => {
+            return Err(invalid(format!(
+                "{ip} can already reach the port {port} for good"
+            )))
+        }
+        None => diff.added.push(rule.clone()),
+    }
+
+    let region_id = client.region_id();
+    let group = TrackedGroup {
+        region_id: region_id.to_owned(),
+        security_group_id: security_group_id.to_owned(),
+        owner: Some(owner.clone()),
+        expires_at: Some(expires_at),
+    };
+    update_tracked_groups(store, |groups| {
+        match groups.iter_mut().find(|tracked| tracked.is_same(&group)) {
+            Some(tracked) => tracked.expires_at = tracked.expires_at.max(group.expires_at),
+            None => groups.push(group),
+        }
+    })
+    .map_err(AliyunRequestCommandError::Specific)?;
+    send_diff(client, &diff).await?;
+    log::info!("Letting {ip} reach the port {port} of {security_group_id} until {expires_at}");
+    Ok(TemporaryAccess {
+        region_id: region_id.to_owned(),
+        security_group_id: security_group_id.to_owned(),
+        rule,
+        expires_at: expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+    })
+}
+
+/// What sweeping a group did.
+enum Sweep {
+    /// The group doesn't exist, at least not with the credentials in use.
+    Gone,
+    Swept {
+        revoked: Vec<TemporaryAccess>,
+        /// Whether the group has temporary access rules left, to be revoked later.
+        left: bool,
+    },
+}
+
+/// Revokes the expired temporary access rules of the group.
+async fn sweep_group(
+    client: &RegionClient,
+    security_group_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Sweep, String> {
+    let current = match describe_security_group_rules(client, security_group_id).await {
+        Ok(current) => current,
+        Err(AliyunRequestCommandError::Specific(EcsError::SecurityGroupNotFound { .. })) => {
+            return Ok(Sweep::Gone)
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+    let mut diff = RuleSetDiff::new(security_group_id);
+    let mut revoked = Vec::new();
+    let mut left = false;
+    for rule in current.rules {
+        match expiry_of(&rule.rule.description) {
+            Some(expires_at) if expires_at <= now => {
+                revoked.push(TemporaryAccess {
+                    region_id: client.region_id().to_owned(),
+                    security_group_id: security_group_id.to_owned(),
+                    rule: rule.rule.clone(),
+                    expires_at: expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                });
+                diff.revoked.push(rule);
+            }
+            Some(_) => left = true,
+            None => {}
+        }
+    }
+    if !diff.is_empty() {
+        send_diff(client, &diff)
+            .await
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(Sweep::Swept { revoked, left })
+}
+
+/// Removes the groups swept clean from `groups`, unless access was granted to them since,
+/// expiring after `now`.
+fn forget_done(groups: &mut Vec<TrackedGroup>, done: &[TrackedGroup], now: DateTime<Utc>) {
+    groups.retain(|group| {
+        group.expires_at.is_some_and(|expires_at| expires_at > now)
+            || !done.iter().any(|swept| swept.is_same(group))
+    });
+}
+
+/// Revokes the temporary access rules expired at `now`, in every group recorded in the
+/// store with the credentials in use, and returns them.
+///
+/// A group is forgotten once it has no temporary access rules left, or when it doesn't
+/// exist for the credentials it was recorded with. The groups recorded with other
+/// credentials, and those of the regions which can't be reached, are left for a later
+/// sweep.
+pub async fn revoke_expired_access<R: tauri::Runtime>(
+    client_service: &AliyunClientService,
+    store: &Store<R>,
+    now: DateTime<Utc>,
+) -> Vec<TemporaryAccess> {
+    let Some(source) = client_service.source() else {
+        return Vec::new();
+    };
+    let mut revoked = Vec::new();
+    let mut done = Vec::new();
+    for group in tracked_groups(store) {
+        if !group.is_owned_by(&source) {
+            continue;
+        }
+        let Some(client) = client_service.region_client(Some(&group.region_id)) else {
+            continue;
+        };
+        match sweep_group(&client, &group.security_group_id, now).await {
+            // Not knowing whose it was, the group may exist for other credentials.
+            Ok(Sweep::Gone) if group.owner.is_none() => {}
+            Ok(Sweep::Gone) => {
+                log::info!(
+                    "Forgetting the temporary access to {}, which doesn't exist",
+                    group.security_group_id
+                );
+                done.push(group);
+            }
+            Ok(Sweep::Swept {
+                revoked: swept,
+                left,
+            }) => {
+                revoked.extend(swept);
+                if !left {
+                    done.push(group);
+                }
+            }
+            Err(message) => log::warn!(
+                "Failed to revoke the expired access to {}: {message}",
+                group.security_group_id
+            ),
+        }
+    }
+
+    if !done.is_empty() {
+        // Read again, a group may have been added or granted access meanwhile.
+        let forgotten = update_tracked_groups(store, |groups| forget_done(groups, &done, now));
+        if let Err(err) = forgotten {
+            log::warn!("Failed to save the temporary access groups: {err}");
+        }
+    }
+    for access in &revoked {
+        log::info!(
+            "Revoked the access to {} expired at {}",
+            access.security_group_id,
+            access.expires_at
+        );
+    }
+    revoked
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        services::{
+            auth::store::store_test_utils,
+            ecs::security_group::RuleDirection,
+            fake::{fake_credentials, FAKE_PUBLIC_IP},
+        },
+        test_utils::start_fake_client,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_expiry_of() {
+        let expires_at = DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let description = temporary_description(expires_at);
+        assert_eq!(description, "[temporary access] until 2026-10-18T10:00:00Z");
+        assert_eq!(expiry_of(&description), Some(expires_at));
+        assert_eq!(expiry_of("SSH"), None);
+        assert_eq!(expiry_of("[temporary access] until tomorrow"), None);
+    }
+
+    #[test]
+    fn test_forget_done() {
+        let now = Utc::now();
+        let group = |security_group_id: &str, expires_at| TrackedGroup {
+            region_id: "cn-hangzhou".to_owned(),
+            security_group_id: security_group_id.to_owned(),
+            owner: Some(CredentialSource::Demo),
+            expires_at,
+        };
+        let swept = group("sg-swept", Some(now - TimeDelta::minutes(1)));
+        let granted = group("sg-granted", Some(now - TimeDelta::minutes(1)));
+        let mut groups = vec![
+            swept.clone(),
+            // Granted access while the sweep ran.
+            group("sg-granted", Some(now + TimeDelta::minutes(10))),
+            group("sg-other", None),
+        ];
+
+        forget_done(&mut groups, &[swept, granted], now);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| group.security_group_id.as_str())
+                .collect::<Vec<_>>(),
+            ["sg-granted", "sg-other"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_allow_and_revoke() {
+        let (server, client_service) = start_fake_client().await;
+        let client = server.region_client();
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("store.json");
+        let store = store_test_utils::init_store_at(&path);
+
+        let ip = PublicIpProvider::new(server.public_ip_url())
+            .public_ip()
+            .await
+            .unwrap();
+        assert_eq!(ip.to_string(), FAKE_PUBLIC_IP);
+        let result = PublicIpProvider::new(server.uri()).public_ip().await;
+        assert_matches!(result, Err(EcsError::PublicIpUnknown { .. }));
+
+        let now = Utc::now();
+        let temporary_rules = || async {
+            describe_security_group_rules(&client, "sg-fake0001")
+                .await
+                .unwrap()
+                .rules
+                .into_iter()
+                .filter(|rule| expiry_of(&rule.rule.description).is_some())
+                .collect::<Vec<_>>()
+        };
+        let owner = client_service.source().unwrap();
+        let allow = |port, minutes, now| {
+            let grant = AccessGrant { ip, port, minutes };
+            let (client, owner, store) = (&client, &owner, &store);
+            async move { allow_ip(client, owner, store, "sg-fake0001", &grant, now).await }
+        };
+        for (port, minutes) in [(0, 60), (22, 0), (22, MAX_ACCESS_MINUTES + 1)] {
+            let result = allow(port, minutes, now).await;
+            assert_matches!(
+                result,
+                Err(AliyunRequestCommandError::Specific(
+                    EcsError::InvalidRule { .. }
+                ))
+            );
+        }
+
+        let access = allow(22, 60, now).await.unwrap();
+        assert_eq!(access.rule.direction, RuleDirection::Ingress);
+        assert_eq!(access.rule.port_range, "22/22");
+        assert_eq!(
+            access.rule.peer,
+            RulePeer::Cidr {
+                cidr_ip: format!("{FAKE_PUBLIC_IP}/32")
+            }
+        );
+        // Allowing again extends the access rather than adding another rule.
+        let later = now + TimeDelta::minutes(30);
+        let extended = allow(22, 60, later).await.unwrap();
+        let rules = temporary_rules().await;
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].rule, extended.rule);
+        assert_eq!(
+            tracked_groups(&store)[0].expires_at,
+            Some(later + TimeDelta::minutes(60))
+        );
+        allow(3389, 10, now).await.unwrap();
+        assert_eq!(temporary_rules().await.len(), 2);
+
+        let revoked =
+            revoke_expired_access(&client_service, &store, now + TimeDelta::minutes(20)).await;
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].rule.port_range, "3389/3389");
+        assert_eq!(temporary_rules().await.len(), 1);
+        assert_eq!(tracked_groups(&store).len(), 1);
+
+        // Other credentials leave the group alone, even where they see it.
+        client_service.initialize(fake_credentials().into(), CredentialSource::Environment);
+        let revoked =
+            revoke_expired_access(&client_service, &store, now + TimeDelta::hours(2)).await;
+        assert!(revoked.is_empty());
+        assert_eq!(temporary_rules().await.len(), 1);
+        assert_eq!(tracked_groups(&store).len(), 1);
+        client_service.initialize(fake_credentials().into(), owner.clone());
+
+        // The rule left behind is still revoked by the next run of the app.
+        drop(store);
+        let store = store_test_utils::init_store_at(&path);
+        let revoked =
+            revoke_expired_access(&client_service, &store, now + TimeDelta::hours(2)).await;
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].rule.port_range, "22/22");
+        assert!(temporary_rules().await.is_empty());
+        assert!(tracked_groups(&store).is_empty());
+    }
+}
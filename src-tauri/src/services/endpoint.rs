@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    services::{
+        auth::{
+            metadata::DEFAULT_METADATA_ENDPOINT, rotation::DEFAULT_RAM_ENDPOINT,
+            sts::DEFAULT_STS_ENDPOINT,
+        },
+        region::{ecs_endpoint, is_valid_region_id, DEFAULT_REGION_ID},
+    },
+    types::Store,
+};
+
+/// The key of the endpoint configuration in the store.
+pub(crate) const ENDPOINT_CONFIG_STORE_KEY: &str = "endpoint_config";
+
+/// The environment variable sending every request to another base URL, taking precedence
+/// over the saved configuration, e.g. `http://127.0.0.1:8080` for a local stand-in.
+pub const ENDPOINT_BASE_URL_ENV: &str = "ALIYUN_ENDPOINT_BASE_URL";
+
+/// The environment variable replacing the URL the public IP of the machine is asked to,
+/// taking precedence over the saved configuration.
+pub const PUBLIC_IP_URL_ENV: &str = "PUBLIC_IP_URL";
+
+/// The environment variable replacing the URL of the ECS instance metadata service,
+/// taking precedence over the saved configuration.
+pub const METADATA_URL_ENV: &str = "ECS_METADATA_URL";
+
+/// Answers the public IP of the caller, as plain text.
+pub const DEFAULT_PUBLIC_IP_URL: &str = "https://api.ipify.org";
+
+/// The Aliyun services the app talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum AliyunService {
+    Ecs,
+    /// The networks, VPCs and vSwitches, which ECS only refers to.
+    Vpc,
+    Sts,
+    Ram,
+}
+
+/// An endpoint replacing the default one of a service, in a single region or in all of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct EndpointOverride {
+    pub service: AliyunService,
+    /// The region the override applies to, all of them when `None`.
+    pub region_id: Option<String>,
+    /// A host name, or a URL when the scheme isn't `https`.
+    pub endpoint: String,
+}
+
+/// Where the requests to each service are sent.
+///
+/// From the most to the least specific: the base URL, an override for the service in the
+/// region, an override for the service, then the default public or VPC endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct EndpointConfig {
+    /// Sends the requests of every service to this URL, e.g. a local stand-in server.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Uses the VPC endpoints, only reachable from within Aliyun.
+    #[serde(default)]
+    pub use_vpc: bool,
+    #[serde(default)]
+    pub overrides: Vec<EndpointOverride>,
+    /// Where the public IP of the machine is asked, [`DEFAULT_PUBLIC_IP_URL`] when `None`.
+    #[serde(default)]
+    pub public_ip_url: Option<String>,
+    /// Where the credentials of the instance RAM role are asked,
+    /// [`DEFAULT_METADATA_ENDPOINT`] when `None`. It is read at startup.
+    #[serde(default)]
+    pub metadata_url: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum EndpointConfigError {
+    #[error("the endpoint {0:?} is not a valid host name or URL")]
+    InvalidEndpoint(String),
+    #[error("the public IP URL {0:?} is not an http(s) URL")]
+    InvalidPublicIpUrl(String),
+    #[error("the metadata URL {0:?} is not an http(s) URL")]
+    InvalidMetadataUrl(String),
+    #[error("the region ID {0:?} is not valid")]
+    InvalidRegion(String),
+    #[error("failed to save the endpoint configuration: {0}")]
+    SaveFailed(String),
+}
+
+/// Whether the endpoint is a host name, with an optional port, or an `http(s)` URL.
+fn is_valid_endpoint(endpoint: &str) -> bool {
+    let host = endpoint
+        .strip_prefix("https://")
+        .or_else(|| endpoint.strip_prefix("http://"))
+        .unwrap_or(endpoint)
+        .trim_end_matches('/');
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-.:_[]/".contains(c))
+}
+
+fn is_valid_http_url(url: &str) -> bool {
+    (url.starts_with("http://") || url.starts_with("https://")) && is_valid_endpoint(url)
+}
+
+impl EndpointConfig {
+    /// Builds a configuration sending everything to `base_url`.
+    pub fn with_base_url<U: Into<String>>(base_url: U) -> Self {
+        Self {
+            base_url: Some(base_url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// The endpoint of the service in the region, the global one of STS when `None`.
+    pub fn resolve(&self, service: AliyunService, region_id: Option<&str>) -> String {
+        if let Some(base_url) = &self.base_url {
+            return base_url.clone();
+        }
+
+        let overridden = |region: Option<&str>| {
+            self.overrides
+                .iter()
+                .find(|o| o.service == service && o.region_id.as_deref() == region)
+        };
+        if let Some(o) = region_id.and_then(|region_id| overridden(Some(region_id))) {
+            return o.endpoint.clone();
+        }
+        if let Some(o) = overridden(None) {
+            return o.endpoint.clone();
+        }
+
+        match (service, self.use_vpc) {
+            (AliyunService::Ecs, false) => ecs_endpoint(region_id.unwrap_or(DEFAULT_REGION_ID)),
+            (AliyunService::Ecs, true) => {
+                format!(
+                    "ecs-vpc.{}.aliyuncs.com",
+                    region_id.unwrap_or(DEFAULT_REGION_ID)
+                )
+            }
+            (AliyunService::Vpc, false) => {
+                format!(
+                    "vpc.{}.aliyuncs.com",
+                    region_id.unwrap_or(DEFAULT_REGION_ID)
+                )
+            }
+            (AliyunService::Vpc, true) => {
+                format!(
+                    "vpc-vpc.{}.aliyuncs.com",
+                    region_id.unwrap_or(DEFAULT_REGION_ID)
+                )
+            }
+            (AliyunService::Sts, false) => match region_id {
+                Some(region_id) => format!("sts.{region_id}.aliyuncs.com"),
+                None => DEFAULT_STS_ENDPOINT.to_owned(),
+            },
+            (AliyunService::Sts, true) => {
+                format!(
+                    "sts-vpc.{}.aliyuncs.com",
+                    region_id.unwrap_or(DEFAULT_REGION_ID)
+                )
+            }
+            (AliyunService::Ram, false) => DEFAULT_RAM_ENDPOINT.to_owned(),
+            (AliyunService::Ram, true) => "ram.vpc-proxy.aliyuncs.com".to_owned(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), EndpointConfigError> {
+        let endpoints = self
+            .base_url
+            .iter()
+            .chain(self.overrides.iter().map(|o| &o.endpoint));
+        for endpoint in endpoints {
+            if !is_valid_endpoint(endpoint) {
+                return Err(EndpointConfigError::InvalidEndpoint(endpoint.clone()));
+            }
+        }
+        for region_id in self.overrides.iter().filter_map(|o| o.region_id.as_ref()) {
+            if !is_valid_region_id(region_id) {
+                return Err(EndpointConfigError::InvalidRegion(region_id.clone()));
+            }
+        }
+        if let Some(url) = &self.public_ip_url {
+            if !is_valid_http_url(url) {
+                return Err(EndpointConfigError::InvalidPublicIpUrl(url.clone()));
+            }
+        }
+        if let Some(url) = &self.metadata_url {
+            if !is_valid_http_url(url) {
+                return Err(EndpointConfigError::InvalidMetadataUrl(url.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The URL the public IP of the machine is asked to.
+    pub fn public_ip_url(&self) -> &str {
+        self.public_ip_url
+            .as_deref()
+            .unwrap_or(DEFAULT_PUBLIC_IP_URL)
+    }
+
+    /// The URL of the ECS instance metadata service.
+    pub fn metadata_url(&self) -> &str {
+        self.metadata_url
+            .as_deref()
+            .unwrap_or(DEFAULT_METADATA_ENDPOINT)
+    }
+
+    /// Applies [`ENDPOINT_BASE_URL_ENV`], [`PUBLIC_IP_URL_ENV`] and [`METADATA_URL_ENV`]
+    /// when they are set.
+    pub fn with_env_overrides(mut self) -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        if let Some(base_url) = var(ENDPOINT_BASE_URL_ENV) {
+            log::info!("Sending every Aliyun request to {base_url}");
+            self.base_url = Some(base_url);
+        }
+        if let Some(public_ip_url) = var(PUBLIC_IP_URL_ENV) {
+            log::info!("Asking the public IP to {public_ip_url}");
+            self.public_ip_url = Some(public_ip_url);
+        }
+        if let Some(metadata_url) = var(METADATA_URL_ENV) {
+            log::info!("Asking the instance metadata to {metadata_url}");
+            self.metadata_url = Some(metadata_url);
+        }
+        self
+    }
+
+    /// Reads the saved configuration, the default one when there is none or it is invalid.
+    pub fn load<R: tauri::Runtime>(store: &Store<R>) -> Self {
+        let Some(value) = store.get(ENDPOINT_CONFIG_STORE_KEY) else {
+            return Self::default();
+        };
+        serde_json::from_value(value)
+            .inspect_err(|err| log::warn!("Ignoring the saved endpoint configuration: {err}"))
+            .unwrap_or_default()
+    }
+
+    pub fn save<R: tauri::Runtime>(&self, store: &Store<R>) -> Result<(), EndpointConfigError> {
+        self.validate()?;
+        let value = serde_json::to_value(self)
+            .map_err(|err| EndpointConfigError::SaveFailed(err.to_string()))?;
+        store.set(ENDPOINT_CONFIG_STORE_KEY, value);
+        store
+            .save()
+            .map_err(|err| EndpointConfigError::SaveFailed(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    use crate::services::auth::store::store_test_utils;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let config = EndpointConfig::default();
+        assert_eq!(
+            config.resolve(AliyunService::Ecs, Some("cn-shanghai")),
+            "ecs.cn-shanghai.aliyuncs.com"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Vpc, Some("cn-shanghai")),
+            "vpc.cn-shanghai.aliyuncs.com"
+        );
+        assert_eq!(config.resolve(AliyunService::Sts, None), "sts.aliyuncs.com");
+        assert_eq!(config.resolve(AliyunService::Ram, None), "ram.aliyuncs.com");
+
+        let mut config = EndpointConfig {
+            use_vpc: true,
+            ..EndpointConfig::default()
+        };
+        assert_eq!(
+            config.resolve(AliyunService::Ecs, Some("cn-shanghai")),
+            "ecs-vpc.cn-shanghai.aliyuncs.com"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Vpc, Some("cn-shanghai")),
+            "vpc-vpc.cn-shanghai.aliyuncs.com"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Sts, Some("cn-beijing")),
+            "sts-vpc.cn-beijing.aliyuncs.com"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Ram, None),
+            "ram.vpc-proxy.aliyuncs.com"
+        );
+
+        config.overrides = vec![
+            EndpointOverride {
+                service: AliyunService::Ecs,
+                region_id: None,
+                endpoint: "ecs.example.com".to_owned(),
+            },
+            EndpointOverride {
+                service: AliyunService::Ecs,
+                region_id: Some("cn-shanghai".to_owned()),
+                endpoint: "http://127.0.0.1:9000".to_owned(),
+            },
+        ];
+        assert_eq!(
+            config.resolve(AliyunService::Ecs, Some("cn-shanghai")),
+            "http://127.0.0.1:9000"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Ecs, Some("cn-beijing")),
+            "ecs.example.com"
+        );
+        assert_eq!(
+            config.resolve(AliyunService::Sts, Some("cn-beijing")),
+            "sts-vpc.cn-beijing.aliyuncs.com"
+        );
+
+        config.base_url = Some("http://localhost:8080".to_owned());
+        for service in [AliyunService::Ecs, AliyunService::Sts, AliyunService::Ram] {
+            assert_eq!(
+                config.resolve(service, Some("cn-shanghai")),
+                "http://localhost:8080"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_and_save() {
+        let store = store_test_utils::init_store();
+        assert_eq!(EndpointConfig::load(&store), EndpointConfig::default());
+
+        let mut config = EndpointConfig::with_base_url("http://127.0.0.1:8080/");
+        config.overrides.push(EndpointOverride {
+            service: AliyunService::Ram,
+            region_id: None,
+            endpoint: "ram.example.com".to_owned(),
+        });
+        config.save(&store).unwrap();
+        assert_eq!(EndpointConfig::load(&store), config);
+
+        let invalid = EndpointConfig::with_base_url("not an endpoint");
+        assert_matches!(
+            invalid.save(&store),
+            Err(EndpointConfigError::InvalidEndpoint(_))
+        );
+        let invalid = EndpointConfig {
+            overrides: vec![EndpointOverride {
+                service: AliyunService::Ecs,
+                region_id: Some("Nowhere".to_owned()),
+                endpoint: "ecs.example.com".to_owned(),
+            }],
+            ..EndpointConfig::default()
+        };
+        assert_matches!(
+            invalid.save(&store),
+            Err(EndpointConfigError::InvalidRegion(_))
+        );
+        let invalid = EndpointConfig {
+            public_ip_url: Some("ipify.org".to_owned()),
+            ..EndpointConfig::default()
+        };
+        assert_matches!(
+            invalid.save(&store),
+            Err(EndpointConfigError::InvalidPublicIpUrl(_))
+        );
+        assert_eq!(EndpointConfig::load(&store), config);
+        assert_eq!(config.public_ip_url(), DEFAULT_PUBLIC_IP_URL);
+    }
+}
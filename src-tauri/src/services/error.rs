@@ -1,8 +1,10 @@
 use alibabacloud::client::error::{AliyunRejection, OperationError, RequestErrorKind};
-use serde::Serialize;
-use std::error::Error as StdError;
+use serde::{Deserialize, Serialize};
+use std::{error::Error as StdError, sync::Arc};
 use thiserror::Error;
 
+use crate::services::retry::CallError;
+
 pub trait SpecificError: StdError + Serialize + specta::Type {}
 
 impl<E: StdError + Serialize + specta::Type> SpecificError for E {}
@@ -13,13 +15,20 @@ pub enum AliyunRequestCommandError<E: SpecificError> {
     #[error("{}", .0)]
     Specific(#[source] E),
 
+    /// A rejection not handled by the specific error, classified by its code.
+    #[error("{}", .0)]
+    Rejected(#[source] Box<ClassifiedRejection>),
+
     #[error("{}", .message)]
     RequestFailure {
         message: String,
+        /// The attempts made, 1 when the call wasn't retried.
+        attempts: u32,
         #[serde(skip)]
         kind: RequestErrorKind,
+        /// Shared so the error can be duplicated, see [`DetachedError::duplicate`].
         #[serde(skip)]
-        source: reqwest::Error,
+        source: Arc<reqwest::Error>,
     },
 
     #[error("{}", .message)]
@@ -28,6 +37,10 @@ pub enum AliyunRequestCommandError<E: SpecificError> {
         #[serde(skip)]
         source: Box<dyn StdError>,
     },
+
+    /// The input was refused before anything was sent, e.g. a malformed role ARN.
+    #[error("{}", .message)]
+    InvalidInput { message: String },
 }
 
 #[derive(Debug, Error, Serialize, specta::Type)]
@@ -48,33 +61,152 @@ impl<E: SpecificError> AliyunRequestCommandError<E> {
     pub fn new_specific(error: E) -> Self {
         Self::Specific(error)
     }
+
+    /// Converts the error of a call needing `permission`, which is named as the missing
+    /// one when the call has been denied.
+    pub fn from_call(err: CallError, permission: &RamPermission) -> Self {
+        match Self::from(err) {
+            Self::Rejected(mut rejection)
+                if rejection.category == RejectionCategory::PermissionDenied =>
+            {
+                rejection.missing_permission = Some(permission.clone());
+                Self::Rejected(rejection)
+            }
+            other => other,
+        }
+    }
+}
+
+/// What a rejection means, for the frontend to react to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum RejectionCategory {
+    /// The access key is unknown, disabled or its secret doesn't match.
+    InvalidCredentials,
+    /// The RAM policies of the caller don't allow the action.
+    PermissionDenied,
+    /// Too many requests, the call can be retried later.
+    Throttled,
+    /// A quota or a limit of the account has been reached.
+    QuotaExceeded,
+    InvalidRegion,
+    /// The resource doesn't exist, or not in this region.
+    NotFound,
+    /// The zone has no stock left of the requested resource.
+    InsufficientStock,
+    /// The instance is not in a status allowing the action, e.g. starting a running one.
+    IncorrectInstanceStatus,
+    /// Any other rejection, its code tells more.
+    Other,
 }
 
-#[derive(Debug, Error, Serialize)]
-#[serde(transparent)]
-#[error("unexpected aliyun rejection")]
-pub struct UnexpectedAliyunRejectionError(AliyunRejection);
+impl RejectionCategory {
+    /// Classifies the error code of a rejection.
+    pub fn of(code: &str) -> Self {
+        let main_code = code
+            .split_once('.')
+            .map_or(code, |(main_code, _)| main_code);
+        match main_code {
+            "InvalidAccessKeyId" | "SignatureDoesNotMatch" | "MissingAccessKeyId" => {
+                Self::InvalidCredentials
+            }
+            "Forbidden" | "NoPermission" => Self::PermissionDenied,
+            "Throttling" => Self::Throttled,
+            "QuotaExceed" | "QuotaExceeded" | "LimitExceeded" => Self::QuotaExceeded,
+            "InvalidRegionId" | "InvalidRegion" => Self::InvalidRegion,
+            "IncorrectInstanceStatus" => Self::IncorrectInstanceStatus,
+            _ if code.contains("NoStock") || code.contains("OutOfStock") => Self::InsufficientStock,
+            _ if code.contains("NoPermission") => Self::PermissionDenied,
+            _ if code.ends_with(".NotFound") || main_code == "EntityNotExist" => Self::NotFound,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// A RAM action on a resource, e.g. `ecs:StopInstance` on `acs:ecs:cn-hangzhou:*:instance/i-1`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type)]
+pub struct RamPermission {
+    pub action: String,
+    pub resource: String,
+}
+
+impl RamPermission {
+    /// The permission an ECS action needs in the region, on the instance when it targets one.
+    pub fn ecs(action: &str, region_id: &str, instance_id: Option<&str>) -> Self {
+        let resource = match instance_id {
+            Some(instance_id) => format!("acs:ecs:{region_id}:*:instance/{instance_id}"),
+            None => format!("acs:ecs:{region_id}:*:*"),
+        };
+        Self {
+            action: format!("ecs:{action}"),
+            resource,
+        }
+    }
+
+    /// The permission a VPC action needs in the region.
+    pub fn vpc(action: &str, region_id: &str) -> Self {
+        Self {
+            action: format!("vpc:{action}"),
+            resource: format!("acs:vpc:{region_id}:*:*"),
+        }
+    }
+}
+
+/// A rejection of Aliyun, with its category.
+#[derive(Debug, Error, Serialize, specta::Type)]
+#[error("aliyun rejected the request with {}: {}", .data.code, .data.message)]
+pub struct ClassifiedRejection {
+    pub category: RejectionCategory,
+    #[specta(type = AliyunRejectionTypeShadow)]
+    pub data: AliyunRejection,
+    /// The permission the caller lacks, when the category is `PermissionDenied` and the
+    /// call is known.
+    pub missing_permission: Option<RamPermission>,
+    /// The attempts made, 1 when the call wasn't retried.
+    pub attempts: u32,
+}
+
+impl Clone for ClassifiedRejection {
+    fn clone(&self) -> Self {
+        Self {
+            category: self.category,
+            data: AliyunRejection {
+                code: self.data.code.clone(),
+                host_id: self.data.host_id.clone(),
+                message: self.data.message.clone(),
+                request_id: self.data.request_id.clone(),
+                recommend: self.data.recommend.clone(),
+            },
+            missing_permission: self.missing_permission.clone(),
+            attempts: self.attempts,
+        }
+    }
+}
+
+impl From<AliyunRejection> for ClassifiedRejection {
+    fn from(data: AliyunRejection) -> Self {
+        Self {
+            category: RejectionCategory::of(&data.code),
+            data,
+            missing_permission: None,
+            attempts: 1,
+        }
+    }
+}
 
 impl<E: SpecificError> From<OperationError> for AliyunRequestCommandError<E> {
     fn from(value: OperationError) -> Self {
         match value {
-            // Unhandled aliyun rejection should be converted into InternalError
-            OperationError::Rejected(rejection) => {
-                let error = UnexpectedAliyunRejectionError(rejection);
-                let message = error.to_string();
-                Self::InternalError {
-                    message,
-                    source: Box::new(error),
-                }
-            }
+            // Rejections not handled by the specific error keep their code, classified.
+            OperationError::Rejected(rejection) => Self::Rejected(Box::new(rejection.into())),
             OperationError::RequestFailure {
                 message,
                 kind,
                 source,
             } => Self::RequestFailure {
                 message,
+                attempts: 1,
                 kind,
-                source,
+                source: Arc::new(source),
             },
             OperationError::InternalError { message, source } => {
                 Self::InternalError { message, source }
@@ -83,6 +215,18 @@ impl<E: SpecificError> From<OperationError> for AliyunRequestCommandError<E> {
     }
 }
 
+impl<E: SpecificError> From<CallError> for AliyunRequestCommandError<E> {
+    fn from(value: CallError) -> Self {
+        let mut error = Self::from(value.error);
+        match &mut error {
+            Self::Rejected(rejection) => rejection.attempts = value.attempts,
+            Self::RequestFailure { attempts, .. } => *attempts = value.attempts,
+            _ => {}
+        }
+        error
+    }
+}
+
 impl AliyunRequestCommandError<NoOther> {
     pub fn from_others<E: SpecificError>(value: AliyunRequestCommandError<E>) -> Self {
         use AliyunRequestCommandError::*;
@@ -93,16 +237,128 @@ impl AliyunRequestCommandError<NoOther> {
             Specific(_) => {
                 unreachable!("You should handle the specific error before using this method")
             }
+            Rejected(rejection) => NoOtherError::Rejected(rejection),
             RequestFailure {
                 message,
+                attempts,
                 kind,
                 source,
             } => NoOtherError::RequestFailure {
                 message,
+                attempts,
                 kind,
                 source,
             },
             InternalError { message, source } => NoOtherError::InternalError { message, source },
+            InvalidInput { message } => NoOtherError::InvalidInput { message },
+        }
+    }
+
+    /// Like [`Self::from_others`], but keeps specific errors as internal errors.
+    pub fn from_any<E: SpecificError + 'static>(value: AliyunRequestCommandError<E>) -> Self {
+        match value {
+            AliyunRequestCommandError::Specific(error) => Self::InternalError {
+                message: error.to_string(),
+                source: Box::new(error),
+            },
+            other => Self::from_others(other),
+        }
+    }
+}
+
+/// An [`AliyunRequestCommandError`] which can be sent to another task.
+///
+/// The source of an internal error isn't `Send`, only its message is kept.
+#[derive(Debug)]
+pub enum DetachedError<E: SpecificError> {
+    Specific(E),
+    Rejected(Box<ClassifiedRejection>),
+    RequestFailure {
+        message: String,
+        attempts: u32,
+        kind: RequestErrorKind,
+        source: Arc<reqwest::Error>,
+    },
+    InternalError {
+        message: String,
+    },
+    InvalidInput {
+        message: String,
+    },
+}
+
+impl<E: SpecificError> From<AliyunRequestCommandError<E>> for DetachedError<E> {
+    fn from(value: AliyunRequestCommandError<E>) -> Self {
+        match value {
+            AliyunRequestCommandError::Specific(error) => Self::Specific(error),
+            AliyunRequestCommandError::Rejected(rejection) => Self::Rejected(rejection),
+            AliyunRequestCommandError::RequestFailure {
+                message,
+                attempts,
+                kind,
+                source,
+            } => Self::RequestFailure {
+                message,
+                attempts,
+                kind,
+                source,
+            },
+            AliyunRequestCommandError::InternalError { message, .. } => {
+                Self::InternalError { message }
+            }
+            AliyunRequestCommandError::InvalidInput { message } => Self::InvalidInput { message },
+        }
+    }
+}
+
+impl<E: SpecificError> From<DetachedError<E>> for AliyunRequestCommandError<E> {
+    fn from(value: DetachedError<E>) -> Self {
+        match value {
+            DetachedError::Specific(error) => Self::Specific(error),
+            DetachedError::Rejected(rejection) => Self::Rejected(rejection),
+            DetachedError::RequestFailure {
+                message,
+                attempts,
+                kind,
+                source,
+            } => Self::RequestFailure {
+                message,
+                attempts,
+                kind,
+                source,
+            },
+            DetachedError::InternalError { message } => Self::InternalError {
+                message,
+                source: NoSource::new_boxed(),
+            },
+            DetachedError::InvalidInput { message } => Self::InvalidInput { message },
+        }
+    }
+}
+
+impl<E: SpecificError + Clone> DetachedError<E> {
+    /// A copy of the error, for each of the items of a request which failed as a whole.
+    pub fn duplicate(&self) -> Self {
+        match self {
+            Self::Specific(error) => Self::Specific(error.clone()),
+            Self::Rejected(rejection) => Self::Rejected(rejection.clone()),
+            Self::RequestFailure {
+                message,
+                attempts,
+                kind,
+                source,
+            } => Self::RequestFailure {
+                message: message.clone(),
+                attempts: *attempts,
+                kind: *kind,
+                source: source.clone(),
+            },
+            Self::InternalError { message } => Self::InternalError {
+                message: message.clone(),
+            },
+            Self::InvalidInput { message } => Self::InvalidInput {
+                message: message.clone(),
+            },
         }
     }
 }
@@ -116,3 +372,84 @@ pub struct AliyunRejectionTypeShadow {
     pub request_id: String,
     pub recommend: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::services::ecs::EcsError;
+
+    #[test]
+    fn test_rejection_category() {
+        use RejectionCategory::*;
+
+        for (code, category) in [
+            ("InvalidAccessKeyId.NotFound", InvalidCredentials),
+            ("SignatureDoesNotMatch", InvalidCredentials),
+            ("Forbidden.RAM", PermissionDenied),
+            ("Forbidden.NoPermission", PermissionDenied),
+            ("NoPermission", PermissionDenied),
+            ("InvalidOperation.NoPermission", PermissionDenied),
+            ("Throttling.User", Throttled),
+            ("Throttling", Throttled),
+            ("QuotaExceed.ElasticQuota", QuotaExceeded),
+            ("LimitExceeded.AccessKey", QuotaExceeded),
+            ("InvalidRegionId.NotFound", InvalidRegion),
+            ("InvalidInstanceId.NotFound", NotFound),
+            ("EntityNotExist.User.AccessKey", NotFound),
+            ("OperationDenied.NoStock", InsufficientStock),
+            ("IncorrectInstanceStatus", IncorrectInstanceStatus),
+            (
+                "IncorrectInstanceStatus.Initializing",
+                IncorrectInstanceStatus,
+            ),
+            ("InternalError", Other),
+        ] {
+            assert_eq!(RejectionCategory::of(code), category, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_rejection_keeps_code() {
+        let rejection = AliyunRejection {
+            code: "Forbidden.RAM".to_owned(),
+            host_id: "ecs.aliyuncs.com".to_owned(),
+            message: "User not authorized to operate on the specified resource.".to_owned(),
+            request_id: "REQUEST-ID".to_owned(),
+            recommend: String::new(),
+        };
+        let err = AliyunRequestCommandError::<NoOther>::from(OperationError::Rejected(rejection));
+        assert_matches!(&err, AliyunRequestCommandError::Rejected(r) if r.category == RejectionCategory::PermissionDenied);
+
+        let serialized = serde_json::to_value(&err).unwrap();
+        assert_eq!(serialized["type"], json!("Rejected"));
+        assert_eq!(serialized["error"]["category"], json!("PermissionDenied"));
+        assert_eq!(serialized["error"]["data"]["code"], json!("Forbidden.RAM"));
+    }
+
+    #[test]
+    fn test_duplicate_request_failure() {
+        let source = reqwest::Client::new().get("not a URL").build().unwrap_err();
+        let err = AliyunRequestCommandError::<EcsError>::from(CallError {
+            error: OperationError::RequestFailure {
+                message: source.to_string(),
+                kind: RequestErrorKind::Timeout,
+                source,
+            },
+            attempts: 3,
+        });
+
+        let duplicate = DetachedError::from(err).duplicate();
+        assert_matches!(
+            AliyunRequestCommandError::from(duplicate),
+            AliyunRequestCommandError::<EcsError>::RequestFailure {
+                attempts: 3,
+                kind: RequestErrorKind::Timeout,
+                ..
+            }
+        );
+    }
+}
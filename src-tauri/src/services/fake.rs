@@ -0,0 +1,1656 @@
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, sync::Mutex};
+
+use chrono::{NaiveDateTime, TimeDelta};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+#[cfg(test)]
+use crate::services::region::{RegionClient, RegionalApi};
+use crate::services::{
+    auth::types::AccessKeyCredentials,
+    rpc::{sign, RpcParams},
+};
+
+pub const FAKE_ACCESS_KEY_ID: &str = "FAKE_ACCESS_KEY_ID";
+pub const FAKE_ACCESS_KEY_SECRET: &str = "FAKE_ACCESS_KEY_SECRET";
+pub const FAKE_ACCOUNT_ID: &str = "100000000000";
+pub const FAKE_USER_ARN: &str = "acs:ram::100000000000:user/demo";
+/// The regions served by the fake, the first one being where its instances are.
+pub const FAKE_REGIONS: &[(&str, &str)] = &[
+    ("cn-hangzhou", "华东1（杭州）"),
+    ("cn-shanghai", "华东2（上海）"),
+    ("ap-southeast-1", "新加坡"),
+];
+
+/// The VPCs of each region of the fake, as (VPC ID, name, CIDR block).
+pub const FAKE_VPCS: &[(&str, &str, &str)] = &[
+    ("vpc-fake0001", "default", "172.16.0.0/12"),
+    ("vpc-fake0002", "staging", "192.168.0.0/16"),
+];
+/// The vSwitches of each region of the fake, as (vSwitch ID, VPC ID, zone suffix).
+pub const FAKE_VSWITCHES: &[(&str, &str, &str)] = &[
+    ("vsw-fake0001", "vpc-fake0001", "h"),
+    ("vsw-fake0002", "vpc-fake0001", "i"),
+    ("vsw-fake0003", "vpc-fake0002", "h"),
+];
+/// The security groups of each region of the fake, as (group ID, VPC ID).
+pub const FAKE_SECURITY_GROUPS: &[(&str, &str)] = &[
+    ("sg-fake0001", "vpc-fake0001"),
+    ("sg-fake0002", "vpc-fake0002"),
+];
+/// The instance types of each zone suffix, as (zone suffix, type, in stock).
+pub const FAKE_INSTANCE_TYPES: &[(&str, &str, bool)] = &[
+    ("h", "ecs.g7.large", true),
+    ("h", "ecs.r7.xlarge", true),
+    ("h", "ecs.g7.xlarge", false),
+    ("i", "ecs.g7.large", true),
+];
+/// The system images of the fake, as (image ID, OS name).
+pub const FAKE_IMAGES: &[(&str, &str)] = &[
+    (
+        "aliyun_3_x64_20G_alibase_20250101.vhd",
+        "Alibaba Cloud Linux 3.2104 LTS 64位",
+    ),
+    (
+        "ubuntu_22_04_x64_20G_alibase_20250101.vhd",
+        "Ubuntu 22.04 64位",
+    ),
+];
+pub const FAKE_KEY_PAIR: &str = "demo-key";
+/// The public IP of the machine, as answered at [`FakeAliyunServer::public_ip_url`].
+pub const FAKE_PUBLIC_IP: &str = "203.0.113.7";
+/// The parameters of CreateLaunchTemplateVersion kept by the fake, or their prefixes.
+const FAKE_TEMPLATE_PARAMS: &[&str] = &[
+    "ImageId",
+    "InstanceType",
+    "ZoneId",
+    "VSwitchId",
+    "SecurityGroupIds.",
+    "InstanceName",
+    "KeyPairName",
+    "SystemDisk.",
+    "DataDisk.",
+    "InstanceChargeType",
+    "InternetMaxBandwidthOut",
+    "InternetChargeType",
+    "Tag.",
+];
+
+/// The path of [`FakeAliyunServer`] answering the public IP of the machine.
+const PUBLIC_IP_PATH: &str = "/ip";
+
+/// How the times of the instances are written, e.g. `2026-01-01T00:00Z`.
+const FAKE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%MZ";
+
+/// The credentials of the account of [`FakeAliyun::demo`].
+pub fn fake_credentials() -> AccessKeyCredentials {
+    AccessKeyCredentials::new(FAKE_ACCESS_KEY_ID, FAKE_ACCESS_KEY_SECRET)
+}
+
+/// An ECS instance of the fake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeInstance {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub region_id: String,
+    pub zone_id: String,
+    pub instance_type: String,
+    pub cpu: u32,
+    pub memory: u32,
+    /// `Running` or `Stopped`, the fake never lingers in transitional states.
+    pub status: String,
+    pub private_ip: String,
+    pub public_ip: Option<String>,
+    pub creation_time: String,
+    /// When it was last started, each start or reboot moving it a minute later.
+    pub start_time: String,
+    /// `PostPaid` or `PrePaid`, the latter expiring at `expired_time`.
+    pub instance_charge_type: String,
+    pub expired_time: String,
+    pub tags: Vec<(String, String)>,
+    /// How the instance was last stopped, `KeepCharging` or `StopCharging`.
+    pub stopped_mode: String,
+    pub deletion_protection: bool,
+    /// The IDs of its disks, the system disk first.
+    pub disk_ids: Vec<String>,
+    pub image_id: String,
+    pub key_pair_name: String,
+}
+
+impl FakeInstance {
+    fn to_json(&self) -> Value {
+        json!({
+            "InstanceId": self.instance_id,
+            "InstanceName": self.instance_name,
+            "RegionId": self.region_id,
+            "ZoneId": self.zone_id,
+            "InstanceType": self.instance_type,
+            "Cpu": self.cpu,
+            "Memory": self.memory,
+            "Status": self.status,
+            "OSName": "Alibaba Cloud Linux 3.2104 LTS 64位",
+            "ImageId": self.image_id,
+            "KeyPairName": self.key_pair_name,
+            "InternetMaxBandwidthOut": if self.public_ip.is_some() { 5 } else { 0 },
+            "CreationTime": self.creation_time,
+            "StartTime": self.start_time,
+            "PublicIpAddress": { "IpAddress": self.public_ip.iter().collect::<Vec<_>>() },
+            "VpcAttributes": {
+                "VpcId": "vpc-fake0001",
+                "VSwitchId": "vsw-fake0001",
+                "PrivateIpAddress": { "IpAddress": [self.private_ip] },
+            },
+            "SecurityGroupIds": { "SecurityGroupId": ["sg-fake0001"] },
+            "InstanceChargeType": self.instance_charge_type,
+            "ExpiredTime": self.expired_time,
+            "StoppedMode": self.stopped_mode,
+            "DeletionProtection": self.deletion_protection,
+            "Tags": {
+                "Tag": self
+                    .tags
+                    .iter()
+                    .map(|(key, value)| json!({ "TagKey": key, "TagValue": value }))
+                    .collect::<Vec<_>>(),
+            },
+        })
+    }
+}
+
+/// A launch template of the fake, in the region of its instances.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeLaunchTemplate {
+    pub launch_template_id: String,
+    pub launch_template_name: String,
+    pub default_version_number: usize,
+    /// The description and the parameters of each version, numbered from 1, the
+    /// parameters being named like those of RunInstances.
+    pub versions: Vec<(String, RpcParams)>,
+}
+
+impl FakeLaunchTemplate {
+    fn to_json(&self) -> Value {
+        json!({
+            "LaunchTemplateId": self.launch_template_id,
+            "LaunchTemplateName": self.launch_template_name,
+            "DefaultVersionNumber": self.default_version_number,
+            "LatestVersionNumber": self.versions.len(),
+            "CreatedBy": FAKE_ACCOUNT_ID,
+            "CreateTime": "2026-01-01T00:00:00Z",
+            "ModifiedTime": "2026-01-01T00:00:00Z",
+        })
+    }
+
+    /// The version as described by DescribeLaunchTemplateVersions.
+    fn version_json(&self, version_number: usize) -> Value {
+        let (description, params) = &self.versions[version_number - 1];
+        let indexed = |prefix: &str| {
+            (1..)
+                .map_while(|index| params.get(&format!("{prefix}.{index}")))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let data_disks = (1..)
+            .map_while(|index| {
+                let category = params.get(&format!("DataDisk.{index}.Category"))?;
+                let size = params.get(&format!("DataDisk.{index}.Size"))?;
+                Some(json!({ "Category": category, "Size": size.parse::<u32>().ok() }))
+            })
+            .collect::<Vec<_>>();
+        let tags = (1..)
+            .map_while(|index| {
+                let key = params.get(&format!("Tag.{index}.Key"))?;
+                let value = params.get(&format!("Tag.{index}.Value"));
+                Some(json!({ "Key": key, "Value": value }))
+            })
+            .collect::<Vec<_>>();
+        let mut data = json!({
+            "SecurityGroupIds": { "SecurityGroupId": indexed("SecurityGroupIds") },
+            "DataDisks": { "DataDisk": data_disks },
+            "Tags": { "InstanceTag": tags },
+        });
+        for (name, value) in params {
+            let indexed = ["SecurityGroupIds.", "DataDisk.", "Tag."]
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+            if !indexed {
+                data[name] = match name.as_str() {
+                    "SystemDisk.Size" | "InternetMaxBandwidthOut" => {
+                        json!(value.parse::<u32>().ok())
+                    }
+                    _ => json!(value),
+                };
+            }
+        }
+        json!({
+            "LaunchTemplateId": self.launch_template_id,
+            "LaunchTemplateName": self.launch_template_name,
+            "VersionNumber": version_number,
+            "VersionDescription": description,
+            "DefaultVersion": version_number == self.default_version_number,
+            "CreateTime": "2026-01-01T00:00:00Z",
+            "LaunchTemplateData": data,
+        })
+    }
+}
+
+/// A rule of a security group of the fake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeSecurityGroupRule {
+    pub region_id: String,
+    pub security_group_id: String,
+    pub security_group_rule_id: String,
+    /// `ingress` or `egress`.
+    pub direction: String,
+    pub ip_protocol: String,
+    pub port_range: String,
+    /// The source of an ingress rule or the destination of an egress one, an IPv4 or IPv6
+    /// CIDR block or a security group ID.
+    pub peer: String,
+    pub policy: String,
+    pub priority: u32,
+    pub description: String,
+}
+
+impl FakeSecurityGroupRule {
+    fn to_json(&self) -> Value {
+        let (cidr_ip, ipv6_cidr_ip, group_id) = if self.peer.starts_with("sg-") {
+            ("", "", self.peer.as_str())
+        } else if self.peer.contains(':') {
+            ("", self.peer.as_str(), "")
+        } else {
+            (self.peer.as_str(), "", "")
+        };
+        let ingress = self.direction == "ingress";
+        json!({
+            "SecurityGroupRuleId": self.security_group_rule_id,
+            "Direction": self.direction,
+            "IpProtocol": self.ip_protocol,
+            "PortRange": self.port_range,
+            "SourceCidrIp": if ingress { cidr_ip } else { "" },
+            "DestCidrIp": if ingress { "" } else { cidr_ip },
+            "Ipv6SourceCidrIp": if ingress { ipv6_cidr_ip } else { "" },
+            "Ipv6DestCidrIp": if ingress { "" } else { ipv6_cidr_ip },
+            "SourceGroupId": if ingress { group_id } else { "" },
+            "DestGroupId": if ingress { "" } else { group_id },
+            "Policy": self.policy,
+            // Sent as text, like Aliyun does.
+            "Priority": self.priority.to_string(),
+            "Description": self.description,
+            "CreateTime": "2026-01-01T00:00:00Z",
+        })
+    }
+
+    /// Reads the rule of AuthorizeSecurityGroup or ModifySecurityGroupRule, or of their
+    /// egress counterparts, from the parameters starting with `prefix`.
+    fn update(&mut self, params: &RpcParams, prefix: &str) {
+        let param = |name: &str| params.get(&format!("{prefix}{name}")).cloned();
+        let peer_params: &[&str] = if self.direction == "ingress" {
+            &["SourceCidrIp", "Ipv6SourceCidrIp", "SourceGroupId"]
+        } else {
+            &["DestCidrIp", "Ipv6DestCidrIp", "DestGroupId"]
+        };
+        if let Some(peer) = peer_params.iter().find_map(|name| param(name)) {
+            self.peer = peer;
+        }
+        if let Some(ip_protocol) = param("IpProtocol") {
+            self.ip_protocol = ip_protocol.to_uppercase().replace("ICMPV6", "ICMPv6");
+        }
+        if let Some(port_range) = param("PortRange") {
+            self.port_range = port_range;
+        }
+        if let Some(policy) = param("Policy") {
+            self.policy = policy;
+        }
+        if let Some(priority) = param("Priority").and_then(|priority| priority.parse().ok()) {
+            self.priority = priority;
+        }
+        if let Some(description) = param("Description") {
+            self.description = description;
+        }
+    }
+}
+
+/// A rejection, as sent back by Aliyun.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeRejection {
+    pub status: u16,
+    pub code: String,
+    /// How many more calls are rejected, all of them when `None`.
+    #[cfg(test)]
+    pub remaining: Option<u32>,
+}
+
+impl FakeRejection {
+    pub fn new(status: u16, code: &str) -> Self {
+        Self {
+            status,
+            code: code.to_owned(),
+            #[cfg(test)]
+            remaining: None,
+        }
+    }
+
+    /// Only rejects the next `times` calls.
+    #[cfg(test)]
+    pub fn times(mut self, times: u32) -> Self {
+        self.remaining = Some(times);
+        self
+    }
+
+    fn into_response(self) -> (u16, Value) {
+        let body = json!({
+            "RequestId": "FAKE-REQUEST-ID",
+            "HostId": "fake.aliyuncs.com",
+            "Code": self.code,
+            "Message": format!("The fake rejected the request with {}.", self.code),
+            "Recommend": "",
+        });
+        (self.status, body)
+    }
+}
+
+/// The state of a fake Aliyun account, answering STS `GetCallerIdentity` and the core ECS
+/// actions the way Aliyun does, signatures included.
+///
+/// Everything is deterministic: instances change state right away and request IDs are
+/// constant.
+#[derive(Default)]
+pub struct FakeAliyun {
+    /// The secret of each access key of the account.
+    pub access_keys: Mutex<HashMap<String, String>>,
+    pub instances: Mutex<Vec<FakeInstance>>,
+    /// Rejections returned for every call of an action, regardless of its parameters.
+    #[cfg(test)]
+    pub rejections: Mutex<HashMap<String, FakeRejection>>,
+    /// The actions called so far, in order.
+    #[cfg(test)]
+    pub calls: Mutex<Vec<String>>,
+    /// The snapshots taken, as (snapshot ID, disk ID, name), all of them complete.
+    pub snapshots: Mutex<Vec<(String, String, String)>>,
+    /// The instances created by each client token of RunInstances.
+    pub launches: Mutex<HashMap<String, Vec<String>>>,
+    pub launch_templates: Mutex<Vec<FakeLaunchTemplate>>,
+    pub security_group_rules: Mutex<Vec<FakeSecurityGroupRule>>,
+    /// The secondary network interfaces, as the instance they are attached to and their
+    /// security group, the primary ones being in `sg-fake0001`.
+    pub secondary_interfaces: Mutex<Vec<(String, String)>>,
+    /// Whether instances are only paged by number, `MaxResults` only sizing the page.
+    #[cfg(test)]
+    pub without_next_token: Mutex<bool>,
+}
+
+impl FakeAliyun {
+    /// An account reached with [`fake_credentials`], having a few instances in its first
+    /// region.
+    pub fn demo() -> Self {
+        let fake = Self::default();
+        fake.add_access_key(&fake_credentials());
+        let region_id = FAKE_REGIONS[0].0;
+        let instances = [
+            ("i-fake0001", "web-1", "ecs.g7.large", "Running"),
+            ("i-fake0002", "web-2", "ecs.g7.large", "Running"),
+            ("i-fake0003", "db-1", "ecs.r7.xlarge", "Stopped"),
+        ];
+        *fake.instances.lock().unwrap() = instances
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, name, instance_type, status))| {
+                let (cpu, memory) = match instance_type {
+                    "ecs.g7.large" => (2, 8192),
+                    _ => (4, 32768),
+                };
+                FakeInstance {
+                    instance_id: id.to_owned(),
+                    instance_name: name.to_owned(),
+                    region_id: region_id.to_owned(),
+                    zone_id: format!("{region_id}-h"),
+                    instance_type: instance_type.to_owned(),
+                    cpu,
+                    memory,
+                    status: status.to_owned(),
+                    private_ip: format!("172.16.0.{}", index + 10),
+                    public_ip: name
+                        .starts_with("web")
+                        .then(|| format!("47.96.0.{}", index + 10)),
+                    creation_time: "2026-01-01T00:00Z".to_owned(),
+                    start_time: "2026-01-01T00:01Z".to_owned(),
+                    instance_charge_type: if status == "Running" {
+                        "PostPaid"
+                    } else {
+                        "PrePaid"
+                    }
+                    .to_owned(),
+                    expired_time: if status == "Running" {
+                        "2099-12-31T15:59Z"
+                    } else {
+                        "2027-01-01T16:00Z"
+                    }
+                    .to_owned(),
+                    tags: vec![("app".to_owned(), name.split('-').next().unwrap().to_owned())],
+                    stopped_mode: "KeepCharging".to_owned(),
+                    deletion_protection: false,
+                    disk_ids: vec![id.replace("i-", "d-")],
+                    image_id: FAKE_IMAGES[0].0.to_owned(),
+                    key_pair_name: FAKE_KEY_PAIR.to_owned(),
+                }
+            })
+            .collect();
+        let web = |instance_type: &str| {
+            RpcParams::from(
+                [
+                    ("ImageId", FAKE_IMAGES[0].0),
+                    ("InstanceType", instance_type),
+                    ("VSwitchId", "vsw-fake0001"),
+                    ("SecurityGroupIds.1", "sg-fake0001"),
+                    ("SystemDisk.Category", "cloud_essd"),
+                    ("SystemDisk.Size", "40"),
+                    ("InstanceChargeType", "PostPaid"),
+                    ("InternetMaxBandwidthOut", "5"),
+                    ("Tag.1.Key", "app"),
+                    ("Tag.1.Value", "web"),
+                ]
+                .map(|(name, value)| (name.to_owned(), value.to_owned())),
+            )
+        };
+        *fake.launch_templates.lock().unwrap() = vec![FakeLaunchTemplate {
+            launch_template_id: "lt-fake0001".to_owned(),
+            launch_template_name: "web".to_owned(),
+            default_version_number: 1,
+            versions: vec![
+                ("first".to_owned(), web("ecs.g7.large")),
+                ("bigger".to_owned(), web("ecs.r7.xlarge")),
+            ],
+        }];
+        let rules = [
+            ("sg-fake0001", "ingress", "TCP", "22/22", "0.0.0.0/0", "SSH"),
+            (
+                "sg-fake0001",
+                "ingress",
+                "TCP",
+                "443/443",
+                "0.0.0.0/0",
+                "HTTPS",
+            ),
+            (
+                "sg-fake0001",
+                "ingress",
+                "ICMP",
+                "-1/-1",
+                "172.16.0.0/12",
+                "ping",
+            ),
+            ("sg-fake0001", "egress", "ALL", "-1/-1", "0.0.0.0/0", ""),
+            (
+                "sg-fake0002",
+                "ingress",
+                "TCP",
+                "3306/3306",
+                "sg-fake0001",
+                "MySQL",
+            ),
+            (
+                "sg-fake0002",
+                "ingress",
+                "TCP",
+                "8000/9999",
+                "0.0.0.0/0",
+                "dev servers",
+            ),
+        ];
+        *fake.security_group_rules.lock().unwrap() = rules
+            .into_iter()
+            .enumerate()
+            .map(
+                |(index, (group_id, direction, ip_protocol, port_range, peer, description))| {
+                    FakeSecurityGroupRule {
+                        region_id: region_id.to_owned(),
+                        security_group_id: group_id.to_owned(),
+                        security_group_rule_id: format!("sgr-fake{:04}", index + 1),
+                        direction: direction.to_owned(),
+                        ip_protocol: ip_protocol.to_owned(),
+                        port_range: port_range.to_owned(),
+                        peer: peer.to_owned(),
+                        policy: "Accept".to_owned(),
+                        priority: 1,
+                        description: description.to_owned(),
+                    }
+                },
+            )
+            .collect();
+        fake
+    }
+
+    pub fn add_access_key(&self, credentials: &AccessKeyCredentials) {
+        self.access_keys.lock().unwrap().insert(
+            credentials.access_key_id.clone(),
+            credentials.access_key_secret.expose_secret().to_owned(),
+        );
+    }
+
+    /// Rejects the following calls of the action, until [`Self::clear_rejections`] or as
+    /// many times as the rejection says.
+    #[cfg(test)]
+    pub fn reject(&self, action: &str, rejection: FakeRejection) {
+        self.rejections
+            .lock()
+            .unwrap()
+            .insert(action.to_owned(), rejection);
+    }
+
+    #[cfg(test)]
+    pub fn clear_rejections(&self) {
+        self.rejections.lock().unwrap().clear();
+    }
+
+    #[cfg(test)]
+    pub fn instance(&self, instance_id: &str) -> Option<FakeInstance> {
+        self.instances
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|instance| instance.instance_id == instance_id)
+            .cloned()
+    }
+
+    /// Answers a signed RPC request, returns the HTTP status and the JSON body.
+    pub fn respond(&self, params: &RpcParams) -> (u16, Value) {
+        let result = self
+            .authenticate(params)
+            .and_then(|action| self.call(action, params));
+        match result {
+            Ok(body) => (200, body),
+            Err(rejection) => rejection.into_response(),
+        }
+    }
+
+    fn call(&self, action: &str, params: &RpcParams) -> Result<Value, FakeRejection> {
+        #[cfg(test)]
+        {
+            self.calls.lock().unwrap().push(action.to_owned());
+            if let Some(rejected) = self.take_rejection(action) {
+                return Err(rejected);
+            }
+        }
+        self.dispatch(action, params)
+    }
+
+    /// The rejection of the call of the action, if any, counting it.
+    #[cfg(test)]
+    fn take_rejection(&self, action: &str) -> Option<FakeRejection> {
+        let mut rejections = self.rejections.lock().unwrap();
+        let rejection = rejections.get_mut(action)?;
+        let rejected = rejection.clone();
+        match &mut rejection.remaining {
+            Some(0) | Some(1) => {
+                rejections.remove(action);
+            }
+            Some(remaining) => *remaining -= 1,
+            None => {}
+        }
+        Some(rejected)
+    }
+
+    /// Checks the access key and the signature, returns the action.
+    fn authenticate<'p>(&self, params: &'p RpcParams) -> Result<&'p str, FakeRejection> {
+        let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+
+        let access_key_id = param("AccessKeyId");
+        if access_key_id.is_empty() {
+            return Err(FakeRejection::new(400, "MissingAccessKeyId"));
+        }
+        let Some(secret) = self.access_keys.lock().unwrap().get(access_key_id).cloned() else {
+            return Err(FakeRejection::new(404, "InvalidAccessKeyId.NotFound"));
+        };
+
+        let mut unsigned = params.clone();
+        let signature = unsigned.remove("Signature").unwrap_or_default();
+        if sign(&secret, &unsigned) != signature {
+            return Err(FakeRejection::new(400, "SignatureDoesNotMatch"));
+        }
+
+        match param("Action") {
+            "" => Err(FakeRejection::new(400, "MissingAction")),
+            action => Ok(action),
+        }
+    }
+
+    fn dispatch(&self, action: &str, params: &RpcParams) -> Result<Value, FakeRejection> {
+        let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+        let request_id = json!("FAKE-REQUEST-ID");
+
+        if action == "GetCallerIdentity" {
+            return Ok(json!({
+                "IdentityType": "RAMUser",
+                "RequestId": request_id,
+                "AccountId": FAKE_ACCOUNT_ID,
+                "PrincipalId": "200000000000000000",
+                "UserId": "200000000000000000",
+                "Arn": FAKE_USER_ARN,
+            }));
+        }
+
+        let region_id = param("RegionId");
+        if !FAKE_REGIONS.iter().any(|(id, _)| *id == region_id) {
+            return Err(FakeRejection::new(400, "InvalidRegionId.NotFound"));
+        }
+        let mut instances = self.instances.lock().unwrap();
+        let in_region = instances
+            .iter_mut()
+            .filter(|instance| instance.region_id == region_id);
+
+        match action {
+            "DescribeRegions" => {
+                let regions = FAKE_REGIONS
+                    .iter()
+                    .map(|(region_id, local_name)| {
+                        json!({
+                            "RegionId": region_id,
+                            "LocalName": local_name,
+                            "RegionEndpoint": format!("ecs.{region_id}.aliyuncs.com"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "RequestId": request_id, "Regions": { "Region": regions } }))
+            }
+            "DescribeInstances" | "DescribeInstanceStatus" => {
+                let ids: Option<Vec<String>> = match param("InstanceIds") {
+                    "" => None,
+                    ids => Some(
+                        serde_json::from_str(ids)
+                            .map_err(|_| FakeRejection::new(400, "InvalidInstanceIds.Malformed"))?,
+                    ),
+                };
+                let status = param("Status");
+                let matching = in_region
+                    .filter(|instance| {
+                        ids.as_ref()
+                            .is_none_or(|ids| ids.contains(&instance.instance_id))
+                    })
+                    .filter(|instance| status.is_empty() || instance.status == status)
+                    .collect::<Vec<_>>();
+                // Paged by `NextToken` when `MaxResults` is given, by page number otherwise.
+                #[cfg(test)]
+                let without_next_token = *self.without_next_token.lock().unwrap();
+                #[cfg(not(test))]
+                let without_next_token = false;
+                let by_token = !param("MaxResults").is_empty() && !without_next_token;
+                let (offset, page_size) = if by_token {
+                    let offset = match param("NextToken") {
+                        "" => 0,
+                        token => token
+                            .parse::<usize>()
+                            .map_err(|_| FakeRejection::new(400, "InvalidNextToken.Malformed"))?,
+                    };
+                    (offset, param("MaxResults").parse::<usize>().unwrap_or(10))
+                } else {
+                    let page_number = param("PageNumber").parse::<usize>().unwrap_or(1).max(1);
+                    let page_size = param("PageSize")
+                        .parse::<usize>()
+                        .or_else(|_| param("MaxResults").parse::<usize>())
+                        .unwrap_or(10);
+                    ((page_number - 1) * page_size.clamp(1, 100), page_size)
+                };
+                let page_size = page_size.clamp(1, 100);
+                let page = matching.iter().skip(offset).take(page_size);
+
+                let mut body = json!({
+                    "RequestId": request_id,
+                    "TotalCount": matching.len(),
+                    "PageNumber": offset / page_size + 1,
+                    "PageSize": page_size,
+                });
+                if by_token {
+                    let next = offset + page_size;
+                    body["NextToken"] = json!(if next < matching.len() {
+                        next.to_string()
+                    } else {
+                        String::new()
+                    });
+                }
+                if action == "DescribeInstances" {
+                    let page = page.map(|instance| instance.to_json()).collect::<Vec<_>>();
+                    body["Instances"] = json!({ "Instance": page });
+                } else {
+                    let page = page
+                        .map(|instance| {
+                            json!({ "InstanceId": instance.instance_id, "Status": instance.status })
+                        })
+                        .collect::<Vec<_>>();
+                    body["InstanceStatuses"] = json!({ "InstanceStatus": page });
+                }
+                Ok(body)
+            }
+            "StartInstance" | "StopInstance" | "RebootInstance" => {
+                let instance_id = param("InstanceId");
+                let stopped_mode = stopped_mode(param("StoppedMode"))?;
+                let mut in_region = in_region;
+                let instance = in_region
+                    .find(|instance| instance.instance_id == instance_id)
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidInstanceId.NotFound"))?;
+                change_status(instance, action, stopped_mode)?;
+                Ok(json!({ "RequestId": request_id }))
+            }
+            // Only the `SuccessFirst` batch optimization, each instance on its own.
+            "StartInstances" | "StopInstances" | "RebootInstances" => {
+                let stopped_mode = stopped_mode(param("StoppedMode"))?;
+                let action = action.trim_end_matches('s');
+                let mut in_region = in_region.collect::<Vec<_>>();
+                let responses = (1..)
+                    .map_while(|index| params.get(&format!("InstanceId.{index}")))
+                    .map(|instance_id| {
+                        let Some(instance) = in_region
+                            .iter_mut()
+                            .find(|instance| &instance.instance_id == instance_id)
+                        else {
+                            return json!({
+                                "InstanceId": instance_id,
+                                "Code": "InvalidInstanceId.NotFound",
+                                "Message": "The specified instance does not exist.",
+                            });
+                        };
+                        let previous_status = instance.status.clone();
+                        let (code, message) = match change_status(instance, action, stopped_mode) {
+                            Ok(()) => ("200".to_owned(), "success".to_owned()),
+                            Err(rejection) => (rejection.code.clone(), rejection.code),
+                        };
+                        json!({
+                            "InstanceId": instance_id,
+                            "Code": code,
+                            "Message": message,
+                            "PreviousStatus": previous_status,
+                            "CurrentStatus": instance.status,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if responses.is_empty() {
+                    return Err(FakeRejection::new(400, "MissingInstanceId"));
+                }
+                Ok(json!({
+                    "RequestId": request_id,
+                    "InstanceResponses": { "InstanceResponse": responses },
+                }))
+            }
+            "DeleteInstance" => {
+                let instance_id = param("InstanceId");
+                let mut in_region = in_region;
+                let instance = in_region
+                    .find(|instance| instance.instance_id == instance_id)
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidInstanceId.NotFound"))?;
+                if instance.deletion_protection {
+                    return Err(FakeRejection::new(
+                        403,
+                        "OperationDenied.DeletionProtection",
+                    ));
+                }
+                if instance.status != "Stopped" && param("Force") != "true" {
+                    return Err(FakeRejection::new(403, "IncorrectInstanceStatus"));
+                }
+                if param("DryRun") == "true" {
+                    return Err(FakeRejection::new(400, "DryRunOperation"));
+                }
+                let instance_id = instance_id.to_owned();
+                instances.retain(|instance| instance.instance_id != instance_id);
+                Ok(json!({ "RequestId": request_id }))
+            }
+            // A primary network interface per instance, in the group of the instance.
+            "DescribeNetworkInterfaces" => {
+                let instance_id = param("InstanceId");
+                let secondary = self.secondary_interfaces.lock().unwrap();
+                let interfaces = in_region
+                    .filter(|instance| {
+                        instance_id.is_empty() || instance.instance_id == instance_id
+                    })
+                    .flat_map(|instance| {
+                        let eni_id = instance.instance_id.replacen("i-", "eni-", 1);
+                        let primary = json!({
+                            "NetworkInterfaceId": eni_id,
+                            "InstanceId": instance.instance_id,
+                            "Type": "Primary",
+                            "PrivateIpAddress": instance.private_ip,
+                            "SecurityGroupIds": { "SecurityGroupId": ["sg-fake0001"] },
+                        });
+                        let secondary = secondary
+                            .iter()
+                            .filter(|(attached_to, _)| *attached_to == instance.instance_id)
+                            .enumerate()
+                            .map(|(index, (_, security_group_id))| {
+                                json!({
+                                    "NetworkInterfaceId": format!("{eni_id}-{}", index + 1),
+                                    "InstanceId": instance.instance_id,
+                                    "Type": "Secondary",
+                                    "SecurityGroupIds": { "SecurityGroupId": [security_group_id] },
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        std::iter::once(primary).chain(secondary)
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": interfaces.len(),
+                    "NextToken": "",
+                    "NetworkInterfaceSets": { "NetworkInterfaceSet": interfaces },
+                }))
+            }
+            "DescribeDisks" => {
+                let instance_id = param("InstanceId");
+                let disks = in_region
+                    .filter(|instance| {
+                        instance_id.is_empty() || instance.instance_id == instance_id
+                    })
+                    .flat_map(|instance| {
+                        instance
+                            .disk_ids
+                            .iter()
+                            .enumerate()
+                            .map(|(index, disk_id)| {
+                                json!({
+                                    "DiskId": disk_id,
+                                    "InstanceId": instance.instance_id,
+                                    "Type": if index == 0 { "system" } else { "data" },
+                                    "Category": "cloud_essd",
+                                    "Size": if index == 0 { 40 } else { 100 },
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                Ok(
+                    json!({ "RequestId": request_id, "TotalCount": disks.len(), "Disks": { "Disk": disks } }),
+                )
+            }
+            "CreateSnapshot" => {
+                let disk_id = param("DiskId");
+                let mut in_region = in_region;
+                if !in_region.any(|instance| instance.disk_ids.iter().any(|id| id == disk_id)) {
+                    return Err(FakeRejection::new(404, "InvalidDiskId.NotFound"));
+                }
+                let mut snapshots = self.snapshots.lock().unwrap();
+                let snapshot_id = format!("s-fake{:04}", snapshots.len() + 1);
+                snapshots.push((
+                    snapshot_id.clone(),
+                    disk_id.to_owned(),
+                    param("SnapshotName").to_owned(),
+                ));
+                Ok(json!({ "RequestId": request_id, "SnapshotId": snapshot_id }))
+            }
+            "DescribeSnapshots" => {
+                let ids: Vec<String> = serde_json::from_str(param("SnapshotIds"))
+                    .map_err(|_| FakeRejection::new(400, "InvalidSnapshotIds.Malformed"))?;
+                let snapshots = self
+                    .snapshots
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(snapshot_id, ..)| ids.contains(snapshot_id))
+                    .map(|(snapshot_id, disk_id, name)| {
+                        json!({
+                            "SnapshotId": snapshot_id,
+                            "SourceDiskId": disk_id,
+                            "SnapshotName": name,
+                            "Status": "accomplished",
+                            "Progress": "100%",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "RequestId": request_id, "Snapshots": { "Snapshot": snapshots } }))
+            }
+            "DescribeLaunchTemplates" => {
+                let templates = self
+                    .launch_templates
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(FakeLaunchTemplate::to_json)
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": templates.len(),
+                    "LaunchTemplateSets": { "LaunchTemplateSet": templates },
+                }))
+            }
+            "DescribeLaunchTemplateVersions" => {
+                let templates = self.launch_templates.lock().unwrap();
+                let template = templates
+                    .iter()
+                    .find(|template| template.launch_template_id == param("LaunchTemplateId"))
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidLaunchTemplate.NotFound"))?;
+                let wanted = (1..)
+                    .map_while(|index| params.get(&format!("LaunchTemplateVersion.{index}")))
+                    .filter_map(|version| version.parse::<usize>().ok())
+                    .collect::<Vec<_>>();
+                let versions = (1..=template.versions.len())
+                    .filter(|version| wanted.is_empty() || wanted.contains(version))
+                    .map(|version| template.version_json(version))
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": versions.len(),
+                    "LaunchTemplateVersionSets": { "LaunchTemplateVersionSet": versions },
+                }))
+            }
+            "CreateLaunchTemplateVersion" => {
+                let mut templates = self.launch_templates.lock().unwrap();
+                let template = templates
+                    .iter_mut()
+                    .find(|template| template.launch_template_id == param("LaunchTemplateId"))
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidLaunchTemplate.NotFound"))?;
+                let data = params
+                    .iter()
+                    .filter(|(name, _)| {
+                        FAKE_TEMPLATE_PARAMS
+                            .iter()
+                            .any(|prefix| name.starts_with(prefix))
+                    })
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                template
+                    .versions
+                    .push((param("VersionDescription").to_owned(), data));
+                Ok(json!({
+                    "RequestId": request_id,
+                    "LaunchTemplateId": template.launch_template_id,
+                    "LaunchTemplateVersionNumber": template.versions.len(),
+                }))
+            }
+            "ModifyLaunchTemplateDefaultVersion" => {
+                let mut templates = self.launch_templates.lock().unwrap();
+                let template = templates
+                    .iter_mut()
+                    .find(|template| template.launch_template_id == param("LaunchTemplateId"))
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidLaunchTemplate.NotFound"))?;
+                let version = param("DefaultVersionNumber").parse::<usize>().unwrap_or(0);
+                if !(1..=template.versions.len()).contains(&version) {
+                    return Err(FakeRejection::new(
+                        404,
+                        "InvalidLaunchTemplateVersion.NotFound",
+                    ));
+                }
+                template.default_version_number = version;
+                Ok(json!({ "RequestId": request_id }))
+            }
+            "DescribeZones" => {
+                let zones = ["h", "i"]
+                    .iter()
+                    .map(|suffix| {
+                        json!({
+                            "ZoneId": format!("{region_id}-{suffix}"),
+                            "LocalName": format!("可用区{}", suffix.to_uppercase()),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "RequestId": request_id, "Zones": { "Zone": zones } }))
+            }
+            "DescribeAvailableResource" => {
+                let suffix = param("ZoneId")
+                    .strip_prefix(region_id)
+                    .and_then(|zone| zone.strip_prefix('-'))
+                    .ok_or_else(|| FakeRejection::new(400, "InvalidZoneId.NotFound"))?;
+                let instance_type = param("InstanceType");
+                let resources = match param("DestinationResource") {
+                    "InstanceType" => FAKE_INSTANCE_TYPES
+                        .iter()
+                        .filter(|(zone, value, _)| {
+                            *zone == suffix && (instance_type.is_empty() || *value == instance_type)
+                        })
+                        .map(|(_, value, in_stock)| {
+                            json!({
+                                "Value": value,
+                                "Status": if *in_stock { "Available" } else { "SoldOut" },
+                                "StatusCategory": if *in_stock { "WithStock" } else { "WithoutStock" },
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                    destination @ ("SystemDisk" | "DataDisk") => {
+                        if instance_type.is_empty() {
+                            return Err(FakeRejection::new(400, "MissingInstanceType"));
+                        }
+                        let max = if destination == "SystemDisk" { 2048 } else { 32768 };
+                        ["cloud_essd", "cloud_efficiency"]
+                            .iter()
+                            .map(|category| {
+                                json!({
+                                    "Value": category,
+                                    "Status": "Available",
+                                    "StatusCategory": "WithStock",
+                                    "Min": 20,
+                                    "Max": max,
+                                    "Unit": "GiB",
+                                })
+                            })
+                            .collect()
+                    }
+                    _ => return Err(FakeRejection::new(400, "InvalidDestinationResource")),
+                };
+                Ok(json!({
+                    "RequestId": request_id,
+                    "AvailableZones": { "AvailableZone": [{
+                        "ZoneId": param("ZoneId"),
+                        "Status": "Available",
+                        "AvailableResources": { "AvailableResource": [{
+                            "Type": param("DestinationResource"),
+                            "SupportedResources": { "SupportedResource": resources },
+                        }] },
+                    }] },
+                }))
+            }
+            "DescribeImages" => {
+                let images = match param("ImageOwnerAlias") {
+                    "" | "system" => FAKE_IMAGES
+                        .iter()
+                        .map(|(image_id, os_name)| {
+                            json!({
+                                "ImageId": image_id,
+                                "ImageName": image_id.trim_end_matches(".vhd"),
+                                "OSName": os_name,
+                                "Architecture": "x86_64",
+                                "ImageOwnerAlias": "system",
+                                "Size": 20,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                    _ => Vec::new(),
+                };
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": images.len(),
+                    "Images": { "Image": images },
+                }))
+            }
+            "DescribeVpcs" => {
+                let vpcs = FAKE_VPCS
+                    .iter()
+                    .map(|(vpc_id, name, cidr_block)| {
+                        json!({
+                            "VpcId": vpc_id,
+                            "VpcName": name,
+                            "CidrBlock": cidr_block,
+                            "IsDefault": *name == "default",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": vpcs.len(),
+                    "Vpcs": { "Vpc": vpcs },
+                }))
+            }
+            "DescribeVSwitches" => {
+                let vswitches = FAKE_VSWITCHES
+                    .iter()
+                    .map(|(vswitch_id, vpc_id, suffix)| {
+                        (*vswitch_id, *vpc_id, format!("{region_id}-{suffix}"))
+                    })
+                    .filter(|(vswitch_id, vpc_id, zone_id)| {
+                        [
+                            ("VSwitchId", *vswitch_id),
+                            ("VpcId", *vpc_id),
+                            ("ZoneId", zone_id.as_str()),
+                        ]
+                        .iter()
+                        .all(|(name, value)| param(name).is_empty() || param(name) == *value)
+                    })
+                    .enumerate()
+                    .map(|(index, (vswitch_id, vpc_id, zone_id))| {
+                        json!({
+                            "VSwitchId": vswitch_id,
+                            "VSwitchName": format!("vsw-{zone_id}"),
+                            "VpcId": vpc_id,
+                            "ZoneId": zone_id,
+                            "CidrBlock": format!("172.16.{index}.0/24"),
+                            "AvailableIpAddressCount": 252,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": vswitches.len(),
+                    "VSwitches": { "VSwitch": vswitches },
+                }))
+            }
+            "DescribeSecurityGroups" => {
+                let ids: Option<Vec<String>> = match param("SecurityGroupIds") {
+                    "" => None,
+                    ids => Some(serde_json::from_str(ids).map_err(|_| {
+                        FakeRejection::new(400, "InvalidSecurityGroupIds.Malformed")
+                    })?),
+                };
+                let vpc_id = param("VpcId");
+                let groups = FAKE_SECURITY_GROUPS
+                    .iter()
+                    .filter(|(group_id, group_vpc_id)| {
+                        ids.as_ref()
+                            .is_none_or(|ids| ids.iter().any(|id| id == group_id))
+                            && (vpc_id.is_empty() || vpc_id == *group_vpc_id)
+                    })
+                    .map(|(group_id, vpc_id)| {
+                        json!({
+                            "SecurityGroupId": group_id,
+                            "SecurityGroupName": format!("{group_id}-name"),
+                            "VpcId": vpc_id,
+                            "Description": "",
+                            "SecurityGroupType": "normal",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "TotalCount": groups.len(),
+                    "SecurityGroups": { "SecurityGroup": groups },
+                }))
+            }
+            "DescribeSecurityGroupAttribute" => {
+                let group_id = param("SecurityGroupId");
+                let (_, vpc_id) = FAKE_SECURITY_GROUPS
+                    .iter()
+                    .find(|(id, _)| *id == group_id)
+                    .ok_or_else(|| FakeRejection::new(404, "InvalidSecurityGroupId.NotFound"))?;
+                let direction = match param("Direction") {
+                    "" => "all",
+                    direction => direction,
+                };
+                let permissions = self
+                    .security_group_rules
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|rule| {
+                        rule.region_id == region_id && rule.security_group_id == group_id
+                    })
+                    .filter(|rule| direction == "all" || rule.direction == direction)
+                    .map(FakeSecurityGroupRule::to_json)
+                    .collect::<Vec<_>>();
+                Ok(json!({
+                    "RequestId": request_id,
+                    "RegionId": region_id,
+                    "SecurityGroupId": group_id,
+                    "SecurityGroupName": format!("{group_id}-name"),
+                    "VpcId": vpc_id,
+                    "Description": "",
+                    "InnerAccessPolicy": "Accept",
+                    "Permissions": { "Permission": permissions },
+                }))
+            }
+            "AuthorizeSecurityGroup"
+            | "AuthorizeSecurityGroupEgress"
+            | "ModifySecurityGroupRule"
+            | "ModifySecurityGroupEgressRule"
+            | "RevokeSecurityGroup"
+            | "RevokeSecurityGroupEgress" => {
+                let group_id = param("SecurityGroupId");
+                if !FAKE_SECURITY_GROUPS.iter().any(|(id, _)| *id == group_id) {
+                    return Err(FakeRejection::new(404, "InvalidSecurityGroupId.NotFound"));
+                }
+                let direction = if action.contains("Egress") {
+                    "egress"
+                } else {
+                    "ingress"
+                };
+                let mut rules = self.security_group_rules.lock().unwrap();
+                let in_group = |rule: &FakeSecurityGroupRule| {
+                    rule.region_id == region_id
+                        && rule.security_group_id == group_id
+                        && rule.direction == direction
+                };
+
+                if action.starts_with("Authorize") {
+                    let mut next_id = rules
+                        .iter()
+                        .filter_map(|rule| rule.security_group_rule_id.strip_prefix("sgr-fake"))
+                        .filter_map(|number| number.parse::<usize>().ok())
+                        .max()
+                        .unwrap_or(0);
+                    let prefixes = (1..)
+                        .map(|index| format!("Permissions.{index}."))
+                        .take_while(|prefix| params.contains_key(&format!("{prefix}IpProtocol")))
+                        .collect::<Vec<_>>();
+                    if prefixes.is_empty() {
+                        return Err(FakeRejection::new(400, "MissingParameter"));
+                    }
+                    for prefix in prefixes {
+                        next_id += 1;
+                        let mut rule = FakeSecurityGroupRule {
+                            region_id: region_id.to_owned(),
+                            security_group_id: group_id.to_owned(),
+                            security_group_rule_id: format!("sgr-fake{next_id:04}"),
+                            direction: direction.to_owned(),
+                            ip_protocol: String::new(),
+                            port_range: String::new(),
+                            peer: String::new(),
+                            policy: "Accept".to_owned(),
+                            priority: 1,
+                            description: String::new(),
+                        };
+                        rule.update(params, &prefix);
+                        let duplicate = rules.iter().filter(|other| in_group(other)).any(|other| {
+                            FakeSecurityGroupRule {
+                                security_group_rule_id: rule.security_group_rule_id.clone(),
+                                description: rule.description.clone(),
+                                ..other.clone()
+                            } == rule
+                        });
+                        if duplicate {
+                            return Err(FakeRejection::new(400, "InvalidPermission.Duplicate"));
+                        }
+                        rules.push(rule);
+                    }
+                } else if action.starts_with("Modify") {
+                    let rule = rules
+                        .iter_mut()
+                        .filter(|rule| in_group(rule))
+                        .find(|rule| rule.security_group_rule_id == param("SecurityGroupRuleId"))
+                        .ok_or_else(|| {
+                            FakeRejection::new(404, "InvalidSecurityGroupRuleId.NotFound")
+                        })?;
+                    rule.update(params, "");
+                } else {
+                    let ids = (1..)
+                        .map_while(|index| params.get(&format!("SecurityGroupRuleId.{index}")))
+                        .collect::<Vec<_>>();
+                    for id in &ids {
+                        if !rules
+                            .iter()
+                            .any(|rule| in_group(rule) && rule.security_group_rule_id == **id)
+                        {
+                            return Err(FakeRejection::new(
+                                404,
+                                "InvalidSecurityGroupRuleId.NotFound",
+                            ));
+                        }
+                    }
+                    rules.retain(|rule| !ids.contains(&&rule.security_group_rule_id));
+                }
+                Ok(json!({ "RequestId": request_id }))
+            }
+            "DescribeKeyPairs" => Ok(json!({
+                "RequestId": request_id,
+                "TotalCount": 1,
+                "KeyPairs": { "KeyPair": [{
+                    "KeyPairName": FAKE_KEY_PAIR,
+                    "KeyPairFingerPrint": "fa:ke:00:01",
+                }] },
+            })),
+            // Only what the launch wizard sends: a vSwitch, security groups and a system
+            // image, nothing being checked against the others.
+            "RunInstances" => {
+                // The parameters of the template version, overridden by those of the call.
+                let template_id = param("LaunchTemplateId");
+                let params = &if template_id.is_empty() {
+                    params.clone()
+                } else {
+                    let templates = self.launch_templates.lock().unwrap();
+                    let template = templates
+                        .iter()
+                        .find(|template| template.launch_template_id == template_id)
+                        .ok_or_else(|| FakeRejection::new(404, "InvalidLaunchTemplate.NotFound"))?;
+                    let version_number = match param("LaunchTemplateVersion") {
+                        "" => template.default_version_number,
+                        version => version.parse().unwrap_or(0),
+                    };
+                    let Some((_, version)) = version_number
+                        .checked_sub(1)
+                        .and_then(|index| template.versions.get(index))
+                    else {
+                        return Err(FakeRejection::new(
+                            404,
+                            "InvalidLaunchTemplateVersion.NotFound",
+                        ));
+                    };
+                    let mut merged = version.clone();
+                    merged.extend(params.clone());
+                    merged
+                };
+                let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+                // The zone is the one of the vSwitch when not given.
+                let zone = match param("ZoneId") {
+                    "" => FAKE_VSWITCHES
+                        .iter()
+                        .find(|(id, ..)| *id == param("VSwitchId"))
+                        .map(|(.., suffix)| *suffix)
+                        .ok_or_else(|| FakeRejection::new(404, "InvalidVSwitchId.NotFound"))?,
+                    zone_id => zone_id
+                        .strip_prefix(region_id)
+                        .and_then(|zone| zone.strip_prefix('-'))
+                        .ok_or_else(|| FakeRejection::new(400, "InvalidZoneId.NotFound"))?,
+                };
+                let instance_type = param("InstanceType");
+                let Some((_, _, in_stock)) = FAKE_INSTANCE_TYPES
+                    .iter()
+                    .find(|(suffix, value, _)| *suffix == zone && *value == instance_type)
+                else {
+                    return Err(FakeRejection::new(
+                        403,
+                        "InvalidInstanceType.ValueNotSupported",
+                    ));
+                };
+                if !in_stock {
+                    return Err(FakeRejection::new(403, "OperationDenied.NoStock"));
+                }
+                if !FAKE_IMAGES.iter().any(|(id, _)| *id == param("ImageId")) {
+                    return Err(FakeRejection::new(404, "InvalidImageId.NotFound"));
+                }
+                if !FAKE_VSWITCHES
+                    .iter()
+                    .any(|(id, ..)| *id == param("VSwitchId"))
+                {
+                    return Err(FakeRejection::new(404, "InvalidVSwitchId.NotFound"));
+                }
+                let security_group_ids = (1..)
+                    .map_while(|index| params.get(&format!("SecurityGroupIds.{index}")))
+                    .collect::<Vec<_>>();
+                if security_group_ids.is_empty()
+                    || !security_group_ids.iter().all(|id| {
+                        FAKE_SECURITY_GROUPS
+                            .iter()
+                            .any(|(group_id, _)| group_id == id)
+                    })
+                {
+                    return Err(FakeRejection::new(404, "InvalidSecurityGroupId.NotFound"));
+                }
+                let key_pair_name = param("KeyPairName");
+                if !key_pair_name.is_empty() && key_pair_name != FAKE_KEY_PAIR {
+                    return Err(FakeRejection::new(404, "InvalidKeyPair.NotFound"));
+                }
+                if param("DryRun") == "true" {
+                    return Err(FakeRejection::new(400, "DryRunOperation"));
+                }
+
+                let client_token = param("ClientToken");
+                let mut launches = self.launches.lock().unwrap();
+                if let Some(instance_ids) = launches.get(client_token) {
+                    return Ok(json!({
+                        "RequestId": request_id,
+                        "InstanceIdSets": { "InstanceIdSet": instance_ids },
+                    }));
+                }
+                let amount = param("Amount").parse::<usize>().unwrap_or(1);
+                let next = instances
+                    .iter()
+                    .filter_map(|instance| instance.instance_id.strip_prefix("i-fake"))
+                    .filter_map(|number| number.parse::<usize>().ok())
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                let (cpu, memory) = match instance_type {
+                    "ecs.r7.xlarge" => (4, 32768),
+                    _ => (2, 8192),
+                };
+                let charge_type = match param("InstanceChargeType") {
+                    "" => "PostPaid",
+                    charge_type => charge_type,
+                };
+                let created = (next..next + amount)
+                    .map(|number| {
+                        let instance_id = format!("i-fake{number:04}");
+                        FakeInstance {
+                            instance_name: match param("InstanceName") {
+                                "" => instance_id.clone(),
+                                name => name.to_owned(),
+                            },
+                            region_id: region_id.to_owned(),
+                            zone_id: format!("{region_id}-{zone}"),
+                            instance_type: instance_type.to_owned(),
+                            cpu,
+                            memory,
+                            status: "Running".to_owned(),
+                            private_ip: format!("172.16.0.{}", number + 10),
+                            public_ip: (param("InternetMaxBandwidthOut")
+                                .parse::<u32>()
+                                .unwrap_or(0)
+                                > 0)
+                            .then(|| format!("47.96.0.{}", number + 10)),
+                            creation_time: "2026-01-01T00:00Z".to_owned(),
+                            start_time: "2026-01-01T00:01Z".to_owned(),
+                            instance_charge_type: charge_type.to_owned(),
+                            expired_time: "2099-12-31T15:59Z".to_owned(),
+                            tags: (1..)
+                                .map_while(|index| {
+                                    let key = params.get(&format!("Tag.{index}.Key"))?;
+                                    let value = params
+                                        .get(&format!("Tag.{index}.Value"))
+                                        .cloned()
+                                        .unwrap_or_default();
+                                    Some((key.clone(), value))
+                                })
+                                .collect(),
+                            stopped_mode: "KeepCharging".to_owned(),
+                            deletion_protection: false,
+                            disk_ids: vec![instance_id.replace("i-", "d-")],
+                            image_id: param("ImageId").to_owned(),
+                            key_pair_name: param("KeyPairName").to_owned(),
+                            instance_id,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let instance_ids = created
+                    .iter()
+                    .map(|instance| instance.instance_id.clone())
+                    .collect::<Vec<_>>();
+                instances.extend(created);
+                if !client_token.is_empty() {
+                    launches.insert(client_token.to_owned(), instance_ids.clone());
+                }
+                Ok(json!({
+                    "RequestId": request_id,
+                    "InstanceIdSets": { "InstanceIdSet": instance_ids },
+                }))
+            }
+            _ => Err(FakeRejection::new(404, "InvalidAction.NotFound")),
+        }
+    }
+}
+
+fn stopped_mode(mode: &str) -> Result<&str, FakeRejection> {
+    match mode {
+        "" => Ok("KeepCharging"),
+        "KeepCharging" | "StopCharging" => Ok(mode),
+        _ => Err(FakeRejection::new(400, "InvalidParameter.StoppedMode")),
+    }
+}
+
+/// Applies a power action to the instance, right away.
+fn change_status(
+    instance: &mut FakeInstance,
+    action: &str,
+    stopped_mode: &str,
+) -> Result<(), FakeRejection> {
+    let (from, to) = match action {
+        "StartInstance" => ("Stopped", "Running"),
+        "StopInstance" => ("Running", "Stopped"),
+        _ => ("Running", "Running"),
+    };
+    if instance.status != from {
+        return Err(FakeRejection::new(403, "IncorrectInstanceStatus"));
+    }
+    instance.status = to.to_owned();
+    if action == "StopInstance" {
+        instance.stopped_mode = stopped_mode.to_owned();
+    } else {
+        let started = NaiveDateTime::parse_from_str(&instance.start_time, FAKE_TIME_FORMAT)
+            .unwrap_or_default();
+        instance.start_time = (started + TimeDelta::minutes(1))
+            .format(FAKE_TIME_FORMAT)
+            .to_string();
+    }
+    Ok(())
+}
+
+/// Serves a [`FakeAliyun`] over HTTP on a local port, until it is dropped.
+///
+/// Point the endpoints at [`Self::uri`] to send every request to it.
+pub struct FakeAliyunServer {
+    #[cfg(test)]
+    fake: Arc<FakeAliyun>,
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl FakeAliyunServer {
+    /// Starts serving the fake on a free port of the loopback interface.
+    pub async fn start(fake: FakeAliyun) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let address = listener.local_addr()?;
+        let fake = Arc::new(fake);
+
+        let served = fake.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    continue;
+                };
+                let fake = served.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = serve(stream, &fake).await {
+                        log::debug!("The fake Aliyun server failed to answer: {err}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            #[cfg(test)]
+            fake,
+            address,
+            task,
+        })
+    }
+
+    pub fn uri(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Answers [`FAKE_PUBLIC_IP`], standing in for the public IP discovery service.
+    pub fn public_ip_url(&self) -> String {
+        format!("{}{PUBLIC_IP_PATH}", self.uri())
+    }
+
+    #[cfg(test)]
+    pub fn fake(&self) -> &FakeAliyun {
+        &self.fake
+    }
+
+    /// An ECS client of the region holding the instances, signing with [`fake_credentials`].
+    #[cfg(test)]
+    pub fn region_client(&self) -> RegionClient {
+        self.api_client(RegionalApi::Ecs)
+    }
+
+    /// Like [`Self::region_client`], for another API of the region.
+    #[cfg(test)]
+    pub fn api_client(&self, api: RegionalApi) -> RegionClient {
+        RegionClient::for_api(
+            api,
+            FAKE_REGIONS[0].0,
+            self.uri(),
+            (&fake_credentials()).into(),
+        )
+    }
+}
+
+impl Drop for FakeAliyunServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answers the single `GET` request of the connection, then closes it.
+async fn serve(mut stream: TcpStream, fake: &FakeAliyun) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 4096];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || head.len() > 64 * 1024 {
+            return Ok(());
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let target = head.split_whitespace().nth(1).unwrap_or("/");
+    let (content_type, status, body) = if target.split('?').next() == Some(PUBLIC_IP_PATH) {
+        ("text/plain", 200, FAKE_PUBLIC_IP.to_owned())
+    } else {
+        let params = reqwest::Url::parse(&format!("http://fake{target}"))
+            .map(|url| url.query_pairs().into_owned().collect::<RpcParams>())
+            .unwrap_or_default();
+        let (status, body) = fake.respond(&params);
+        ("application/json", status, body.to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        if status == 200 { "OK" } else { "Error" },
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use alibabacloud::client::error::OperationError;
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    use crate::services::rpc::RpcClient;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Described {
+        total_count: u32,
+    }
+
+    #[tokio::test]
+    async fn test_fake_aliyun_server() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let client = server.region_client();
+
+        let described: Described = client
+            .call("DescribeInstances", RpcParams::new())
+            .await
+            .unwrap();
+        assert_eq!(described.total_count, 3);
+
+        let params = RpcParams::from([("InstanceId".to_owned(), "i-fake0003".to_owned())]);
+        client
+            .call::<Value>("StartInstance", params.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            server.fake().instance("i-fake0003").unwrap().status,
+            "Running"
+        );
+        let err = client
+            .call::<Value>("StartInstance", params)
+            .await
+            .unwrap_err();
+        assert_matches!(err.error, OperationError::Rejected(r) if r.code == "IncorrectInstanceStatus");
+
+        server.fake().reject(
+            "StopInstance",
+            FakeRejection::new(503, "ServiceUnavailable"),
+        );
+        let params = RpcParams::from([("InstanceId".to_owned(), "i-fake0001".to_owned())]);
+        assert!(client.call::<Value>("StopInstance", params).await.is_err());
+        assert_eq!(
+            server.fake().instance("i-fake0001").unwrap().status,
+            "Running"
+        );
+
+        let wrong_secret = RpcClient::new(
+            server.uri(),
+            (&AccessKeyCredentials::new(FAKE_ACCESS_KEY_ID, "WRONG")).into(),
+        );
+        let err = wrong_secret
+            .call::<Value>("GetCallerIdentity", "2015-04-01", RpcParams::new())
+            .await
+            .unwrap_err();
+        assert_matches!(err.error, OperationError::Rejected(r) if r.code == "SignatureDoesNotMatch");
+    }
+}
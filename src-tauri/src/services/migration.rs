@@ -0,0 +1,278 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::types::Store;
+
+/// The key holding the schema version of the store, absent from stores written before
+/// versioning, which are at version 0.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// The entries of the store, migrated as a whole.
+pub type StoreEntries = Map<String, Value>;
+
+/// Brings the entries of the store from the previous version to `version`.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub migrate: fn(&mut StoreEntries) -> Result<(), String>,
+}
+
+/// Every migration, ordered by version. Never edit a released migration, add a new one.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "record the schema version",
+    migrate: |_| Ok(()),
+}];
+
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// The descriptions of the migrations which ran, in order.
+    pub applied: Vec<String>,
+    /// Where the store has been copied before migrating, if it has been.
+    pub backup_path: Option<String>,
+}
+
+#[derive(Debug, Clone, thiserror::Error, Serialize, specta::Type)]
+#[serde(tag = "type", content = "error")]
+pub enum StoreMigrationError {
+    #[error("the store has schema version {found}, this version of the app only supports up to {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("the schema version of the store is not valid: {0}")]
+    InvalidVersion(String),
+    #[error("failed to back up the store to {path}: {message}")]
+    BackupFailed { path: String, message: String },
+    #[error("failed to migrate the store to version {version} ({description}): {message}")]
+    MigrationFailed {
+        version: u32,
+        description: String,
+        message: String,
+    },
+    #[error("failed to save the migrated store: {0}")]
+    SaveFailed(String),
+}
+
+/// The outcome of the migration run at startup, kept for the frontend to check.
+pub struct StoreMigrationStatus(pub Result<MigrationReport, StoreMigrationError>);
+
+fn schema_version(entries: &StoreEntries) -> Result<u32, StoreMigrationError> {
+    match entries.get(SCHEMA_VERSION_KEY) {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| StoreMigrationError::InvalidVersion(value.to_string())),
+    }
+}
+
+/// Runs the migrations the entries haven't been through yet, in order.
+///
+/// Returns the version the entries were at and the migrations which ran. The entries
+/// are left in an unspecified state when a migration fails.
+pub fn migrate_entries<'m>(
+    entries: &mut StoreEntries,
+    migrations: &'m [Migration],
+) -> Result<(u32, Vec<&'m Migration>), StoreMigrationError> {
+    let from_version = schema_version(entries)?;
+    let supported = migrations.last().map_or(0, |migration| migration.version);
+    if from_version > supported {
+        return Err(StoreMigrationError::UnsupportedVersion {
+            found: from_version,
+            supported,
+        });
+    }
+
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > from_version) {
+        (migration.migrate)(entries).map_err(|message| StoreMigrationError::MigrationFailed {
+            version: migration.version,
+            description: migration.description.to_owned(),
+            message,
+        })?;
+        entries.insert(SCHEMA_VERSION_KEY.to_owned(), migration.version.into());
+        applied.push(migration);
+    }
+    Ok((from_version, applied))
+}
+
+/// Where the store file at `path` is copied before migrating it from `version`.
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".v{version}.bak"));
+    path.with_file_name(file_name)
+}
+
+/// Migrates the store, saved at `path`, to the latest schema version.
+///
+/// The file is copied next to itself before anything is changed, and the store is
+/// only rewritten once every migration succeeded, so a failure never loses data.
+pub fn migrate_store<R: tauri::Runtime>(
+    store: &Store<R>,
+    path: &Path,
+    migrations: &[Migration],
+) -> Result<MigrationReport, StoreMigrationError> {
+    let mut entries: StoreEntries = store.entries().into_iter().collect();
+    let is_new = entries.is_empty();
+    let from_version = schema_version(&entries)?;
+
+    let mut backup = None;
+    if !is_new && path.exists() && migrations.iter().any(|m| m.version > from_version) {
+        let to = backup_path(path, from_version);
+        std::fs::copy(path, &to).map_err(|err| StoreMigrationError::BackupFailed {
+            path: to.display().to_string(),
+            message: err.to_string(),
+        })?;
+        log::info!("Backed up the store to {}", to.display());
+        backup = Some(to.display().to_string());
+    }
+
+    let (from_version, applied) = migrate_entries(&mut entries, migrations)?;
+    let report = MigrationReport {
+        from_version,
+        to_version: schema_version(&entries)?,
+        applied: applied
+            .iter()
+            .map(|migration| migration.description.to_owned())
+            .collect(),
+        backup_path: backup,
+    };
+    if applied.is_empty() {
+        return Ok(report);
+    }
+
+    for key in store.keys() {
+        if !entries.contains_key(&key) {
+            store.delete(&key);
+        }
+    }
+    for (key, value) in entries {
+        store.set(key, value);
+    }
+    store
+        .save()
+        .map_err(|err| StoreMigrationError::SaveFailed(err.to_string()))?;
+
+    log::info!(
+        "Migrated the store from schema version {} to {}",
+        report.from_version,
+        report.to_version
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::services::auth::store::store_test_utils;
+
+    use super::*;
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "rename the greeting",
+            migrate: |entries| {
+                let greeting = entries.remove("greeting").ok_or("no greeting")?;
+                entries.insert("salutation".to_owned(), greeting);
+                Ok(())
+            },
+        },
+        Migration {
+            version: 2,
+            description: "shout the salutation",
+            migrate: |entries| {
+                let salutation = entries
+                    .get("salutation")
+                    .and_then(Value::as_str)
+                    .ok_or("the salutation is not a string")?
+                    .to_uppercase();
+                entries.insert("salutation".to_owned(), salutation.into());
+                Ok(())
+            },
+        },
+    ];
+
+    #[test]
+    fn test_migrate_entries() {
+        let mut entries = StoreEntries::from_iter([("greeting".to_owned(), json!("hello"))]);
+        let (from_version, applied) = migrate_entries(&mut entries, TEST_MIGRATIONS).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(applied.len(), 2);
+        assert_eq!(
+            Value::Object(entries.clone()),
+            json!({ "salutation": "HELLO", "schema_version": 2 })
+        );
+
+        let (from_version, applied) = migrate_entries(&mut entries, TEST_MIGRATIONS).unwrap();
+        assert_eq!(from_version, 2);
+        assert!(applied.is_empty());
+
+        let mut entries = StoreEntries::from_iter([
+            ("salutation".to_owned(), json!(42)),
+            (SCHEMA_VERSION_KEY.to_owned(), json!(1)),
+        ]);
+        assert_matches!(
+            migrate_entries(&mut entries, TEST_MIGRATIONS),
+            Err(StoreMigrationError::MigrationFailed { version: 2, .. })
+        );
+
+        let mut entries = StoreEntries::from_iter([(SCHEMA_VERSION_KEY.to_owned(), json!(3))]);
+        assert_matches!(
+            migrate_entries(&mut entries, TEST_MIGRATIONS),
+            Err(StoreMigrationError::UnsupportedVersion {
+                found: 3,
+                supported: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_migrate_store() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("store.json");
+        std::fs::write(&path, json!({ "greeting": "hello" }).to_string()).unwrap();
+        let store = store_test_utils::init_store_at(&path);
+
+        let report = migrate_store(&store, &path, TEST_MIGRATIONS).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, 2);
+        let backup = backup_path(&path, 0);
+        assert_eq!(report.backup_path, Some(backup.display().to_string()));
+        assert_eq!(
+            std::fs::read_to_string(&backup).unwrap(),
+            json!({ "greeting": "hello" }).to_string()
+        );
+        assert_eq!(store.get("greeting"), None);
+        assert_eq!(store.get("salutation"), Some(json!("HELLO")));
+
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved[SCHEMA_VERSION_KEY], json!(2));
+
+        let report = migrate_store(&store, &path, TEST_MIGRATIONS).unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.backup_path, None);
+    }
+
+    #[test]
+    fn test_failed_migration_keeps_store() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("store.json");
+        std::fs::write(&path, json!({ "unrelated": true }).to_string()).unwrap();
+        let store = store_test_utils::init_store_at(&path);
+
+        assert_matches!(
+            migrate_store(&store, &path, TEST_MIGRATIONS),
+            Err(StoreMigrationError::MigrationFailed { version: 1, .. })
+        );
+        assert_eq!(store.get("unrelated"), Some(json!(true)));
+        assert_eq!(store.get(SCHEMA_VERSION_KEY), None);
+        assert!(backup_path(&path, 0).exists());
+    }
+}
@@ -2,3 +2,12 @@ pub mod error;
 
 pub mod auth;
 pub mod client;
+pub mod demo;
+pub mod ecs;
+pub mod endpoint;
+pub mod fake;
+pub mod migration;
+pub mod policy;
+pub mod region;
+pub mod retry;
+pub mod rpc;
@@ -0,0 +1,275 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::error::RamPermission;
+
+/// The version of the RAM policy language.
+const POLICY_VERSION: &str = "1";
+
+/// A part of the app needing its own RAM actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum AppFeature {
+    /// Rotating the access key of the current RAM user.
+    AccessKeyRotation,
+    /// Assuming roles from role profiles.
+    RoleProfiles,
+    /// Listing the instances of every region.
+    Inventory,
+    /// Starting, stopping and rebooting instances, one by one or in batches.
+    PowerActions,
+    /// Releasing instances, after a final snapshot of their disks.
+    Release,
+    /// Creating instances, after resolving and checking their configuration.
+    Launch,
+    /// Managing launch templates and launching instances from them.
+    LaunchTemplates,
+    /// Editing the rules of security groups, and rolling the last change back.
+    SecurityGroups,
+    /// Letting the IP of the machine reach a port for a while.
+    TemporaryAccess,
+    /// Auditing the security groups of every region.
+    SecurityAudit,
+    /// Simulating whether traffic reaches an instance through its security groups.
+    Reachability,
+}
+
+impl AppFeature {
+    pub const ALL: &'static [Self] = &[
+        Self::AccessKeyRotation,
+        Self::RoleProfiles,
+        Self::Inventory,
+        Self::PowerActions,
+        Self::Release,
+        Self::Launch,
+        Self::LaunchTemplates,
+        Self::SecurityGroups,
+        Self::TemporaryAccess,
+        Self::SecurityAudit,
+        Self::Reachability,
+    ];
+
+    /// The RAM actions the feature calls.
+    pub fn actions(&self) -> &'static [&'static str] {
+        match self {
+            Self::AccessKeyRotation => &[
+                "ram:ListAccessKeys",
+                "ram:GetAccessKeyLastUsed",
+                "ram:CreateAccessKey",
+                "ram:UpdateAccessKey",
+                "ram:DeleteAccessKey",
+            ],
+            Self::RoleProfiles => &["sts:AssumeRole"],
+            Self::Inventory => &["ecs:DescribeRegions", "ecs:DescribeInstances"],
+            Self::PowerActions => &[
+                "ecs:StartInstance",
+                "ecs:StopInstance",
+                "ecs:RebootInstance",
+                "ecs:StartInstances",
+                "ecs:StopInstances",
+                "ecs:RebootInstances",
+                "ecs:DescribeInstances",
+            ],
+            Self::Release => &[
+                "ecs:DescribeInstances",
+                "ecs:DescribeDisks",
+                "ecs:CreateSnapshot",
+                "ecs:DescribeSnapshots",
+                "ecs:DeleteInstance",
+            ],
+            Self::Launch => &[
+                "ecs:DescribeRegions",
+                "ecs:DescribeZones",
+                "ecs:DescribeAvailableResource",
+                "ecs:DescribeImages",
+                "ecs:DescribeSecurityGroups",
+                "ecs:DescribeKeyPairs",
+                "ecs:RunInstances",
+                "vpc:DescribeVpcs",
+                "vpc:DescribeVSwitches",
+            ],
+            Self::LaunchTemplates => &[
+                "ecs:DescribeLaunchTemplates",
+                "ecs:DescribeLaunchTemplateVersions",
+                "ecs:CreateLaunchTemplateVersion",
+                "ecs:ModifyLaunchTemplateDefaultVersion",
+                "ecs:DescribeInstances",
+                "ecs:DescribeDisks",
+                "ecs:RunInstances",
+            ],
+            Self::SecurityGroups => &[
+                "ecs:DescribeSecurityGroups",
+                "ecs:DescribeSecurityGroupAttribute",
+                "ecs:AuthorizeSecurityGroup",
+                "ecs:AuthorizeSecurityGroupEgress",
+                "ecs:ModifySecurityGroupRule",
+                "ecs:ModifySecurityGroupEgressRule",
+                "ecs:RevokeSecurityGroup",
+                "ecs:RevokeSecurityGroupEgress",
+                "vpc:DescribeVpcs",
+            ],
+            Self::TemporaryAccess => &[
+                "ecs:DescribeSecurityGroupAttribute",
+                "ecs:AuthorizeSecurityGroup",
+                "ecs:ModifySecurityGroupRule",
+                "ecs:RevokeSecurityGroup",
+            ],
+            Self::SecurityAudit => &[
+                "ecs:DescribeRegions",
+                "ecs:DescribeSecurityGroups",
+                "ecs:DescribeSecurityGroupAttribute",
+                "ecs:DescribeInstances",
+            ],
+            Self::Reachability => &[
+                "ecs:DescribeNetworkInterfaces",
+                "ecs:DescribeSecurityGroups",
+                "ecs:DescribeSecurityGroupAttribute",
+            ],
+        }
+    }
+}
+
+/// The permissions of the calls attempted with the current credentials, whether they
+/// succeeded or not.
+///
+/// Filled by the region clients, and emptied whenever the credentials change.
+#[derive(Debug, Default)]
+pub struct PermissionLog {
+    attempted: Mutex<BTreeSet<RamPermission>>,
+}
+
+impl PermissionLog {
+    pub fn record(&self, permission: &RamPermission) {
+        self.attempted.lock().unwrap().insert(permission.clone());
+    }
+
+    /// The attempted permissions, sorted by action then resource.
+    pub fn permissions(&self) -> Vec<RamPermission> {
+        self.attempted.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.attempted.lock().unwrap().clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PolicyStatement {
+    pub effect: String,
+    pub action: Vec<String>,
+    pub resource: Vec<String>,
+}
+
+/// A RAM policy document, only allowing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RamPolicy {
+    pub version: String,
+    pub statement: Vec<PolicyStatement>,
+}
+
+impl RamPolicy {
+    /// The policy allowing the given features, on every resource.
+    pub fn for_features(features: &[AppFeature]) -> Self {
+        let permissions = features.iter().flat_map(|feature| {
+            feature.actions().iter().map(|action| RamPermission {
+                action: (*action).to_owned(),
+                resource: "*".to_owned(),
+            })
+        });
+        Self::for_permissions(permissions)
+    }
+
+    /// The policy allowing exactly the given permissions.
+    ///
+    /// The actions needed on the same resources share a statement, so that the policy
+    /// stays short.
+    pub fn for_permissions<I: IntoIterator<Item = RamPermission>>(permissions: I) -> Self {
+        let mut resources_by_action = BTreeMap::<String, BTreeSet<String>>::new();
+        for permission in permissions {
+            resources_by_action
+                .entry(permission.action)
+                .or_default()
+                .insert(permission.resource);
+        }
+
+        let mut actions_by_resources = BTreeMap::<BTreeSet<String>, Vec<String>>::new();
+        for (action, resources) in resources_by_action {
+            actions_by_resources
+                .entry(resources)
+                .or_default()
+                .push(action);
+        }
+
+        let statement = actions_by_resources
+            .into_iter()
+            .map(|(resources, action)| PolicyStatement {
+                effect: "Allow".to_owned(),
+                action,
+                resource: resources.into_iter().collect(),
+            })
+            .collect();
+        Self {
+            version: POLICY_VERSION.to_owned(),
+            statement,
+        }
+    }
+
+    /// The policy as pasted in the RAM console.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a policy is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_policy() {
+        let policy = RamPolicy::for_features(AppFeature::ALL);
+        assert_eq!(policy.statement.len(), 1);
+        assert_eq!(policy.statement[0].action.len(), 38);
+        assert_eq!(policy.statement[0].resource, vec!["*"]);
+
+        let log = PermissionLog::default();
+        for permission in [
+            RamPermission::ecs("DescribeInstances", "cn-hangzhou", None),
+            RamPermission::ecs("StopInstance", "cn-hangzhou", Some("i-1")),
+            RamPermission::ecs("StartInstance", "cn-hangzhou", Some("i-1")),
+            RamPermission::ecs("StopInstance", "cn-hangzhou", Some("i-1")),
+        ] {
+            log.record(&permission);
+        }
+        let policy = RamPolicy::for_permissions(log.permissions());
+        let value: serde_json::Value = serde_json::from_str(&policy.to_json()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "Version": "1",
+                "Statement": [
+                    {
+                        "Effect": "Allow",
+                        "Action": ["ecs:DescribeInstances"],
+                        "Resource": ["acs:ecs:cn-hangzhou:*:*"],
+                    },
+                    {
+                        "Effect": "Allow",
+                        "Action": ["ecs:StartInstance", "ecs:StopInstance"],
+                        "Resource": ["acs:ecs:cn-hangzhou:*:instance/i-1"],
+                    },
+                ],
+            })
+        );
+
+        log.clear();
+        assert!(log.permissions().is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::services::{
+    endpoint::AliyunService,
+    error::{AliyunRequestCommandError, RamPermission, SpecificError},
+    policy::PermissionLog,
+    retry::CallError,
+    rpc::{normalize_endpoint, RpcClient, RpcCredentials, RpcParams},
+};
+
+/// The region used when neither the call nor the profile picks one.
+pub const DEFAULT_REGION_ID: &str = "cn-hangzhou";
+const ECS_API_VERSION: &str = "2014-05-26";
+const VPC_API_VERSION: &str = "2016-04-28";
+
+/// Whether the region ID looks like one, e.g. `cn-hangzhou` or `ap-southeast-1`.
+pub fn is_valid_region_id(region_id: &str) -> bool {
+    !region_id.is_empty()
+        && region_id.len() <= 64
+        && !region_id.starts_with('-')
+        && !region_id.ends_with('-')
+        && region_id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// The public ECS endpoint serving the region.
+pub fn ecs_endpoint(region_id: &str) -> String {
+    format!("ecs.{region_id}.aliyuncs.com")
+}
+
+/// The regional APIs a [`RegionClient`] calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionalApi {
+    Ecs,
+    Vpc,
+}
+
+impl RegionalApi {
+    pub fn service(&self) -> AliyunService {
+        match self {
+            Self::Ecs => AliyunService::Ecs,
+            Self::Vpc => AliyunService::Vpc,
+        }
+    }
+
+    fn version(&self) -> &'static str {
+        match self {
+            Self::Ecs => ECS_API_VERSION,
+            Self::Vpc => VPC_API_VERSION,
+        }
+    }
+}
+
+/// A client of a regional API, ECS unless built [`RegionClient::for_api`], in a single
+/// region.
+#[derive(Clone)]
+pub struct RegionClient {
+    api: RegionalApi,
+    region_id: String,
+    rpc: RpcClient,
+    /// Where the permissions of the requests are recorded.
+    permission_log: Option<Arc<PermissionLog>>,
+}
+
+impl RegionClient {
+    pub fn new<E: Into<String>>(region_id: &str, endpoint: E, credentials: RpcCredentials) -> Self {
+        Self::for_api(RegionalApi::Ecs, region_id, endpoint, credentials)
+    }
+
+    pub fn for_api<E: Into<String>>(
+        api: RegionalApi,
+        region_id: &str,
+        endpoint: E,
+        credentials: RpcCredentials,
+    ) -> Self {
+        Self {
+            api,
+            region_id: region_id.to_owned(),
+            rpc: RpcClient::new(endpoint, credentials),
+            permission_log: None,
+        }
+    }
+
+    /// Records the permissions of the requests made by the client in `log`.
+    pub fn with_permission_log(mut self, log: Arc<PermissionLog>) -> Self {
+        self.permission_log = Some(log);
+        self
+    }
+
+    pub fn api(&self) -> RegionalApi {
+        self.api
+    }
+
+    pub fn region_id(&self) -> &str {
+        &self.region_id
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    /// Calls an action of the API in the region of the client, `RegionId` being filled in.
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        action: &str,
+        mut params: RpcParams,
+    ) -> Result<T, CallError> {
+        params.insert("RegionId".to_owned(), self.region_id.clone());
+        self.rpc.call(action, self.api.version(), params).await
+    }
+
+    /// Like [`Self::call`], for commands: the permission of the call is recorded, and
+    /// named in the error when it is missing.
+    pub async fn request<T: DeserializeOwned, E: SpecificError>(
+        &self,
+        action: &str,
+        params: RpcParams,
+    ) -> Result<T, AliyunRequestCommandError<E>> {
+        let permission = match self.api {
+            RegionalApi::Ecs => RamPermission::ecs(
+                action,
+                &self.region_id,
+                params.get("InstanceId").map(String::as_str),
+            ),
+            RegionalApi::Vpc => RamPermission::vpc(action, &self.region_id),
+        };
+        if let Some(log) = &self.permission_log {
+            log.record(&permission);
+        }
+        self.call(action, params)
+            .await
+            .map_err(|err| AliyunRequestCommandError::from_call(err, &permission))
+    }
+}
+
+/// The region clients built so far from the credentials in use.
+///
+/// Shared with the task refreshing temporary credentials, which empties it whenever the
+/// credentials change.
+#[derive(Default)]
+pub struct ClientPool {
+    clients: RwLock<HashMap<(RegionalApi, String), RegionClient>>,
+}
+
+impl ClientPool {
+    /// Returns the client of the API in the region, building it with `credentials` when
+    /// there is none yet, or when the pooled one was built from other credentials or for
+    /// another endpoint.
+    pub fn get_or_insert(
+        &self,
+        api: RegionalApi,
+        region_id: &str,
+        endpoint: &str,
+        credentials: RpcCredentials,
+    ) -> RegionClient {
+        let endpoint = normalize_endpoint(endpoint);
+        let is_reusable = |pooled: &RegionClient| {
+            pooled.rpc.credentials() == &credentials && pooled.rpc.endpoint() == endpoint
+        };
+        let key = (api, region_id.to_owned());
+        if let Some(pooled) = self.clients.read().unwrap().get(&key) {
+            if is_reusable(pooled) {
+                return pooled.clone();
+            }
+        }
+
+        let mut clients = self.clients.write().unwrap();
+        // Another task may have built it while the lock was released.
+        if let Some(pooled) = clients.get(&key).filter(|pooled| is_reusable(pooled)) {
+            return pooled.clone();
+        }
+        let client = RegionClient::for_api(api, region_id, endpoint, credentials);
+        clients.insert(key, client.clone());
+        client
+    }
+
+    /// The regions having a client of any API, sorted.
+    pub fn regions(&self) -> Vec<String> {
+        let clients = self.clients.read().unwrap();
+        let regions = clients
+            .keys()
+            .map(|(_, region_id)| region_id.clone())
+            .collect::<BTreeSet<_>>();
+        regions.into_iter().collect()
+    }
+
+    pub fn clear(&self) {
+        self.clients.write().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use crate::services::{
+        error::{NoOther, RejectionCategory},
+        fake::{fake_credentials, FakeAliyun, FakeAliyunServer, FakeRejection},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_region_id() {
+        for region_id in ["cn-hangzhou", "cn-shanghai", "ap-southeast-1"] {
+            assert!(is_valid_region_id(region_id), "{region_id}");
+        }
+        for region_id in [
+            "",
+            "CN-Hangzhou",
+            "cn hangzhou",
+            "-cn",
+            "cn-",
+            "cn.hangzhou",
+        ] {
+            assert!(!is_valid_region_id(region_id), "{region_id:?}");
+        }
+        assert_eq!(
+            ecs_endpoint("ap-southeast-1"),
+            "ecs.ap-southeast-1.aliyuncs.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_permission() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let log = Arc::new(PermissionLog::default());
+        let client = RegionClient::new("cn-hangzhou", server.uri(), (&fake_credentials()).into())
+            .with_permission_log(log.clone());
+
+        server
+            .fake()
+            .reject("StopInstance", FakeRejection::new(403, "Forbidden.RAM"));
+        let params = RpcParams::from([("InstanceId".to_owned(), "i-fake0001".to_owned())]);
+        let err = client
+            .request::<Value, NoOther>("StopInstance", params)
+            .await
+            .unwrap_err();
+        let stop = RamPermission::ecs("StopInstance", "cn-hangzhou", Some("i-fake0001"));
+        assert_matches!(
+            &err,
+            AliyunRequestCommandError::Rejected(r)
+                if r.category == RejectionCategory::PermissionDenied
+                    && r.missing_permission.as_ref() == Some(&stop)
+        );
+
+        server.fake().clear_rejections();
+        client
+            .request::<Value, NoOther>("DescribeInstances", RpcParams::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            log.permissions(),
+            vec![
+                RamPermission::ecs("DescribeInstances", "cn-hangzhou", None),
+                stop
+            ]
+        );
+    }
+}
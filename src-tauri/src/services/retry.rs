@@ -0,0 +1,280 @@
+use std::{fmt, time::Duration};
+
+use alibabacloud::client::error::{OperationError, RequestErrorKind};
+
+use crate::services::{error::RejectionCategory, rpc::RpcParams};
+
+/// Prefixes of the actions which only read, safe to send again.
+const READ_ONLY_ACTION_PREFIXES: &[&str] = &["Describe", "List", "Get", "Query", "Check"];
+
+/// When a failed call is sent again.
+///
+/// Only transient failures are retried, see [`is_transient`], and only for calls which can
+/// be sent twice without harm, see [`is_retry_safe`]. Attempts are spaced by a jittered
+/// exponential delay, until `max_attempts` have been made or the next attempt would end
+/// after `budget`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The attempts made at most, the first one included.
+    pub max_attempts: u32,
+    /// The delay before the second attempt, doubled for each of the following ones.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// How long the attempts of a call may take altogether, delays included.
+    pub budget: Duration,
+    /// How long a single attempt may take, from connecting to reading the response.
+    pub attempt_timeout: Duration,
+    pub connect_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            budget: Duration::from_secs(20),
+            attempt_timeout: Duration::from_secs(10),
+            connect_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt, never retried.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the attempt following `attempt`, picked at random up to the
+    /// exponential backoff so that throttled clients don't retry in lockstep.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        // A random UUID is as good a source of jitter as any, without another dependency.
+        let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as u32;
+        backoff / 2 + backoff / 2 * jitter / 1000
+    }
+}
+
+/// Whether the call failed for a reason which may be gone on the next attempt: throttling,
+/// an unavailable service, or a connection failure or a timeout.
+pub fn is_transient(error: &OperationError) -> bool {
+    match error {
+        OperationError::Rejected(rejection) => {
+            RejectionCategory::of(&rejection.code) == RejectionCategory::Throttled
+                || rejection.code == "ServiceUnavailable"
+        }
+        OperationError::RequestFailure { kind, .. } => {
+            matches!(kind, RequestErrorKind::Timeout | RequestErrorKind::Connect)
+        }
+        OperationError::InternalError { .. } => false,
+    }
+}
+
+/// Whether the call can be sent again without risking doing things twice: it only reads,
+/// or Aliyun deduplicates it by its `ClientToken`.
+pub fn is_retry_safe(action: &str, params: &RpcParams) -> bool {
+    READ_ONLY_ACTION_PREFIXES
+        .iter()
+        .any(|prefix| action.starts_with(prefix))
+        || params.contains_key("ClientToken")
+}
+
+/// The error of a call, with how many attempts were made before giving up.
+#[derive(Debug)]
+pub struct CallError {
+    pub error: OperationError,
+    /// The attempts made, 1 when the call wasn't retried.
+    pub attempts: u32,
+}
+
+impl CallError {
+    pub fn new(error: OperationError, attempts: u32) -> Self {
+        Self { error, attempts }
+    }
+}
+
+impl From<OperationError> for CallError {
+    fn from(error: OperationError) -> Self {
+        Self::new(error, 1)
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if self.attempts > 1 {
+            write!(f, " (gave up after {} attempts)", self.attempts)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use crate::services::{
+        error::{AliyunRequestCommandError, NoOther},
+        fake::{fake_credentials, FakeAliyun, FakeAliyunServer, FakeRejection, FAKE_REGIONS},
+        rpc::RpcClient,
+    };
+
+    use super::*;
+
+    const ECS_API_VERSION: &str = "2014-05-26";
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            budget: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> RpcParams {
+        let mut params = RpcParams::from([("RegionId".to_owned(), FAKE_REGIONS[0].0.to_owned())]);
+        params.extend(pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        params
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::default();
+        for (attempt, backoff) in [(1, 200), (2, 400), (3, 800), (10, 5000)] {
+            let delay = policy.delay(attempt).as_millis();
+            assert!(
+                (backoff / 2..=backoff).contains(&delay),
+                "{attempt}: {delay}"
+            );
+        }
+        assert!(is_retry_safe("DescribeInstances", &RpcParams::new()));
+        assert!(!is_retry_safe("StopInstance", &RpcParams::new()));
+        assert!(is_retry_safe(
+            "StopInstance",
+            &params(&[("ClientToken", "t")])
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+        let fake = server.fake();
+        let client =
+            RpcClient::new(server.uri(), (&fake_credentials()).into()).with_retry(fast_policy());
+        let calls = |action: &str| {
+            let calls = fake.calls.lock().unwrap();
+            calls.iter().filter(|call| *call == action).count()
+        };
+
+        fake.reject(
+            "DescribeInstances",
+            FakeRejection::new(400, "Throttling.User").times(2),
+        );
+        let described: Value = client
+            .call("DescribeInstances", ECS_API_VERSION, params(&[]))
+            .await
+            .unwrap();
+        assert_eq!(described["TotalCount"], 3);
+        assert_eq!(calls("DescribeInstances"), 3);
+
+        fake.reject(
+            "DescribeInstanceStatus",
+            FakeRejection::new(503, "ServiceUnavailable"),
+        );
+        let err = client
+            .call::<Value>("DescribeInstanceStatus", ECS_API_VERSION, params(&[]))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            &err,
+            CallError {
+                error: OperationError::Rejected(_),
+                attempts: 3
+            }
+        );
+        assert!(err.to_string().ends_with("(gave up after 3 attempts)"));
+        let err = AliyunRequestCommandError::<NoOther>::from(err);
+        assert_matches!(err, AliyunRequestCommandError::Rejected(r) if r.attempts == 3);
+        assert_eq!(calls("DescribeInstanceStatus"), 3);
+
+        // Mutations are only sent again when they carry a client token.
+        fake.reject(
+            "StopInstance",
+            FakeRejection::new(503, "ServiceUnavailable").times(1),
+        );
+        let stop = params(&[("InstanceId", "i-fake0001")]);
+        assert!(client
+            .call::<Value>("StopInstance", ECS_API_VERSION, stop.clone())
+            .await
+            .is_err());
+        assert_eq!(calls("StopInstance"), 1);
+        assert_eq!(fake.instance("i-fake0001").unwrap().status, "Running");
+
+        fake.reject(
+            "StopInstance",
+            FakeRejection::new(503, "ServiceUnavailable").times(1),
+        );
+        let mut stop = stop;
+        stop.insert("ClientToken".to_owned(), "stop-i-fake0001".to_owned());
+        client
+            .call::<Value>("StopInstance", ECS_API_VERSION, stop)
+            .await
+            .unwrap();
+        assert_eq!(calls("StopInstance"), 3);
+        assert_eq!(fake.instance("i-fake0001").unwrap().status, "Stopped");
+
+        // Other rejections are never retried.
+        let err = client
+            .call::<Value>(
+                "StopInstance",
+                ECS_API_VERSION,
+                params(&[("InstanceId", "i-none")]),
+            )
+            .await
+            .unwrap_err();
+        assert_matches!(err, CallError { error: OperationError::Rejected(r), attempts: 1 } if r.code == "InvalidInstanceId.NotFound");
+    }
+
+    #[tokio::test]
+    async fn test_attempt_timeout() {
+        // Accepts connections but never answers them.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri = format!("http://{}", listener.local_addr().unwrap());
+        let policy = RetryPolicy {
+            attempt_timeout: Duration::from_millis(100),
+            ..RetryPolicy::none()
+        };
+        let client = RpcClient::new(uri, (&fake_credentials()).into()).with_retry(policy);
+
+        let call = client.call::<Value>("DescribeRegions", ECS_API_VERSION, params(&[]));
+        let err = tokio::time::timeout(Duration::from_secs(5), call)
+            .await
+            .expect("the attempt times out")
+            .unwrap_err();
+        assert_matches!(
+            err,
+            CallError {
+                error: OperationError::RequestFailure { .. },
+                attempts: 1
+            }
+        );
+        drop(listener);
+    }
+}
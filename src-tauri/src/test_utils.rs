@@ -1,5 +1,13 @@
 use tauri::{plugin::TauriPlugin, Runtime};
 
+#[cfg(test)]
+use crate::services::{
+    auth::chain::CredentialSource,
+    client::AliyunClientService,
+    endpoint::EndpointConfig,
+    fake::{fake_credentials, FakeAliyun, FakeAliyunServer},
+};
+
 pub fn log_plugin_builder<R: Runtime>() -> TauriPlugin<R> {
     tauri_plugin_log::Builder::default()
         .target(tauri_plugin_log::Target::new(
@@ -7,3 +15,13 @@ pub fn log_plugin_builder<R: Runtime>() -> TauriPlugin<R> {
         ))
         .build()
 }
+
+/// Starts the demo fake and a client service sending every request to it.
+#[cfg(test)]
+pub async fn start_fake_client() -> (FakeAliyunServer, AliyunClientService) {
+    let server = FakeAliyunServer::start(FakeAliyun::demo()).await.unwrap();
+    let client_service = AliyunClientService::new();
+    client_service.set_endpoints(EndpointConfig::with_base_url(server.uri()));
+    client_service.initialize(fake_credentials().into(), CredentialSource::Demo);
+    (server, client_service)
+}
@@ -9,14 +9,15 @@ async greet(name: string) : Promise<string> {
     return await TAURI_INVOKE("greet", { name });
 },
 /**
- * Retrieve the currently stored access key credential, if any.
+ * Retrieve a redacted view of the currently stored access key credential, if any.
  * 
- * This command queries the authentication service for the current
- * `AccessKeyCredential`. It returns `Ok(Some(credential))` when a
- * credential is available, `Ok(None)` when no credential is stored,
- * and `Err(QueryError::Internal(_))` for all other service-level
- * failures. The documentation intentionally omits details about the
- * service's internal error variants.
+ * This command queries the authentication service for the access key of
+ * the active profile. The secret never leaves the backend, only its last
+ * characters are shown along with when it was last validated. It returns
+ * `Ok(Some(view))` when a credential is available, `Ok(None)` when no
+ * credential is stored, and `Err(QueryError::Internal(_))` for all other
+ * service-level failures. The documentation intentionally omits details
+ * about the service's internal error variants.
  * 
  * # Errors
  * 
@@ -30,7 +31,7 @@ async greet(name: string) : Promise<string> {
  * let cred = invoke("current_access_key_credential");
  * ```
  */
-async currentAccessKeyCredential() : Promise<Result<AccessKeyCredentials | null, QueryError>> {
+async currentAccessKeyCredential() : Promise<Result<AccessKeyView | null, QueryError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("current_access_key_credential") };
 } catch (e) {
@@ -42,7 +43,8 @@ async currentAccessKeyCredential() : Promise<Result<AccessKeyCredentials | null,
  * Validate the provided access key credentials.
  * 
  * This command validates the given `AccessKeyCredentials` by making
- * a request to Aliyun's STS service to retrieve the caller identity.
+ * a request to Aliyun's STS service, at the configured endpoint, to
+ * retrieve the caller identity.
  * It returns `Ok(CallerIdentityBody)` when the credentials are valid,
  * and `Err(AKValidationError)` when validation fails due to invalid
  * credentials or service errors.
@@ -96,6 +98,89 @@ async fulfillAccessKeyCredentials(credentials: AccessKeyCredentials) : Promise<R
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * List the profiles of the `aliyun` CLI config and tell which ones can be imported.
+ * 
+ * Profiles in the `AK` and `RamRoleArn` modes can be imported, unless a
+ * saved profile already has the same name. `StsToken` and `EcsRamRole`
+ * profiles are listed with the reason they can't be.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(CliConfigError)` when the config can't be read or parsed, or
+ * when the saved profiles can't be read.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend, `path` defaults to `~/.aliyun/config.json`
+ * let profiles = invoke("preview_aliyun_cli_profiles", { path: null });
+ * ```
+ */
+async previewAliyunCliProfiles(path: string | null) : Promise<Result<CliProfilePreview[], CliConfigError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_aliyun_cli_profiles", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Validate and save the given profiles of the `aliyun` CLI config.
+ * 
+ * Each profile is validated with its access key before being saved under
+ * the same name, and a failure doesn't stop the other profiles from being
+ * imported. Profiles in a mode this app doesn't support are skipped with
+ * the reason, and the region of a profile becomes its default region. The
+ * current profile of the CLI is imported first, so that it
+ * becomes the active one when no profile was active yet.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(CliConfigError)` when the config can't be read or parsed.
+ * Failures of single profiles are reported in the returned list instead.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let imports = invoke("import_aliyun_cli_profiles", { names, path: null });
+ * ```
+ */
+async importAliyunCliProfiles(names: string[], path: string | null) : Promise<Result<CliProfileImport[], CliConfigError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_aliyun_cli_profiles", { names, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write the saved profiles into the `aliyun` CLI config, returning its path.
+ * 
+ * CLI profiles with the same name get their credentials replaced and keep
+ * their other settings. Profiles new to the config get the given region.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(CliConfigError)` when the config can't be read, parsed or
+ * written, or when the saved profiles can't be read.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let path = invoke("export_aliyun_cli_profiles", { regionId: "cn-hangzhou", path: null });
+ * ```
+ */
+async exportAliyunCliProfiles(regionId: string, path: string | null) : Promise<Result<string, CliConfigError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_aliyun_cli_profiles", { regionId, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async hasAliyunClient() : Promise<Result<boolean, null>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("has_aliyun_client") };
@@ -132,30 +217,2510 @@ async hasValidAliyunClient() : Promise<Result<boolean, AliyunRequestCommandError
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+/**
+ * Report whether the encrypted credential store has been created and unlocked.
+ * 
+ * The frontend uses this to decide between asking the user to choose a
+ * master passphrase and asking them to enter the existing one.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let status = invoke("credential_store_status");
+ * ```
+ */
+async credentialStoreStatus() : Promise<Result<VaultStatus, VaultError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("credential_store_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Unlock the encrypted credential store with the master passphrase.
+ * 
+ * On first use the store is created with the given passphrase, and any
+ * plaintext credential saved by older versions is encrypted. Once
+ * unlocked, the Aliyun client is rebuilt from the credential provider
+ * chain, so the active profile is used unless credentials were found
+ * in the environment or the `aliyun` CLI config first.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(VaultError::WrongPassphrase)` when the passphrase doesn't
+ * match, and other `VaultError` variants when the store data can't be
+ * read or migrated.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let outcome = invoke("unlock_credential_store", { passphrase });
+ * ```
+ */
+async unlockCredentialStore(passphrase: SecretString) : Promise<Result<UnlockOutcome, VaultError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unlock_credential_store", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
 }
+},
+/**
+ * Lock the encrypted credential store and drop the in-memory Aliyun client.
+ * 
+ * The client is kept when its credentials don't come from a stored
+ * profile, e.g. from the environment variables.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("lock_credential_store");
+ * ```
+ */
+async lockCredentialStore() : Promise<Result<null, VaultError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("lock_credential_store") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
 }
-
-/** user-defined events **/
-
-
-
-/** user-defined constants **/
-
-
-
-/** user-defined types **/
-
-export type AKNotValid = { type: "AKNotValid"; data: AliyunRejectionTypeShadow }
-export type AccessKeyCredentials = { access_key_id: string; access_key_secret: string }
-export type AliyunRejectionTypeShadow = { code: string; host_id: string; message: string; request_id: string; recommend: string }
-export type AliyunRequestCommandError<E> = { type: "Specific"; error: E } | { type: "RequestFailure"; error: { message: string } } | { type: "InternalError"; error: { message: string } }
-export type CallerIdentity = CallerIdentityBodyTypeShadow
-export type CallerIdentityBodyTypeShadow = { identity_type: IdentityTypeShadow; request_id: string; account_id: string; principal_id: string; user_id: string; arn: string; role_id: string | null }
-export type IdentityTypeShadow = "Account" | "RAMUser" | "AssumedRoleUser"
-export type NoOther = null
-export type QueryCredentialError = { type: "NotExist" } | { type: "DeserializeError"; error: SerdeJsonError }
-export type QueryError = { type: "UnderlyingError"; error: QueryCredentialError }
-export type SerdeJsonError = string
+},
+/**
+ * Tell which provider of the credential chain supplied the credentials of
+ * the Aliyun client, `None` when there is no client.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let source = invoke("active_credential_source");
+ * ```
+ */
+async activeCredentialSource() : Promise<CredentialSource | null> {
+    return await TAURI_INVOKE("active_credential_source");
+},
+/**
+ * Log out, forgetting the given credential profile, or every stored
+ * credential when no profile is given.
+ * 
+ * Forgetting everything works while the credential store is locked, and
+ * always drops the in-memory Aliyun client along with the background
+ * refresh of its temporary credentials. Forgetting a single profile only
+ * drops the client built from that profile. `AuthStateChanged` is emitted
+ * afterwards.
+ * 
+ * Returns `Ok(LogoutOutcome::NothingToDelete)` when there was nothing
+ * stored to forget.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError)` when the profiles can't be read or saved,
+ * e.g. forgetting a single profile while the credential store is locked.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let outcome = invoke("logout", { profile: null });
+ * ```
+ */
+async logout(profile: string | null) : Promise<Result<LogoutOutcome, ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("logout", { profile }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Switch to the demo account, served by a fake Aliyun running within the
+ * app, so the app can be tried without an Aliyun account.
+ * 
+ * The demo account starts over from the same few instances every time.
+ * Saved profiles are left untouched but can't be used until the demo
+ * mode is turned off with `stop_demo_mode`. `ActiveProfileChanged` is
+ * emitted afterwards, with the `Demo` credential source.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(DemoModeError)` when the fake can't listen on a local port,
+ * or the client of the demo account can't be built.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("start_demo_mode");
+ * ```
+ */
+async startDemoMode() : Promise<Result<null, DemoModeError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_demo_mode") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Leave the demo account, and rebuild the client the way it is at startup.
+ * 
+ * It returns `false` when the demo mode was already off, in which case
+ * nothing is changed. Otherwise `ActiveProfileChanged` is emitted.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let stopped = invoke("stop_demo_mode");
+ * ```
+ */
+async stopDemoMode() : Promise<Result<boolean, null>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stop_demo_mode") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tell whether the demo mode is on.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let on = invoke("is_demo_mode");
+ * ```
+ */
+async isDemoMode() : Promise<boolean> {
+    return await TAURI_INVOKE("is_demo_mode");
+},
+/**
+ * List the ECS instances of the given regions, of every region available
+ * to the account when `region_ids` is `null`.
+ * 
+ * Regions are listed in parallel, each one to its last page. A region
+ * failing doesn't fail the whole list: its error is returned among the
+ * `failures`, next to the instances of the other regions.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials, and the error of
+ * DescribeRegions when the regions of the account can't be listed.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let inventory = invoke("list_instances", { regionIds: null });
+ * ```
+ */
+async listInstances(regionIds: string[] | null) : Promise<Result<InstanceInventory, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_instances", { regionIds }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Start a stopped instance and wait until it is running.
+ * 
+ * The progress is streamed through `on_progress`: once Aliyun accepted
+ * the action, after each poll of the status, and once the instance is
+ * running. Returns the status reached.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::IncorrectInstanceStatus)` when the instance is
+ * not stopped, `Err(EcsError::InstanceNotFound)` when it doesn't exist in
+ * the region, and `Err(EcsError::WaitTimedOut)` when it isn't running
+ * after five minutes.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let onProgress = new Channel();
+ * onProgress.onmessage = (progress) => console.log(progress);
+ * let status = invoke("start_instance", { regionId, instanceId, onProgress });
+ * ```
+ */
+async startInstance(regionId: string, instanceId: string, onProgress: TAURI_CHANNEL<PowerProgress>) : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_instance", { regionId, instanceId, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stop a running instance gracefully and wait until it is stopped.
+ * 
+ * With `StopCharging`, a pay-as-you-go instance in a VPC stops being
+ * billed for its vCPUs and memory, which may be out of stock when it is
+ * started again. The progress is streamed as with `start_instance`.
+ * 
+ * # Errors
+ * 
+ * Same as `start_instance`, the instance having to be running.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let status = invoke("stop_instance", { regionId, instanceId, stoppedMode: "KeepCharging", onProgress });
+ * ```
+ */
+async stopInstance(regionId: string, instanceId: string, stoppedMode: StoppedMode, onProgress: TAURI_CHANNEL<PowerProgress>) : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stop_instance", { regionId, instanceId, stoppedMode, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stop a running instance without waiting for its OS to shut down, and
+ * wait until it is stopped.
+ * 
+ * Unsaved data may be lost, like pulling the plug. See `stop_instance`
+ * for the stopped modes.
+ * 
+ * # Errors
+ * 
+ * Same as `start_instance`, the instance having to be running.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let status = invoke("force_stop_instance", { regionId, instanceId, stoppedMode: "StopCharging", onProgress });
+ * ```
+ */
+async forceStopInstance(regionId: string, instanceId: string, stoppedMode: StoppedMode, onProgress: TAURI_CHANNEL<PowerProgress>) : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("force_stop_instance", { regionId, instanceId, stoppedMode, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Reboot a running instance and wait until it is running again.
+ * 
+ * The progress is streamed as with `start_instance`.
+ * 
+ * # Errors
+ * 
+ * Same as `start_instance`, the instance having to be running.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let status = invoke("reboot_instance", { regionId, instanceId, onProgress });
+ * ```
+ */
+async rebootInstance(regionId: string, instanceId: string, onProgress: TAURI_CHANNEL<PowerProgress>) : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reboot_instance", { regionId, instanceId, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Apply a power action to many instances at once, e.g. stopping the 30
+ * selected ones.
+ * 
+ * The instances are sent in batch requests of each region, a few
+ * requests at a time. Each instance succeeds or fails on its own: the
+ * report has the outcome of each one, and how many succeeded, failed or
+ * were skipped. The batch doesn't wait for the instances to reach the
+ * target status, their status when Aliyun answered is reported.
+ * 
+ * `batch_id` is picked by the frontend, to cancel the batch with
+ * `cancel_batch`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials, failures of
+ * instances being in the report.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let report = invoke("run_power_batch", {
+ * batchId: crypto.randomUUID(),
+ * action: { type: "Stop", stopped_mode: "StopCharging" },
+ * instances: [{ region_id: "cn-hangzhou", instance_id: "i-1" }],
+ * });
+ * ```
+ */
+async runPowerBatch(batchId: string, action: PowerAction, instances: InstanceRef[]) : Promise<Result<BatchReport, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_power_batch", { batchId, action, instances }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Cancel a batch started by `run_power_batch`: the requests not sent yet
+ * are dropped and their instances skipped, the ones already sent go on.
+ * 
+ * Returns whether the batch was running.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let cancelled = invoke("cancel_batch", { batchId });
+ * ```
+ */
+async cancelBatch(batchId: string) : Promise<boolean> {
+    return await TAURI_INVOKE("cancel_batch", { batchId });
+},
+/**
+ * Release an instance, for good.
+ * 
+ * `options.confirmation` must be the name of the instance as typed by the
+ * user, or its ID when it has no name. Instances with deletion protection
+ * and subscription instances which haven't expired are refused. With
+ * `snapshot_disks`, a final snapshot of each disk is taken and waited for
+ * before releasing. With `dry_run`, the same checks are made and Aliyun is
+ * asked whether the release would go through, nothing being changed.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::ConfirmationMismatch)`,
+ * `Err(EcsError::DeletionProtected)` or
+ * `Err(EcsError::SubscriptionNotExpired)` when the release is refused,
+ * `Err(EcsError::IncorrectInstanceStatus)` when the instance is running
+ * without `force`, and `Err(EcsError::SnapshotFailed)` when a final
+ * snapshot didn't complete, in which case the instance is kept.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let report = invoke("release_instance", {
+ * regionId,
+ * instanceId,
+ * options: { confirmation: "web-1", snapshot_disks: true, force: false, dry_run: true },
+ * });
+ * ```
+ */
+async releaseInstance(regionId: string, instanceId: string, options: ReleaseOptions) : Promise<Result<ReleaseReport, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("release_instance", { regionId, instanceId, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Get the endpoint configuration in use.
+ * 
+ * The base URL set through the `ALIYUN_ENDPOINT_BASE_URL` environment
+ * variable, if any, takes precedence over the saved one.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let config = invoke("endpoint_config");
+ * ```
+ */
+async endpointConfig() : Promise<EndpointConfig> {
+    return await TAURI_INVOKE("endpoint_config");
+},
+/**
+ * Save the endpoint configuration and send the following requests
+ * according to it, once the demo mode is off if it is on.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EndpointConfigError)` when an endpoint or a region ID is
+ * not valid, or when the configuration can't be saved.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("set_endpoint_config", { config: { base_url: null, use_vpc: true, overrides: [] } });
+ * ```
+ */
+async setEndpointConfig(config: EndpointConfig) : Promise<Result<null, EndpointConfigError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_endpoint_config", { config }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the regions where ECS is available to the account, with their
+ * names.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let regions = invoke("list_regions");
+ * ```
+ */
+async listRegions() : Promise<Result<RegionInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_regions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the zones of a region.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let zones = invoke("list_zones", { regionId: "cn-hangzhou" });
+ * ```
+ */
+async listZones(regionId: string) : Promise<Result<ZoneInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_zones", { regionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the instance types, or the disk categories of an instance type,
+ * offered in a zone with the charge type.
+ * 
+ * Sold out ones are listed too, with `available` being `false`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let categories = invoke("list_available_resources", {
+ * regionId: "cn-hangzhou",
+ * zoneId: "cn-hangzhou-h",
+ * kind: "SystemDisk",
+ * chargeType: { type: "PostPaid" },
+ * instanceType: "ecs.g7.large",
+ * });
+ * ```
+ */
+async listAvailableResources(regionId: string, zoneId: string, kind: ResourceKind, chargeType: ChargeType, instanceType: string | null) : Promise<Result<ZoneResource[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_available_resources", { regionId, zoneId, kind, chargeType, instanceType }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the available images of an owner in a region, only those the
+ * instance type can boot when one is given.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let images = invoke("list_images", {
+ * regionId: "cn-hangzhou",
+ * owner: "System",
+ * instanceType: "ecs.g7.large",
+ * });
+ * ```
+ */
+async listImages(regionId: string, owner: ImageOwner, instanceType: string | null) : Promise<Result<ImageInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_images", { regionId, owner, instanceType }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the VPCs of a region.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let vpcs = invoke("list_vpcs", { regionId: "cn-hangzhou" });
+ * ```
+ */
+async listVpcs(regionId: string) : Promise<Result<VpcInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_vpcs", { regionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the vSwitches of a VPC, only those of a zone when one is given.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let vswitches = invoke("list_vswitches", {
+ * regionId: "cn-hangzhou",
+ * vpcId: "vpc-1",
+ * zoneId: "cn-hangzhou-h",
+ * });
+ * ```
+ */
+async listVswitches(regionId: string, vpcId: string, zoneId: string | null) : Promise<Result<VSwitchInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_vswitches", { regionId, vpcId, zoneId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the security groups of a VPC.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let groups = invoke("list_security_groups", { regionId: "cn-hangzhou", vpcId: "vpc-1" });
+ * ```
+ */
+async listSecurityGroups(regionId: string, vpcId: string) : Promise<Result<SecurityGroupInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_security_groups", { regionId, vpcId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the SSH key pairs of a region.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let keyPairs = invoke("list_key_pairs", { regionId: "cn-hangzhou" });
+ * ```
+ */
+async listKeyPairs(regionId: string) : Promise<Result<KeyPairInfo[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_key_pairs", { regionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Check a launch without creating anything: each choice on its own, the
+ * choices against each other (the vSwitch is in the zone, the security
+ * groups in its VPC, the instance type and system disk are offered there),
+ * then with a dry run of RunInstances.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::InvalidLaunchSpec)` naming the wrong field, or
+ * `Err(EcsError::LaunchRejected)` when the dry run was rejected because of
+ * a field.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * await invoke("validate_launch", { regionId: "cn-hangzhou", spec });
+ * ```
+ */
+async validateLaunch(regionId: string, spec: LaunchSpec) : Promise<Result<null, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("validate_launch", { regionId, spec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Create instances, after checking the launch like `validate_launch`.
+ * 
+ * `client_token` is generated by the frontend once per launch, up to 64
+ * ASCII characters: sending the same launch again with it, e.g. after a
+ * timeout, doesn't create the instances twice.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::InvalidLaunchSpec)` or
+ * `Err(EcsError::LaunchRejected)` naming the field at fault.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let result = invoke("run_instances", {
+ * regionId: "cn-hangzhou",
+ * spec,
+ * clientToken: crypto.randomUUID(),
+ * });
+ * ```
+ */
+async runInstances(regionId: string, spec: LaunchSpec, clientToken: string) : Promise<Result<LaunchResult, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_instances", { regionId, spec, clientToken }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Generate the minimal RAM policy allowing the given features of the app,
+ * all of them when the list is empty.
+ * 
+ * The policy is returned as the JSON document pasted in the RAM console.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let policy = invoke("feature_ram_policy", { features: ["AccessKeyRotation"] });
+ * ```
+ */
+async featureRamPolicy(features: AppFeature[]) : Promise<string> {
+    return await TAURI_INVOKE("feature_ram_policy", { features });
+},
+/**
+ * Generate the minimal RAM policy allowing every ECS call attempted with
+ * the current credentials, including the denied ones.
+ * 
+ * The policy has no statement when nothing has been attempted yet.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let policy = invoke("attempted_ram_policy");
+ * ```
+ */
+async attemptedRamPolicy() : Promise<string> {
+    return await TAURI_INVOKE("attempted_ram_policy");
+},
+/**
+ * List the saved credential profiles, without their secrets.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError)` when the profiles can't be read, e.g. the
+ * credential store is locked.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let profiles = invoke("list_credential_profiles");
+ * ```
+ */
+async listCredentialProfiles() : Promise<Result<ProfileSummary[], ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_credential_profiles") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Validate the provided access key credentials and save them as a new profile.
+ * 
+ * The first profile added becomes the active one, in which case the Aliyun
+ * client is rebuilt and `ActiveProfileChanged` is emitted.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(AKFulfillError)` when the credentials are invalid, when
+ * there are failures communicating with the Aliyun service, or when the
+ * profile name is invalid or already taken.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let identity = invoke("add_credential_profile", { name, credentials });
+ * ```
+ */
+async addCredentialProfile(name: string, credentials: AccessKeyCredentials) : Promise<Result<CallerIdentity, AliyunRequestCommandError<AKNotValid>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_credential_profile", { name, credentials }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Save a new profile assuming a RAM role with a source access key.
+ * 
+ * The role is assumed once to validate the configuration. As with other
+ * profiles, the first profile added becomes the active one, in which case
+ * the temporary credentials are kept refreshed in the background.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(AKFulfillError)` when the source access key is invalid,
+ * when the role can't be assumed, or when the profile name is invalid or
+ * already taken.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let assumedRole = invoke("add_role_profile", { name, role });
+ * ```
+ */
+async addRoleProfile(name: string, role: AssumeRoleCredentials) : Promise<Result<AssumedRole, AliyunRequestCommandError<AKNotValid>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_role_profile", { name, role }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Rename a credential profile, keeping it active if it was.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError)` when the profile doesn't exist, or the new
+ * name is invalid or already taken.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("rename_credential_profile", { from, to });
+ * ```
+ */
+async renameCredentialProfile(from: string, to: string) : Promise<Result<null, ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rename_credential_profile", { from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete a credential profile.
+ * 
+ * Deleting the active profile leaves no profile active and clears the
+ * Aliyun client.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError)` when the profile doesn't exist or the
+ * profiles can't be saved.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("delete_credential_profile", { name });
+ * ```
+ */
+async deleteCredentialProfile(name: string) : Promise<Result<null, ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_credential_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Make the given profile the active one and rebuild the Aliyun client from it.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError::NotFound)` when the profile doesn't exist, and
+ * `Err(ProfileError::ClientError)` when the role of the profile can't be
+ * assumed.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("set_active_credential_profile", { name });
+ * ```
+ */
+async setActiveCredentialProfile(name: string) : Promise<Result<null, ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_active_credential_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Set the region a profile works in when a call doesn't pick one, or forget
+ * it with `null`, falling back to `cn-hangzhou`.
+ * 
+ * The default region of the Aliyun client follows when its credentials come
+ * from this profile.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(ProfileError::InvalidRegion)` when the region ID is not
+ * valid, and `Err(ProfileError::NotFound)` when the profile doesn't exist.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * invoke("set_profile_default_region", { name, regionId: "cn-shanghai" });
+ * ```
+ */
+async setProfileDefaultRegion(name: string, regionId: string | null) : Promise<Result<null, ProfileError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_profile_default_region", { name, regionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tell how old the access key of a profile is, when it was last used, and
+ * whether it is due for rotation. For role profiles, it is the source access
+ * key.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(AKFulfillError)` when the profile doesn't exist, or when RAM
+ * can't list the access keys of the user.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let age = invoke("access_key_age", { name });
+ * ```
+ */
+async accessKeyAge(name: string) : Promise<Result<AccessKeyAge, AliyunRequestCommandError<AKNotValid>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("access_key_age", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Rotate the access key of a profile: create a new access key through RAM,
+ * validate it, switch the profile and the Aliyun client to it, then
+ * deactivate the old access key.
+ * 
+ * The old access key is kept deactivated until `delete_retired_access_key`,
+ * so that it can be turned back on from the console if something still
+ * needs it.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(RotationError::StepFailed)` naming the step which failed,
+ * after the steps already taken have been undone, and
+ * `Err(RotationError::NotRotatable)` while the access key retired by the
+ * previous rotation hasn't been deleted, RAM allowing only two access keys
+ * per user.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let rotation = invoke("rotate_access_key", { name });
+ * ```
+ */
+async rotateAccessKey(name: string) : Promise<Result<AccessKeyRotation, RotationError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rotate_access_key", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete the access key deactivated by the last rotation of a profile.
+ * 
+ * Returns the ID of the deleted access key, `None` when there was nothing
+ * to delete.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(RotationError::StepFailed)` when the profile doesn't exist or
+ * RAM refuses to delete the access key.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let deleted = invoke("delete_retired_access_key", { name });
+ * ```
+ */
+async deleteRetiredAccessKey(name: string) : Promise<Result<string | null, RotationError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_retired_access_key", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Get a security group with its ingress and egress rules.
+ * 
+ * The groups of a VPC are listed by `list_security_groups`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::SecurityGroupNotFound)` when the group doesn't
+ * exist in the region.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let group = invoke("list_security_group_rules", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * });
+ * ```
+ */
+async listSecurityGroupRules(regionId: string, securityGroupId: string) : Promise<Result<SecurityGroupRules, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_security_group_rules", { regionId, securityGroupId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Check changes to the rules of a security group and tell which rules they
+ * add, modify and revoke, without changing anything.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::InvalidRule)` when a change is wrong, and
+ * `Err(EcsError::RuleNotFound)` when it names a rule the group doesn't
+ * have.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let preview = invoke("preview_security_group_changes", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * edits: [{ type: "Revoke", security_group_rule_id: "sgr-1" }],
+ * });
+ * ```
+ */
+async previewSecurityGroupChanges(regionId: string, securityGroupId: string, edits: RuleEdit[]) : Promise<Result<RuleSetDiff, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_security_group_changes", { regionId, securityGroupId, edits }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Apply changes previewed with `preview_security_group_changes`, after
+ * keeping the current rules of the group so that they can be rolled back.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::RulesChanged)` when the changes no longer do what
+ * `preview` says, e.g. the group has been edited since, and
+ * `Err(EcsError::RuleBackupFailed)` when the rules can't be kept, in which
+ * case nothing is changed.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let applied = invoke("apply_security_group_changes", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * edits,
+ * preview,
+ * });
+ * ```
+ */
+async applySecurityGroupChanges(regionId: string, securityGroupId: string, edits: RuleEdit[], preview: RuleSetDiff) : Promise<Result<RuleSetDiff, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_security_group_changes", { regionId, securityGroupId, edits, preview }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tell which rules rolling a security group back to how it was before the
+ * last change applied from the app would add and revoke, without changing
+ * anything.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoRuleBackup)` when no change of the group can be
+ * rolled back.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let preview = invoke("preview_security_group_rollback", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * });
+ * ```
+ */
+async previewSecurityGroupRollback(regionId: string, securityGroupId: string) : Promise<Result<RuleSetDiff, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_security_group_rollback", { regionId, securityGroupId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Roll a security group back to how it was before the last change applied
+ * from the app, as previewed with `preview_security_group_rollback`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoRuleBackup)` when no change of the group can be
+ * rolled back, and `Err(EcsError::RulesChanged)` when the rollback no
+ * longer does what `preview` says.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let rolledBack = invoke("rollback_security_group", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * preview,
+ * });
+ * ```
+ */
+async rollbackSecurityGroup(regionId: string, securityGroupId: string, preview: RuleSetDiff) : Promise<Result<RuleSetDiff, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rollback_security_group", { regionId, securityGroupId, preview }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Find out the public IP of this machine, asked to the public IP URL of the
+ * endpoint configuration.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::PublicIpUnknown)` when the service doesn't answer
+ * with an IP.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let ip = invoke("public_ip");
+ * ```
+ */
+async publicIp() : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("public_ip") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Let the public IP of this machine reach a TCP port, e.g. 22 for SSH or
+ * 3389 for RDP, of the instances of a security group for `minutes`.
+ * 
+ * The rule is revoked in the background once expired, even if the app is
+ * restarted meanwhile. Allowing the same IP and port again extends the
+ * access.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::PublicIpUnknown)` when the IP can't be found out,
+ * and `Err(EcsError::InvalidRule)` when the port or the duration is out of
+ * range, or when the IP can already reach the port for good.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let access = invoke("allow_current_ip", {
+ * regionId: "cn-hangzhou",
+ * securityGroupId: "sg-1",
+ * port: 22,
+ * minutes: 120,
+ * });
+ * ```
+ */
+async allowCurrentIp(regionId: string, securityGroupId: string, port: number, minutes: number) : Promise<Result<TemporaryAccess, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("allow_current_ip", { regionId, securityGroupId, port, minutes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Revoke the expired temporary access rules right away, rather than at the
+ * next background sweep, and return them.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let revoked = invoke("revoke_expired_access");
+ * ```
+ */
+async revokeExpiredAccess() : Promise<Result<TemporaryAccess[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("revoke_expired_access") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Audit the security groups of every region, or of the regions of the
+ * options, for sensitive ports open to the internet, overly broad port
+ * ranges and groups no instance uses.
+ * 
+ * A region failing doesn't fail the others, it is reported in the failures.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let report = invoke("audit_security_groups", {
+ * options: { extra_sensitive_ports: [5432], max_port_range: null, region_ids: null },
+ * });
+ * ```
+ */
+async auditSecurityGroups(options: AuditOptions) : Promise<Result<AuditReport, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("audit_security_groups", { options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write an audit report as JSON or CSV to a new file of the download
+ * directory, returning the path of the file.
+ * 
+ * The file is named after the time of the audit, e.g.
+ * `security-audit-20261018T100000Z.csv`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::ExportFailed)` when the file can't be written, or
+ * already exists.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let path = invoke("export_security_audit", { report, format: "Csv" });
+ * ```
+ */
+async exportSecurityAudit(report: AuditReport, format: ExportFormat) : Promise<Result<string, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_security_audit", { report, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tell whether traffic from a CIDR block, an instance or a security group
+ * enters each network interface of an instance, and which rule or policy
+ * decided it, by evaluating the security groups locally.
+ * 
+ * Nothing is changed, only the instances and security groups are described.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::InvalidTrafficQuery)` when the protocol, port or
+ * CIDR block is wrong, or `Err(EcsError::InstanceNotFound)` for an unknown
+ * destination or source instance.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let report = invoke("simulate_reachability", {
+ * regionId: "cn-hangzhou",
+ * query: {
+ * source: { type: "Cidr", cidr: "203.0.113.7" },
+ * instance_id: "i-1",
+ * ip_protocol: "TCP",
+ * port: 22,
+ * },
+ * });
+ * ```
+ */
+async simulateReachability(regionId: string, query: TrafficQuery) : Promise<Result<ReachabilityReport, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("simulate_reachability", { regionId, query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tell how the schema of the store has been migrated at startup.
+ * 
+ * It returns `Ok(MigrationReport)` listing the migrations which ran, if
+ * any, and where the store was backed up before running them.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(StoreMigrationError)` when the store couldn't be migrated,
+ * in which case it has been left untouched, e.g. it was written by a newer
+ * version of the app. The services using the store are then not started,
+ * so every other command fails.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let report = invoke("store_migration_status");
+ * ```
+ */
+async storeMigrationStatus() : Promise<Result<MigrationReport, StoreMigrationError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("store_migration_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the launch templates of a region.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::NoClient)` without credentials.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let templates = invoke("list_launch_templates", { regionId: "cn-hangzhou" });
+ * ```
+ */
+async listLaunchTemplates(regionId: string) : Promise<Result<LaunchTemplateSummary[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_launch_templates", { regionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the versions of a launch template with their settings, sorted by
+ * number.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::TemplateNotFound)` when the template doesn't
+ * exist in the region.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let versions = invoke("list_launch_template_versions", {
+ * regionId: "cn-hangzhou",
+ * launchTemplateId: "lt-1",
+ * });
+ * ```
+ */
+async listLaunchTemplateVersions(regionId: string, launchTemplateId: string) : Promise<Result<LaunchTemplateVersion[], AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_launch_template_versions", { regionId, launchTemplateId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Show how version `to` of a launch template differs from version `from`,
+ * setting by setting.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::TemplateNotFound)` or
+ * `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let diff = invoke("diff_launch_template_versions", {
+ * regionId: "cn-hangzhou",
+ * launchTemplateId: "lt-1",
+ * from: 1,
+ * to: 2,
+ * });
+ * ```
+ */
+async diffLaunchTemplateVersions(regionId: string, launchTemplateId: string, from: number, to: number) : Promise<Result<VersionDiff, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("diff_launch_template_versions", { regionId, launchTemplateId, from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Add a version to a launch template with the configuration of an
+ * instance: its image, type, network, key pair, disks, billing and tags,
+ * but not its name. Returns the number of the new version.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::InstanceNotFound)` or
+ * `Err(EcsError::TemplateNotFound)` when either doesn't exist.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let versionNumber = invoke("create_launch_template_version_from_instance", {
+ * regionId: "cn-hangzhou",
+ * launchTemplateId: "lt-1",
+ * instanceId: "i-1",
+ * description: "web after the upgrade",
+ * });
+ * ```
+ */
+async createLaunchTemplateVersionFromInstance(regionId: string, launchTemplateId: string, instanceId: string, description: string) : Promise<Result<number, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_launch_template_version_from_instance", { regionId, launchTemplateId, instanceId, description }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Make a version the default one of its launch template, the one launched
+ * when none is named.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::TemplateNotFound)` or
+ * `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * await invoke("set_default_launch_template_version", {
+ * regionId: "cn-hangzhou",
+ * launchTemplateId: "lt-1",
+ * versionNumber: 3,
+ * });
+ * ```
+ */
+async setDefaultLaunchTemplateVersion(regionId: string, launchTemplateId: string, versionNumber: number) : Promise<Result<null, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_default_launch_template_version", { regionId, launchTemplateId, versionNumber }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Create instances from a launch template, its default version unless
+ * `launch.version_number` names one, the settings given in `launch`
+ * overriding those of the template.
+ * 
+ * The launch is checked with a dry run first. `client_token` is generated
+ * by the frontend once per launch, like for `run_instances`.
+ * 
+ * # Errors
+ * 
+ * Returns `Err(EcsError::TemplateNotFound)` or
+ * `Err(EcsError::TemplateVersionNotFound)` when either doesn't exist, and
+ * `Err(EcsError::InvalidLaunchSpec)` or `Err(EcsError::LaunchRejected)`
+ * naming the setting at fault.
+ * 
+ * # Examples
+ * 
+ * ```rust,ignore
+ * // from a Tauri frontend
+ * let result = invoke("run_instances_from_template", {
+ * regionId: "cn-hangzhou",
+ * launch: { launch_template_id: "lt-1", version_number: null, amount: 2, ... },
+ * clientToken: crypto.randomUUID(),
+ * });
+ * ```
+ */
+async runInstancesFromTemplate(regionId: string, launch: TemplateLaunch, clientToken: string) : Promise<Result<LaunchResult, AliyunRequestCommandError<EcsError>>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_instances_from_template", { regionId, launch, clientToken }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+}
+}
+
+/** user-defined events **/
+
+
+export const events = __makeEvents__<{
+activeProfileChanged: ActiveProfileChanged,
+authStateChanged: AuthStateChanged
+}>({
+activeProfileChanged: "active-profile-changed",
+authStateChanged: "auth-state-changed"
+})
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type AKNotValid = { type: "AKNotValid"; data: AliyunRejectionTypeShadow }
+/**
+ * How old the access key of a profile is, and whether it is due for rotation.
+ */
+export type AccessKeyAge = { access_key_id: string; 
+/**
+ * In RFC 3339 format, like the other timestamps.
+ */
+created_at: string; age_days: number; 
+/**
+ * `None` when the key has never been used, or RAM can't tell, e.g. for the root
+ * account.
+ */
+last_used_at: string | null; rotation_due_at: string; rotation_due: boolean; 
+/**
+ * The key deactivated by the last rotation, still to be deleted.
+ */
+retired_access_key_id: string | null }
+/**
+ * An access key, as entered by the user and kept in the credential store.
+ * 
+ * Never returned to the frontend, which gets an [`AccessKeyView`] instead.
+ */
+export type AccessKeyCredentials = { access_key_id: string; access_key_secret: SecretString }
+/**
+ * The result of a successful rotation.
+ */
+export type AccessKeyRotation = { profile: string; 
+/**
+ * The new access key, now in use.
+ */
+access_key: AccessKeyView; 
+/**
+ * The previous access key, deactivated but not deleted yet.
+ */
+retired_access_key_id: string }
+/**
+ * What the frontend gets to see of an access key.
+ */
+export type AccessKeyView = { access_key_id: string; 
+/**
+ * The last characters of the secret, the rest being masked.
+ */
+masked_secret: string; 
+/**
+ * When the access key was last validated against Aliyun, in RFC 3339 format.
+ */
+validated_at: string | null }
+/**
+ * Emitted whenever the active credential profile changes, including when it is
+ * renamed or deleted, so the frontend can refresh everything bound to the account.
+ */
+export type ActiveProfileChanged = { 
+/**
+ * The newly active profile, `None` when no profile is active anymore.
+ */
+name: string | null; 
+/**
+ * Whether the Aliyun client has been rebuilt from the active profile.
+ */
+has_client: boolean; 
+/**
+ * Where the credentials of the client come from, which is not the active profile
+ * when the credential provider chain found credentials before it.
+ */
+source: CredentialSource | null }
+export type AffectedInstance = { instance_id: string; instance_name: string; public_ips: string[] }
+export type AliyunRejectionTypeShadow = { code: string; host_id: string; message: string; request_id: string; recommend: string }
+export type AliyunRequestCommandError<E> = { type: "Specific"; error: E } | 
+/**
+ * A rejection not handled by the specific error, classified by its code.
+ */
+{ type: "Rejected"; error: ClassifiedRejection } | { type: "RequestFailure"; error: { message: string; 
+/**
+ * The attempts made, 1 when the call wasn't retried.
+ */
+attempts: number } } | { type: "InternalError"; error: { message: string } } | 
+/**
+ * The input was refused before anything was sent, e.g. a malformed role ARN.
+ */
+{ type: "InvalidInput"; error: { message: string } }
+/**
+ * The Aliyun services the app talks to.
+ */
+export type AliyunService = "ecs" | 
+/**
+ * The networks, VPCs and vSwitches, which ECS only refers to.
+ */
+"vpc" | "sts" | "ram"
+/**
+ * A part of the app needing its own RAM actions.
+ */
+export type AppFeature = 
+/**
+ * Rotating the access key of the current RAM user.
+ */
+"AccessKeyRotation" | 
+/**
+ * Assuming roles from role profiles.
+ */
+"RoleProfiles" | 
+/**
+ * Listing the instances of every region.
+ */
+"Inventory" | 
+/**
+ * Starting, stopping and rebooting instances, one by one or in batches.
+ */
+"PowerActions" | 
+/**
+ * Releasing instances, after a final snapshot of their disks.
+ */
+"Release" | 
+/**
+ * Creating instances, after resolving and checking their configuration.
+ */
+"Launch" | 
+/**
+ * Managing launch templates and launching instances from them.
+ */
+"LaunchTemplates" | 
+/**
+ * Editing the rules of security groups, and rolling the last change back.
+ */
+"SecurityGroups" | 
+/**
+ * Letting the IP of the machine reach a port for a while.
+ */
+"TemporaryAccess" | 
+/**
+ * Auditing the security groups of every region.
+ */
+"SecurityAudit" | 
+/**
+ * Simulating whether traffic reaches an instance through its security groups.
+ */
+"Reachability"
+/**
+ * A profile assuming a RAM role, signed with the source access key.
+ */
+export type AssumeRoleCredentials = { source: AccessKeyCredentials; role_arn: string; role_session_name: string; 
+/**
+ * Lifetime of the temporary credentials, between 900 and 43200 seconds.
+ */
+duration_seconds: number; external_id: string | null }
+/**
+ * The identity taken by a successful AssumeRole call.
+ */
+export type AssumedRole = { arn: string; assumed_role_id: string; 
+/**
+ * When the temporary credentials expire, in RFC 3339 format.
+ */
+expiration: string }
+/**
+ * What the audit looks for.
+ */
+export type AuditOptions = { 
+/**
+ * Flagged when open to the whole internet, besides [`SENSITIVE_PORTS`].
+ */
+extra_sensitive_ports?: number[]; 
+/**
+ * The widest port range not flagged, [`DEFAULT_MAX_PORT_RANGE`] when `None`.
+ */
+max_port_range?: number | null; 
+/**
+ * The regions audited, every region available to the account when `None`.
+ */
+region_ids?: string[] | null }
+/**
+ * The findings of an audit, the most severe first, and the regions which couldn't be
+ * audited.
+ */
+export type AuditReport = { 
+/**
+ * In RFC 3339.
+ */
+audited_at: string; groups_audited: number; findings: Finding[]; failures: RegionFailure[] }
+/**
+ * Emitted after logging out, so the frontend can drop everything tied to the
+ * credentials which have been forgotten.
+ */
+export type AuthStateChanged = { 
+/**
+ * What has been removed from the credential store.
+ */
+outcome: LogoutOutcome; 
+/**
+ * The profile still active, `None` when the active profile has been removed.
+ */
+active_profile: string | null; 
+/**
+ * Whether an Aliyun client is still available.
+ */
+has_client: boolean; 
+/**
+ * Where the credentials of the remaining client come from.
+ */
+source: CredentialSource | null }
+/**
+ * The outcome of each instance of a batch, by instance ID.
+ */
+export type BatchReport = { results: Partial<{ [key in string]: InstanceOutcome }>; summary: BatchSummary }
+export type BatchSummary = { succeeded: number; failed: number; skipped: number }
+export type CallerIdentity = CallerIdentityBodyTypeShadow
+export type CallerIdentityBodyTypeShadow = { identity_type: IdentityTypeShadow; request_id: string; account_id: string; principal_id: string; user_id: string; arn: string; role_id: string | null }
+/**
+ * How instances are billed.
+ */
+export type ChargeType = 
+/**
+ * Pay-as-you-go.
+ */
+{ type: "PostPaid" } | 
+/**
+ * A subscription for `period` weeks or months, paid upfront.
+ */
+{ type: "PrePaid"; period: number; period_unit: PeriodUnit }
+/**
+ * A rejection of Aliyun, with its category.
+ */
+export type ClassifiedRejection = { category: RejectionCategory; data: AliyunRejectionTypeShadow; 
+/**
+ * The permission the caller lacks, when the category is `PermissionDenied` and the
+ * call is known.
+ */
+missing_permission: RamPermission | null; 
+/**
+ * The attempts made, 1 when the call wasn't retried.
+ */
+attempts: number }
+export type CliConfigError = { type: "NoHomeDir" } | { type: "Io"; error: { path: string; message: string } } | { type: "Invalid"; error: SerdeJsonError } | { type: "Profile"; error: ProfileError }
+/**
+ * The outcome of importing one profile of the `aliyun` CLI config.
+ */
+export type CliProfileImport = { name: string; 
+/**
+ * Why the profile failed to import, `None` if it was imported or skipped.
+ */
+error: string | null; 
+/**
+ * Why the profile was skipped without trying, e.g. `StsToken` and `EcsRamRole`
+ * profiles which this app can't keep using.
+ */
+skipped: string | null }
+/**
+ * What the frontend gets to know about a profile of the `aliyun` CLI config before
+ * importing it.
+ */
+export type CliProfilePreview = { name: string; mode: string; region_id: string; access_key_id: string; role_arn: string | null; 
+/**
+ * Whether it is the current profile of the CLI.
+ */
+current: boolean; 
+/**
+ * Whether a saved profile already has this name, in which case it can't be imported.
+ */
+exists: boolean; 
+/**
+ * Why the profile can't be imported, `None` if it can.
+ */
+unsupported_reason: string | null }
+/**
+ * A setting differing between two versions, `None` when a version doesn't set it.
+ */
+export type ConfigChange = { 
+/**
+ * The name of the setting in [`TemplateConfig`].
+ */
+field: string; from: string | null; to: string | null }
+/**
+ * Which provider of the chain supplied the credentials of the client.
+ */
+export type CredentialSource = 
+/**
+ * The `ALIBABA_CLOUD_ACCESS_KEY_ID` and `ALIBABA_CLOUD_ACCESS_KEY_SECRET` variables.
+ */
+{ type: "Environment" } | 
+/**
+ * The current profile of the `aliyun` CLI config.
+ */
+{ type: "CliConfig"; detail: { profile: string } } | 
+/**
+ * A profile saved in the credential store of the app.
+ */
+{ type: "StoredProfile"; detail: { name: string } } | 
+/**
+ * The RAM role attached to the ECS instance the app runs on.
+ */
+{ type: "InstanceMetadata"; detail: { role_name: string } } | 
+/**
+ * The demo account, served by a fake Aliyun running within the app.
+ */
+{ type: "Demo" }
+/**
+ * What decided whether the traffic enters.
+ */
+export type Decision = 
+/**
+ * The first rule matching the traffic, by priority.
+ */
+{ type: "Rule"; security_group_id: string; security_group_rule_id: string; rule: RuleSpec } | 
+/**
+ * No rule matched, and the source is in the same group as the destination.
+ */
+{ type: "InnerAccessPolicy"; security_group_id: string; policy: RulePolicy } | 
+/**
+ * No rule matched, and ingress traffic is denied by default.
+ */
+{ type: "NoMatchingRule" }
+export type DemoModeError = { type: "ServerFailed"; error: string } | { type: "ClientFailed"; error: string }
+/**
+ * A disk created with the instances.
+ */
+export type DiskSpec = { 
+/**
+ * E.g. `cloud_essd`.
+ */
+category: string; 
+/**
+ * In GiB.
+ */
+size: number }
+export type EcsError = { type: "NoClient" } | { type: "InvalidRegion"; error: { region_id: string } } | { type: "InstanceNotFound"; error: { instance_id: string } } | 
+/**
+ * The instance is not in a status allowing the action, e.g. stopping a stopped one.
+ */
+{ type: "IncorrectInstanceStatus"; error: { instance_id: string; code: string; message: string } } | { type: "ConfirmationMismatch"; error: { instance_id: string } } | { type: "DeletionProtected"; error: { instance_id: string } } | 
+/**
+ * A subscription instance can't be released before its term ends.
+ */
+{ type: "SubscriptionNotExpired"; error: { instance_id: string; expired_time: string } } | 
+/**
+ * A snapshot didn't complete, `status` being where it stopped.
+ */
+{ type: "SnapshotFailed"; error: { snapshot_id: string; status: string } } | 
+/**
+ * A choice of the launch wizard is wrong on its own or with the others.
+ */
+{ type: "InvalidLaunchSpec"; error: { field: LaunchField; reason: string } } | 
+/**
+ * Aliyun rejected a launch because of `field`, when checking it or creating it.
+ */
+{ type: "LaunchRejected"; error: { field: LaunchField; code: string; message: string } } | { type: "TemplateNotFound"; error: { launch_template_id: string } } | { type: "TemplateVersionNotFound"; error: { launch_template_id: string; version_number: number } } | { type: "SecurityGroupNotFound"; error: { security_group_id: string } } | { type: "RuleNotFound"; error: { security_group_id: string; security_group_rule_id: string } } | 
+/**
+ * A change to the rules of a security group is wrong on its own or with the others.
+ */
+{ type: "InvalidRule"; error: { reason: string } } | 
+/**
+ * The rules changed since the change was previewed, it has to be previewed again.
+ */
+{ type: "RulesChanged"; error: { security_group_id: string } } | { type: "NoRuleBackup"; error: { security_group_id: string } } | { type: "RuleBackupFailed"; error: { message: string } } | { type: "PublicIpUnknown"; error: { message: string } } | { type: "TemporaryAccessNotRecorded"; error: { message: string } } | 
+/**
+ * The traffic to simulate is wrong, e.g. TCP without a port.
+ */
+{ type: "InvalidTrafficQuery"; error: { reason: string } } | { type: "ExportFailed"; error: { path: string; message: string } } | 
+/**
+ * The instance was still in `status` when the wait gave up.
+ */
+{ type: "WaitTimedOut"; error: { instance_id: string; expected: string; status: string } }
+/**
+ * Where the requests to each service are sent.
+ * 
+ * From the most to the least specific: the base URL, an override for the service in the
+ * region, an override for the service, then the default public or VPC endpoint.
+ */
+export type EndpointConfig = { 
+/**
+ * Sends the requests of every service to this URL, e.g. a local stand-in server.
+ */
+base_url?: string | null; 
+/**
+ * Uses the VPC endpoints, only reachable from within Aliyun.
+ */
+use_vpc?: boolean; overrides?: EndpointOverride[]; 
+/**
+ * Where the public IP of the machine is asked, [`DEFAULT_PUBLIC_IP_URL`] when `None`.
+ */
+public_ip_url?: string | null; 
+/**
+ * Where the credentials of the instance RAM role are asked,
+ * [`DEFAULT_METADATA_ENDPOINT`] when `None`. It is read at startup.
+ */
+metadata_url?: string | null }
+export type EndpointConfigError = { type: "InvalidEndpoint"; error: string } | { type: "InvalidPublicIpUrl"; error: string } | { type: "InvalidMetadataUrl"; error: string } | { type: "InvalidRegion"; error: string } | { type: "SaveFailed"; error: string }
+/**
+ * An endpoint replacing the default one of a service, in a single region or in all of them.
+ */
+export type EndpointOverride = { service: AliyunService; 
+/**
+ * The region the override applies to, all of them when `None`.
+ */
+region_id: string | null; 
+/**
+ * A host name, or a URL when the scheme isn't `https`.
+ */
+endpoint: string }
+export type ExportFormat = "Json" | "Csv"
+export type Finding = { severity: Severity; kind: FindingKind; region_id: string; security_group_id: string; security_group_name: string; 
+/**
+ * The rule at fault, `None` for the findings about the whole group.
+ */
+security_group_rule_id: string | null; rule: RuleSpec | null; message: string; 
+/**
+ * The instances using the group.
+ */
+affected_instances: AffectedInstance[] }
+export type FindingKind = 
+/**
+ * An ingress rule lets the whole internet reach sensitive ports.
+ */
+{ type: "SensitivePortOpen"; ports: number[] } | 
+/**
+ * An ingress rule lets more ports be reached than the options allow.
+ */
+{ type: "BroadPortRange"; port_count: number } | 
+/**
+ * No instance uses the group.
+ */
+{ type: "UnusedGroup" }
+export type IdentityTypeShadow = "Account" | "RAMUser" | "AssumedRoleUser"
+/**
+ * A bootable image.
+ */
+export type ImageInfo = { image_id: string; image_name: string; os_name: string; 
+/**
+ * `x86_64` or `arm64`.
+ */
+architecture: string; 
+/**
+ * `system`, `self`, `others` or `marketplace`.
+ */
+owner_alias: string; 
+/**
+ * The size of the image, the least the system disk can be, in GiB.
+ */
+size: number }
+/**
+ * Whose images to list.
+ */
+export type ImageOwner = 
+/**
+ * The public images of Aliyun.
+ */
+"System" | 
+/**
+ * The custom images of the account.
+ */
+"OwnImages" | 
+/**
+ * The images shared with the account.
+ */
+"Shared" | "Marketplace"
+/**
+ * The instances of several regions, and the regions which couldn't be listed.
+ */
+export type InstanceInventory = { instances: InstanceSummary[]; failures: RegionFailure[] }
+/**
+ * What happened to an instance of a batch.
+ */
+export type InstanceOutcome = 
+/**
+ * Aliyun accepted the action, the instance now being in `status`.
+ */
+{ type: "Succeeded"; data: { status: string } } | { type: "Failed"; data: AliyunRequestCommandError<EcsError> } | { type: "Skipped"; data: { reason: SkipReason } }
+export type InstanceRef = { region_id: string; instance_id: string }
+/**
+ * What the inventory shows of an instance.
+ */
+export type InstanceSummary = { instance_id: string; instance_name: string; region_id: string; zone_id: string; 
+/**
+ * E.g. `Running`, `Stopped`, `Starting` or `Stopping`.
+ */
+status: string; 
+/**
+ * When the instance was last started or rebooted, `None` if it never was.
+ */
+start_time: string | null; instance_type: string; 
+/**
+ * The private addresses, in the VPC or the classic network.
+ */
+private_ips: string[]; 
+/**
+ * The public addresses, elastic ones included.
+ */
+public_ips: string[]; 
+/**
+ * `PostPaid` (pay-as-you-go) or `PrePaid` (subscription).
+ */
+charge_type: string; 
+/**
+ * When the subscription ends, `None` for pay-as-you-go instances.
+ */
+expired_time: string | null; tags: InstanceTag[]; 
+/**
+ * Whether the instance can't be released until the protection is turned off.
+ */
+deletion_protection: boolean; 
+/**
+ * The security groups of its primary network interface.
+ */
+security_group_ids: string[] }
+export type InstanceTag = { key: string; value: string }
+/**
+ * The verdict for the traffic entering a network interface of the destination.
+ */
+export type InterfaceVerdict = { network_interface_id: string; 
+/**
+ * Whether it is the primary network interface of the instance.
+ */
+primary: boolean; private_ip_address: string; security_group_ids: string[]; verdict: Verdict }
+export type KeyPairInfo = { key_pair_name: string; key_pair_finger_print: string }
+/**
+ * The part of a launch an error is about, for the wizard to point at.
+ */
+export type LaunchField = "zone_id" | "instance_type" | "image_id" | "vswitch_id" | "security_group_ids" | "instance_name" | "key_pair_name" | "system_disk" | "data_disks" | "charge_type" | "internet_max_bandwidth_out" | "amount" | "tags" | "client_token"
+/**
+ * The instances created by RunInstances.
+ */
+export type LaunchResult = { instance_ids: string[]; 
+/**
+ * The order of the subscription, `None` for pay-as-you-go instances.
+ */
+order_id: string | null }
+/**
+ * The configuration of the instances to create, as picked in the wizard.
+ */
+export type LaunchSpec = { zone_id: string; instance_type: string; image_id: string; vswitch_id: string; 
+/**
+ * The security groups, all of them in the VPC of the vSwitch.
+ */
+security_group_ids: string[]; instance_name: string | null; key_pair_name: string | null; system_disk: DiskSpec; data_disks: DiskSpec[]; charge_type: ChargeType; 
+/**
+ * The public bandwidth, billed by traffic, no public IP when 0. In Mbit/s.
+ */
+internet_max_bandwidth_out: number; 
+/**
+ * How many instances to create.
+ */
+amount: number; tags: InstanceTag[] }
+/**
+ * A launch template, without its versions.
+ */
+export type LaunchTemplateSummary = { launch_template_id: string; launch_template_name: string; 
+/**
+ * The version used when launching without naming one.
+ */
+default_version_number: number; latest_version_number: number; modified_time: string }
+/**
+ * A version of a launch template.
+ */
+export type LaunchTemplateVersion = { version_number: number; description: string; default_version: boolean; create_time: string; config: TemplateConfig }
+/**
+ * What logging out removed from the credential store.
+ */
+export type LogoutOutcome = 
+/**
+ * There was no stored credential to remove.
+ */
+{ type: "NothingToDelete" } | 
+/**
+ * The profile has been removed, the others are kept.
+ */
+{ type: "ProfileDeleted"; detail: { name: string; was_active: boolean } } | 
+/**
+ * Every stored profile has been removed.
+ */
+{ type: "AllDeleted" }
+export type MigrationReport = { from_version: number; to_version: number; 
+/**
+ * The descriptions of the migrations which ran, in order.
+ */
+applied: string[]; 
+/**
+ * Where the store has been copied before migrating, if it has been.
+ */
+backup_path: string | null }
+export type NoOther = null
+/**
+ * The unit of the term of a subscription.
+ */
+export type PeriodUnit = "Week" | "Month"
+/**
+ * A change of the power state of an instance.
+ */
+export type PowerAction = { type: "Start" } | { type: "Stop"; stopped_mode: StoppedMode } | 
+/**
+ * Stops without waiting for the OS to shut down, like pulling the plug.
+ */
+{ type: "ForceStop"; stopped_mode: StoppedMode } | { type: "Reboot" }
+/**
+ * The progress of a power action, streamed to the frontend.
+ */
+export type PowerProgress = 
+/**
+ * Aliyun accepted the action, the instance is on its way to `target_status`.
+ */
+{ type: "Requested"; instance_id: string; target_status: string } | 
+/**
+ * The status of the instance while waiting.
+ */
+{ type: "Polled"; instance_id: string; status: string; elapsed_secs: number } | { type: "Reached"; instance_id: string; status: string }
+export type ProfileError = { type: "InvalidName"; error: string } | { type: "NotFound"; error: string } | { type: "AlreadyExists"; error: string } | { type: "InvalidRegion"; error: string } | { type: "QueryError"; error: QueryCredentialError } | { type: "SaveError"; error: SaveCredentialError } | { type: "ClientError"; error: AliyunRequestCommandError<NoOther> }
+/**
+ * What the frontend gets to know about a profile.
+ */
+export type ProfileSummary = { name: string; 
+/**
+ * The access key in use, the source access key for role profiles.
+ */
+access_key: AccessKeyView; 
+/**
+ * The role assumed by the profile, if it is a role profile.
+ */
+role_arn: string | null; default_region: string | null; active: boolean }
+export type QueryCredentialError = { type: "NotExist" } | { type: "Locked" } | { type: "DecryptError" } | { type: "DeserializeError"; error: SerdeJsonError }
+export type QueryError = { type: "UnderlyingError"; error: QueryCredentialError }
+/**
+ * A RAM action on a resource, e.g. `ecs:StopInstance` on `acs:ecs:cn-hangzhou:*:instance/i-1`.
+ */
+export type RamPermission = { action: string; resource: string }
+export type ReachabilityReport = { instance_id: string; 
+/**
+ * The primary network interface first.
+ */
+interfaces: InterfaceVerdict[] }
+/**
+ * A failure of a region, while the other regions went through.
+ */
+export type RegionFailure = { region_id: string; message: string; 
+/**
+ * The category of the rejection, `None` when the request didn't get an answer.
+ */
+category: RejectionCategory | null; missing_permission: RamPermission | null }
+/**
+ * A region where ECS is available.
+ */
+export type RegionInfo = { region_id: string; 
+/**
+ * The name of the region, e.g. `华东1（杭州）`.
+ */
+local_name: string }
+/**
+ * What a rejection means, for the frontend to react to it.
+ */
+export type RejectionCategory = 
+/**
+ * The access key is unknown, disabled or its secret doesn't match.
+ */
+"InvalidCredentials" | 
+/**
+ * The RAM policies of the caller don't allow the action.
+ */
+"PermissionDenied" | 
+/**
+ * Too many requests, the call can be retried later.
+ */
+"Throttled" | 
+/**
+ * A quota or a limit of the account has been reached.
+ */
+"QuotaExceeded" | "InvalidRegion" | 
+/**
+ * The resource doesn't exist, or not in this region.
+ */
+"NotFound" | 
+/**
+ * The zone has no stock left of the requested resource.
+ */
+"InsufficientStock" | 
+/**
+ * The instance is not in a status allowing the action, e.g. starting a running one.
+ */
+"IncorrectInstanceStatus" | 
+/**
+ * Any other rejection, its code tells more.
+ */
+"Other"
+/**
+ * How a release is carried out.
+ */
+export type ReleaseOptions = { 
+/**
+ * Typed by the user: the name of the instance, its ID when it has no name.
+ */
+confirmation: string; 
+/**
+ * Takes a snapshot of each disk before releasing, kept after the disks are gone.
+ */
+snapshot_disks?: boolean; 
+/**
+ * Releases the instance even when it is running.
+ */
+force?: boolean; 
+/**
+ * Only checks whether the release would go through, nothing is changed.
+ */
+dry_run?: boolean }
+/**
+ * What a release did, or would do in a dry run.
+ */
+export type ReleaseReport = { instance_id: string; dry_run: boolean; 
+/**
+ * The disks snapshotted before releasing, or which would be.
+ */
+disk_ids: string[]; 
+/**
+ * The final snapshots taken, none in a dry run.
+ */
+snapshot_ids: string[]; 
+/**
+ * Whether the instance has been released, never in a dry run.
+ */
+released: boolean }
+/**
+ * What DescribeAvailableResource tells about.
+ */
+export type ResourceKind = "InstanceType" | 
+/**
+ * The disk categories of the system disk, for an instance type.
+ */
+"SystemDisk" | 
+/**
+ * The disk categories of the data disks, for an instance type.
+ */
+"DataDisk"
+export type RotationError = { type: "NotRotatable"; error: { profile: string; reason: string } } | 
+/**
+ * A step failed, after which the changes made so far have been undone, unless
+ * `rolled_back` is false.
+ */
+{ type: "StepFailed"; error: { step: RotationStep; message: string; rolled_back: boolean } }
+export type RotationStep = "LoadProfile" | "CreateAccessKey" | "ValidateAccessKey" | "SwapAccessKey" | "DeactivateOldAccessKey" | "DeleteOldAccessKey"
+/**
+ * Whether a rule is about the traffic entering or leaving the instances.
+ */
+export type RuleDirection = "ingress" | "egress"
+/**
+ * A change to the rules of a security group.
+ */
+export type RuleEdit = { type: "Add"; rule: RuleSpec } | 
+/**
+ * Replaces a rule, in the same direction.
+ */
+{ type: "Modify"; security_group_rule_id: string; rule: RuleSpec } | { type: "Revoke"; security_group_rule_id: string }
+export type RuleModification = { security_group_rule_id: string; from: RuleSpec; to: RuleSpec }
+/**
+ * Where the traffic of an ingress rule comes from, or where the traffic of an egress
+ * rule goes to.
+ */
+export type RulePeer = 
+/**
+ * An IPv4 address or CIDR block, e.g. `0.0.0.0/0`.
+ */
+{ type: "Cidr"; cidr_ip: string } | { type: "Ipv6Cidr"; ipv6_cidr_ip: string } | 
+/**
+ * The instances of another security group, or of the same one.
+ */
+{ type: "SecurityGroup"; security_group_id: string } | { type: "PrefixList"; prefix_list_id: string }
+export type RulePolicy = "Accept" | "Drop"
+/**
+ * How the rules of a security group change, or would change.
+ */
+export type RuleSetDiff = { security_group_id: string; added: RuleSpec[]; modified: RuleModification[]; revoked: SecurityGroupRule[] }
+/**
+ * What a security group rule allows or denies.
+ */
+export type RuleSpec = { direction: RuleDirection; 
+/**
+ * `TCP`, `UDP`, `ICMP`, `ICMPv6`, `GRE` or `ALL`.
+ */
+ip_protocol: string; 
+/**
+ * `start/end`, e.g. `22/22`, always `-1/-1` for the protocols without ports.
+ */
+port_range: string; peer: RulePeer; policy: RulePolicy; 
+/**
+ * From 1, the first one evaluated, to 100.
+ */
+priority: number; description?: string }
+export type SaveCredentialError = { SerializeError: SerdeJsonError } | "Locked" | "EncryptError"
+/**
+ * A string which never shows up in `Debug` or `Display` output, and is wiped from
+ * memory when dropped.
+ * 
+ * The value has to be read explicitly through [`SecretString::expose_secret`]. It is
+ * still serialized as is, since the credential store has to persist it, so types
+ * holding one must not be sent to the frontend.
+ */
+export type SecretString = string
+export type SecurityGroupInfo = { security_group_id: string; security_group_name: string; vpc_id: string; description: string; security_group_type: SecurityGroupType }
+/**
+ * A rule of a security group.
+ */
+export type SecurityGroupRule = { security_group_rule_id: string; rule: RuleSpec }
+/**
+ * A security group with its ingress and egress rules.
+ */
+export type SecurityGroupRules = { security_group_id: string; security_group_name: string; vpc_id: string; 
+/**
+ * Whether the instances of the group can reach each other.
+ */
+inner_access_policy: RulePolicy; rules: SecurityGroupRule[] }
+export type SecurityGroupType = 
+/**
+ * A basic group, whose instances can reach each other unless its inner access
+ * policy drops it, and whose rules can name other groups as peers.
+ */
+"Normal" | 
+/**
+ * An enterprise group, whose instances can't reach each other and whose rules
+ * can't name other groups as peers.
+ */
+"Enterprise"
+export type SerdeJsonError = string
+export type Severity = "Low" | "Medium" | "High" | 
+/**
+ * Exposed to the internet on an instance with a public IP.
+ */
+"Critical"
+export type SkipReason = 
+/**
+ * The batch was cancelled before the request of the instance was sent.
+ */
+"Cancelled" | 
+/**
+ * The instance already was in the status the action leads to.
+ */
+"AlreadyInStatus"
+/**
+ * Whether a stopped pay-as-you-go instance keeps being billed for its vCPUs and memory.
+ */
+export type StoppedMode = 
+/**
+ * Keeps the resources, so the instance is sure to start again.
+ */
+"KeepCharging" | 
+/**
+ * Releases the vCPUs and memory, which may be out of stock when starting again.
+ */
+"StopCharging"
+export type StoreMigrationError = { type: "UnsupportedVersion"; error: { found: number; supported: number } } | { type: "InvalidVersion"; error: string } | { type: "BackupFailed"; error: { path: string; message: string } } | { type: "MigrationFailed"; error: { version: number; description: string; message: string } } | { type: "SaveFailed"; error: string }
+/**
+ * What a version of a launch template sets, everything being optional.
+ */
+export type TemplateConfig = { image_id: string | null; instance_type: string | null; zone_id: string | null; vswitch_id: string | null; security_group_ids: string[]; instance_name: string | null; key_pair_name: string | null; system_disk: DiskSpec | null; data_disks: DiskSpec[]; 
+/**
+ * `PostPaid` or `PrePaid`.
+ */
+charge_type: string | null; 
+/**
+ * In Mbit/s.
+ */
+internet_max_bandwidth_out: number | null; tags: InstanceTag[] }
+/**
+ * Launches instances from a version of a template, overriding some of its settings.
+ */
+export type TemplateLaunch = { launch_template_id: string; 
+/**
+ * The default version when `None`.
+ */
+version_number: number | null; instance_name: string | null; instance_type: string | null; vswitch_id: string | null; 
+/**
+ * The security groups replacing those of the template, when not empty.
+ */
+security_group_ids: string[]; key_pair_name: string | null; 
+/**
+ * In Mbit/s.
+ */
+internet_max_bandwidth_out: number | null; 
+/**
+ * How many instances to create.
+ */
+amount: number; 
+/**
+ * The tags replacing those of the template, when not empty.
+ */
+tags: InstanceTag[] }
+/**
+ * An ingress rule letting an IP reach a port until it expires.
+ */
+export type TemporaryAccess = { region_id: string; security_group_id: string; rule: RuleSpec; 
+/**
+ * In RFC 3339.
+ */
+expires_at: string }
+/**
+ * The traffic simulated, entering an instance.
+ */
+export type TrafficQuery = { source: TrafficSource; instance_id: string; 
+/**
+ * `TCP`, `UDP`, `ICMP`, `ICMPv6` or `GRE`.
+ */
+ip_protocol: string; 
+/**
+ * Required for TCP and UDP, ignored otherwise.
+ */
+port: number | null }
+/**
+ * Where the simulated traffic comes from.
+ */
+export type TrafficSource = 
+/**
+ * An IPv4 or IPv6 address or CIDR block, outside of any security group.
+ */
+{ type: "Cidr"; cidr: string } | 
+/**
+ * The primary network interface of an instance of the region, with its address and
+ * security groups.
+ */
+{ type: "Instance"; instance_id: string } | 
+/**
+ * Any instance of a security group, whose address isn't known.
+ */
+{ type: "SecurityGroup"; security_group_id: string }
+/**
+ * A rule which might have decided otherwise, but can't be evaluated locally.
+ */
+export type UnevaluatedRule = { security_group_id: string; security_group_rule_id: string; reason: string }
+export type UnlockOutcome = { 
+/**
+ * The vault didn't exist yet and has been created with the given passphrase.
+ */
+created: boolean; 
+/**
+ * A plaintext credential left by an older version has been encrypted
+ * into the default profile, or a new one when a profile has that name.
+ */
+migrated: boolean }
+export type VSwitchInfo = { vswitch_id: string; vswitch_name: string; vpc_id: string; zone_id: string; cidr_block: string; available_ip_address_count: number }
+export type VaultError = { type: "NotEncrypted" } | { type: "EmptyPassphrase" } | { type: "WrongPassphrase" } | { type: "KeyDerivation"; error: string } | { type: "EncryptError" } | { type: "Corrupted"; error: SerdeJsonError } | { type: "Migration"; error: QueryCredentialError }
+export type VaultStatus = { 
+/**
+ * Whether a master passphrase has ever been set for this store.
+ */
+initialized: boolean; 
+/**
+ * Whether the derived key is currently held in memory.
+ */
+unlocked: boolean }
+export type Verdict = { allowed: boolean; decided_by: Decision; 
+/**
+ * The rules evaluated before the decision but skipped, the verdict assuming they
+ * don't match.
+ */
+unevaluated_rules: UnevaluatedRule[] }
+/**
+ * How a version of a launch template differs from another.
+ */
+export type VersionDiff = { from_version: number; to_version: number; 
+/**
+ * The settings which changed, in the order of [`TemplateConfig`].
+ */
+changes: ConfigChange[] }
+export type VpcInfo = { vpc_id: string; vpc_name: string; cidr_block: string; is_default: boolean }
+/**
+ * A zone of a region, where instances and vSwitches live.
+ */
+export type ZoneInfo = { zone_id: string; local_name: string }
+/**
+ * An instance type or a disk category offered in a zone.
+ */
+export type ZoneResource = { 
+/**
+ * E.g. `ecs.g7.large` or `cloud_essd`.
+ */
+value: string; 
+/**
+ * Whether it is in stock, sold out ones being listed too.
+ */
+available: boolean; 
+/**
+ * E.g. `WithStock`, `ClosedWithStock` or `WithoutStock`.
+ */
+status_category: string; 
+/**
+ * The smallest and largest disk size, in GiB.
+ */
+min: number | null; max: number | null }
 
 /** tauri-specta globals **/
 
@@ -13,8 +13,12 @@ import { LoginPage } from "./pages/login/LoginPage";
 import { NotFoundPage } from "./pages/globs/NotFound";
 import { DashboardPage } from "./pages/dashboard/DashboardPage";
 import { useAliyunClientStatus } from "./lib/auth";
+import { useStoreMigrationStatus } from "./lib/store";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { CenterPromptLayout } from "./layouts/CenterPromptLayout";
+import {
+  CenterPromptBox,
+  CenterPromptLayout,
+} from "./layouts/CenterPromptLayout";
 
 const RootContainer: Component = () => {
   /**
@@ -22,6 +26,7 @@ const RootContainer: Component = () => {
    * i.e., when hasValidClient is ready, redirect to the target page.
    */
   const { hasValidClient, hasClient } = useAliyunClientStatus();
+  const { migrationError } = useStoreMigrationStatus();
   const [show, setShow] = createSignal(false);
 
   createEffect(() => {
@@ -49,6 +54,15 @@ const RootContainer: Component = () => {
         path={"/"}
         component={() => (
           <Switch>
+            <Match when={migrationError()}>
+              {(error) => (
+                <CenterPromptLayout>
+                  <CenterPromptBox title="The store can't be used">
+                    <p>{error()}</p>
+                  </CenterPromptBox>
+                </CenterPromptLayout>
+              )}
+            </Match>
             <Match when={!hasClient.loading && hasClient()}>
               <DashboardPage></DashboardPage>
             </Match>
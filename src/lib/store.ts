@@ -0,0 +1,39 @@
+import { createResource } from "solid-js";
+import { commands, StoreMigrationError } from "~/binding";
+
+function describeMigrationError(error: StoreMigrationError): string {
+  switch (error.type) {
+    case "UnsupportedVersion":
+      return `The store has schema version ${error.error.found}, this version of the app only supports up to ${error.error.supported}.`;
+    case "InvalidVersion":
+      return `The schema version of the store is not valid: ${error.error}`;
+    case "BackupFailed":
+      return `Failed to back up the store to ${error.error.path}: ${error.error.message}`;
+    case "MigrationFailed":
+      return `Failed to migrate the store to version ${error.error.version} (${error.error.description}): ${error.error.message}`;
+    case "SaveFailed":
+      return `Failed to save the migrated store: ${error.error}`;
+  }
+}
+
+/**
+ * Why the store couldn't be migrated at startup, `null` when it was.
+ *
+ * The app can't be used until then, the store is left untouched.
+ */
+const [migrationError] = createResource(async (): Promise<string | null> => {
+  try {
+    const result = await commands.storeMigrationStatus();
+    if (result.status == "ok") {
+      return null;
+    }
+    return describeMigrationError(result.error);
+  } catch (error) {
+    console.error(error);
+    return null;
+  }
+});
+
+export function useStoreMigrationStatus() {
+  return { migrationError };
+}